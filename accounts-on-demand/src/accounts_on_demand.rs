@@ -171,6 +171,7 @@ impl AccountStorageInterface for AccountsOnDemand {
                                                 pubkey: account_pk,
                                                 account: Arc::new(account),
                                                 updated_slot: response.context.slot,
+                                                write_version: 0,
                                             };
                                             self.accounts_storage
                                                 .update_account(account_data.clone(), commitment)
@@ -268,6 +269,7 @@ impl AccountStorageInterface for AccountsOnDemand {
                                 pubkey: *pk,
                                 account: Arc::new(account.clone()),
                                 updated_slot: 0,
+                                write_version: 0,
                             })
                             .collect_vec();
                         // add fetched accounts into cache
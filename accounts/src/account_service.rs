@@ -1,9 +1,17 @@
-use std::{str::FromStr, sync::Arc};
+use std::{
+    str::FromStr,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, OnceLock,
+    },
+};
 
 use anyhow::bail;
+use futures::StreamExt;
 use itertools::Itertools;
 use prometheus::{opts, register_int_gauge, IntGauge};
-use solana_account_decoder::{UiAccount, UiDataSliceConfig};
+use solana_account_decoder::parse_token::{token_amount_to_ui_amount, UiTokenAmount};
+use solana_account_decoder::{UiAccount, UiAccountEncoding, UiDataSliceConfig, MAX_BASE58_BYTES};
 use solana_lite_rpc_core::types::BlockInfoStream;
 use solana_lite_rpc_core::{
     commitment_utils::Commitment,
@@ -16,12 +24,22 @@ use solana_lite_rpc_core::{
 use solana_rpc_client::nonblocking::rpc_client::RpcClient;
 use solana_rpc_client_api::{
     config::{RpcAccountInfoConfig, RpcProgramAccountsConfig},
+    filter::{Memcmp, RpcFilterType},
+    request::TokenAccountsFilter,
     response::RpcKeyedAccount,
 };
 use solana_sdk::{commitment_config::CommitmentConfig, pubkey::Pubkey, slot_history::Slot};
 use tokio::sync::broadcast::Sender;
 
 use crate::account_store_interface::{AccountLoadingError, AccountStorageInterface};
+use crate::program_accounts_page::{
+    program_accounts_page, verify_page_limit, ProgramAccountsCursor, RpcProgramAccountsPage,
+    MAX_CURSOR_AGE_SLOTS,
+};
+use crate::token_account::{
+    is_token_program, parse_mint_decimals, parse_token_account, SPL_TOKEN_PROGRAM_ID,
+    TOKEN_ACCOUNT_MINT_OFFSET, TOKEN_ACCOUNT_OWNER_OFFSET,
+};
 
 lazy_static::lazy_static! {
     static ref ACCOUNT_UPDATES: IntGauge =
@@ -36,12 +54,51 @@ lazy_static::lazy_static! {
 
     static ref GET_ACCOUNT_CALLED: IntGauge =
        register_int_gauge!(opts!("literpc_get_account_called", "Account Updates by lite-rpc service")).unwrap();
+
+    static ref ACCOUNT_SLOTS_BEHIND_TIP: IntGauge =
+       register_int_gauge!(opts!("literpc_accounts_slots_behind_tip", "Slots between the cluster tip and the last account update")).unwrap();
+}
+
+/// Tracks how far the ingested account updates lag behind the processed tip
+#[derive(Default)]
+pub struct AccountFreshness {
+    last_account_update_slot: AtomicU64,
+    tip_slot: AtomicU64,
+}
+
+impl AccountFreshness {
+    pub fn on_account_update(&self, slot: Slot) {
+        self.last_account_update_slot
+            .fetch_max(slot, Ordering::Relaxed);
+        self.update_gauge();
+    }
+
+    pub fn on_tip(&self, slot: Slot) {
+        self.tip_slot.fetch_max(slot, Ordering::Relaxed);
+        self.update_gauge();
+    }
+
+    pub fn slots_behind_tip(&self) -> u64 {
+        self.tip_slot
+            .load(Ordering::Relaxed)
+            .saturating_sub(self.last_account_update_slot.load(Ordering::Relaxed))
+    }
+
+    fn update_gauge(&self) {
+        ACCOUNT_SLOTS_BEHIND_TIP.set(self.slots_behind_tip() as i64);
+    }
 }
 
+// max number of accounts looked up concurrently for a single getMultipleAccounts call
+const MAX_PARALLEL_GET_ACCOUNT: usize = 32;
+
 #[derive(Clone)]
 pub struct AccountService {
     account_store: Arc<dyn AccountStorageInterface>,
     pub account_notification_sender: Sender<AccountNotificationMessage>,
+    freshness: Arc<AccountFreshness>,
+    // slot of the initial snapshot, set once it is loaded
+    ready_slot: Arc<OnceLock<Slot>>,
 }
 
 impl AccountService {
@@ -52,6 +109,24 @@ impl AccountService {
         Self {
             account_store,
             account_notification_sender,
+            freshness: Arc::new(AccountFreshness::default()),
+            ready_slot: Arc::new(OnceLock::new()),
+        }
+    }
+
+    /// the initial snapshot is loaded, accounts missing before might just not be loaded yet
+    pub fn is_ready(&self) -> bool {
+        self.ready_slot.get().is_some()
+    }
+
+    pub fn ready_slot(&self) -> Option<Slot> {
+        self.ready_slot.get().copied()
+    }
+
+    /// signals that the initial snapshot up to `slot` is loaded, only the first call counts
+    pub fn mark_ready(&self, slot: Slot) {
+        if self.ready_slot.set(slot).is_ok() {
+            log::info!("accounts are ready at slot {slot}");
         }
     }
 
@@ -102,9 +177,11 @@ impl AccountService {
             }
         }
         log::info!("Fetching {} accounts", accounts.len());
+        let mut snapshot_slot = 0;
+        let mut failed_chunks = 0;
         for accounts in accounts.chunks(max_request_in_parallel * NB_ACCOUNTS_IN_GMA) {
             for accounts in accounts.chunks(NB_ACCOUNTS_IN_GMA) {
-                let mut fetch_accounts = vec![];
+                let mut fetch_accounts = None;
                 let mut updated_slot = 0;
                 for _ in 0..NB_RETRY {
                     let accounts = rpc_client
@@ -120,8 +197,9 @@ impl AccountService {
                         .await;
                     match accounts {
                         Ok(response) => {
-                            fetch_accounts = response.value;
+                            fetch_accounts = Some(response.value);
                             updated_slot = response.context.slot;
+                            snapshot_slot = snapshot_slot.max(updated_slot);
                             break;
                         }
                         Err(e) => {
@@ -131,6 +209,14 @@ impl AccountService {
                         }
                     }
                 }
+                let Some(fetch_accounts) = fetch_accounts else {
+                    log::error!(
+                        "Could not fetch {} accounts after {NB_RETRY} retries",
+                        accounts.len()
+                    );
+                    failed_chunks += 1;
+                    continue;
+                };
                 for (index, account) in fetch_accounts.iter().enumerate() {
                     if let Some(account) = account {
                         self.account_store
@@ -138,13 +224,19 @@ impl AccountService {
                                 pubkey: accounts[index],
                                 account: Arc::new(account.clone()),
                                 updated_slot,
+                                write_version: 0,
                             })
                             .await;
                     }
                 }
             }
         }
+        // a missing account must not be reported as not found, stay warming up
+        if failed_chunks > 0 {
+            bail!("{failed_chunks} chunks of accounts could not be fetched");
+        }
         log::info!("{} accounts successfully fetched", accounts.len());
+        self.mark_ready(snapshot_slot);
         Ok(())
     }
 
@@ -159,6 +251,8 @@ impl AccountService {
                 match account_stream.recv().await {
                     Ok(account_notification) => {
                         ACCOUNT_UPDATES.inc();
+                        this.freshness
+                            .on_account_update(account_notification.data.updated_slot);
                         if this
                             .account_store
                             .update_account(
@@ -189,6 +283,7 @@ impl AccountService {
             loop {
                 match blockinfo_stream.recv().await {
                     Ok(block_info) => {
+                        this.freshness.on_tip(block_info.slot);
                         if block_info.commitment_config.is_processed() {
                             // processed commitment is not processed in this loop
                             continue;
@@ -235,7 +330,7 @@ impl AccountService {
             .as_ref()
             .map(|c| c.encoding)
             .unwrap_or_default()
-            .unwrap_or(solana_account_decoder::UiAccountEncoding::Base64);
+            .unwrap_or(UiAccountEncoding::Base64);
         let data_slice = config.as_ref().map(|c| c.data_slice).unwrap_or_default();
         UiAccount::encode(
             &account_data.pubkey,
@@ -246,6 +341,39 @@ impl AccountService {
         )
     }
 
+    fn is_data_slice_in_range(
+        account_data: &AccountData,
+        data_slice: Option<UiDataSliceConfig>,
+    ) -> bool {
+        data_slice
+            .map(|slice| {
+                slice
+                    .offset
+                    .checked_add(slice.length)
+                    .map(|end| end <= account_data.account.data.len())
+                    .unwrap_or(false)
+            })
+            .unwrap_or(true)
+    }
+
+    /// solana refuses to base58 encode more than MAX_BASE58_BYTES of (sliced) account data
+    fn is_encodable(account_data: &AccountData, config: Option<&RpcAccountInfoConfig>) -> bool {
+        let Some(config) = config else {
+            return true;
+        };
+        match config.encoding {
+            Some(UiAccountEncoding::Base58) | Some(UiAccountEncoding::Binary) => {
+                let data_len = account_data.account.data.len();
+                let sliced_len = config
+                    .data_slice
+                    .map(|slice| slice.length.min(data_len.saturating_sub(slice.offset)))
+                    .unwrap_or(data_len);
+                sliced_len <= MAX_BASE58_BYTES
+            }
+            _ => true,
+        }
+    }
+
     pub async fn get_account(
         &self,
         account: Pubkey,
@@ -260,6 +388,13 @@ impl AccountService {
         let commitment = Commitment::from(commitment);
 
         if let Some(account_data) = self.account_store.get_account(account, commitment).await? {
+            let data_slice = config.as_ref().and_then(|c| c.data_slice);
+            if !Self::is_data_slice_in_range(&account_data, data_slice) {
+                return Err(AccountLoadingError::DataSliceOutOfRange);
+            }
+            if !Self::is_encodable(&account_data, config.as_ref()) {
+                return Err(AccountLoadingError::Base58DataTooLarge);
+            }
             // if minimum context slot is not satisfied return Null
             let minimum_context_slot = config
                 .as_ref()
@@ -277,6 +412,203 @@ impl AccountService {
         }
     }
 
+    /// accounts in the order of `accounts` and the max slot across them, missing accounts are None
+    pub async fn get_multiple_accounts(
+        &self,
+        accounts: Vec<Pubkey>,
+        config: Option<RpcAccountInfoConfig>,
+    ) -> Result<(Slot, Vec<Option<UiAccount>>), AccountLoadingError> {
+        // buffered keeps the input order
+        let results = futures::stream::iter(accounts)
+            .map(|account| self.get_account(account, config.clone()))
+            .buffered(MAX_PARALLEL_GET_ACCOUNT)
+            .collect::<Vec<_>>()
+            .await;
+
+        let mut max_slot = 0;
+        let mut ui_accounts = Vec::with_capacity(results.len());
+        for result in results {
+            match result {
+                Ok((slot, ui_account)) => {
+                    max_slot = max_slot.max(slot);
+                    ui_accounts.push(ui_account);
+                }
+                // get_account reports an account missing from the store as not configured
+                Err(
+                    AccountLoadingError::AccountNotFound
+                    | AccountLoadingError::ConfigDoesnotContainRequiredFilters,
+                ) => {
+                    ui_accounts.push(None);
+                }
+                Err(err) => {
+                    return Err(err);
+                }
+            }
+        }
+        Ok((max_slot, ui_accounts))
+    }
+
+    /// lamports of the account, 0 if the account does not exist (same as solana)
+    pub async fn get_balance(
+        &self,
+        account: Pubkey,
+        config: Option<RpcAccountInfoConfig>,
+    ) -> Result<u64, AccountLoadingError> {
+        match self.get_account(account, config).await {
+            Ok((_, ui_account)) => Ok(ui_account.map(|x| x.lamports).unwrap_or_default()),
+            Err(AccountLoadingError::AccountNotFound)
+            | Err(AccountLoadingError::ConfigDoesnotContainRequiredFilters) => Ok(0),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// balance of a spl token account, decimals are taken from its mint
+    pub async fn get_token_account_balance(
+        &self,
+        account: Pubkey,
+        commitment: CommitmentConfig,
+    ) -> Result<(Slot, UiTokenAmount), AccountLoadingError> {
+        let commitment = Commitment::from(commitment);
+        let token_account = self
+            .account_store
+            .get_account(account, commitment)
+            .await?
+            .ok_or(AccountLoadingError::AccountNotFound)?;
+        if !is_token_program(&token_account.account.owner) {
+            return Err(AccountLoadingError::NotATokenAccount);
+        }
+        let (mint, amount) = parse_token_account(&token_account.account.data)
+            .ok_or(AccountLoadingError::NotATokenAccount)?;
+
+        let mint_account = self
+            .account_store
+            .get_account(mint, commitment)
+            .await?
+            .ok_or(AccountLoadingError::AccountNotFound)?;
+        let decimals = parse_mint_decimals(&mint_account.account.data)
+            .ok_or(AccountLoadingError::NotATokenAccount)?;
+
+        Ok((
+            token_account.updated_slot,
+            token_amount_to_ui_amount(amount, decimals),
+        ))
+    }
+
+    /// token accounts of `owner` filtered by mint or token program, uses the program accounts of the token program
+    pub async fn get_token_accounts_by_owner(
+        &self,
+        owner: Pubkey,
+        filter: TokenAccountsFilter,
+        config: Option<RpcAccountInfoConfig>,
+    ) -> anyhow::Result<(Slot, Vec<RpcKeyedAccount>)> {
+        let mut filters = vec![RpcFilterType::Memcmp(Memcmp::new_base58_encoded(
+            TOKEN_ACCOUNT_OWNER_OFFSET,
+            owner.as_ref(),
+        ))];
+        let program_id = match filter {
+            TokenAccountsFilter::Mint(mint) => {
+                filters.push(RpcFilterType::Memcmp(Memcmp::new_base58_encoded(
+                    TOKEN_ACCOUNT_MINT_OFFSET,
+                    mint.as_ref(),
+                )));
+                // the token program owning the mint also owns its token accounts
+                let commitment = config
+                    .as_ref()
+                    .and_then(|c| c.commitment)
+                    .unwrap_or_default();
+                match self
+                    .account_store
+                    .get_account(mint, Commitment::from(commitment))
+                    .await
+                {
+                    Ok(Some(mint_account)) => mint_account.account.owner,
+                    _ => SPL_TOKEN_PROGRAM_ID,
+                }
+            }
+            TokenAccountsFilter::ProgramId(program_id) => program_id,
+        };
+        if !is_token_program(&program_id) {
+            bail!("{program_id} is not a token program");
+        }
+
+        self.get_program_accounts(
+            program_id,
+            Some(RpcProgramAccountsConfig {
+                filters: Some(filters),
+                account_config: config.unwrap_or_default(),
+                with_context: None,
+            }),
+        )
+        .await
+    }
+
+    /// one page of program accounts ordered by pubkey, see `ProgramAccountsCursor`,
+    /// the page is read at `current_slot` which is returned as its context slot
+    pub async fn get_program_accounts_page(
+        &self,
+        program_id: Pubkey,
+        config: Option<RpcProgramAccountsConfig>,
+        limit: usize,
+        cursor: Option<String>,
+        current_slot: Slot,
+    ) -> anyhow::Result<(Slot, RpcProgramAccountsPage)> {
+        verify_page_limit(limit)?;
+        let cursor = cursor
+            .map(|cursor| ProgramAccountsCursor::decode(&cursor))
+            .transpose()?;
+        if let Some(cursor) = cursor {
+            if cursor.first_page_slot.saturating_add(MAX_CURSOR_AGE_SLOTS) < current_slot {
+                bail!(
+                    "cursor of slot {} expired, restart from the first page",
+                    cursor.first_page_slot
+                );
+            }
+        }
+        // the cursor of the next page keeps the slot of the first page to expire the walk
+        let first_page_slot = cursor.map_or(current_slot, |cursor| cursor.first_page_slot);
+        GET_PROGRAM_ACCOUNT_CALLED.inc();
+
+        let account_filter = config.as_ref().and_then(|config| config.filters.clone());
+        let commitment = config
+            .as_ref()
+            .and_then(|config| config.account_config.commitment)
+            .unwrap_or_default();
+        // one more account tells if there is a next page
+        let Some(accounts) = self
+            .account_store
+            .get_program_accounts_page(
+                program_id,
+                account_filter,
+                Commitment::from(commitment),
+                cursor.map(|cursor| cursor.last_pubkey),
+                limit + 1,
+            )
+            .await
+        else {
+            bail!("Program id {program_id} does not satisfy any configured filters")
+        };
+        let acc_config = config.map(|config| config.account_config);
+        let keyed_accounts = accounts
+            .iter()
+            .map(|account_data| {
+                (
+                    account_data.pubkey,
+                    RpcKeyedAccount {
+                        pubkey: account_data.pubkey.to_string(),
+                        account: Self::convert_account_data_to_ui_account(
+                            account_data,
+                            acc_config.clone(),
+                        ),
+                    },
+                )
+            })
+            .collect_vec();
+        Ok((
+            current_slot,
+            program_accounts_page(keyed_accounts, first_page_slot, limit),
+        ))
+    }
+
     pub async fn get_program_accounts(
         &self,
         program_id: Pubkey,
@@ -342,3 +674,521 @@ impl AccountService {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    };
+    use std::time::Duration;
+
+    use async_trait::async_trait;
+    use solana_lite_rpc_core::{
+        commitment_utils::Commitment, structures::account_data::AccountData,
+    };
+    use solana_rpc_client_api::filter::RpcFilterType;
+    use solana_sdk::{account::Account, pubkey::Pubkey, slot_history::Slot};
+
+    use solana_account_decoder::{UiAccountData, UiAccountEncoding, UiDataSliceConfig};
+    use solana_rpc_client_api::config::RpcAccountInfoConfig;
+
+    use super::AccountService;
+    use crate::{
+        account_store_interface::{AccountLoadingError, AccountStorageInterface},
+        inmemory_account_store::InmemoryAccountStore,
+    };
+
+    #[tokio::test]
+    pub async fn test_get_balance() {
+        let store = Arc::new(InmemoryAccountStore::default());
+        let (account_notification_sender, _) = tokio::sync::broadcast::channel(16);
+        let account_service = AccountService::new(store.clone(), account_notification_sender);
+
+        let pk = Pubkey::new_unique();
+        store
+            .initilize_or_update_account(AccountData {
+                pubkey: pk,
+                account: Arc::new(Account {
+                    lamports: 42_000,
+                    data: vec![1, 2, 3],
+                    owner: Pubkey::new_unique(),
+                    executable: false,
+                    rent_epoch: 0,
+                }),
+                updated_slot: 10,
+                write_version: 0,
+            })
+            .await;
+
+        assert_eq!(account_service.get_balance(pk, None).await, Ok(42_000));
+        // missing accounts have a zero balance
+        assert_eq!(
+            account_service
+                .get_balance(Pubkey::new_unique(), None)
+                .await,
+            Ok(0)
+        );
+    }
+
+    // counts the concurrent get_account calls
+    struct InFlightCountingStore {
+        inner: InmemoryAccountStore,
+        in_flight: AtomicUsize,
+        max_in_flight: AtomicUsize,
+        // get_account of this account times out
+        timed_out: Option<Pubkey>,
+    }
+
+    #[async_trait]
+    impl AccountStorageInterface for InFlightCountingStore {
+        async fn update_account(&self, account_data: AccountData, commitment: Commitment) -> bool {
+            self.inner.update_account(account_data, commitment).await
+        }
+
+        async fn initilize_or_update_account(&self, account_data: AccountData) {
+            self.inner.initilize_or_update_account(account_data).await
+        }
+
+        async fn get_account(
+            &self,
+            account_pk: Pubkey,
+            commitment: Commitment,
+        ) -> Result<Option<AccountData>, AccountLoadingError> {
+            let in_flight = self.in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+            self.max_in_flight.fetch_max(in_flight, Ordering::SeqCst);
+            tokio::time::sleep(Duration::from_millis(5)).await;
+            self.in_flight.fetch_sub(1, Ordering::SeqCst);
+            if self.timed_out == Some(account_pk) {
+                return Err(AccountLoadingError::OperationTimeOut);
+            }
+            self.inner.get_account(account_pk, commitment).await
+        }
+
+        async fn get_program_accounts(
+            &self,
+            program_pubkey: Pubkey,
+            account_filter: Option<Vec<RpcFilterType>>,
+            commitment: Commitment,
+        ) -> Option<Vec<AccountData>> {
+            self.inner
+                .get_program_accounts(program_pubkey, account_filter, commitment)
+                .await
+        }
+
+        async fn process_slot_data(&self, slot: Slot, commitment: Commitment) -> Vec<AccountData> {
+            self.inner.process_slot_data(slot, commitment).await
+        }
+    }
+
+    #[tokio::test]
+    pub async fn test_get_multiple_accounts_order_and_concurrency() {
+        let store = Arc::new(InFlightCountingStore {
+            inner: InmemoryAccountStore::default(),
+            in_flight: AtomicUsize::new(0),
+            max_in_flight: AtomicUsize::new(0),
+            timed_out: None,
+        });
+        let (account_notification_sender, _) = tokio::sync::broadcast::channel(16);
+        let account_service = AccountService::new(store.clone(), account_notification_sender);
+
+        let pubkeys = (0..50).map(|_| Pubkey::new_unique()).collect::<Vec<_>>();
+        for (i, pk) in pubkeys.iter().enumerate() {
+            // every 10th account is missing
+            if i % 10 == 9 {
+                continue;
+            }
+            store
+                .initilize_or_update_account(AccountData {
+                    pubkey: *pk,
+                    account: Arc::new(Account {
+                        lamports: i as u64,
+                        data: vec![],
+                        owner: Pubkey::new_unique(),
+                        executable: false,
+                        rent_epoch: 0,
+                    }),
+                    updated_slot: 100 + i as u64,
+                    write_version: 0,
+                })
+                .await;
+        }
+
+        let (max_slot, accounts) = account_service
+            .get_multiple_accounts(pubkeys.clone(), None)
+            .await
+            .unwrap();
+
+        assert_eq!(accounts.len(), 50);
+        for (i, account) in accounts.iter().enumerate() {
+            if i % 10 == 9 {
+                assert!(account.is_none());
+            } else {
+                assert_eq!(account.as_ref().unwrap().lamports, i as u64);
+            }
+        }
+        assert_eq!(max_slot, 148);
+        let max_in_flight = store.max_in_flight.load(Ordering::SeqCst);
+        assert!(max_in_flight > 1);
+        assert!(max_in_flight <= super::MAX_PARALLEL_GET_ACCOUNT);
+    }
+
+    #[tokio::test]
+    pub async fn test_get_multiple_accounts_returns_store_errors() {
+        let timed_out = Pubkey::new_unique();
+        let store = Arc::new(InFlightCountingStore {
+            inner: InmemoryAccountStore::default(),
+            in_flight: AtomicUsize::new(0),
+            max_in_flight: AtomicUsize::new(0),
+            timed_out: Some(timed_out),
+        });
+        let (account_notification_sender, _) = tokio::sync::broadcast::channel(16);
+        let account_service = AccountService::new(store, account_notification_sender);
+
+        // a missing account is none
+        let (_, accounts) = account_service
+            .get_multiple_accounts(vec![Pubkey::new_unique()], None)
+            .await
+            .unwrap();
+        assert_eq!(accounts, vec![None]);
+
+        // not reported as missing
+        assert_eq!(
+            account_service
+                .get_multiple_accounts(vec![Pubkey::new_unique(), timed_out], None)
+                .await
+                .unwrap_err(),
+            AccountLoadingError::OperationTimeOut
+        );
+    }
+
+    #[tokio::test]
+    pub async fn test_get_account_data_slice() {
+        let store = Arc::new(InmemoryAccountStore::default());
+        let (account_notification_sender, _) = tokio::sync::broadcast::channel(16);
+        let account_service = AccountService::new(store.clone(), account_notification_sender);
+
+        let pk = Pubkey::new_unique();
+        store
+            .initilize_or_update_account(AccountData {
+                pubkey: pk,
+                account: Arc::new(Account {
+                    lamports: 1,
+                    data: (0..10).collect(),
+                    owner: Pubkey::new_unique(),
+                    executable: false,
+                    rent_epoch: 0,
+                }),
+                updated_slot: 10,
+                write_version: 0,
+            })
+            .await;
+
+        let config = |offset, length| {
+            Some(RpcAccountInfoConfig {
+                encoding: Some(UiAccountEncoding::Base64),
+                data_slice: Some(UiDataSliceConfig { offset, length }),
+                commitment: None,
+                min_context_slot: None,
+            })
+        };
+
+        let (_, ui_account) = account_service.get_account(pk, config(2, 3)).await.unwrap();
+        let data = ui_account.unwrap().data.decode().unwrap();
+        assert_eq!(data, vec![2, 3, 4]);
+
+        assert_eq!(
+            account_service.get_account(pk, config(8, 3)).await,
+            Err(AccountLoadingError::DataSliceOutOfRange)
+        );
+    }
+
+    #[tokio::test]
+    pub async fn test_get_account_encodings() {
+        let store = Arc::new(InmemoryAccountStore::default());
+        let (account_notification_sender, _) = tokio::sync::broadcast::channel(16);
+        let account_service = AccountService::new(store.clone(), account_notification_sender);
+
+        let pk = Pubkey::new_unique();
+        let data = (0..200).map(|i| (i % 7) as u8).collect::<Vec<_>>();
+        store
+            .initilize_or_update_account(AccountData {
+                pubkey: pk,
+                account: Arc::new(Account {
+                    lamports: 1,
+                    data: data.clone(),
+                    owner: Pubkey::new_unique(),
+                    executable: false,
+                    rent_epoch: 0,
+                }),
+                updated_slot: 10,
+                write_version: 0,
+            })
+            .await;
+
+        let config = |encoding, data_slice| {
+            Some(RpcAccountInfoConfig {
+                encoding: Some(encoding),
+                data_slice,
+                commitment: None,
+                min_context_slot: None,
+            })
+        };
+
+        // base64+zstd round-trip
+        let (_, ui_account) = account_service
+            .get_account(pk, config(UiAccountEncoding::Base64Zstd, None))
+            .await
+            .unwrap();
+        let ui_account = ui_account.unwrap();
+        assert!(matches!(
+            ui_account.data,
+            UiAccountData::Binary(_, UiAccountEncoding::Base64Zstd)
+        ));
+        assert_eq!(ui_account.data.decode().unwrap(), data);
+
+        // base58 of a data slice
+        let (_, ui_account) = account_service
+            .get_account(
+                pk,
+                config(
+                    UiAccountEncoding::Base58,
+                    Some(UiDataSliceConfig {
+                        offset: 10,
+                        length: 20,
+                    }),
+                ),
+            )
+            .await
+            .unwrap();
+        let ui_account = ui_account.unwrap();
+        assert!(matches!(
+            ui_account.data,
+            UiAccountData::Binary(_, UiAccountEncoding::Base58)
+        ));
+        assert_eq!(ui_account.data.decode().unwrap(), data[10..30].to_vec());
+
+        // full account is too large for base58
+        assert_eq!(
+            account_service
+                .get_account(pk, config(UiAccountEncoding::Base58, None))
+                .await,
+            Err(AccountLoadingError::Base58DataTooLarge)
+        );
+    }
+
+    #[test]
+    pub fn test_account_freshness_gauge() {
+        let freshness = super::AccountFreshness::default();
+        freshness.on_account_update(100);
+        freshness.on_tip(100);
+        assert_eq!(freshness.slots_behind_tip(), 0);
+        assert_eq!(super::ACCOUNT_SLOTS_BEHIND_TIP.get(), 0);
+
+        // tip advances while account updates stall
+        freshness.on_tip(150);
+        assert_eq!(freshness.slots_behind_tip(), 50);
+        assert_eq!(super::ACCOUNT_SLOTS_BEHIND_TIP.get(), 50);
+
+        freshness.on_account_update(149);
+        assert_eq!(super::ACCOUNT_SLOTS_BEHIND_TIP.get(), 1);
+    }
+
+    #[tokio::test]
+    pub async fn test_get_token_account_balance() {
+        use crate::token_account::{
+            test_utils::{mint_data, token_account_data},
+            SPL_TOKEN_PROGRAM_ID,
+        };
+        use solana_sdk::commitment_config::CommitmentConfig;
+
+        let store = Arc::new(InmemoryAccountStore::default());
+        let (account_notification_sender, _) = tokio::sync::broadcast::channel(16);
+        let account_service = AccountService::new(store.clone(), account_notification_sender);
+
+        let mint = Pubkey::new_unique();
+        let token_account = Pubkey::new_unique();
+        let system_account = Pubkey::new_unique();
+        for (pubkey, owner, data) in [
+            (mint, SPL_TOKEN_PROGRAM_ID, mint_data(6)),
+            (
+                token_account,
+                SPL_TOKEN_PROGRAM_ID,
+                token_account_data(&mint, &Pubkey::new_unique(), 1_234_567),
+            ),
+            (system_account, Pubkey::default(), vec![]),
+        ] {
+            store
+                .initilize_or_update_account(AccountData {
+                    pubkey,
+                    account: Arc::new(Account {
+                        lamports: 2_039_280,
+                        data,
+                        owner,
+                        executable: false,
+                        rent_epoch: 0,
+                    }),
+                    updated_slot: 10,
+                    write_version: 0,
+                })
+                .await;
+        }
+
+        let (slot, balance) = account_service
+            .get_token_account_balance(token_account, CommitmentConfig::processed())
+            .await
+            .unwrap();
+        assert_eq!(slot, 10);
+        assert_eq!(balance.amount, "1234567");
+        assert_eq!(balance.decimals, 6);
+        assert_eq!(balance.ui_amount_string, "1.234567");
+
+        assert_eq!(
+            account_service
+                .get_token_account_balance(system_account, CommitmentConfig::processed())
+                .await
+                .unwrap_err(),
+            AccountLoadingError::NotATokenAccount
+        );
+    }
+
+    #[tokio::test]
+    pub async fn test_get_token_accounts_by_owner() {
+        use crate::token_account::{test_utils::token_account_data, SPL_TOKEN_PROGRAM_ID};
+        use solana_rpc_client_api::request::TokenAccountsFilter;
+
+        let store = Arc::new(InmemoryAccountStore::default());
+        let (account_notification_sender, _) = tokio::sync::broadcast::channel(16);
+        let account_service = AccountService::new(store.clone(), account_notification_sender);
+
+        let owner = Pubkey::new_unique();
+        let other_owner = Pubkey::new_unique();
+        let mint = Pubkey::new_unique();
+        let other_mint = Pubkey::new_unique();
+        let owned_token_account = Pubkey::new_unique();
+        let owned_other_mint_account = Pubkey::new_unique();
+        for (pubkey, token_owner, token_mint) in [
+            (owned_token_account, owner, mint),
+            (owned_other_mint_account, owner, other_mint),
+            (Pubkey::new_unique(), other_owner, mint),
+        ] {
+            store
+                .initilize_or_update_account(AccountData {
+                    pubkey,
+                    account: Arc::new(Account {
+                        lamports: 2_039_280,
+                        data: token_account_data(&token_mint, &token_owner, 1),
+                        owner: SPL_TOKEN_PROGRAM_ID,
+                        executable: false,
+                        rent_epoch: 0,
+                    }),
+                    updated_slot: 10,
+                    write_version: 0,
+                })
+                .await;
+        }
+
+        let (_, by_program) = account_service
+            .get_token_accounts_by_owner(
+                owner,
+                TokenAccountsFilter::ProgramId(SPL_TOKEN_PROGRAM_ID),
+                None,
+            )
+            .await
+            .unwrap();
+        let mut pubkeys = by_program.into_iter().map(|a| a.pubkey).collect::<Vec<_>>();
+        pubkeys.sort();
+        let mut expected = vec![
+            owned_token_account.to_string(),
+            owned_other_mint_account.to_string(),
+        ];
+        expected.sort();
+        assert_eq!(pubkeys, expected);
+
+        let (_, by_mint) = account_service
+            .get_token_accounts_by_owner(owner, TokenAccountsFilter::Mint(mint), None)
+            .await
+            .unwrap();
+        assert_eq!(by_mint.len(), 1);
+        assert_eq!(by_mint[0].pubkey, owned_token_account.to_string());
+
+        assert!(account_service
+            .get_token_accounts_by_owner(
+                owner,
+                TokenAccountsFilter::ProgramId(Pubkey::new_unique()),
+                None
+            )
+            .await
+            .is_err());
+    }
+
+    #[tokio::test]
+    pub async fn test_get_program_accounts_pages() {
+        use crate::program_accounts_page::{ProgramAccountsCursor, MAX_CURSOR_AGE_SLOTS};
+        use std::collections::HashSet;
+
+        let store = Arc::new(InmemoryAccountStore::default());
+        let (account_notification_sender, _) = tokio::sync::broadcast::channel(16);
+        let account_service = AccountService::new(store.clone(), account_notification_sender);
+        let program_id = Pubkey::new_unique();
+        for _ in 0..2500 {
+            store
+                .initilize_or_update_account(AccountData {
+                    pubkey: Pubkey::new_unique(),
+                    account: Arc::new(Account {
+                        lamports: 1,
+                        data: vec![],
+                        owner: program_id,
+                        executable: false,
+                        rent_epoch: 0,
+                    }),
+                    updated_slot: 10,
+                    write_version: 0,
+                })
+                .await;
+        }
+
+        let mut seen = vec![];
+        let mut page_sizes = vec![];
+        let mut cursor = None;
+        loop {
+            let current_slot = 100 + page_sizes.len() as u64;
+            let (slot, page) = account_service
+                .get_program_accounts_page(program_id, None, 1000, cursor, current_slot)
+                .await
+                .unwrap();
+            // each page reports the slot it was read at
+            assert_eq!(slot, current_slot);
+            page_sizes.push(page.accounts.len());
+            seen.extend(page.accounts.into_iter().map(|account| account.pubkey));
+            match page.cursor {
+                Some(next) => cursor = Some(next),
+                None => break,
+            }
+        }
+        assert_eq!(page_sizes, vec![1000, 1000, 500]);
+        assert_eq!(seen.iter().collect::<HashSet<_>>().len(), 2500);
+        // walked in pubkey order
+        let pubkeys = seen
+            .iter()
+            .map(|pubkey| pubkey.parse::<Pubkey>().unwrap())
+            .collect::<Vec<_>>();
+        assert!(pubkeys.windows(2).all(|pair| pair[0] < pair[1]));
+
+        let stale_cursor = ProgramAccountsCursor {
+            first_page_slot: 100,
+            last_pubkey: pubkeys[999],
+        }
+        .encode();
+        assert!(account_service
+            .get_program_accounts_page(
+                program_id,
+                None,
+                1000,
+                Some(stale_cursor),
+                101 + MAX_CURSOR_AGE_SLOTS,
+            )
+            .await
+            .is_err());
+    }
+}
@@ -10,6 +10,12 @@ pub enum AccountLoadingError {
     AccountNotFound,
     ConfigDoesnotContainRequiredFilters,
     OperationTimeOut,
+    // requested dataSlice goes beyond the account data
+    DataSliceOutOfRange,
+    // account is not owned by a token program or is not a token account
+    NotATokenAccount,
+    // base58 encoded data is limited to MAX_BASE58_BYTES (same as solana)
+    Base58DataTooLarge,
 }
 
 #[async_trait]
@@ -32,5 +38,25 @@ pub trait AccountStorageInterface: Send + Sync {
         commitment: Commitment,
     ) -> Option<Vec<AccountData>>;
 
+    /// up to `limit` program accounts with a pubkey after `after`, ordered by pubkey
+    async fn get_program_accounts_page(
+        &self,
+        program_pubkey: Pubkey,
+        account_filter: Option<Vec<RpcFilterType>>,
+        commitment: Commitment,
+        after: Option<Pubkey>,
+        limit: usize,
+    ) -> Option<Vec<AccountData>> {
+        let mut accounts = self
+            .get_program_accounts(program_pubkey, account_filter, commitment)
+            .await?;
+        if let Some(after) = after {
+            accounts.retain(|account_data| account_data.pubkey > after);
+        }
+        accounts.sort_unstable_by_key(|account_data| account_data.pubkey);
+        accounts.truncate(limit);
+        Some(accounts)
+    }
+
     async fn process_slot_data(&self, slot: Slot, commitment: Commitment) -> Vec<AccountData>;
 }
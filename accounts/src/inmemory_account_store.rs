@@ -1,4 +1,4 @@
-use std::{collections::HashSet, sync::Arc};
+use std::{collections::BTreeSet, ops::Bound, sync::Arc};
 
 use crate::account_store_interface::{AccountLoadingError, AccountStorageInterface};
 use async_trait::async_trait;
@@ -80,20 +80,26 @@ impl AccountDataByCommitment {
         // if commitmentment is processed check and update processed
         // if commitmentment is confirmed check and update processed and confirmed
         // if commitmentment is finalized check and update all
+        // an update arriving out of order must not overwrite a newer one of the same slot
         let update_confirmed = self
             .confirmed_account
             .as_ref()
-            .map(|x| x.updated_slot < data.updated_slot)
+            .map(|x| data.is_newer_than(x))
             .unwrap_or(true);
         let update_finalized = self
             .finalized_account
             .as_ref()
-            .map(|x| x.updated_slot < data.updated_slot)
+            .map(|x| data.is_newer_than(x))
             .unwrap_or(true);
 
         let mut updated = false;
-        if self.processed_accounts.get(&data.updated_slot).is_none() {
-            // processed not present for the slot
+        if self
+            .processed_accounts
+            .get(&data.updated_slot)
+            .map(|x| data.is_newer_than(x))
+            .unwrap_or(true)
+        {
+            // processed not present for the slot or older write version
             self.processed_accounts
                 .insert(data.updated_slot, data.clone());
             updated = true;
@@ -201,7 +207,8 @@ impl AccountDataByCommitment {
 pub struct InmemoryAccountStore {
     account_store: Arc<DashMap<Pubkey, AccountDataByCommitment>>,
     confirmed_slots_map: DashSet<Slot>,
-    accounts_by_owner: Arc<DashMap<Pubkey, HashSet<Pubkey>>>,
+    // ordered so program accounts are paged by pubkey without sorting them
+    accounts_by_owner: Arc<DashMap<Pubkey, BTreeSet<Pubkey>>>,
 }
 
 impl InmemoryAccountStore {
@@ -219,7 +226,7 @@ impl InmemoryAccountStore {
                 occ.get_mut().insert(account);
             }
             dashmap::mapref::entry::Entry::Vacant(vc) => {
-                let mut set = HashSet::new();
+                let mut set = BTreeSet::new();
                 set.insert(account);
                 vc.insert(set);
             }
@@ -357,6 +364,53 @@ impl AccountStorageInterface for InmemoryAccountStore {
         }
     }
 
+    async fn get_program_accounts_page(
+        &self,
+        program_pubkey: Pubkey,
+        account_filters: Option<Vec<RpcFilterType>>,
+        commitment: Commitment,
+        after: Option<Pubkey>,
+        limit: usize,
+    ) -> Option<Vec<AccountData>> {
+        let mut page = Vec::with_capacity(limit);
+        let mut after = after;
+        while page.len() < limit {
+            // the owner index is released before the accounts are read, update_account locks them the other way around
+            let pubkeys = {
+                let program_accounts = self.accounts_by_owner.get(&program_pubkey)?;
+                program_accounts
+                    .range((
+                        after.map_or(Bound::Unbounded, Bound::Excluded),
+                        Bound::Unbounded,
+                    ))
+                    .take(limit)
+                    .copied()
+                    .collect_vec()
+            };
+            let Some(last_pubkey) = pubkeys.last() else {
+                break;
+            };
+            after = Some(*last_pubkey);
+            let remaining = limit - page.len();
+            page.extend(
+                pubkeys
+                    .iter()
+                    .filter_map(|pubkey| {
+                        self.account_store.get(pubkey)?.get_account_data(commitment)
+                    })
+                    // recheck program owner and filters
+                    .filter(|account_data| {
+                        account_data.account.owner == program_pubkey
+                            && account_filters.as_ref().map_or(true, |filters| {
+                                filters.iter().all(|filter| account_data.allows(filter))
+                            })
+                    })
+                    .take(remaining),
+            );
+        }
+        Some(page)
+    }
+
     async fn process_slot_data(&self, slot: Slot, commitment: Commitment) -> Vec<AccountData> {
         match commitment {
             Commitment::Confirmed => {
@@ -464,6 +518,7 @@ mod tests {
                 rent_epoch: 0,
             }),
             updated_slot,
+            write_version: 0,
         }
     }
 
@@ -706,6 +761,52 @@ mod tests {
         );
     }
 
+    #[tokio::test]
+    pub async fn test_same_slot_updates_out_of_order() {
+        let store = InmemoryAccountStore::default();
+        let mut rng = rand::thread_rng();
+        let program = Pubkey::new_unique();
+        let pk1 = Pubkey::new_unique();
+
+        store
+            .initilize_or_update_account(create_random_account(&mut rng, 0, pk1, program))
+            .await;
+
+        let newer = AccountData {
+            write_version: 2,
+            ..create_random_account(&mut rng, 1, pk1, program)
+        };
+        let older = AccountData {
+            write_version: 1,
+            ..create_random_account(&mut rng, 1, pk1, program)
+        };
+        assert!(
+            store
+                .update_account(newer.clone(), Commitment::Processed)
+                .await
+        );
+        assert!(
+            !store
+                .update_account(older.clone(), Commitment::Processed)
+                .await
+        );
+        assert_eq!(
+            store.get_account(pk1, Commitment::Processed).await,
+            Ok(Some(newer.clone()))
+        );
+
+        assert!(
+            store
+                .update_account(newer.clone(), Commitment::Confirmed)
+                .await
+        );
+        assert!(!store.update_account(older, Commitment::Confirmed).await);
+        assert_eq!(
+            store.get_account(pk1, Commitment::Confirmed).await,
+            Ok(Some(newer))
+        );
+    }
+
     #[tokio::test]
     pub async fn test_get_program_account() {
         let store = InmemoryAccountStore::default();
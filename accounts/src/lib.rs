@@ -1,3 +1,5 @@
 pub mod account_service;
 pub mod account_store_interface;
 pub mod inmemory_account_store;
+pub mod program_accounts_page;
+pub mod token_account;
@@ -0,0 +1,152 @@
+use anyhow::{bail, Context};
+use serde::{Deserialize, Serialize};
+use solana_rpc_client_api::response::RpcKeyedAccount;
+use solana_sdk::{pubkey::Pubkey, slot_history::Slot};
+
+// upper bound of a page, keeps the jsonrpsee response bounded
+pub const MAX_PROGRAM_ACCOUNTS_PAGE_SIZE: usize = 10_000;
+
+// about a minute, a walk over a longer time mixes too many account updates into its pages
+pub const MAX_CURSOR_AGE_SLOTS: Slot = 150;
+
+/// Position in a paginated getProgramAccounts walk.
+/// Accounts are returned ordered by pubkey, the next page starts after `last_pubkey`.
+/// Every page is read from the live account store and is not a snapshot, its context is the slot it was read at.
+/// `first_page_slot` is the slot of the first page, the cursor expires `MAX_CURSOR_AGE_SLOTS` after it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ProgramAccountsCursor {
+    pub first_page_slot: Slot,
+    pub last_pubkey: Pubkey,
+}
+
+impl ProgramAccountsCursor {
+    /// opaque base58 string: slot (8 bytes LE) followed by the pubkey
+    pub fn encode(&self) -> String {
+        let mut bytes = Vec::with_capacity(8 + 32);
+        bytes.extend_from_slice(&self.first_page_slot.to_le_bytes());
+        bytes.extend_from_slice(self.last_pubkey.as_ref());
+        bs58::encode(bytes).into_string()
+    }
+
+    pub fn decode(cursor: &str) -> anyhow::Result<Self> {
+        let bytes = bs58::decode(cursor)
+            .into_vec()
+            .context("cursor is not base58")?;
+        if bytes.len() != 8 + 32 {
+            bail!("invalid cursor length {}", bytes.len());
+        }
+        let (slot_bytes, pubkey_bytes) = bytes.split_at(8);
+        Ok(Self {
+            first_page_slot: Slot::from_le_bytes(slot_bytes.try_into()?),
+            last_pubkey: Pubkey::try_from(pubkey_bytes)?,
+        })
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RpcProgramAccountsPage {
+    pub accounts: Vec<RpcKeyedAccount>,
+    /// none on the last page
+    pub cursor: Option<String>,
+}
+
+pub fn verify_page_limit(limit: usize) -> anyhow::Result<()> {
+    if limit == 0 || limit > MAX_PROGRAM_ACCOUNTS_PAGE_SIZE {
+        bail!("limit must be between 1 and {MAX_PROGRAM_ACCOUNTS_PAGE_SIZE}");
+    }
+    Ok(())
+}
+
+/// `accounts` are ordered by pubkey, an account beyond `limit` means there is a next page
+pub fn program_accounts_page(
+    mut accounts: Vec<(Pubkey, RpcKeyedAccount)>,
+    first_page_slot: Slot,
+    limit: usize,
+) -> RpcProgramAccountsPage {
+    let has_more = accounts.len() > limit;
+    accounts.truncate(limit);
+    let next_cursor = if has_more {
+        accounts.last().map(|(last_pubkey, _)| {
+            ProgramAccountsCursor {
+                first_page_slot,
+                last_pubkey: *last_pubkey,
+            }
+            .encode()
+        })
+    } else {
+        None
+    };
+
+    RpcProgramAccountsPage {
+        accounts: accounts.into_iter().map(|(_, account)| account).collect(),
+        cursor: next_cursor,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use solana_account_decoder::{UiAccount, UiAccountData, UiAccountEncoding};
+    use solana_rpc_client_api::response::RpcKeyedAccount;
+    use solana_sdk::pubkey::Pubkey;
+
+    use super::{
+        program_accounts_page, verify_page_limit, ProgramAccountsCursor,
+        MAX_PROGRAM_ACCOUNTS_PAGE_SIZE,
+    };
+
+    fn keyed_account(pubkey: Pubkey) -> RpcKeyedAccount {
+        RpcKeyedAccount {
+            pubkey: pubkey.to_string(),
+            account: UiAccount {
+                lamports: 1,
+                data: UiAccountData::Binary(String::new(), UiAccountEncoding::Base64),
+                owner: Pubkey::default().to_string(),
+                executable: false,
+                rent_epoch: 0,
+                space: Some(0),
+            },
+        }
+    }
+
+    #[test]
+    pub fn test_cursor_roundtrip() {
+        let cursor = ProgramAccountsCursor {
+            first_page_slot: 264_000_123,
+            last_pubkey: Pubkey::new_unique(),
+        };
+        assert_eq!(
+            ProgramAccountsCursor::decode(&cursor.encode()).unwrap(),
+            cursor
+        );
+        assert!(ProgramAccountsCursor::decode("not-a-cursor").is_err());
+    }
+
+    #[test]
+    pub fn test_page_cursor() {
+        let mut pubkeys = (0..3).map(|_| Pubkey::new_unique()).collect::<Vec<_>>();
+        pubkeys.sort();
+        let accounts = pubkeys
+            .iter()
+            .map(|pubkey| (*pubkey, keyed_account(*pubkey)))
+            .collect::<Vec<_>>();
+
+        let page = program_accounts_page(accounts.clone(), 100, 2);
+        assert_eq!(page.accounts.len(), 2);
+        assert_eq!(
+            ProgramAccountsCursor::decode(&page.cursor.unwrap()).unwrap(),
+            ProgramAccountsCursor {
+                first_page_slot: 100,
+                last_pubkey: pubkeys[1],
+            }
+        );
+
+        // last page
+        let page = program_accounts_page(accounts, 100, 3);
+        assert_eq!(page.accounts.len(), 3);
+        assert!(page.cursor.is_none());
+
+        assert!(verify_page_limit(0).is_err());
+        assert!(verify_page_limit(MAX_PROGRAM_ACCOUNTS_PAGE_SIZE + 1).is_err());
+    }
+}
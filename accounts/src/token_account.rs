@@ -0,0 +1,94 @@
+use solana_sdk::pubkey::Pubkey;
+
+// spl token program and token-2022, both share the base account and mint layout
+pub const SPL_TOKEN_PROGRAM_ID: Pubkey =
+    solana_sdk::pubkey!("TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA");
+pub const SPL_TOKEN_2022_PROGRAM_ID: Pubkey =
+    solana_sdk::pubkey!("TokenzQdBNbLqP5VEhdkAS6EPFLC1PWzLYNhn1uEYJ9a");
+
+// spl_token::state::Account: mint (32), owner (32), amount (8), ...
+pub const TOKEN_ACCOUNT_LEN: usize = 165;
+pub const TOKEN_ACCOUNT_MINT_OFFSET: usize = 0;
+pub const TOKEN_ACCOUNT_OWNER_OFFSET: usize = 32;
+const TOKEN_ACCOUNT_AMOUNT_OFFSET: usize = 64;
+const TOKEN_ACCOUNT_STATE_OFFSET: usize = 108;
+
+// spl_token::state::Mint: mint_authority (36), supply (8), decimals (1), is_initialized (1), ...
+pub const MINT_LEN: usize = 82;
+const MINT_DECIMALS_OFFSET: usize = 44;
+const MINT_IS_INITIALIZED_OFFSET: usize = 45;
+
+pub fn is_token_program(program_id: &Pubkey) -> bool {
+    *program_id == SPL_TOKEN_PROGRAM_ID || *program_id == SPL_TOKEN_2022_PROGRAM_ID
+}
+
+/// mint and amount of an initialized token account, token-2022 extensions are ignored
+pub fn parse_token_account(data: &[u8]) -> Option<(Pubkey, u64)> {
+    if data.len() < TOKEN_ACCOUNT_LEN {
+        return None;
+    }
+    // 0 = uninitialized
+    if data[TOKEN_ACCOUNT_STATE_OFFSET] == 0 {
+        return None;
+    }
+    let mint =
+        Pubkey::try_from(&data[TOKEN_ACCOUNT_MINT_OFFSET..TOKEN_ACCOUNT_MINT_OFFSET + 32]).ok()?;
+    let amount = u64::from_le_bytes(
+        data[TOKEN_ACCOUNT_AMOUNT_OFFSET..TOKEN_ACCOUNT_AMOUNT_OFFSET + 8]
+            .try_into()
+            .ok()?,
+    );
+    Some((mint, amount))
+}
+
+/// decimals of an initialized mint
+pub fn parse_mint_decimals(data: &[u8]) -> Option<u8> {
+    if data.len() < MINT_LEN || data[MINT_IS_INITIALIZED_OFFSET] == 0 {
+        return None;
+    }
+    Some(data[MINT_DECIMALS_OFFSET])
+}
+
+#[cfg(test)]
+pub mod test_utils {
+    use super::*;
+
+    pub fn token_account_data(mint: &Pubkey, owner: &Pubkey, amount: u64) -> Vec<u8> {
+        let mut data = vec![0; TOKEN_ACCOUNT_LEN];
+        data[TOKEN_ACCOUNT_MINT_OFFSET..TOKEN_ACCOUNT_MINT_OFFSET + 32]
+            .copy_from_slice(mint.as_ref());
+        data[TOKEN_ACCOUNT_OWNER_OFFSET..TOKEN_ACCOUNT_OWNER_OFFSET + 32]
+            .copy_from_slice(owner.as_ref());
+        data[TOKEN_ACCOUNT_AMOUNT_OFFSET..TOKEN_ACCOUNT_AMOUNT_OFFSET + 8]
+            .copy_from_slice(&amount.to_le_bytes());
+        // initialized
+        data[TOKEN_ACCOUNT_STATE_OFFSET] = 1;
+        data
+    }
+
+    pub fn mint_data(decimals: u8) -> Vec<u8> {
+        let mut data = vec![0; MINT_LEN];
+        data[MINT_DECIMALS_OFFSET] = decimals;
+        data[MINT_IS_INITIALIZED_OFFSET] = 1;
+        data
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::test_utils::{mint_data, token_account_data};
+    use super::*;
+
+    #[test]
+    pub fn test_parse_token_account() {
+        let mint = Pubkey::new_unique();
+        let data = token_account_data(&mint, &Pubkey::new_unique(), 1_500_000);
+        assert_eq!(parse_token_account(&data), Some((mint, 1_500_000)));
+        assert_eq!(parse_mint_decimals(&mint_data(6)), Some(6));
+
+        // too short or uninitialized
+        assert_eq!(parse_token_account(&data[..100]), None);
+        assert_eq!(parse_token_account(&[0; TOKEN_ACCOUNT_LEN]), None);
+        assert_eq!(parse_mint_decimals(&[0; MINT_LEN]), None);
+    }
+}
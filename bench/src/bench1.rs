@@ -1,23 +1,36 @@
 use crate::{create_memo_tx_large, create_memo_tx_small, generate_random_strings};
-use crate::{metrics::Metric, metrics::TxMetricData};
+use crate::{metrics::Metric, metrics::SharedMetric, metrics::TxMetricData};
 use dashmap::DashMap;
+use futures::StreamExt;
 use log::warn;
+use solana_pubsub_client::nonblocking::pubsub_client::PubsubClient;
 use solana_rpc_client::nonblocking::rpc_client::RpcClient;
+use solana_rpc_client::rpc_client::SerializableTransaction;
+use solana_rpc_client_api::config::RpcSignatureSubscribeConfig;
+use solana_sdk::commitment_config::CommitmentConfig;
 use solana_sdk::hash::Hash;
 use solana_sdk::signature::Keypair;
 use solana_sdk::signature::Signature;
 use solana_sdk::slot_history::Slot;
+use solana_transaction_status::{TransactionConfirmationStatus, TransactionStatus};
+use std::collections::HashMap;
 use std::sync::{
     atomic::{AtomicU64, Ordering},
     Arc,
 };
 use tokio::{
-    sync::{mpsc::UnboundedSender, RwLock},
+    sync::{
+        mpsc::{UnboundedReceiver, UnboundedSender},
+        oneshot, RwLock,
+    },
+    task::JoinHandle,
     time::{Duration, Instant},
 };
 
 #[derive(Clone, Debug, Copy)]
 struct TxSendData {
+    // sent during the warmup phase
+    warmup: bool,
     sent_duration: Duration,
     sent_instant: Instant,
     sent_slot: Slot,
@@ -26,8 +39,54 @@ struct TxSendData {
 
 struct ApiCallerResult {
     gross_send_time: Duration,
+    // time to send and size of the txs rejected by the rpc after the warmup
+    send_failures: Vec<(Duration, u64)>,
+    // time spent subscribing the signatures after the warmup, not counted as send time
+    subscribe_time: Duration,
+    subscriptions: Vec<JoinHandle<()>>,
 }
 
+/// with ws confirmation the transactions are signed with a fresh blockhash and subscribed per batch,
+/// subscribing all of them before sending would expire the blockhash of the last ones
+const WS_SUBSCRIBE_BATCH_SIZE: usize = 100;
+
+/// how the bench detects that a transaction got confirmed
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum ConfirmVia {
+    /// poll getSignatureStatuses
+    #[default]
+    Poll,
+    /// signatureSubscribe per transaction, confirmation time is taken from the push notification
+    Ws,
+}
+
+/// commitment a transaction must reach to count as confirmed
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum BenchCommitment {
+    #[default]
+    Confirmed,
+    Finalized,
+}
+
+impl BenchCommitment {
+    pub fn commitment_config(&self) -> CommitmentConfig {
+        match self {
+            BenchCommitment::Confirmed => CommitmentConfig::confirmed(),
+            BenchCommitment::Finalized => CommitmentConfig::finalized(),
+        }
+    }
+
+    pub fn confirmation_status(&self) -> TransactionConfirmationStatus {
+        match self {
+            BenchCommitment::Confirmed => TransactionConfirmationStatus::Confirmed,
+            BenchCommitment::Finalized => TransactionConfirmationStatus::Finalized,
+        }
+    }
+}
+
+/// confirmation pushed by a signature subscription: signature, time received, slot
+type WsConfirmation = (Signature, Instant, Slot);
+
 // called by benchrunner-service
 #[allow(clippy::too_many_arguments)]
 pub async fn bench(
@@ -41,59 +100,129 @@ pub async fn bench(
     log_txs: bool,
     transaction_size: TransactionSize,
     cu_price_micro_lamports: u64,
+    warmup: Duration,
+    // confirm via signatureSubscribe if set, else poll
+    pubsub_client: Option<Arc<PubsubClient>>,
+    commitment: BenchCommitment,
+    // updated with the in-flight metric while confirming
+    live_metric: Option<SharedMetric>,
 ) -> Metric {
     let map_of_txs: Arc<DashMap<Signature, TxSendData>> = Arc::new(DashMap::new());
+    let (ws_confirmation_sx, mut ws_confirmation_rx) =
+        tokio::sync::mpsc::unbounded_channel::<WsConfirmation>();
+    let n_chars = match transaction_size {
+        TransactionSize::Small => 10,
+        TransactionSize::Large => 232, // 565 is max but we need to lower that to not burn the CUs
+    };
+    let rand_strings = generate_random_strings(tx_count, Some(seed), n_chars);
+    let create_tx = move |rand_string: &[u8], blockhash: Hash| match transaction_size {
+        TransactionSize::Small => create_memo_tx_small(
+            rand_string,
+            &funded_payer,
+            blockhash,
+            cu_price_micro_lamports,
+        ),
+        TransactionSize::Large => create_memo_tx_large(
+            rand_string,
+            &funded_payer,
+            blockhash,
+            cu_price_micro_lamports,
+        ),
+    };
+
+    let bench_start_time = Instant::now();
+    let warmup_end = bench_start_time + warmup;
     // transaction sender task
     let api_caller_result = {
         let map_of_txs = map_of_txs.clone();
         let rpc_client = rpc_client.clone();
         let current_slot = current_slot.clone();
+        let pubsub_client = pubsub_client.clone();
+        let ws_confirmation_sx = ws_confirmation_sx.clone();
         tokio::spawn(async move {
             let map_of_txs = map_of_txs.clone();
-            let n_chars = match transaction_size {
-                TransactionSize::Small => 10,
-                TransactionSize::Large => 232, // 565 is max but we need to lower that to not burn the CUs
-            };
-            let rand_strings = generate_random_strings(tx_count, Some(seed), n_chars);
-
-            let bench_start_time = Instant::now();
-
-            for rand_string in &rand_strings {
-                let blockhash = { *block_hash.read().await };
-                let tx = match transaction_size {
-                    TransactionSize::Small => create_memo_tx_small(
-                        rand_string,
-                        &funded_payer,
-                        blockhash,
-                        cu_price_micro_lamports,
-                    ),
-                    TransactionSize::Large => create_memo_tx_large(
-                        rand_string,
-                        &funded_payer,
-                        blockhash,
-                        cu_price_micro_lamports,
-                    ),
-                };
-                let start_time = Instant::now();
-                match rpc_client.send_transaction(&tx).await {
-                    Ok(signature) => {
-                        map_of_txs.insert(
-                            signature,
-                            TxSendData {
-                                sent_duration: start_time.elapsed(),
-                                sent_instant: Instant::now(),
-                                sent_slot: current_slot.load(std::sync::atomic::Ordering::Relaxed),
-                                transaction_bytes: bincode::serialized_size(&tx).unwrap(),
-                            },
+
+            let mut measured_start_time = None;
+            let mut send_failures = vec![];
+            let mut subscribe_time = Duration::ZERO;
+            let mut subscriptions = vec![];
+
+            for batch in rand_strings.chunks(WS_SUBSCRIBE_BATCH_SIZE) {
+                // the batch is signed and subscribed before sending it,
+                // waiting for the subscriptions must not count as send time
+                let mut presigned_txs = match &pubsub_client {
+                    Some(pubsub_client) => {
+                        let subscribe_start = Instant::now();
+                        let blockhash = { *block_hash.read().await };
+                        let txs = batch
+                            .iter()
+                            .map(|rand_string| create_tx(rand_string, blockhash))
+                            .collect::<Vec<_>>();
+                        subscriptions.extend(
+                            futures::future::join_all(txs.iter().map(|tx| {
+                                subscribe_signature(
+                                    pubsub_client.clone(),
+                                    *tx.get_signature(),
+                                    commitment,
+                                    ws_confirmation_sx.clone(),
+                                )
+                            }))
+                            .await,
                         );
+                        if measured_start_time.is_some() {
+                            subscribe_time += subscribe_start.elapsed();
+                        }
+                        Some(txs.into_iter())
                     }
-                    Err(e) => {
-                        warn!("tx send failed with error {}", e);
+                    None => None,
+                };
+
+                for rand_string in batch {
+                    let tx = match presigned_txs.as_mut().and_then(|txs| txs.next()) {
+                        Some(tx) => tx,
+                        None => {
+                            let blockhash = { *block_hash.read().await };
+                            create_tx(rand_string, blockhash)
+                        }
+                    };
+                    let start_time = Instant::now();
+                    let is_warmup = start_time < warmup_end;
+                    if !is_warmup && measured_start_time.is_none() {
+                        measured_start_time = Some(start_time);
+                    }
+                    match rpc_client.send_transaction(&tx).await {
+                        Ok(signature) => {
+                            map_of_txs.insert(
+                                signature,
+                                TxSendData {
+                                    warmup: is_warmup,
+                                    sent_duration: start_time.elapsed(),
+                                    sent_instant: Instant::now(),
+                                    sent_slot: current_slot
+                                        .load(std::sync::atomic::Ordering::Relaxed),
+                                    transaction_bytes: bincode::serialized_size(&tx).unwrap(),
+                                },
+                            );
+                        }
+                        Err(e) => {
+                            warn!("tx send failed with error {}", e);
+                            if !is_warmup {
+                                send_failures.push((
+                                    start_time.elapsed(),
+                                    bincode::serialized_size(&tx).unwrap(),
+                                ));
+                            }
+                        }
                     }
                 }
             }
             ApiCallerResult {
-                gross_send_time: bench_start_time.elapsed(),
+                gross_send_time: measured_start_time
+                    .map(|start| start.elapsed().saturating_sub(subscribe_time))
+                    .unwrap_or_default(),
+                send_failures,
+                subscribe_time,
+                subscriptions,
             }
         })
     };
@@ -101,9 +230,42 @@ pub async fn bench(
     let mut metric = Metric::default();
     let confirmation_time = Instant::now();
     let mut confirmed_count = 0;
+    // ws notifications which arrived before the send call returned
+    let mut early_ws_confirmations: HashMap<Signature, (Instant, Slot)> = HashMap::new();
     while confirmation_time.elapsed() < Duration::from_secs(60)
         && !(map_of_txs.is_empty() && confirmed_count == tx_count)
     {
+        if let Some(live_metric) = &live_metric {
+            *live_metric.lock().unwrap() = metric.clone();
+        }
+        if pubsub_client.is_some() {
+            early_ws_confirmations.retain(|signature, (confirmed_at, confirmed_slot)| {
+                let recorded = record_confirmation(
+                    &mut metric,
+                    &map_of_txs,
+                    signature,
+                    *confirmed_at,
+                    *confirmed_slot,
+                    &tx_metric_sx,
+                    log_txs,
+                );
+                if recorded {
+                    confirmed_count += 1;
+                }
+                !recorded
+            });
+            confirmed_count += drain_ws_confirmations(
+                &mut ws_confirmation_rx,
+                &mut early_ws_confirmations,
+                &mut metric,
+                &map_of_txs,
+                &tx_metric_sx,
+                log_txs,
+            )
+            .await;
+            continue;
+        }
+
         let signatures = map_of_txs.iter().map(|x| *x.key()).collect::<Vec<_>>();
         if signatures.is_empty() {
             tokio::time::sleep(Duration::from_millis(1)).await;
@@ -111,29 +273,19 @@ pub async fn bench(
         }
 
         if let Ok(res) = rpc_client.get_signature_statuses(&signatures).await {
+            let confirmed_at = Instant::now();
             for (i, signature) in signatures.iter().enumerate() {
-                let tx_status = &res.value[i];
-                if tx_status.is_some() {
-                    let tx_data = map_of_txs.get(signature).unwrap();
-                    let time_to_confirm = tx_data.sent_instant.elapsed();
-                    let transaction_bytes = tx_data.transaction_bytes;
-                    metric.add_successful_transaction(
-                        tx_data.sent_duration,
-                        time_to_confirm,
-                        transaction_bytes,
-                    );
-
-                    if log_txs {
-                        let _ = tx_metric_sx.send(TxMetricData {
-                            signature: signature.to_string(),
-                            sent_slot: tx_data.sent_slot,
-                            confirmed_slot: current_slot.load(Ordering::Relaxed),
-                            time_to_send_in_millis: tx_data.sent_duration.as_millis() as u64,
-                            time_to_confirm_in_millis: time_to_confirm.as_millis() as u64,
-                        });
-                    }
-                    drop(tx_data);
-                    map_of_txs.remove(signature);
+                if reached_commitment(&res.value[i], commitment)
+                    && record_confirmation(
+                        &mut metric,
+                        &map_of_txs,
+                        signature,
+                        confirmed_at,
+                        current_slot.load(Ordering::Relaxed),
+                        &tx_metric_sx,
+                        log_txs,
+                    )
+                {
                     confirmed_count += 1;
                 }
             }
@@ -141,20 +293,162 @@ pub async fn bench(
     }
 
     for tx in map_of_txs.iter() {
-        metric.add_unsuccessful_transaction(tx.sent_duration, tx.transaction_bytes);
+        if tx.warmup {
+            metric.add_warmup_transaction();
+        } else {
+            metric.add_unsuccessful_transaction(tx.sent_duration, tx.transaction_bytes);
+        }
     }
 
     let api_caller_result = api_caller_result
         .await
         .expect("api caller task must succeed");
 
+    for (time_to_send, transaction_bytes) in api_caller_result.send_failures {
+        metric.add_send_failure(time_to_send, transaction_bytes);
+    }
     metric
         .set_total_gross_send_time(api_caller_result.gross_send_time.as_micros() as f64 / 1_000.0);
+    metric.set_total_elapsed_time(
+        Instant::now()
+            .saturating_duration_since(warmup_end)
+            .saturating_sub(api_caller_result.subscribe_time)
+            .as_micros() as f64
+            / 1_000.0,
+    );
+
+    // signatures which never got a notification
+    for subscription in api_caller_result.subscriptions {
+        subscription.abort();
+    }
 
     metric.finalize();
+    if let Some(live_metric) = &live_metric {
+        *live_metric.lock().unwrap() = metric.clone();
+    }
     metric
 }
 
+/// subscribes to the signature and returns once the subscription is active;
+/// the confirmation is pushed to `ws_confirmation_sx`, the returned task waits for it until aborted
+async fn subscribe_signature(
+    pubsub_client: Arc<PubsubClient>,
+    signature: Signature,
+    commitment: BenchCommitment,
+    ws_confirmation_sx: UnboundedSender<WsConfirmation>,
+) -> JoinHandle<()> {
+    let (subscribed_sx, subscribed_rx) = oneshot::channel();
+    let subscription = tokio::spawn(async move {
+        let config = RpcSignatureSubscribeConfig {
+            commitment: Some(commitment.commitment_config()),
+            enable_received_notification: Some(false),
+        };
+        match pubsub_client
+            .signature_subscribe(&signature, Some(config))
+            .await
+        {
+            Ok((mut stream, unsubscribe)) => {
+                let _ = subscribed_sx.send(());
+                if let Some(notification) = stream.next().await {
+                    let _ = ws_confirmation_sx.send((
+                        signature,
+                        Instant::now(),
+                        notification.context.slot,
+                    ));
+                }
+                unsubscribe().await;
+            }
+            Err(e) => {
+                warn!("signature subscribe failed with error {}", e);
+                let _ = subscribed_sx.send(());
+            }
+        }
+    });
+    let _ = subscribed_rx.await;
+    subscription
+}
+
+/// waits for the next ws confirmations, returns the number of recorded transactions
+async fn drain_ws_confirmations(
+    ws_confirmation_rx: &mut UnboundedReceiver<WsConfirmation>,
+    early_ws_confirmations: &mut HashMap<Signature, (Instant, Slot)>,
+    metric: &mut Metric,
+    map_of_txs: &DashMap<Signature, TxSendData>,
+    tx_metric_sx: &UnboundedSender<TxMetricData>,
+    log_txs: bool,
+) -> usize {
+    let mut recorded_count = 0;
+    let Ok(Some(first)) =
+        tokio::time::timeout(Duration::from_millis(10), ws_confirmation_rx.recv()).await
+    else {
+        return 0;
+    };
+    let mut next = Some(first);
+    while let Some((signature, confirmed_at, confirmed_slot)) = next {
+        if record_confirmation(
+            metric,
+            map_of_txs,
+            &signature,
+            confirmed_at,
+            confirmed_slot,
+            tx_metric_sx,
+            log_txs,
+        ) {
+            recorded_count += 1;
+        } else {
+            early_ws_confirmations.insert(signature, (confirmed_at, confirmed_slot));
+        }
+        next = ws_confirmation_rx.try_recv().ok();
+    }
+    recorded_count
+}
+
+/// true if the polled status of a transaction satisfies the bench commitment
+pub fn reached_commitment(
+    tx_status: &Option<TransactionStatus>,
+    commitment: BenchCommitment,
+) -> bool {
+    tx_status
+        .as_ref()
+        .is_some_and(|status| status.satisfies_commitment(commitment.commitment_config()))
+}
+
+/// returns false if the signature is not (yet) known
+fn record_confirmation(
+    metric: &mut Metric,
+    map_of_txs: &DashMap<Signature, TxSendData>,
+    signature: &Signature,
+    confirmed_at: Instant,
+    confirmed_slot: Slot,
+    tx_metric_sx: &UnboundedSender<TxMetricData>,
+    log_txs: bool,
+) -> bool {
+    let Some((_, tx_data)) = map_of_txs.remove(signature) else {
+        return false;
+    };
+    let time_to_confirm = confirmed_at.saturating_duration_since(tx_data.sent_instant);
+    if tx_data.warmup {
+        metric.add_warmup_transaction();
+    } else {
+        metric.add_successful_transaction(
+            tx_data.sent_duration,
+            time_to_confirm,
+            tx_data.transaction_bytes,
+        );
+    }
+
+    if log_txs {
+        let _ = tx_metric_sx.send(TxMetricData {
+            signature: signature.to_string(),
+            sent_slot: tx_data.sent_slot,
+            confirmed_slot,
+            time_to_send_in_millis: tx_data.sent_duration.as_millis() as u64,
+            time_to_confirm_in_millis: time_to_confirm.as_millis() as u64,
+        });
+    }
+    true
+}
+
 // see https://spl.solana.com/memo for sizing of transactions
 // As of v1.5.1, an unsigned instruction can support single-byte UTF-8 of up to 566 bytes.
 // An instruction with a simple memo of 32 bytes can support up to 12 signers.
@@ -165,3 +459,125 @@ pub enum TransactionSize {
     // 1186 bytes, 193175 CUs
     Large,
 }
+
+#[tokio::test]
+async fn test_ws_confirmation_time_from_notification() {
+    let map_of_txs = DashMap::new();
+    let sent_instant = Instant::now();
+    let signature = Signature::new_unique();
+    let (tx_metric_sx, mut tx_metric_rx) = tokio::sync::mpsc::unbounded_channel();
+    // mock subscription
+    let (ws_confirmation_sx, mut ws_confirmation_rx) = tokio::sync::mpsc::unbounded_channel();
+    let mut early_ws_confirmations = HashMap::new();
+    let mut metric = Metric::default();
+
+    // notification arrives before the send call returned
+    ws_confirmation_sx
+        .send((signature, sent_instant + Duration::from_millis(120), 42))
+        .unwrap();
+    let recorded = drain_ws_confirmations(
+        &mut ws_confirmation_rx,
+        &mut early_ws_confirmations,
+        &mut metric,
+        &map_of_txs,
+        &tx_metric_sx,
+        true,
+    )
+    .await;
+    assert_eq!(recorded, 0);
+    assert_eq!(early_ws_confirmations.len(), 1);
+
+    map_of_txs.insert(
+        signature,
+        TxSendData {
+            warmup: false,
+            sent_duration: Duration::from_millis(3),
+            sent_instant,
+            sent_slot: 40,
+            transaction_bytes: 233,
+        },
+    );
+    let (confirmed_at, confirmed_slot) = early_ws_confirmations.remove(&signature).unwrap();
+    assert!(record_confirmation(
+        &mut metric,
+        &map_of_txs,
+        &signature,
+        confirmed_at,
+        confirmed_slot,
+        &tx_metric_sx,
+        true,
+    ));
+    metric.finalize();
+
+    let tx_metric = tx_metric_rx.recv().await.unwrap();
+    assert_eq!(tx_metric.time_to_confirm_in_millis, 120);
+    assert_eq!(tx_metric.confirmed_slot, 42);
+    assert_eq!(metric.average_confirmation_time_ms, 120.0);
+    assert!(map_of_txs.is_empty());
+}
+
+#[test]
+fn test_commitment_gates_poll_confirmation() {
+    let map_of_txs = DashMap::new();
+    let signature = Signature::new_unique();
+    let (tx_metric_sx, _tx_metric_rx) = tokio::sync::mpsc::unbounded_channel();
+    let mut metric = Metric::default();
+    map_of_txs.insert(
+        signature,
+        TxSendData {
+            warmup: false,
+            sent_duration: Duration::from_millis(3),
+            sent_instant: Instant::now(),
+            sent_slot: 40,
+            transaction_bytes: 233,
+        },
+    );
+    let confirmed_status = Some(TransactionStatus {
+        slot: 42,
+        confirmations: Some(1),
+        status: Ok(()),
+        err: None,
+        confirmation_status: Some(
+            solana_transaction_status::TransactionConfirmationStatus::Confirmed,
+        ),
+    });
+
+    assert!(reached_commitment(
+        &confirmed_status,
+        BenchCommitment::Confirmed
+    ));
+    assert!(!reached_commitment(
+        &confirmed_status,
+        BenchCommitment::Finalized
+    ));
+    assert!(!reached_commitment(&None, BenchCommitment::Confirmed));
+
+    // with finalized commitment the confirmed status is not recorded yet
+    let poll = |metric: &mut Metric, status: &Option<TransactionStatus>| {
+        reached_commitment(status, BenchCommitment::Finalized)
+            && record_confirmation(
+                metric,
+                &map_of_txs,
+                &signature,
+                Instant::now(),
+                42,
+                &tx_metric_sx,
+                false,
+            )
+    };
+    assert!(!poll(&mut metric, &confirmed_status));
+    assert_eq!(metric.txs_confirmed, 0);
+    assert_eq!(map_of_txs.len(), 1);
+
+    let finalized_status = Some(TransactionStatus {
+        confirmations: None,
+        confirmation_status: Some(
+            solana_transaction_status::TransactionConfirmationStatus::Finalized,
+        ),
+        ..confirmed_status.unwrap()
+    });
+    assert!(poll(&mut metric, &finalized_status));
+    metric.finalize();
+    assert_eq!(metric.txs_confirmed, 1);
+    assert!(map_of_txs.is_empty());
+}
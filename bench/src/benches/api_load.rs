@@ -1,24 +1,22 @@
+use futures::future::join_all;
 use log::{info, warn};
-use std::{
-    path::Path,
-    sync::{
-        atomic::{AtomicUsize, Ordering},
-        Arc,
-    },
-};
+use std::{path::Path, sync::Arc, time::Duration};
 
 use solana_rpc_client::nonblocking::rpc_client::RpcClient;
 use solana_sdk::signature::{read_keypair_file, Keypair, Signer};
+use tokio::time::Instant;
 
 use crate::create_memo_tx_small;
+use crate::metrics::Metric;
 
 // TC3 measure how much load the API endpoint can take
+// confirmations are not tracked, txs are only counted as accepted or rejected by the rpc
 pub async fn api_load(
     payer_path: &Path,
     rpc_url: String,
     test_duration_ms: u64,
     cu_price_micro_lamports: u64,
-) -> anyhow::Result<()> {
+) -> anyhow::Result<Metric> {
     warn!("THIS IS WORK IN PROGRESS");
 
     let rpc = Arc::new(RpcClient::new(rpc_url));
@@ -28,37 +26,46 @@ pub async fn api_load(
     info!("Payer: {}", payer.pubkey().to_string());
 
     let mut txs = 0;
-    let failed = Arc::new(AtomicUsize::new(0));
-    let success = Arc::new(AtomicUsize::new(0));
+    let mut tasks = vec![];
 
     let hash = rpc.get_latest_blockhash().await?;
-    let time = tokio::time::Instant::now();
+    let time = Instant::now();
 
     while time.elapsed().as_millis() < test_duration_ms.into() {
         let rpc = rpc.clone();
         let payer = payer.clone();
 
-        let failed = failed.clone();
-        let success = success.clone();
-
         let msg = format!("tx: {txs}");
 
-        tokio::spawn(async move {
+        tasks.push(tokio::spawn(async move {
             let msg = msg.as_bytes();
             let tx = create_memo_tx_small(msg, &payer, hash, cu_price_micro_lamports);
-            match rpc.send_transaction(&tx).await {
-                Ok(_) => success.fetch_add(1, Ordering::Relaxed),
-                Err(_) => failed.fetch_add(1, Ordering::Relaxed),
-            };
-        });
+            let transaction_bytes = bincode::serialized_size(&tx).unwrap();
+            let start = Instant::now();
+            let result = rpc.send_transaction(&tx).await;
+            (result.is_ok(), start.elapsed(), transaction_bytes)
+        }));
 
         txs += 1;
     }
+    let gross_send_time = time.elapsed();
+
+    let mut metric = Metric::default();
+    for (success, time_to_send, transaction_bytes) in join_all(tasks).await.into_iter().flatten() {
+        if success {
+            metric.add_accepted_transaction(time_to_send, transaction_bytes);
+        } else {
+            metric.add_send_failure(time_to_send, transaction_bytes);
+        }
+    }
+    metric.set_total_gross_send_time(gross_send_time.as_secs_f64() * 1_000.0);
+    metric.set_total_elapsed_time(time.elapsed().as_secs_f64() * 1_000.0);
+    metric.finalize();
 
-    let calls_per_second = txs as f64 / (test_duration_ms as f64 * 1000.0);
+    let calls_per_second = txs as f64 / Duration::from_millis(test_duration_ms).as_secs_f64();
     info!("calls_per_second: {}", calls_per_second);
-    info!("failed: {}", failed.load(Ordering::Relaxed));
-    info!("success: {}", success.load(Ordering::Relaxed));
+    info!("failed: {}", metric.txs_send_failed);
+    info!("success: {}", metric.txs_accepted);
 
-    Ok(())
+    Ok(metric)
 }
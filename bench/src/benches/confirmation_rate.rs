@@ -1,3 +1,4 @@
+use crate::bench1::BenchCommitment;
 use crate::{create_rng, generate_txs, BenchmarkTransactionParams};
 use anyhow::Context;
 use log::{debug, info, trace, warn};
@@ -35,6 +36,7 @@ pub async fn confirmation_rate(
     max_timeout: Duration,
     txs_per_run: usize,
     num_of_runs: usize,
+    commitment: BenchCommitment,
 ) -> anyhow::Result<()> {
     warn!("THIS IS WORK IN PROGRESS");
 
@@ -60,6 +62,7 @@ pub async fn confirmation_rate(
             txs_per_run,
             &tx_params,
             max_timeout,
+            commitment,
         )
         .await
         .context("send bulk tx and wait")
@@ -91,6 +94,7 @@ pub async fn send_bulk_txs_and_wait(
     num_txs: usize,
     tx_params: &BenchmarkTransactionParams,
     max_timeout: Duration,
+    commitment: BenchCommitment,
 ) -> anyhow::Result<Metric> {
     trace!("Get latest blockhash and generate transactions");
     let hash = rpc.get_latest_blockhash().await.map_err(|err| {
@@ -108,6 +112,7 @@ pub async fn send_bulk_txs_and_wait(
             payer.pubkey(),
             &txs,
             max_timeout,
+            commitment,
         )
         .await
         .context("send and confirm bulk tx")?;
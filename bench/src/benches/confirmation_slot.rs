@@ -1,10 +1,11 @@
 use std::path::Path;
 use std::time::Duration;
 
+use crate::bench1::BenchCommitment;
 use crate::benches::rpc_interface::{
     create_rpc_client, send_and_confirm_bulk_transactions, ConfirmationResponseFromRpc,
 };
-use crate::metrics::PingThing;
+use crate::metrics::{PingThing, PingThingReport, PingThingTxType};
 use crate::{create_memo_tx, create_rng, BenchmarkTransactionParams, Rng8};
 use anyhow::anyhow;
 use log::{debug, info, warn};
@@ -17,6 +18,9 @@ use solana_sdk::{commitment_config::CommitmentConfig, signature::Keypair};
 use tokio::time::{sleep, Instant};
 use url::Url;
 
+/// confirmed transactions are reported to ping thing in batches of this size
+const PING_THING_BATCH_SIZE: usize = 50;
+
 #[derive(Clone, Copy, Debug, Default)]
 pub struct Metric {
     pub txs_sent: u64,
@@ -52,7 +56,8 @@ pub async fn confirmation_slot(
     tx_params: BenchmarkTransactionParams,
     max_timeout: Duration,
     num_of_runs: usize,
-    _maybe_ping_thing: Option<PingThing>,
+    maybe_ping_thing: Option<PingThing>,
+    commitment: BenchCommitment,
 ) -> anyhow::Result<()> {
     info!(
         "START BENCHMARK: confirmation_slot (prio_fees={})",
@@ -78,7 +83,7 @@ pub async fn confirmation_slot(
     let payer = read_keypair_file(payer_path).expect("payer file");
     let payer_pubkey = payer.pubkey();
     info!("Payer: {}", payer_pubkey.to_string());
-    // let mut ping_thing_tasks = vec![];
+    let mut ping_thing_reports = vec![];
 
     // FIXME
     // let (tx_status_map, jh_collector) = start_tx_status_collector(Url::parse(&tx_status_websocket_addr).unwrap(), payer.pubkey(), CommitmentConfig::confirmed()).await;
@@ -113,21 +118,40 @@ pub async fn confirmation_slot(
         let a_task = tokio::spawn(async move {
             sleep(Duration::from_secs_f64(a_delay)).await;
             debug!("(A) sending tx {}", rpc_a_tx.signatures[0]);
-            send_and_confirm_transaction(&rpc_a, ws_addr_a, payer_pubkey, rpc_a_tx, max_timeout)
-                .await
+            send_and_confirm_transaction(
+                &rpc_a,
+                ws_addr_a,
+                payer_pubkey,
+                rpc_a_tx,
+                max_timeout,
+                commitment,
+            )
+            .await
         });
 
         let b_task = tokio::spawn(async move {
             sleep(Duration::from_secs_f64(b_delay)).await;
             debug!("(B) sending tx {}", rpc_b_tx.signatures[0]);
-            send_and_confirm_transaction(&rpc_b, ws_addr_b, payer_pubkey, rpc_b_tx, max_timeout)
-                .await
+            send_and_confirm_transaction(
+                &rpc_b,
+                ws_addr_b,
+                payer_pubkey,
+                rpc_b_tx,
+                max_timeout,
+                commitment,
+            )
+            .await
         });
 
         let (a, b) = tokio::join!(a_task, b_task);
         // only continue if both paths suceed
-        let a_result: ConfirmationResponseFromRpc = a??;
-        let b_result: ConfirmationResponseFromRpc = b??;
+        let (a_sig, a_result) = a??;
+        let (b_sig, b_result) = b??;
+
+        if maybe_ping_thing.is_some() {
+            ping_thing_reports.extend(ping_thing_report(a_sig, &a_result));
+            ping_thing_reports.extend(ping_thing_report(b_sig, &b_result));
+        }
 
         if let (
             ConfirmationResponseFromRpc::Success(a_slot_sent, a_slot_confirmed, _, _),
@@ -144,23 +168,54 @@ pub async fn confirmation_slot(
             );
         }
 
-        // if let Some(ping_thing) = maybe_ping_thing.clone() {
-        //     ping_thing_tasks.push(tokio::spawn(async move {
-        //         submit_ping_thing_stats(&a_result, &ping_thing)
-        //             .await
-        //             .unwrap();
-        //         submit_ping_thing_stats(&b_result, &ping_thing)
-        //             .await
-        //             .unwrap();
-        //     }));
-        // };
+        if let Some(ping_thing) = &maybe_ping_thing {
+            if ping_thing_reports.len() >= PING_THING_BATCH_SIZE {
+                submit_ping_thing_reports(ping_thing, &mut ping_thing_reports).await;
+            }
+        }
     }
 
-    // futures::future::join_all(ping_thing_tasks).await;
+    if let Some(ping_thing) = &maybe_ping_thing {
+        submit_ping_thing_reports(ping_thing, &mut ping_thing_reports).await;
+    }
 
     Ok(())
 }
 
+fn ping_thing_report(
+    signature: Signature,
+    confirmation_response: &ConfirmationResponseFromRpc,
+) -> Option<PingThingReport> {
+    match confirmation_response {
+        ConfirmationResponseFromRpc::Success(slot_sent, slot_landed, _, confirmation_time) => {
+            Some(PingThingReport {
+                tx_elapsed: *confirmation_time,
+                tx_sig: signature,
+                tx_type: PingThingTxType::Memo,
+                tx_success: true,
+                slot_sent: *slot_sent,
+                slot_landed: *slot_landed,
+            })
+        }
+        ConfirmationResponseFromRpc::SendError(_) | ConfirmationResponseFromRpc::Timeout(_) => None,
+    }
+}
+
+/// reporting is best effort, a failed submission does not fail the benchmark
+async fn submit_ping_thing_reports(ping_thing: &PingThing, reports: &mut Vec<PingThingReport>) {
+    if reports.is_empty() {
+        return;
+    }
+    match ping_thing.submit_confirmed_stats_batch(reports).await {
+        Ok(sent) => debug!("reported {sent} of {} txs to ping thing", reports.len()),
+        Err(err) => warn!(
+            "failed to report {} txs to ping thing: {err}",
+            reports.len()
+        ),
+    }
+    reports.clear();
+}
+
 async fn create_tx(
     rpc: &RpcClient,
     payer: &Keypair,
@@ -180,7 +235,8 @@ async fn send_and_confirm_transaction(
     payer_pubkey: Pubkey,
     tx: VersionedTransaction,
     max_timeout: Duration,
-) -> anyhow::Result<ConfirmationResponseFromRpc> {
+    commitment: BenchCommitment,
+) -> anyhow::Result<(Signature, ConfirmationResponseFromRpc)> {
     let result_vec: Vec<(Signature, ConfirmationResponseFromRpc)> =
         send_and_confirm_bulk_transactions(
             rpc,
@@ -188,12 +244,11 @@ async fn send_and_confirm_transaction(
             payer_pubkey,
             &[tx],
             max_timeout,
+            commitment,
         )
         .await?;
     assert_eq!(result_vec.len(), 1, "expected 1 result");
-    let (_sig, confirmation_response) = result_vec.into_iter().next().unwrap();
-
-    Ok(confirmation_response)
+    Ok(result_vec.into_iter().next().unwrap())
 }
 
 pub async fn rpc_roundtrip_duration(rpc: &RpcClient) -> anyhow::Result<Duration> {
@@ -202,24 +257,3 @@ pub async fn rpc_roundtrip_duration(rpc: &RpcClient) -> anyhow::Result<Duration>
     let duration = started_at.elapsed();
     Ok(duration)
 }
-
-// async fn submit_ping_thing_stats(
-//     confirmation_info: &ConfirmationSlotResult,
-//     ping_thing: &PingThing,
-// ) -> anyhow::Result<()> {
-//     match confirmation_info.result {
-//         ConfirmationSlotResult::Timeout(_) => Ok(()),
-//         ConfirmationSlotResult::Success(slot_landed) => {
-//             ping_thing
-//                 .submit_confirmed_stats(
-//                     confirmation_info.confirmation_time,
-//                     confirmation_info.signature,
-//                     PingThingTxType::Memo,
-//                     true,
-//                     confirmation_info.slot_sent,
-//                     slot_landed,
-//                 )
-//                 .await
-//         }
-//     }
-// }
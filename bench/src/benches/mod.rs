@@ -1,5 +1,7 @@
 pub mod api_load;
 pub mod confirmation_rate;
 pub mod confirmation_slot;
+pub mod priofee_escalation;
 pub mod rpc_interface;
+pub mod tps_ramp;
 mod tx_status_websocket_collector;
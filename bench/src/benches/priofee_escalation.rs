@@ -0,0 +1,196 @@
+use crate::bench1::BenchCommitment;
+use crate::benches::confirmation_rate::{send_bulk_txs_and_wait, Metric};
+use crate::output::CsvOptions;
+use crate::tx_size::TxSize;
+use crate::BenchmarkTransactionParams;
+use anyhow::Context;
+use log::{info, warn};
+use std::future::Future;
+use std::num::NonZeroUsize;
+use std::path::Path;
+use std::sync::Arc;
+use std::time::Duration;
+
+use solana_lite_rpc_util::obfuscate_rpcurl;
+use solana_rpc_client::nonblocking::rpc_client::RpcClient;
+use solana_sdk::signature::{read_keypair_file, Keypair, Signer};
+use url::Url;
+
+/// Fee tiers to walk through: `num_of_tiers` tiers starting at `start_cu_price`, each `cu_price_step` higher
+#[derive(Clone, Copy, Debug)]
+pub struct FeeEscalation {
+    pub start_cu_price: u64,
+    pub cu_price_step: u64,
+    pub num_of_tiers: NonZeroUsize,
+    pub txs_per_tier: usize,
+}
+
+impl FeeEscalation {
+    pub fn tier_prices(&self) -> Vec<u64> {
+        (0..self.num_of_tiers.get() as u64)
+            .map(|tier| {
+                self.start_cu_price
+                    .saturating_add(self.cu_price_step.saturating_mul(tier))
+            })
+            .collect()
+    }
+}
+
+/// one csv row per fee tier
+#[derive(Clone, Copy, Debug, Default, serde::Serialize)]
+pub struct FeeTierMetric {
+    pub run: usize,
+    pub cu_price_micro_lamports: u64,
+    pub txs_sent: u64,
+    pub txs_confirmed: u64,
+    pub txs_send_errors: u64,
+    pub txs_un_confirmed: u64,
+    pub confirmation_rate: f32,
+    // in ms
+    pub average_confirmation_time: f32,
+    // in slots
+    pub average_slot_confirmation_time: f32,
+}
+
+impl FeeTierMetric {
+    pub fn new(cu_price_micro_lamports: u64, metric: &Metric) -> Self {
+        let confirmation_rate = if metric.txs_sent > 0 {
+            metric.txs_confirmed as f32 / metric.txs_sent as f32
+        } else {
+            0.0
+        };
+        Self {
+            run: 0,
+            cu_price_micro_lamports,
+            txs_sent: metric.txs_sent,
+            txs_confirmed: metric.txs_confirmed,
+            txs_send_errors: metric.txs_send_errors,
+            txs_un_confirmed: metric.txs_un_confirmed,
+            confirmation_rate,
+            average_confirmation_time: metric.average_confirmation_time,
+            average_slot_confirmation_time: metric.average_slot_confirmation_time,
+        }
+    }
+}
+
+/// TC5 send txs with a progressively increasing CU price, measure the confirmation rate per fee tier.
+/// The escalation is repeated `runs` times, the rows of each run are written one after the other.
+#[allow(clippy::too_many_arguments)]
+pub async fn priofee_escalation(
+    payer_path: &Path,
+    rpc_url: String,
+    tx_status_websocket_addr: Option<String>,
+    tx_size: TxSize,
+    max_timeout: Duration,
+    escalation: FeeEscalation,
+    runs: NonZeroUsize,
+    metrics_file_name: &Path,
+    csv_options: &CsvOptions,
+) -> anyhow::Result<()> {
+    let rpc = Arc::new(RpcClient::new(rpc_url.clone()));
+    info!("RPC: {}", obfuscate_rpcurl(&rpc.as_ref().url()));
+
+    let ws_addr = tx_status_websocket_addr
+        .unwrap_or_else(|| rpc_url.replace("http:", "ws:").replace("https:", "wss:"));
+    info!("WS ADDR: {}", obfuscate_rpcurl(&ws_addr));
+    let ws_addr = Url::parse(&ws_addr).context("invalid websocket url")?;
+
+    let payer: Arc<Keypair> = Arc::new(read_keypair_file(payer_path).unwrap());
+    info!("Payer: {}", payer.pubkey().to_string());
+
+    let mut rows = Vec::with_capacity(runs.get() * escalation.num_of_tiers.get());
+    for run in 0..runs.get() {
+        let run_rows = run_fee_tiers(&escalation, |cu_price_micro_lamports| {
+            let rpc = rpc.clone();
+            let payer = payer.clone();
+            let ws_addr = ws_addr.clone();
+            async move {
+                let tx_params = BenchmarkTransactionParams {
+                    tx_size,
+                    cu_price_micro_lamports,
+                };
+                send_bulk_txs_and_wait(
+                    &rpc,
+                    ws_addr,
+                    &payer,
+                    escalation.txs_per_tier,
+                    &tx_params,
+                    max_timeout,
+                    BenchCommitment::Confirmed,
+                )
+                .await
+            }
+        })
+        .await;
+        rows.extend(run_rows.into_iter().map(|row| FeeTierMetric { run, ..row }));
+    }
+
+    csv_options.write_rows_atomically(metrics_file_name, rows)?;
+
+    Ok(())
+}
+
+/// runs each fee tier in ascending price order; tiers that fail are logged and left out
+pub async fn run_fee_tiers<F, Fut>(
+    escalation: &FeeEscalation,
+    mut run_tier: F,
+) -> Vec<FeeTierMetric>
+where
+    F: FnMut(u64) -> Fut,
+    Fut: Future<Output = anyhow::Result<Metric>>,
+{
+    let mut rows = Vec::with_capacity(escalation.num_of_tiers.get());
+    for cu_price in escalation.tier_prices() {
+        match run_tier(cu_price).await {
+            Ok(metric) => {
+                let row = FeeTierMetric::new(cu_price, &metric);
+                info!("fee tier {cu_price}: {row:?}");
+                rows.push(row);
+            }
+            Err(err) => {
+                warn!("Failed to run fee tier {cu_price} - no stats available: {err}");
+            }
+        }
+    }
+    rows
+}
+
+#[tokio::test]
+async fn test_run_fee_tiers_one_row_per_tier() {
+    let escalation = FeeEscalation {
+        start_cu_price: 100,
+        cu_price_step: 50,
+        num_of_tiers: NonZeroUsize::new(4).unwrap(),
+        txs_per_tier: 10,
+    };
+
+    // mock sender: confirmation improves with the price
+    let rows = run_fee_tiers(&escalation, |cu_price| async move {
+        let txs_confirmed = (cu_price / 50).min(10);
+        Ok(Metric {
+            txs_sent: 10,
+            txs_confirmed,
+            txs_un_confirmed: 10 - txs_confirmed,
+            ..Default::default()
+        })
+    })
+    .await;
+
+    assert_eq!(rows.len(), 4);
+    assert_eq!(
+        rows.iter()
+            .map(|r| r.cu_price_micro_lamports)
+            .collect::<Vec<_>>(),
+        vec![100, 150, 200, 250]
+    );
+    assert_eq!(rows[0].confirmation_rate, 0.2);
+    assert_eq!(rows[3].confirmation_rate, 0.5);
+
+    let mut csv_writer = csv::Writer::from_writer(vec![]);
+    for row in rows {
+        csv_writer.serialize(row).unwrap();
+    }
+    let csv = String::from_utf8(csv_writer.into_inner().unwrap()).unwrap();
+    // header + one line per tier
+    assert_eq!(csv.lines().count(), 5);
+}
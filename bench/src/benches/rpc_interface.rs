@@ -1,3 +1,4 @@
+use crate::bench1::BenchCommitment;
 use crate::benches::tx_status_websocket_collector::start_tx_status_collector;
 use anyhow::{bail, Context, Error};
 
@@ -34,7 +35,7 @@ pub enum ConfirmationResponseFromRpc {
     // RPC error on send_transaction
     SendError(Arc<ErrorKind>),
     // (sent slot at confirmed commitment, confirmed slot, ..., ...)
-    // transaction_confirmation_status is "confirmed" (finalized is not reported by blockSubscribe websocket
+    // transaction_confirmation_status is the commitment of the blockSubscribe websocket
     Success(Slot, Slot, TransactionConfirmationStatus, Duration),
     // timout waiting for confirmation status
     Timeout(Duration),
@@ -46,6 +47,7 @@ pub async fn send_and_confirm_bulk_transactions(
     payer_pubkey: Pubkey,
     txs: &[VersionedTransaction],
     max_timeout: Duration,
+    commitment: BenchCommitment,
 ) -> anyhow::Result<Vec<(Signature, ConfirmationResponseFromRpc)>> {
     trace!("Polling for next slot ..");
     let send_slot = poll_next_slot_start(rpc_client)
@@ -61,11 +63,10 @@ pub async fn send_and_confirm_bulk_transactions(
         min_context_slot: None,
     };
 
-    // note: we get confirmed but never finaliized
     let (tx_status_map, _jh_collector) = start_tx_status_collector(
         tx_status_websocket_addr.clone(),
         payer_pubkey,
-        CommitmentConfig::confirmed(),
+        commitment.commitment_config(),
     )
     .await;
 
@@ -179,8 +180,7 @@ pub async fn send_and_confirm_bulk_transactions(
                     ConfirmationResponseFromRpc::Success(
                         send_slot,
                         *confirmed_slot,
-                        // note: this is not optimal as we do not cover finalized here
-                        TransactionConfirmationStatus::Confirmed,
+                        commitment.confirmation_status(),
                         elapsed,
                     ),
                 );
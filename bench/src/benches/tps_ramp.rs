@@ -0,0 +1,443 @@
+use crate::bench1::{reached_commitment, BenchCommitment};
+use crate::create_memo_tx_small;
+use crate::metrics::Metric;
+use crate::output::CsvOptions;
+use futures::stream::{FuturesUnordered, StreamExt};
+use futures::FutureExt;
+use log::{info, warn};
+use solana_lite_rpc_util::obfuscate_rpcurl;
+use solana_rpc_client::nonblocking::rpc_client::RpcClient;
+use solana_sdk::hash::Hash;
+use solana_sdk::signature::{read_keypair_file, Keypair, Signer};
+use std::future::Future;
+use std::path::Path;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::RwLock;
+use tokio::time::Instant;
+
+/// Linear ramp of the send rate from `start_tps` to `target_tps` in `num_of_steps` steps of equal duration
+#[derive(Clone, Copy, Debug)]
+pub struct TpsRamp {
+    pub start_tps: f64,
+    pub target_tps: f64,
+    pub duration: Duration,
+    pub num_of_steps: usize,
+}
+
+impl TpsRamp {
+    pub fn step_tps(&self) -> Vec<f64> {
+        if self.num_of_steps <= 1 {
+            return vec![self.start_tps; self.num_of_steps];
+        }
+        let increment = (self.target_tps - self.start_tps) / (self.num_of_steps - 1) as f64;
+        (0..self.num_of_steps)
+            .map(|step| self.start_tps + increment * step as f64)
+            .collect()
+    }
+
+    pub fn step_duration(&self) -> Duration {
+        self.duration / self.num_of_steps.max(1) as u32
+    }
+}
+
+/// Token bucket pacing, refilled continuously at `rate` tokens per second.
+/// Bursts are limited to `capacity` tokens so a stalled sender does not fire a batch afterwards.
+#[derive(Debug)]
+pub struct TokenBucket {
+    rate: f64,
+    capacity: f64,
+    tokens: f64,
+    last_refill: Instant,
+}
+
+impl TokenBucket {
+    pub fn new(rate: f64, capacity: f64, now: Instant) -> Self {
+        Self {
+            rate,
+            capacity: capacity.max(1.0),
+            tokens: 0.0,
+            last_refill: now,
+        }
+    }
+
+    pub fn set_rate(&mut self, rate: f64, now: Instant) {
+        self.refill(now);
+        self.rate = rate;
+    }
+
+    fn refill(&mut self, now: Instant) {
+        let elapsed = now
+            .saturating_duration_since(self.last_refill)
+            .as_secs_f64();
+        self.tokens = (self.tokens + elapsed * self.rate).min(self.capacity);
+        self.last_refill = now;
+    }
+
+    /// takes one token or returns how long to wait until one is available
+    pub fn try_acquire(&mut self, now: Instant) -> Result<(), Duration> {
+        self.refill(now);
+        if self.tokens >= 1.0 {
+            self.tokens -= 1.0;
+            return Ok(());
+        }
+        if self.rate <= 0.0 {
+            return Err(Duration::MAX);
+        }
+        Err(Duration::from_secs_f64((1.0 - self.tokens) / self.rate))
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+pub enum RampTxOutcome {
+    Confirmed { time_to_confirm: Duration },
+    Unconfirmed,
+    SendFailed,
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct RampTxResult {
+    pub time_to_send: Duration,
+    pub transaction_bytes: u64,
+    pub outcome: RampTxOutcome,
+}
+
+/// one csv row per ramp step
+#[derive(Clone, Copy, Debug, Default, serde::Serialize)]
+pub struct TpsRampStepMetric {
+    pub run: usize,
+    pub step: usize,
+    pub target_tps: f64,
+    pub send_tps: f64,
+    pub confirmed_tps: f64,
+    pub txs_sent: u64,
+    pub txs_confirmed: u64,
+    pub txs_un_confirmed: u64,
+    pub txs_send_failed: u64,
+    pub confirmation_rate: f64,
+    pub average_confirmation_time_ms: f64,
+    pub p90_confirmation_time_ms: f64,
+}
+
+impl TpsRampStepMetric {
+    pub fn new(step: usize, target_tps: f64, metric: &Metric) -> Self {
+        Self {
+            run: 0,
+            step,
+            target_tps,
+            send_tps: metric.send_tps,
+            confirmed_tps: metric.confirmed_tps,
+            txs_sent: metric.txs_sent,
+            txs_confirmed: metric.txs_confirmed,
+            txs_un_confirmed: metric.txs_un_confirmed,
+            txs_send_failed: metric.txs_send_failed,
+            confirmation_rate: metric.confirmation_rate,
+            average_confirmation_time_ms: metric.average_confirmation_time_ms,
+            p90_confirmation_time_ms: metric.p90_confirmation_time_ms,
+        }
+    }
+}
+
+/// walks through the ramp steps, `send_tx` is called with a running tx index at the paced rate.
+/// The next step starts sending right away, txs still in flight are accounted to the step they were sent in.
+pub async fn run_tps_ramp<F, Fut>(ramp: &TpsRamp, mut send_tx: F) -> Vec<TpsRampStepMetric>
+where
+    F: FnMut(u64) -> Fut,
+    Fut: Future<Output = RampTxResult>,
+{
+    let step_duration = ramp.step_duration();
+    let step_tps = ramp.step_tps();
+    let mut metrics = vec![Metric::default(); step_tps.len()];
+    // start, end of sending and completion of the last tx of each step
+    let mut step_times = Vec::with_capacity(step_tps.len());
+    let mut completed_at = vec![None; step_tps.len()];
+    let mut tx_index = 0;
+    let mut bucket = TokenBucket::new(ramp.start_tps, 1.0, Instant::now());
+    let mut in_flight = FuturesUnordered::new();
+
+    for (step, target_tps) in step_tps.iter().copied().enumerate() {
+        let step_start = Instant::now();
+        let step_end = step_start + step_duration;
+        // allow bursts of up to 10ms of sends to compensate for timer granularity
+        bucket.capacity = (target_tps / 100.0).max(1.0);
+        bucket.set_rate(target_tps, step_start);
+
+        loop {
+            let now = Instant::now();
+            if now >= step_end {
+                break;
+            }
+            let next_send = match bucket.try_acquire(now) {
+                Ok(()) => {
+                    in_flight.push(send_tx(tx_index).map(move |result| (step, result)));
+                    tx_index += 1;
+                    continue;
+                }
+                Err(wait) => now.checked_add(wait).unwrap_or(step_end).min(step_end),
+            };
+            tokio::select! {
+                _ = tokio::time::sleep_until(next_send) => {}
+                Some((tx_step, result)) = in_flight.next(), if !in_flight.is_empty() => {
+                    record_result(&mut metrics[tx_step], result);
+                    completed_at[tx_step] = Some(Instant::now());
+                }
+            }
+        }
+        step_times.push((step_start, Instant::now()));
+    }
+    while let Some((tx_step, result)) = in_flight.next().await {
+        record_result(&mut metrics[tx_step], result);
+        completed_at[tx_step] = Some(Instant::now());
+    }
+
+    let mut rows = Vec::with_capacity(step_tps.len());
+    for (step, (mut metric, target_tps)) in metrics.into_iter().zip(step_tps).enumerate() {
+        let (step_start, step_end) = step_times[step];
+        let step_completed_at = completed_at[step].unwrap_or(step_end).max(step_end);
+        metric.set_total_gross_send_time((step_end - step_start).as_secs_f64() * 1_000.0);
+        metric.set_total_elapsed_time((step_completed_at - step_start).as_secs_f64() * 1_000.0);
+        metric.finalize();
+        let row = TpsRampStepMetric::new(step, target_tps, &metric);
+        info!("ramp step {step} at {target_tps:.1} tps: {row:?}");
+        rows.push(row);
+    }
+    rows
+}
+
+fn record_result(metric: &mut Metric, result: RampTxResult) {
+    match result.outcome {
+        RampTxOutcome::Confirmed { time_to_confirm } => metric.add_successful_transaction(
+            result.time_to_send,
+            time_to_confirm,
+            result.transaction_bytes,
+        ),
+        RampTxOutcome::Unconfirmed => {
+            metric.add_unsuccessful_transaction(result.time_to_send, result.transaction_bytes)
+        }
+        RampTxOutcome::SendFailed => {
+            metric.add_send_failure(result.time_to_send, result.transaction_bytes)
+        }
+    }
+}
+
+/// TC6 ramp the send rate up to find the rate where the confirmation rate collapses.
+/// The ramp is repeated `runs` times, the rows of each run are written one after the other.
+#[allow(clippy::too_many_arguments)]
+pub async fn tps_ramp(
+    payer_path: &Path,
+    rpc_url: String,
+    ramp: TpsRamp,
+    cu_price_micro_lamports: u64,
+    max_timeout: Duration,
+    commitment: BenchCommitment,
+    runs: usize,
+    metrics_file_name: &Path,
+    csv_options: &CsvOptions,
+) -> anyhow::Result<()> {
+    let rpc = Arc::new(RpcClient::new(rpc_url));
+    info!("RPC: {}", obfuscate_rpcurl(&rpc.as_ref().url()));
+
+    let payer: Arc<Keypair> = Arc::new(read_keypair_file(payer_path).unwrap());
+    info!("Payer: {}", payer.pubkey().to_string());
+
+    let block_hash: Arc<RwLock<Hash>> = Arc::new(RwLock::new(rpc.get_latest_blockhash().await?));
+    let block_hash_updater = {
+        let rpc = rpc.clone();
+        let block_hash = block_hash.clone();
+        tokio::spawn(async move {
+            loop {
+                tokio::time::sleep(Duration::from_secs(2)).await;
+                match rpc.get_latest_blockhash().await {
+                    Ok(bh) => *block_hash.write().await = bh,
+                    Err(err) => warn!("blockhash update error {err}"),
+                }
+            }
+        })
+    };
+
+    let mut rows = Vec::with_capacity(runs * ramp.num_of_steps);
+    for run in 0..runs {
+        let run_rows = run_tps_ramp(&ramp, |tx_index| {
+            let rpc = rpc.clone();
+            let payer = payer.clone();
+            let block_hash = block_hash.clone();
+            async move {
+                let blockhash = *block_hash.read().await;
+                let msg = format!("tps ramp run {run} tx: {tx_index}");
+                let tx = create_memo_tx_small(
+                    msg.as_bytes(),
+                    &payer,
+                    blockhash,
+                    cu_price_micro_lamports,
+                );
+                send_and_poll_confirmation(&rpc, tx, max_timeout, commitment).await
+            }
+        })
+        .await;
+        rows.extend(
+            run_rows
+                .into_iter()
+                .map(|row| TpsRampStepMetric { run, ..row }),
+        );
+    }
+    block_hash_updater.abort();
+
+    csv_options.write_rows_atomically(metrics_file_name, rows)?;
+
+    Ok(())
+}
+
+async fn send_and_poll_confirmation(
+    rpc: &RpcClient,
+    tx: solana_sdk::transaction::VersionedTransaction,
+    max_timeout: Duration,
+    commitment: BenchCommitment,
+) -> RampTxResult {
+    let transaction_bytes = bincode::serialized_size(&tx).unwrap();
+    let start = Instant::now();
+    let signature = match rpc.send_transaction(&tx).await {
+        Ok(signature) => signature,
+        Err(err) => {
+            warn!("tx send failed with error {err}");
+            return RampTxResult {
+                time_to_send: start.elapsed(),
+                transaction_bytes,
+                outcome: RampTxOutcome::SendFailed,
+            };
+        }
+    };
+    let time_to_send = start.elapsed();
+    let sent_at = Instant::now();
+
+    let mut outcome = RampTxOutcome::Unconfirmed;
+    while sent_at.elapsed() < max_timeout {
+        tokio::time::sleep(Duration::from_millis(200)).await;
+        if let Ok(res) = rpc.get_signature_statuses(&[signature]).await {
+            if reached_commitment(&res.value[0], commitment) {
+                outcome = RampTxOutcome::Confirmed {
+                    time_to_confirm: sent_at.elapsed(),
+                };
+                break;
+            }
+        }
+    }
+
+    RampTxResult {
+        time_to_send,
+        transaction_bytes,
+        outcome,
+    }
+}
+
+#[tokio::test(start_paused = true)]
+async fn test_tps_ramp_follows_schedule() {
+    let ramp = TpsRamp {
+        start_tps: 100.0,
+        target_tps: 400.0,
+        duration: Duration::from_secs(8),
+        num_of_steps: 4,
+    };
+    assert_eq!(ramp.step_tps(), vec![100.0, 200.0, 300.0, 400.0]);
+
+    let send_times = Arc::new(std::sync::Mutex::new(vec![]));
+    let rows = run_tps_ramp(&ramp, |tx_index| {
+        let send_times = send_times.clone();
+        async move {
+            send_times.lock().unwrap().push(Instant::now());
+            // mock sender: every 10th tx is rejected, confirmation takes 500ms
+            if tx_index % 10 == 0 {
+                return RampTxResult {
+                    time_to_send: Duration::from_millis(1),
+                    transaction_bytes: 200,
+                    outcome: RampTxOutcome::SendFailed,
+                };
+            }
+            tokio::time::sleep(Duration::from_millis(500)).await;
+            RampTxResult {
+                time_to_send: Duration::from_millis(1),
+                transaction_bytes: 200,
+                outcome: RampTxOutcome::Confirmed {
+                    time_to_confirm: Duration::from_millis(500),
+                },
+            }
+        }
+    })
+    .await;
+
+    assert_eq!(rows.len(), 4);
+    let send_times = send_times.lock().unwrap();
+    assert_eq!(
+        send_times.len() as u64,
+        rows.iter().map(|r| r.txs_sent).sum::<u64>()
+    );
+    let mut step_sends = send_times.as_slice();
+    for row in &rows {
+        let expected = row.target_tps * ramp.step_duration().as_secs_f64();
+        assert!(
+            (row.txs_sent as f64 - expected).abs() <= expected * 0.05,
+            "step {}: sent {}, expected {expected}",
+            row.step,
+            row.txs_sent
+        );
+        // rate between the first and the last send of the step
+        let (sends, rest) = step_sends.split_at(row.txs_sent as usize);
+        step_sends = rest;
+        let span = sends.last().unwrap().duration_since(sends[0]).as_secs_f64();
+        let emitted_tps = (sends.len() - 1) as f64 / span;
+        assert!(
+            (emitted_tps - row.target_tps).abs() <= row.target_tps * 0.05,
+            "step {}: emitted {emitted_tps} tps",
+            row.step
+        );
+        assert!((row.send_tps - row.target_tps).abs() <= row.target_tps * 0.05);
+        assert!((row.txs_send_failed as f64 - row.txs_sent as f64 / 10.0).abs() <= 1.0);
+        // rejected txs are not part of the confirmation rate
+        assert_eq!(row.confirmation_rate, 1.0);
+    }
+
+    let mut csv_writer = csv::Writer::from_writer(vec![]);
+    for row in rows {
+        csv_writer.serialize(row).unwrap();
+    }
+    let csv = String::from_utf8(csv_writer.into_inner().unwrap()).unwrap();
+    // header + one line per step
+    assert_eq!(csv.lines().count(), 5);
+}
+
+#[tokio::test(start_paused = true)]
+async fn test_tps_ramp_does_not_wait_for_confirmations() {
+    let ramp = TpsRamp {
+        start_tps: 10.0,
+        target_tps: 20.0,
+        duration: Duration::from_secs(4),
+        num_of_steps: 2,
+    };
+
+    let started_at = Instant::now();
+    let send_times = Arc::new(std::sync::Mutex::new(vec![]));
+    let rows = run_tps_ramp(&ramp, |_| {
+        let send_times = send_times.clone();
+        async move {
+            send_times.lock().unwrap().push(Instant::now());
+            // confirmations take longer than a step
+            tokio::time::sleep(Duration::from_secs(10)).await;
+            RampTxResult {
+                time_to_send: Duration::from_millis(1),
+                transaction_bytes: 200,
+                outcome: RampTxOutcome::Confirmed {
+                    time_to_confirm: Duration::from_secs(10),
+                },
+            }
+        }
+    })
+    .await;
+
+    let send_times = send_times.lock().unwrap();
+    let first_send_of_second_step = send_times[rows[0].txs_sent as usize];
+    assert!(first_send_of_second_step.duration_since(started_at) < Duration::from_millis(2_100));
+    // the txs in flight at the end of the first step are still accounted to it
+    assert_eq!(rows[0].txs_confirmed, rows[0].txs_sent);
+    assert_eq!(rows[1].txs_confirmed, rows[1].txs_sent);
+    assert!((rows[0].txs_sent as f64 - 20.0).abs() <= 1.0);
+    assert!((rows[1].txs_sent as f64 - 40.0).abs() <= 2.0);
+}
@@ -1,12 +1,18 @@
+use std::num::NonZeroUsize;
 use std::path::PathBuf;
 use std::time::Duration;
 
 use bench::{
+    bench1::BenchCommitment,
     benches::{
-        api_load::api_load, confirmation_rate::confirmation_rate,
+        api_load::api_load,
+        confirmation_rate::confirmation_rate,
         confirmation_slot::confirmation_slot,
+        priofee_escalation::{priofee_escalation, FeeEscalation},
+        tps_ramp::{tps_ramp, TpsRamp},
     },
-    metrics::{PingThing, PingThingCluster},
+    metrics::{run_averaged, PingThing, PingThingCluster},
+    output::{write_metrics, CsvOptions, OutputFormat},
     tx_size::TxSize,
     BenchmarkTransactionParams,
 };
@@ -33,6 +39,15 @@ enum SubCommand {
         #[clap(short, long, default_value_t = 3)]
         #[arg(short = 'f')]
         cu_price: u64,
+        /// Number of runs, the metrics are averaged over all runs
+        #[clap(long, default_value_t = 1)]
+        runs: usize,
+        /// Metrics output file name
+        #[clap(long, default_value = "api_load.csv")]
+        metrics_file_name: PathBuf,
+        /// Write one row per run before the averaged row
+        #[clap(long, default_value_t = false)]
+        with_run_rows: bool,
     },
     ConfirmationRate {
         #[clap(short, long)]
@@ -52,12 +67,17 @@ enum SubCommand {
         max_timeout_ms: u64,
         #[clap(short, long)]
         txs_per_run: usize,
-        #[clap(short, long)]
+        /// Number of runs, the stats are averaged over all runs
+        #[clap(short, long = "runs", default_value_t = 1)]
+        #[arg(visible_alias = "num-of-runs")]
         num_of_runs: usize,
         /// The CU price in micro lamports
         #[clap(short, long, default_value_t = 300)]
         #[arg(short = 'f')]
         cu_price: u64,
+        /// Commitment a transaction must reach to count as confirmed
+        #[clap(long, value_enum, default_value_t = BenchCommitment::Confirmed)]
+        commitment: BenchCommitment,
     },
     /// Compares the confirmation slot of txs sent to 2 different RPCs
     ConfirmationSlot {
@@ -80,7 +100,9 @@ enum SubCommand {
         /// Maximum confirmation time in milliseconds. After this, the txn is considered unconfirmed
         #[clap(short, long, default_value_t = 15_000)]
         max_timeout_ms: u64,
-        #[clap(short, long)]
+        /// Number of runs, one tx is sent to each RPC per run
+        #[clap(short, long = "runs", default_value_t = 1)]
+        #[arg(visible_alias = "num-of-runs")]
         num_of_runs: usize,
         /// The CU price in micro lamports
         #[clap(short, long, default_value_t = 300)]
@@ -88,6 +110,80 @@ enum SubCommand {
         cu_price: u64,
         #[clap(long)]
         ping_thing_token: Option<String>,
+        /// report 1 in N confirmed transactions to ping thing
+        #[clap(long, default_value_t = 1)]
+        ping_thing_sample_rate: u64,
+        /// Commitment a transaction must reach to count as confirmed
+        #[clap(long, value_enum, default_value_t = BenchCommitment::Confirmed)]
+        commitment: BenchCommitment,
+    },
+    /// Sends txs with a progressively increasing CU price and reports the confirmation rate per fee tier
+    PriofeeEscalation {
+        #[clap(short, long)]
+        payer_path: PathBuf,
+        #[clap(short, long)]
+        rpc_url: String,
+        #[clap(short = 'w', long)]
+        tx_status_websocket_addr: Option<String>,
+        #[clap(short, long)]
+        size_tx: TxSize,
+        /// Maximum confirmation time in milliseconds. After this, the txn is considered unconfirmed
+        #[clap(short, long, default_value_t = 15_000)]
+        max_timeout_ms: u64,
+        /// CU price of the first tier in micro lamports
+        #[clap(long, default_value_t = 100)]
+        start_cu_price: u64,
+        /// CU price increase between two tiers in micro lamports
+        #[clap(long, default_value_t = 1_000)]
+        cu_price_step: u64,
+        #[clap(long, default_value = "5")]
+        num_of_tiers: NonZeroUsize,
+        #[clap(long, default_value_t = 20)]
+        txs_per_tier: usize,
+        /// Metrics output file name
+        #[clap(long, default_value = "priofee_escalation.csv")]
+        metrics_file_name: PathBuf,
+        /// Append the rows to an existing metrics file, without a header row
+        #[clap(long, default_value_t = false)]
+        csv_append: bool,
+        /// Number of runs, the rows of each run are written one after the other
+        #[clap(long, default_value = "1")]
+        runs: NonZeroUsize,
+    },
+    /// Ramps the send rate from start to target tps and reports the confirmation rate per step
+    TpsRamp {
+        #[clap(short, long)]
+        payer_path: PathBuf,
+        #[clap(short, long)]
+        rpc_url: String,
+        #[clap(long, default_value_t = 10.0)]
+        start_tps: f64,
+        #[clap(long)]
+        target_tps: f64,
+        /// Duration of the whole ramp in seconds
+        #[clap(long, default_value_t = 60)]
+        ramp_duration_secs: u64,
+        #[clap(long, default_value = "10")]
+        num_of_steps: NonZeroUsize,
+        /// Maximum confirmation time in milliseconds. After this, the txn is considered unconfirmed
+        #[clap(short, long, default_value_t = 15_000)]
+        max_timeout_ms: u64,
+        /// The CU price in micro lamports
+        #[clap(short, long, default_value_t = 300)]
+        #[arg(short = 'f')]
+        cu_price: u64,
+        /// Metrics output file name
+        #[clap(long, default_value = "tps_ramp.csv")]
+        metrics_file_name: PathBuf,
+        /// Append the rows to an existing metrics file, without a header row
+        #[clap(long, default_value_t = false)]
+        csv_append: bool,
+        /// Number of runs, the rows of each run are written one after the other
+        #[clap(long, default_value = "1")]
+        runs: NonZeroUsize,
+        /// Commitment a transaction must reach to count as confirmed
+        #[clap(long, value_enum, default_value_t = BenchCommitment::Confirmed)]
+        commitment: BenchCommitment,
     },
 }
 
@@ -110,9 +206,27 @@ async fn main() {
             rpc_url,
             test_duration_ms,
             cu_price,
+            runs,
+            metrics_file_name,
+            with_run_rows,
         } => {
-            api_load(&payer_path, rpc_url, test_duration_ms, cu_price)
-                .await
+            let (run_metrics, avg_metric) = run_averaged(runs, |_| {
+                api_load(&payer_path, rpc_url.clone(), test_duration_ms, cu_price)
+            })
+            .await
+            .unwrap();
+            let csv_options = CsvOptions::default();
+            let run_rows: &[_] = if with_run_rows { &run_metrics } else { &[] };
+            csv_options
+                .write_file_atomically(&metrics_file_name, |metrics_file| {
+                    write_metrics(
+                        metrics_file,
+                        OutputFormat::Csv,
+                        &csv_options,
+                        run_rows,
+                        &avg_metric,
+                    )
+                })
                 .unwrap();
         }
         SubCommand::ConfirmationRate {
@@ -124,6 +238,7 @@ async fn main() {
             txs_per_run,
             num_of_runs,
             cu_price,
+            commitment,
         } => confirmation_rate(
             &payer_path,
             rpc_url,
@@ -135,6 +250,7 @@ async fn main() {
             Duration::from_millis(max_timeout_ms),
             txs_per_run,
             num_of_runs,
+            commitment,
         )
         .await
         .unwrap(),
@@ -149,6 +265,8 @@ async fn main() {
             num_of_runs,
             cu_price,
             ping_thing_token,
+            ping_thing_sample_rate,
+            commitment,
         } => confirmation_slot(
             &payer_path,
             rpc_a,
@@ -164,7 +282,79 @@ async fn main() {
             ping_thing_token.map(|t| PingThing {
                 cluster: PingThingCluster::Mainnet,
                 va_api_key: t,
+                sample_rate: ping_thing_sample_rate,
             }),
+            commitment,
+        )
+        .await
+        .unwrap(),
+        SubCommand::PriofeeEscalation {
+            payer_path,
+            rpc_url,
+            tx_status_websocket_addr,
+            size_tx,
+            max_timeout_ms,
+            start_cu_price,
+            cu_price_step,
+            num_of_tiers,
+            txs_per_tier,
+            metrics_file_name,
+            csv_append,
+            runs,
+        } => priofee_escalation(
+            &payer_path,
+            rpc_url,
+            tx_status_websocket_addr,
+            size_tx,
+            Duration::from_millis(max_timeout_ms),
+            FeeEscalation {
+                start_cu_price,
+                cu_price_step,
+                num_of_tiers,
+                txs_per_tier,
+            },
+            runs,
+            &metrics_file_name,
+            &CsvOptions {
+                has_headers: !csv_append,
+                append: csv_append,
+                ..CsvOptions::default()
+            },
+        )
+        .await
+        .unwrap(),
+        SubCommand::TpsRamp {
+            payer_path,
+            rpc_url,
+            start_tps,
+            target_tps,
+            ramp_duration_secs,
+            num_of_steps,
+            max_timeout_ms,
+            cu_price,
+            metrics_file_name,
+            csv_append,
+            commitment,
+            runs,
+        } => tps_ramp(
+            &payer_path,
+            rpc_url,
+            TpsRamp {
+                start_tps,
+                target_tps,
+                duration: Duration::from_secs(ramp_duration_secs),
+                num_of_steps: num_of_steps.get(),
+            },
+            cu_price,
+            Duration::from_millis(max_timeout_ms),
+            commitment,
+            runs.get(),
+            &metrics_file_name,
+            &CsvOptions {
+                has_headers: !csv_append,
+                append: csv_append,
+                ..CsvOptions::default()
+            },
         )
         .await
         .unwrap(),
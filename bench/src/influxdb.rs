@@ -0,0 +1,179 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use anyhow::{bail, Context};
+use lazy_static::lazy_static;
+use log::debug;
+
+use crate::metrics::{Metric, TxMetricData};
+
+const METRIC_MEASUREMENT: &str = "literpc_bench";
+const TX_METRIC_MEASUREMENT: &str = "literpc_bench_tx";
+
+lazy_static! {
+    // shared across submissions to reuse the connection pool
+    static ref INFLUXDB_CLIENT: reqwest::Client = reqwest::Client::new();
+}
+
+/// InfluxDB write endpoint, e.g. http://localhost:8086/api/v2/write?org=lite-rpc&bucket=bench&precision=ns
+#[derive(Clone, Debug)]
+pub struct InfluxDb {
+    pub write_url: String,
+    /// sent as `Authorization: Token <token>`
+    pub token: Option<String>,
+}
+
+/// tags added to every measurement
+#[derive(Clone, Debug)]
+pub struct InfluxDbTags {
+    pub strategy: String,
+    pub commitment: String,
+}
+
+impl InfluxDb {
+    /// submits the final metric and the optional per transaction metrics in InfluxDB line protocol
+    pub async fn submit(
+        &self,
+        tags: &InfluxDbTags,
+        metric: &Metric,
+        tx_metrics: &[TxMetricData],
+    ) -> anyhow::Result<()> {
+        let timestamp_ns = SystemTime::now().duration_since(UNIX_EPOCH)?.as_nanos();
+        let body = line_protocol(tags, metric, tx_metrics, timestamp_ns)?;
+        post_lines(
+            &INFLUXDB_CLIENT,
+            &self.write_url,
+            self.token.as_deref(),
+            body,
+        )
+        .await?;
+        debug!(
+            "Sent metric and {} transaction metrics to influxdb",
+            tx_metrics.len()
+        );
+        Ok(())
+    }
+}
+
+fn line_protocol(
+    tags: &InfluxDbTags,
+    metric: &Metric,
+    tx_metrics: &[TxMetricData],
+    timestamp_ns: u128,
+) -> anyhow::Result<String> {
+    let tags = format!(
+        "strategy={},commitment={}",
+        escape_tag(&tags.strategy),
+        escape_tag(&tags.commitment)
+    );
+    let mut lines = vec![format!(
+        "{METRIC_MEASUREMENT},{tags} {} {timestamp_ns}",
+        fields(metric)?
+    )];
+    for tx_metric in tx_metrics {
+        lines.push(format!(
+            "{TX_METRIC_MEASUREMENT},{tags} {} {timestamp_ns}",
+            fields(tx_metric)?
+        ));
+    }
+    Ok(lines.join("\n"))
+}
+
+/// go through serde to export exactly the fields that are serialized for csv and json
+fn fields(value: &impl serde::Serialize) -> anyhow::Result<String> {
+    let serde_json::Value::Object(fields) = serde_json::to_value(value)? else {
+        bail!("metric must serialize to an object");
+    };
+    let fields = fields
+        .into_iter()
+        .filter_map(|(name, value)| {
+            let value = match value {
+                serde_json::Value::Number(number) if number.is_u64() => format!("{number}i"),
+                serde_json::Value::Number(number) => number.to_string(),
+                serde_json::Value::String(string) => {
+                    format!("\"{}\"", string.replace('\\', "\\\\").replace('"', "\\\""))
+                }
+                _ => return None,
+            };
+            Some(format!("{}={value}", escape_tag(&name)))
+        })
+        .collect::<Vec<_>>();
+    Ok(fields.join(","))
+}
+
+fn escape_tag(value: &str) -> String {
+    value
+        .replace(',', "\\,")
+        .replace('=', "\\=")
+        .replace(' ', "\\ ")
+}
+
+async fn post_lines(
+    client: &reqwest::Client,
+    write_url: &str,
+    token: Option<&str>,
+    body: String,
+) -> anyhow::Result<()> {
+    let mut request = client
+        .post(write_url)
+        .header("Content-Type", "text/plain; charset=utf-8")
+        .body(body);
+    if let Some(token) = token {
+        request = request.header("Authorization", format!("Token {token}"));
+    }
+    request
+        .send()
+        .await
+        .context("failed to send metrics to influxdb")?
+        .error_for_status()?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_influxdb_line_protocol_payload() {
+    use solana_lite_rpc_util::http_mock::{start_http_mock, HttpRequest, HttpResponse};
+
+    let (addr, mut requests) = start_http_mock(|_, _| HttpResponse::empty("204 No Content")).await;
+    let write_url = format!("http://{addr}/api/v2/write?org=lite-rpc&bucket=bench");
+
+    let mut metric = Metric::default();
+    metric.add_successful_transaction(
+        std::time::Duration::from_millis(4),
+        std::time::Duration::from_millis(400),
+        100,
+    );
+    metric.add_unsuccessful_transaction(std::time::Duration::from_millis(4), 100);
+    metric.finalize();
+    let tx_metric = TxMetricData {
+        signature: "5sig".to_string(),
+        sent_slot: 123,
+        confirmed_slot: 124,
+        time_to_send_in_millis: 4,
+        time_to_confirm_in_millis: 400,
+    };
+    let tags = InfluxDbTags {
+        strategy: "bench1 poll".to_string(),
+        commitment: "confirmed".to_string(),
+    };
+
+    let body = line_protocol(&tags, &metric, &[tx_metric], 1_700_000_000_000_000_000).unwrap();
+    post_lines(&INFLUXDB_CLIENT, &write_url, Some("secret"), body)
+        .await
+        .unwrap();
+
+    let HttpRequest { head, body } = requests.recv().await.unwrap();
+    assert!(head.starts_with("POST /api/v2/write?org=lite-rpc&bucket=bench HTTP/1.1"));
+    assert!(head.to_lowercase().contains("authorization: token secret"));
+
+    let lines = body.lines().collect::<Vec<_>>();
+    assert_eq!(lines.len(), 2);
+    assert!(lines[0].starts_with("literpc_bench,strategy=bench1\\ poll,commitment=confirmed "));
+    assert!(lines[0].contains("txs_sent=2i,"));
+    assert!(lines[0].contains("confirmation_rate=0.5,"));
+    assert!(lines[0].ends_with(" 1700000000000000000"));
+    assert_eq!(
+        lines[1],
+        "literpc_bench_tx,strategy=bench1\\ poll,commitment=confirmed \
+         confirmed_slot=124i,sent_slot=123i,signature=\"5sig\",time_to_confirm_in_millis=400i,time_to_send_in_millis=4i \
+         1700000000000000000"
+    );
+}
@@ -1,8 +1,10 @@
 use anyhow::bail;
+use bench1::{BenchCommitment, ConfirmVia};
 use clap::Parser;
 use futures::future::join_all;
 use itertools::Itertools;
 use log::{debug, warn};
+use output::{CsvDelimiter, OutputFormat};
 use rand::{distributions::Alphanumeric, prelude::Distribution, SeedableRng};
 use solana_rpc_client::{nonblocking::rpc_client::RpcClient, rpc_client::SerializableTransaction};
 use solana_sdk::compute_budget::ComputeBudgetInstruction;
@@ -27,7 +29,11 @@ use tx_size::TxSize;
 pub mod bench1;
 pub mod benches;
 pub mod helpers;
+pub mod influxdb;
 pub mod metrics;
+#[cfg(feature = "metrics-server")]
+pub mod metrics_server;
+pub mod output;
 pub mod service_adapter1;
 pub mod service_adapter_new;
 pub mod tx_size;
@@ -55,6 +61,42 @@ pub struct Args {
     // choose between small (179 bytes) and large (1186 bytes) transactions
     #[arg(short = 'L', long, default_value_t = false)]
     pub large_transactions: bool,
+    /// Transactions sent during the first seconds of each run are excluded from the metrics
+    #[arg(long, default_value_t = 0)]
+    pub warmup_secs: u64,
+    /// How transaction confirmations are detected
+    #[arg(long, value_enum, default_value_t = ConfirmVia::Poll)]
+    pub confirm_via: ConfirmVia,
+    /// Commitment a transaction must reach to count as confirmed
+    #[arg(long, value_enum, default_value_t = BenchCommitment::Confirmed)]
+    pub commitment: BenchCommitment,
+    /// Lite Rpc websocket address for --confirm-via=ws, derived from the rpc address if not set
+    #[arg(long)]
+    pub lite_rpc_ws_addr: Option<String>,
+    /// Format of the metrics output file
+    #[arg(long, value_enum, default_value_t = OutputFormat::Csv)]
+    pub output_format: OutputFormat,
+    /// Field delimiter of the csv outputs
+    #[arg(long, value_enum, default_value_t = CsvDelimiter::Comma)]
+    pub csv_delimiter: CsvDelimiter,
+    /// Do not write a header row to the csv outputs
+    #[arg(long, default_value_t = false)]
+    pub csv_no_headers: bool,
+    /// Append to existing csv outputs instead of overwriting them, no header row is written;
+    /// only valid with --output-format csv
+    #[arg(long, default_value_t = false)]
+    pub csv_append: bool,
+    /// InfluxDB write url the average metric is posted to in line protocol,
+    /// e.g. http://localhost:8086/api/v2/write?org=lite-rpc&bucket=bench
+    #[arg(long)]
+    pub influxdb_url: Option<String>,
+    /// InfluxDB api token
+    #[arg(long, env = "INFLUXDB_TOKEN")]
+    pub influxdb_token: Option<String>,
+    /// Serve the in-flight metric of each run with getMetric, e.g. 127.0.0.1:9091
+    #[cfg(feature = "metrics-server")]
+    #[arg(long)]
+    pub metrics_server_addr: Option<std::net::SocketAddr>,
 }
 
 pub struct BenchmarkTransactionParams {
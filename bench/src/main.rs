@@ -1,7 +1,9 @@
 use bench::{
     bench1,
     helpers::BenchHelper,
-    metrics::{AvgMetric, Metric, TxMetricData},
+    influxdb::{InfluxDb, InfluxDbTags},
+    metrics::{AvgMetric, Metric, SharedMetric, TxMetricData},
+    output::{write_metrics, CsvOptions},
     Args,
 };
 use clap::Parser;
@@ -10,7 +12,8 @@ use futures::future::join_all;
 use log::{error, info};
 use solana_rpc_client::nonblocking::rpc_client::RpcClient;
 
-use bench::bench1::TransactionSize;
+use bench::bench1::{ConfirmVia, TransactionSize};
+use solana_pubsub_client::nonblocking::pubsub_client::PubsubClient;
 use solana_sdk::{
     commitment_config::CommitmentConfig, hash::Hash, signature::Keypair, signer::Signer,
 };
@@ -29,8 +32,29 @@ async fn main() {
         lite_rpc_addr,
         transaction_save_file,
         large_transactions,
+        output_format,
+        warmup_secs,
+        confirm_via,
+        commitment,
+        lite_rpc_ws_addr,
+        csv_delimiter,
+        csv_no_headers,
+        csv_append,
+        influxdb_url,
+        influxdb_token,
+        #[cfg(feature = "metrics-server")]
+        metrics_server_addr,
     } = Args::parse();
 
+    let csv_options = CsvOptions {
+        delimiter: csv_delimiter,
+        // appended rows go below the header row of the existing file
+        has_headers: !(csv_no_headers || csv_append),
+        append: csv_append,
+    };
+    // fail before running the bench
+    output_format.check_csv_options(&csv_options).unwrap();
+
     let cu_price_micro_lamports = 300;
 
     let mut run_interval_ms = tokio::time::interval(Duration::from_millis(run_interval_ms));
@@ -54,6 +78,19 @@ async fn main() {
         lite_rpc_addr.clone(),
         CommitmentConfig::confirmed(),
     ));
+    let pubsub_client = match confirm_via {
+        ConfirmVia::Poll => None,
+        ConfirmVia::Ws => {
+            let ws_addr = lite_rpc_ws_addr.unwrap_or_else(|| {
+                lite_rpc_addr
+                    .replace("http:", "ws:")
+                    .replace("https:", "wss:")
+            });
+            info!("Confirming transactions via signatureSubscribe on {ws_addr}");
+            Some(Arc::new(PubsubClient::new(&ws_addr).await.unwrap()))
+        }
+    };
+
     let bh = rpc_client.get_latest_blockhash().await.unwrap();
     let slot = rpc_client.get_slot().await.unwrap();
     let block_hash: Arc<RwLock<Hash>> = Arc::new(RwLock::new(bh));
@@ -86,17 +123,39 @@ async fn main() {
         })
     };
 
-    // transaction logger
+    // transaction logger, the transactions are written once all runs are done
     let (tx_log_sx, mut tx_log_rx) = tokio::sync::mpsc::unbounded_channel::<TxMetricData>();
     let log_transactions = !transaction_save_file.is_empty();
-    if log_transactions {
+    let tx_log_collector = log_transactions.then(|| {
         tokio::spawn(async move {
-            let mut tx_writer = csv::Writer::from_path(transaction_save_file).unwrap();
+            let mut logged_txs = vec![];
             while let Some(x) = tx_log_rx.recv().await {
-                tx_writer.serialize(x).unwrap();
+                logged_txs.push(x);
             }
-        });
-    }
+            logged_txs
+        })
+    });
+
+    // in-flight metric of each run, only kept up to date if they are served
+    #[allow(unused_mut)]
+    let mut live_metrics: Vec<SharedMetric> = vec![];
+    #[cfg(feature = "metrics-server")]
+    let _metrics_server = match metrics_server_addr {
+        Some(addr) => {
+            live_metrics = (0..runs).map(|_| SharedMetric::default()).collect();
+            match bench::metrics_server::start_metrics_server(addr, live_metrics.clone()).await {
+                Ok((addr, server)) => {
+                    info!("Serving the in-flight metrics on {addr}");
+                    Some(server)
+                }
+                Err(err) => {
+                    error!("Cannot start the metrics server: {err:?}");
+                    return;
+                }
+            }
+        }
+        None => None,
+    };
 
     for seed in 0..runs {
         let funded_payer = Keypair::from_bytes(funded_payer.to_bytes().as_slice()).unwrap();
@@ -111,6 +170,10 @@ async fn main() {
             log_transactions,
             transaction_size,
             cu_price_micro_lamports,
+            Duration::from_secs(warmup_secs),
+            pubsub_client.clone(),
+            commitment,
+            live_metrics.get(seed).cloned(),
         )));
         // wait for an interval
         run_interval_ms.tick().await;
@@ -118,16 +181,31 @@ async fn main() {
 
     let join_res = join_all(tasks).await;
 
+    // the runs are done, close the channel to end the transaction logger
+    drop(tx_log_sx);
+    if let Some(tx_log_collector) = tx_log_collector {
+        match tx_log_collector.await {
+            Ok(logged_txs) => {
+                if let Err(err) =
+                    csv_options.write_rows_atomically(&transaction_save_file, logged_txs)
+                {
+                    error!("Cannot log transactions: {err:?}");
+                }
+            }
+            Err(err) => error!("Transaction logger failed: {err}"),
+        }
+    }
+
     let mut run_num = 1;
 
-    let mut csv_writer = csv::Writer::from_path(metrics_file_name).unwrap();
+    let mut run_metrics = Vec::with_capacity(join_res.len());
     for res in join_res {
         match res {
             Ok(metric) => {
                 info!("Run {run_num}: Sent and Confirmed {tx_count} tx(s) in {metric:?} with",);
                 // update avg metric
                 avg_metric += &metric;
-                csv_writer.serialize(metric).unwrap();
+                run_metrics.push(metric);
             }
             Err(_) => {
                 error!("join error for run {}", run_num);
@@ -139,7 +217,30 @@ async fn main() {
     let avg_metric = Metric::from(avg_metric);
 
     info!("Avg Metric {avg_metric:?}",);
-    csv_writer.serialize(avg_metric).unwrap();
 
-    csv_writer.flush().unwrap();
+    if let Some(write_url) = influxdb_url {
+        let influxdb = InfluxDb {
+            write_url,
+            token: influxdb_token,
+        };
+        let tags = InfluxDbTags {
+            strategy: "bench1".to_string(),
+            commitment: commitment.commitment_config().commitment.to_string(),
+        };
+        if let Err(err) = influxdb.submit(&tags, &avg_metric, &[]).await {
+            error!("Cannot send metrics to influxdb: {err:?}");
+        }
+    }
+
+    if let Err(err) = csv_options.write_file_atomically(&metrics_file_name, |metrics_file| {
+        write_metrics(
+            metrics_file,
+            output_format,
+            &csv_options,
+            &run_metrics,
+            &avg_metric,
+        )
+    }) {
+        error!("Cannot write metrics: {err:?}");
+    }
 }
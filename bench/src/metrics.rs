@@ -1,23 +1,40 @@
 use std::{
     fmt::{self, Display},
+    future::Future,
     ops::{AddAssign, DivAssign},
+    str::FromStr,
+    sync::{Arc, Mutex},
     time::Duration,
 };
 
+use anyhow::bail;
+use lazy_static::lazy_static;
 use reqwest::StatusCode;
 use serde::{Deserialize, Serialize};
-use solana_sdk::{signature::Signature, slot_history::Slot};
-use tracing::debug;
+use solana_sdk::{hash::hashv, signature::Signature, slot_history::Slot};
+use tracing::{debug, info, warn};
 
-#[derive(Clone, Copy, Debug, Default, serde::Serialize)]
+#[derive(Clone, Debug, Default, serde::Serialize)]
 pub struct Metric {
     pub txs_sent: u64,
     pub txs_confirmed: u64,
     pub txs_un_confirmed: u64,
+    // rejected by the rpc, counted in txs_sent but never unconfirmed
+    pub txs_send_failed: u64,
+    // accepted by the rpc without tracking the confirmation, counted in txs_sent but not in the confirmation rate
+    pub txs_accepted: u64,
+    // sent during the warmup phase, not part of any other field
+    pub txs_warmup: u64,
+    // confirmed share of the txs accepted by the rpc
+    pub confirmation_rate: f64,
     pub average_confirmation_time_ms: f64,
     pub average_time_to_send_txs: f64,
     pub average_transaction_bytes: f64,
     pub send_tps: f64,
+    pub confirmed_tps: f64,
+    pub p50_confirmation_time_ms: f64,
+    pub p90_confirmation_time_ms: f64,
+    pub p99_confirmation_time_ms: f64,
 
     #[serde(skip_serializing)]
     total_sent_time: Duration,
@@ -27,8 +44,17 @@ pub struct Metric {
     total_confirmation_time: Duration,
     #[serde(skip_serializing)]
     total_gross_send_time_ms: f64,
+    // wall-clock window from the first send until the end of confirmation polling
+    #[serde(skip_serializing)]
+    total_elapsed_time_ms: f64,
+    // raw samples of the confirmed transactions, used for the percentiles
+    #[serde(skip_serializing)]
+    confirmation_times: Vec<Duration>,
 }
 
+/// metric of a run still in progress, updated by the run while it confirms transactions
+pub type SharedMetric = Arc<Mutex<Metric>>;
+
 impl Metric {
     pub fn add_successful_transaction(
         &mut self,
@@ -39,6 +65,7 @@ impl Metric {
         self.total_sent_time += time_to_send;
         self.total_confirmation_time += time_to_confrim;
         self.total_transaction_bytes += transaction_bytes;
+        self.confirmation_times.push(time_to_confrim);
 
         self.txs_confirmed += 1;
         self.txs_sent += 1;
@@ -51,6 +78,27 @@ impl Metric {
         self.txs_sent += 1;
     }
 
+    /// transaction rejected by the rpc on send
+    pub fn add_send_failure(&mut self, time_to_send: Duration, transaction_bytes: u64) {
+        self.total_sent_time += time_to_send;
+        self.total_transaction_bytes += transaction_bytes;
+        self.txs_send_failed += 1;
+        self.txs_sent += 1;
+    }
+
+    /// transaction accepted by the rpc whose confirmation is not tracked
+    pub fn add_accepted_transaction(&mut self, time_to_send: Duration, transaction_bytes: u64) {
+        self.total_sent_time += time_to_send;
+        self.total_transaction_bytes += transaction_bytes;
+        self.txs_accepted += 1;
+        self.txs_sent += 1;
+    }
+
+    /// transaction sent during the warmup phase, only counted and excluded from the aggregates
+    pub fn add_warmup_transaction(&mut self) {
+        self.txs_warmup += 1;
+    }
+
     pub fn finalize(&mut self) {
         if self.txs_sent > 0 {
             self.average_time_to_send_txs =
@@ -64,15 +112,46 @@ impl Metric {
             self.send_tps = self.txs_sent as f64 / total_gross_send_time_secs;
         }
 
+        self.confirmed_tps = self.calc_tps();
+
+        let txs_tracked = self
+            .txs_sent
+            .saturating_sub(self.txs_send_failed + self.txs_accepted);
+        if txs_tracked > 0 {
+            self.confirmation_rate = self.txs_confirmed as f64 / txs_tracked as f64;
+        }
+
         if self.txs_confirmed > 0 {
             self.average_confirmation_time_ms =
                 self.total_confirmation_time.as_millis() as f64 / self.txs_confirmed as f64;
         }
+
+        if !self.confirmation_times.is_empty() {
+            let mut sorted_ms = self
+                .confirmation_times
+                .iter()
+                .map(|d| d.as_secs_f64() * 1_000.0)
+                .collect::<Vec<_>>();
+            sorted_ms.sort_by(|a, b| a.total_cmp(b));
+            self.p50_confirmation_time_ms = percentile_of_sorted(&sorted_ms, 50);
+            self.p90_confirmation_time_ms = percentile_of_sorted(&sorted_ms, 90);
+            self.p99_confirmation_time_ms = percentile_of_sorted(&sorted_ms, 99);
+        }
     }
 
     pub fn set_total_gross_send_time(&mut self, total_gross_send_time_ms: f64) {
         self.total_gross_send_time_ms = total_gross_send_time_ms;
     }
+
+    pub fn set_total_elapsed_time(&mut self, total_elapsed_time_ms: f64) {
+        self.total_elapsed_time_ms = total_elapsed_time_ms;
+    }
+}
+
+/// nearest-rank percentile, `sorted` must be sorted ascending and not empty
+fn percentile_of_sorted(sorted: &[f64], percentile: usize) -> f64 {
+    let rank = (sorted.len() * percentile).div_ceil(100);
+    sorted[rank.saturating_sub(1).min(sorted.len() - 1)]
 }
 
 #[derive(Default)]
@@ -82,8 +161,13 @@ pub struct AvgMetric {
 }
 
 impl Metric {
-    pub fn calc_tps(&mut self) -> f64 {
-        self.txs_confirmed as f64
+    /// confirmed transactions per second over the send+confirm window
+    pub fn calc_tps(&self) -> f64 {
+        if self.total_elapsed_time_ms > 0.01 {
+            self.txs_confirmed as f64 / (self.total_elapsed_time_ms / 1_000.0)
+        } else {
+            0.0
+        }
     }
 }
 
@@ -92,12 +176,18 @@ impl AddAssign<&Self> for Metric {
         self.txs_sent += rhs.txs_sent;
         self.txs_confirmed += rhs.txs_confirmed;
         self.txs_un_confirmed += rhs.txs_un_confirmed;
+        self.txs_send_failed += rhs.txs_send_failed;
+        self.txs_accepted += rhs.txs_accepted;
+        self.txs_warmup += rhs.txs_warmup;
 
         self.total_confirmation_time += rhs.total_confirmation_time;
         self.total_sent_time += rhs.total_sent_time;
         self.total_transaction_bytes += rhs.total_transaction_bytes;
         self.total_gross_send_time_ms += rhs.total_gross_send_time_ms;
+        self.total_elapsed_time_ms += rhs.total_elapsed_time_ms;
         self.send_tps += rhs.send_tps;
+        self.confirmation_times
+            .extend_from_slice(&rhs.confirmation_times);
 
         self.finalize();
     }
@@ -112,6 +202,9 @@ impl DivAssign<u64> for Metric {
         self.txs_sent /= rhs;
         self.txs_confirmed /= rhs;
         self.txs_un_confirmed /= rhs;
+        self.txs_send_failed /= rhs;
+        self.txs_accepted /= rhs;
+        self.txs_warmup /= rhs;
 
         self.total_confirmation_time =
             Duration::from_micros((self.total_confirmation_time.as_micros() / rhs as u128) as u64);
@@ -120,6 +213,8 @@ impl DivAssign<u64> for Metric {
         self.total_transaction_bytes = self.total_transaction_bytes / rhs;
         self.send_tps = self.send_tps / rhs as f64;
         self.total_gross_send_time_ms = self.total_gross_send_time_ms / rhs as f64;
+        self.total_elapsed_time_ms = self.total_elapsed_time_ms / rhs as f64;
+        // the samples are kept as-is: the percentiles of the averaged metric are taken over all runs
 
         self.finalize();
     }
@@ -139,6 +234,41 @@ impl From<AvgMetric> for Metric {
     }
 }
 
+/// runs the bench `runs` times one after another, returns the metric of each run and their average.
+/// Failed runs are logged and left out of the average.
+pub async fn run_averaged<F, Fut>(runs: usize, mut run: F) -> anyhow::Result<(Vec<Metric>, Metric)>
+where
+    F: FnMut(usize) -> Fut,
+    Fut: Future<Output = anyhow::Result<Metric>>,
+{
+    if runs == 0 {
+        bail!("number of runs must be greater than 0");
+    }
+
+    let mut avg_metric = AvgMetric::default();
+    let mut run_metrics = Vec::with_capacity(runs);
+    for run_num in 0..runs {
+        match run(run_num).await {
+            Ok(metric) => {
+                info!("Run {}: {metric:?}", run_num + 1);
+                avg_metric += &metric;
+                run_metrics.push(metric);
+            }
+            Err(err) => {
+                warn!(
+                    "Run {} failed - not part of the average: {err:?}",
+                    run_num + 1
+                );
+            }
+        }
+    }
+    if run_metrics.is_empty() {
+        bail!("all {runs} runs failed");
+    }
+
+    Ok((run_metrics, Metric::from(avg_metric)))
+}
+
 #[derive(Clone, Debug, Default, serde::Serialize)]
 pub struct TxMetricData {
     pub signature: String,
@@ -157,11 +287,21 @@ pub enum PingThingCluster {
 
 impl PingThingCluster {
     pub fn from_arg(cluster: String) -> Self {
-        match cluster.to_lowercase().as_str() {
-            "mainnet" => PingThingCluster::Mainnet,
-            "testnet" => PingThingCluster::Testnet,
-            "devnet" => PingThingCluster::Devnet,
-            _ => panic!("incorrect cluster name"),
+        cluster.parse().expect("incorrect cluster name")
+    }
+}
+
+impl FromStr for PingThingCluster {
+    type Err = anyhow::Error;
+
+    fn from_str(network: &str) -> Result<Self, Self::Err> {
+        match network.to_lowercase().as_str() {
+            "mainnet" => Ok(PingThingCluster::Mainnet),
+            "testnet" => Ok(PingThingCluster::Testnet),
+            "devnet" => Ok(PingThingCluster::Devnet),
+            _ => {
+                bail!("unknown ping thing network '{network}', expected mainnet, testnet or devnet")
+            }
         }
     }
 }
@@ -192,10 +332,51 @@ impl Display for PingThingTxType {
     }
 }
 
+const PING_THING_BASE_URL: &str = "https://www.validators.app/api/v1/ping-thing";
+const PING_THING_BATCH_BASE_URL: &str = "https://www.validators.app/api/v1/ping-thing-batch";
+
+lazy_static! {
+    // shared across reports to reuse the connection pool
+    static ref PING_THING_CLIENT: reqwest::Client = reqwest::Client::new();
+}
+
 #[derive(Clone)]
 pub struct PingThing {
     pub cluster: PingThingCluster,
     pub va_api_key: String,
+    /// report 1 in N confirmed transactions, 1 reports all of them
+    pub sample_rate: u64,
+}
+
+/// a confirmed transaction to be reported in a batch
+#[derive(Clone, Debug)]
+pub struct PingThingReport {
+    pub tx_elapsed: Duration,
+    pub tx_sig: Signature,
+    pub tx_type: PingThingTxType,
+    pub tx_success: bool,
+    pub slot_sent: Slot,
+    pub slot_landed: Slot,
+}
+
+impl From<&PingThingReport> for PingThingData {
+    fn from(report: &PingThingReport) -> Self {
+        PingThingData {
+            time: report.tx_elapsed.as_millis(),
+            signature: report.tx_sig.to_string(),
+            transaction_type: report.tx_type.to_string(),
+            success: report.tx_success,
+            application: "LiteRPC.bench".to_string(),
+            commitment_level: "confirmed".to_string(),
+            slot_sent: report.slot_sent,
+            slot_landed: report.slot_landed,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct PingThingBatchData {
+    pub transactions: Vec<PingThingData>,
 }
 
 /// request format see https://github.com/Block-Logic/ping-thing-client/blob/4c008c741164702a639c282f1503a237f7d95e64/ping-thing-client.mjs#L160
@@ -212,6 +393,18 @@ struct PingThingData {
 }
 
 impl PingThing {
+    /// deterministic per signature, so a transaction is either always or never reported
+    pub fn is_sampled(&self, tx_sig: &Signature) -> bool {
+        if self.sample_rate <= 1 {
+            return true;
+        }
+        // signatures are not guaranteed to be uniform, hash them first
+        let hash = hashv(&[tx_sig.as_ref()]);
+        let bucket = u64::from_le_bytes(hash.as_ref()[..8].try_into().unwrap());
+        bucket % self.sample_rate == 0
+    }
+
+    /// skips transactions not sampled
     pub async fn submit_confirmed_stats(
         &self,
         tx_elapsed: Duration,
@@ -221,8 +414,11 @@ impl PingThing {
         slot_sent: Slot,
         slot_landed: Slot,
     ) -> anyhow::Result<()> {
+        if !self.is_sampled(&tx_sig) {
+            return Ok(());
+        }
         submit_stats_to_ping_thing(
-            self.cluster.clone(),
+            &self.cluster,
             self.va_api_key.clone(),
             tx_elapsed,
             tx_sig,
@@ -233,13 +429,45 @@ impl PingThing {
         )
         .await
     }
+
+    /// submits the sampled reports with one request, returns the number of reports sent
+    pub async fn submit_confirmed_stats_batch(
+        &self,
+        reports: &[PingThingReport],
+    ) -> anyhow::Result<usize> {
+        let batch = PingThingBatchData {
+            transactions: reports
+                .iter()
+                .filter(|report| self.is_sampled(&report.tx_sig))
+                .map(PingThingData::from)
+                .collect(),
+        };
+        if batch.transactions.is_empty() {
+            return Ok(0);
+        }
+
+        post_ping_thing_data(
+            &PING_THING_CLIENT,
+            PING_THING_BATCH_BASE_URL,
+            &self.cluster,
+            self.va_api_key.clone(),
+            &batch,
+        )
+        .await?;
+
+        debug!(
+            "Sent data for {} txs to ping-thing server",
+            batch.transactions.len()
+        );
+        Ok(batch.transactions.len())
+    }
 }
 
-/// submits to https://www.validators.app/ping-thing?network=mainnet
-/// Assumes that the txn was sent on Mainnet and had the "confirmed" commitment level
+/// submits to https://www.validators.app/ping-thing?network=<network>
+/// Assumes that the txn had the "confirmed" commitment level
 #[allow(clippy::too_many_arguments)]
 async fn submit_stats_to_ping_thing(
-    cluster: PingThingCluster,
+    cluster: &PingThingCluster,
     va_api_key: String,
     tx_elapsed: Duration,
     tx_sig: Signature,
@@ -259,26 +487,284 @@ async fn submit_stats_to_ping_thing(
         slot_landed,
     };
 
-    let client = reqwest::Client::new();
-    // cluster: 'mainnet'
+    post_ping_thing_data(
+        &PING_THING_CLIENT,
+        PING_THING_BASE_URL,
+        cluster,
+        va_api_key,
+        &submit_data_request,
+    )
+    .await?;
+
+    debug!("Sent data for tx {} to ping-thing server", tx_sig);
+    Ok(())
+}
+
+async fn post_ping_thing_data<T: Serialize>(
+    client: &reqwest::Client,
+    base_url: &str,
+    cluster: &PingThingCluster,
+    va_api_key: String,
+    submit_data_request: &T,
+) -> anyhow::Result<()> {
     let response = client
-        .post(format!(
-            "https://www.validators.app/api/v1/ping-thing/{}",
-            cluster.to_url_part()
-        ))
+        .post(format!("{}/{}", base_url, cluster.to_url_part()))
         .header("Content-Type", "application/json")
         .header("Token", va_api_key)
-        .json(&submit_data_request)
+        .json(submit_data_request)
         .send()
         .await?
         .error_for_status()?;
 
-    assert_eq!(response.status(), StatusCode::CREATED);
-
-    debug!("Sent data for tx {} to ping-thing server", tx_sig);
+    if response.status() != StatusCode::CREATED {
+        bail!(
+            "unexpected ping thing response status {}",
+            response.status()
+        );
+    }
     Ok(())
 }
 
+#[test]
+fn test_confirmation_time_percentiles() {
+    let mut metric = Metric::default();
+    // 1ms, 2ms, ..., 100ms
+    for ms in (1..=100).rev() {
+        metric.add_successful_transaction(Duration::from_millis(1), Duration::from_millis(ms), 100);
+    }
+    metric.finalize();
+
+    assert_eq!(metric.p50_confirmation_time_ms, 50.0);
+    assert_eq!(metric.p90_confirmation_time_ms, 90.0);
+    assert_eq!(metric.p99_confirmation_time_ms, 99.0);
+
+    // merging a second run of slow transactions moves the tail
+    let mut slow_run = Metric::default();
+    for _ in 0..100 {
+        slow_run.add_successful_transaction(
+            Duration::from_millis(1),
+            Duration::from_millis(1_000),
+            100,
+        );
+    }
+    let mut avg_metric = AvgMetric::default();
+    avg_metric += &metric;
+    avg_metric += &slow_run;
+    let avg_metric = Metric::from(avg_metric);
+
+    assert_eq!(avg_metric.p50_confirmation_time_ms, 100.0);
+    assert_eq!(avg_metric.p90_confirmation_time_ms, 1_000.0);
+    assert_eq!(avg_metric.p99_confirmation_time_ms, 1_000.0);
+}
+
+#[test]
+fn test_calc_tps() {
+    let mut metric = Metric::default();
+    for _ in 0..100 {
+        metric.add_successful_transaction(
+            Duration::from_millis(1),
+            Duration::from_millis(400),
+            100,
+        );
+    }
+    metric.set_total_elapsed_time(2_000.0);
+    metric.finalize();
+
+    assert_eq!(metric.calc_tps(), 50.0);
+    assert_eq!(metric.confirmed_tps, 50.0);
+}
+
+#[test]
+fn test_warmup_transactions_excluded() {
+    let mut metric = Metric::default();
+    // slow cold start
+    for _ in 0..10 {
+        metric.add_warmup_transaction();
+    }
+    for _ in 0..4 {
+        metric.add_successful_transaction(
+            Duration::from_millis(2),
+            Duration::from_millis(500),
+            200,
+        );
+    }
+    metric.add_unsuccessful_transaction(Duration::from_millis(2), 200);
+    metric.finalize();
+
+    assert_eq!(metric.txs_warmup, 10);
+    assert_eq!(metric.txs_sent, 5);
+    assert_eq!(metric.txs_confirmed, 4);
+    assert_eq!(metric.average_confirmation_time_ms, 500.0);
+    assert_eq!(metric.average_time_to_send_txs, 2.0);
+    assert_eq!(metric.p99_confirmation_time_ms, 500.0);
+}
+
+#[test]
+fn test_send_failures_counted_separately() {
+    let mut metric = Metric::default();
+    for _ in 0..3 {
+        metric.add_successful_transaction(
+            Duration::from_millis(4),
+            Duration::from_millis(400),
+            100,
+        );
+    }
+    metric.add_unsuccessful_transaction(Duration::from_millis(4), 100);
+    for _ in 0..4 {
+        metric.add_send_failure(Duration::from_millis(4), 100);
+    }
+    metric.finalize();
+
+    assert_eq!(metric.txs_sent, 8);
+    assert_eq!(metric.txs_send_failed, 4);
+    assert_eq!(metric.txs_un_confirmed, 1);
+    // rejected txs do not lower the confirmation rate
+    assert_eq!(metric.confirmation_rate, 0.75);
+    assert_eq!(metric.average_time_to_send_txs, 4.0);
+
+    let mut other_run = Metric::default();
+    other_run.add_successful_transaction(Duration::from_millis(4), Duration::from_millis(400), 100);
+    other_run.add_send_failure(Duration::from_millis(4), 100);
+    other_run.add_send_failure(Duration::from_millis(4), 100);
+    other_run.add_send_failure(Duration::from_millis(4), 100);
+    other_run.finalize();
+
+    let mut avg_metric = AvgMetric::default();
+    avg_metric += &metric;
+    avg_metric += &other_run;
+    let avg_metric = Metric::from(avg_metric);
+    assert_eq!(avg_metric.txs_sent, 6);
+    assert_eq!(avg_metric.txs_send_failed, 3);
+    assert_eq!(avg_metric.txs_confirmed, 2);
+    assert_eq!(avg_metric.confirmation_rate, 2.0 / 3.0);
+}
+
+#[test]
+fn test_accepted_transactions_are_not_unconfirmed() {
+    let mut metric = Metric::default();
+    for _ in 0..3 {
+        metric.add_accepted_transaction(Duration::from_millis(2), 100);
+    }
+    metric.add_send_failure(Duration::from_millis(2), 100);
+    metric.finalize();
+
+    assert_eq!(metric.txs_sent, 4);
+    assert_eq!(metric.txs_accepted, 3);
+    assert_eq!(metric.txs_send_failed, 1);
+    assert_eq!(metric.txs_un_confirmed, 0);
+    // confirmations are not tracked
+    assert_eq!(metric.confirmation_rate, 0.0);
+    assert_eq!(metric.average_time_to_send_txs, 2.0);
+}
+
+#[tokio::test]
+async fn test_run_averaged_three_runs() {
+    // run n: 2 confirmed after n*300ms, 1 unconfirmed
+    let (run_metrics, avg_metric) = run_averaged(3, |run_num| async move {
+        let mut metric = Metric::default();
+        let confirmation_time = Duration::from_millis(300 * (run_num as u64 + 1));
+        for _ in 0..2 {
+            metric.add_successful_transaction(Duration::from_millis(10), confirmation_time, 100);
+        }
+        metric.add_unsuccessful_transaction(Duration::from_millis(10), 100);
+        metric.finalize();
+        Ok(metric)
+    })
+    .await
+    .unwrap();
+
+    assert_eq!(run_metrics.len(), 3);
+    assert_eq!(run_metrics[2].average_confirmation_time_ms, 900.0);
+    assert_eq!(avg_metric.txs_sent, 3);
+    assert_eq!(avg_metric.txs_confirmed, 2);
+    assert_eq!(avg_metric.txs_un_confirmed, 1);
+    // (2 * 300 + 2 * 600 + 2 * 900) / 6
+    assert_eq!(avg_metric.average_confirmation_time_ms, 600.0);
+    assert_eq!(avg_metric.average_time_to_send_txs, 10.0);
+
+    assert!(run_averaged(0, |_| async { Ok(Metric::default()) })
+        .await
+        .is_err());
+}
+
+#[tokio::test]
+async fn test_ping_thing_request_path_and_body() {
+    use solana_lite_rpc_util::http_mock::{start_http_mock, HttpRequest, HttpResponse};
+
+    let (addr, mut requests) = start_http_mock(|_, _| HttpResponse::empty("201 Created")).await;
+    let base_url = format!("http://{addr}/api/v1/ping-thing");
+
+    let data = PingThingData {
+        time: 2000,
+        signature: Signature::new_unique().to_string(),
+        transaction_type: PingThingTxType::Memo.to_string(),
+        success: true,
+        application: "LiteRPC.bench".to_string(),
+        commitment_level: "confirmed".to_string(),
+        slot_sent: 123,
+        slot_landed: 124,
+    };
+    post_ping_thing_data(
+        &PING_THING_CLIENT,
+        &base_url,
+        &PingThingCluster::Testnet,
+        "token".to_string(),
+        &data,
+    )
+    .await
+    .unwrap();
+
+    let HttpRequest { head, body } = requests.recv().await.unwrap();
+    assert!(head.starts_with("POST /api/v1/ping-thing/testnet HTTP/1.1"));
+    let sent: PingThingData = serde_json::from_str(&body).unwrap();
+    assert_eq!(sent.signature, data.signature);
+    assert_eq!(sent.slot_landed, 124);
+}
+
+#[test]
+fn test_ping_thing_cluster_from_str() {
+    assert!(matches!(
+        "Testnet".parse::<PingThingCluster>(),
+        Ok(PingThingCluster::Testnet)
+    ));
+    assert!(":network".parse::<PingThingCluster>().is_err());
+}
+
+#[test]
+fn test_ping_thing_sampling() {
+    let ping_thing = PingThing {
+        cluster: PingThingCluster::Mainnet,
+        va_api_key: "token".to_string(),
+        sample_rate: 10,
+    };
+    let signatures: Vec<Signature> = (0..10_000).map(|_| Signature::new_unique()).collect();
+    let sampled = signatures
+        .iter()
+        .filter(|sig| ping_thing.is_sampled(sig))
+        .count();
+    assert!((900..=1100).contains(&sampled), "sampled {sampled}");
+
+    // sampling only depends on the signature, not on the reporter
+    let other_reporter = PingThing {
+        cluster: PingThingCluster::Devnet,
+        va_api_key: "other token".to_string(),
+        sample_rate: 10,
+    };
+    for sig in &signatures {
+        let parsed: Signature = sig.to_string().parse().unwrap();
+        assert_eq!(
+            ping_thing.is_sampled(sig),
+            other_reporter.is_sampled(&parsed)
+        );
+    }
+
+    let report_all = PingThing {
+        sample_rate: 1,
+        ..ping_thing
+    };
+    assert!(signatures.iter().all(|sig| report_all.is_sampled(sig)));
+}
+
 #[ignore]
 #[tokio::test]
 async fn test_ping_thing() {
@@ -288,6 +774,7 @@ async fn test_ping_thing() {
     let ping_thing = PingThing {
         cluster: PingThingCluster::Mainnet,
         va_api_key: token,
+        sample_rate: 1,
     };
 
     ping_thing
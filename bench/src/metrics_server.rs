@@ -0,0 +1,76 @@
+use std::net::SocketAddr;
+
+use jsonrpsee::{
+    core::RpcResult,
+    server::{ServerBuilder, ServerHandle},
+    types::{error::ErrorCode, ErrorObject},
+    RpcModule,
+};
+
+use crate::metrics::{Metric, SharedMetric};
+
+/// serves `getMetric` with the in-flight metric of a run (index of `metrics`, 0 if not given),
+/// finalized on every request so a dashboard can poll it before the run ends
+pub async fn start_metrics_server(
+    addr: SocketAddr,
+    metrics: Vec<SharedMetric>,
+) -> anyhow::Result<(SocketAddr, ServerHandle)> {
+    let mut module = RpcModule::new(metrics);
+    module.register_method("getMetric", |params, metrics| -> RpcResult<Metric> {
+        let run = params.sequence().optional_next::<usize>()?.unwrap_or(0);
+        let Some(metric) = metrics.get(run) else {
+            return Err(ErrorObject::owned(
+                ErrorCode::InvalidParams.code(),
+                format!("no run {run}, {} run(s) in progress", metrics.len()),
+                None::<()>,
+            ));
+        };
+        let mut snapshot = metric.lock().unwrap().clone();
+        snapshot.finalize();
+        Ok(snapshot)
+    })?;
+
+    let server = ServerBuilder::default().build(addr).await?;
+    let local_addr = server.local_addr()?;
+    Ok((local_addr, server.start(module)))
+}
+
+#[tokio::test]
+async fn test_get_metric_returns_current_metric() {
+    let metric = SharedMetric::default();
+    let (addr, server) = start_metrics_server("127.0.0.1:0".parse().unwrap(), vec![metric.clone()])
+        .await
+        .unwrap();
+
+    // run still in progress
+    {
+        let mut metric = metric.lock().unwrap();
+        metric.add_successful_transaction(
+            std::time::Duration::from_millis(4),
+            std::time::Duration::from_millis(400),
+            100,
+        );
+        metric.add_unsuccessful_transaction(std::time::Duration::from_millis(4), 100);
+    }
+    let mut expected = metric.lock().unwrap().clone();
+    expected.finalize();
+
+    let response: serde_json::Value = reqwest::Client::new()
+        .post(format!("http://{addr}"))
+        .json(&serde_json::json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "method": "getMetric",
+            "params": [],
+        }))
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(response["result"], serde_json::to_value(&expected).unwrap());
+    assert_eq!(response["result"]["txs_confirmed"], 1);
+
+    server.stop().unwrap();
+}
@@ -0,0 +1,433 @@
+use crate::metrics::Metric;
+use anyhow::Context;
+use std::fs::{File, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+/// output format of the bench results, see `--output-format`
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum OutputFormat {
+    /// one row per run followed by the average row
+    #[default]
+    Csv,
+    /// pretty printed json with all runs and the average
+    Json,
+    /// prometheus textfile (node_exporter textfile collector) of the average metric
+    Prometheus,
+}
+
+impl OutputFormat {
+    /// appending a json or prometheus output to an existing file results in an invalid document
+    pub fn check_csv_options(&self, csv_options: &CsvOptions) -> anyhow::Result<()> {
+        if csv_options.append && *self != OutputFormat::Csv {
+            anyhow::bail!("appending is only supported for the csv output format");
+        }
+        Ok(())
+    }
+}
+
+/// field delimiter of the csv outputs, see `--csv-delimiter`
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum CsvDelimiter {
+    #[default]
+    Comma,
+    Tab,
+}
+
+impl CsvDelimiter {
+    fn as_byte(&self) -> u8 {
+        match self {
+            CsvDelimiter::Comma => b',',
+            CsvDelimiter::Tab => b'\t',
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct CsvOptions {
+    pub delimiter: CsvDelimiter,
+    pub has_headers: bool,
+    /// append to an existing file instead of truncating it, usually combined with `has_headers: false`
+    pub append: bool,
+}
+
+impl Default for CsvOptions {
+    fn default() -> Self {
+        Self {
+            delimiter: CsvDelimiter::Comma,
+            has_headers: true,
+            append: false,
+        }
+    }
+}
+
+impl CsvOptions {
+    pub fn writer_builder(&self) -> csv::WriterBuilder {
+        let mut builder = csv::WriterBuilder::new();
+        builder
+            .delimiter(self.delimiter.as_byte())
+            .has_headers(self.has_headers);
+        builder
+    }
+
+    /// opens (or creates) the output file according to `append`
+    pub fn open_file(&self, path: impl AsRef<Path>) -> anyhow::Result<File> {
+        let path = path.as_ref();
+        OpenOptions::new()
+            .write(true)
+            .create(true)
+            .append(self.append)
+            .truncate(!self.append)
+            .open(path)
+            .with_context(|| format!("failed to open csv output file {}", path.display()))
+    }
+
+    pub fn create_writer(&self, path: impl AsRef<Path>) -> anyhow::Result<csv::Writer<File>> {
+        Ok(self.writer_builder().from_writer(self.open_file(path)?))
+    }
+
+    /// writes to `<path>.tmp` which is renamed to `path` only if `write` succeeds,
+    /// on error the temporary file is removed and `path` is left untouched
+    pub fn write_file_atomically(
+        &self,
+        path: impl AsRef<Path>,
+        write: impl FnOnce(File) -> anyhow::Result<()>,
+    ) -> anyhow::Result<()> {
+        let path = path.as_ref();
+        let tmp_path = tmp_path(path);
+        let result = self.write_and_rename(path, &tmp_path, write);
+        if result.is_err() {
+            let _ = std::fs::remove_file(&tmp_path);
+        }
+        result
+    }
+
+    /// writes one csv row per item with `write_file_atomically`
+    pub fn write_rows_atomically<T: serde::Serialize>(
+        &self,
+        path: impl AsRef<Path>,
+        rows: impl IntoIterator<Item = T>,
+    ) -> anyhow::Result<()> {
+        self.write_file_atomically(path, |file| {
+            let mut csv_writer = self.writer_builder().from_writer(file);
+            for row in rows {
+                csv_writer.serialize(row)?;
+            }
+            csv_writer.flush()?;
+            Ok(())
+        })
+    }
+
+    fn write_and_rename(
+        &self,
+        path: &Path,
+        tmp_path: &Path,
+        write: impl FnOnce(File) -> anyhow::Result<()>,
+    ) -> anyhow::Result<()> {
+        if self.append && path.exists() {
+            std::fs::copy(path, tmp_path)
+                .with_context(|| format!("failed to copy {}", path.display()))?;
+        }
+        write(self.open_file(tmp_path)?)?;
+        std::fs::rename(tmp_path, path)
+            .with_context(|| format!("failed to rename {}", tmp_path.display()))
+    }
+}
+
+fn tmp_path(path: &Path) -> PathBuf {
+    let mut tmp_path = path.as_os_str().to_owned();
+    tmp_path.push(".tmp");
+    PathBuf::from(tmp_path)
+}
+
+const PROMETHEUS_METRIC_PREFIX: &str = "literpc_bench";
+
+#[derive(serde::Serialize)]
+struct JsonOutput<'a> {
+    runs: &'a [Metric],
+    average: &'a Metric,
+}
+
+pub fn write_metrics(
+    writer: impl Write,
+    format: OutputFormat,
+    csv_options: &CsvOptions,
+    run_metrics: &[Metric],
+    avg_metric: &Metric,
+) -> anyhow::Result<()> {
+    format.check_csv_options(csv_options)?;
+    match format {
+        OutputFormat::Csv => write_csv(writer, csv_options, run_metrics, avg_metric),
+        OutputFormat::Json => write_json(writer, run_metrics, avg_metric),
+        OutputFormat::Prometheus => write_prometheus(writer, avg_metric),
+    }
+}
+
+fn write_csv(
+    writer: impl Write,
+    csv_options: &CsvOptions,
+    run_metrics: &[Metric],
+    avg_metric: &Metric,
+) -> anyhow::Result<()> {
+    let mut csv_writer = csv_options.writer_builder().from_writer(writer);
+    for metric in run_metrics {
+        csv_writer.serialize(metric)?;
+    }
+    csv_writer.serialize(avg_metric)?;
+    csv_writer.flush()?;
+    Ok(())
+}
+
+fn write_json(
+    mut writer: impl Write,
+    run_metrics: &[Metric],
+    avg_metric: &Metric,
+) -> anyhow::Result<()> {
+    let output = JsonOutput {
+        runs: run_metrics,
+        average: avg_metric,
+    };
+    serde_json::to_writer_pretty(&mut writer, &output)?;
+    writeln!(writer)?;
+    writer.flush()?;
+    Ok(())
+}
+
+fn write_prometheus(mut writer: impl Write, avg_metric: &Metric) -> anyhow::Result<()> {
+    // go through serde to export exactly the fields that are serialized for csv and json
+    let serde_json::Value::Object(fields) = serde_json::to_value(avg_metric)? else {
+        anyhow::bail!("metric must serialize to an object");
+    };
+    for (name, value) in fields {
+        let Some(value) = value.as_f64() else {
+            continue;
+        };
+        writeln!(writer, "# TYPE {PROMETHEUS_METRIC_PREFIX}_{name} gauge")?;
+        writeln!(writer, "{PROMETHEUS_METRIC_PREFIX}_{name} {value}")?;
+    }
+    writer.flush()?;
+    Ok(())
+}
+
+#[cfg(test)]
+fn bench1_result() -> Metric {
+    let mut metric = Metric::default();
+    for ms in [100, 200, 300] {
+        metric.add_successful_transaction(
+            std::time::Duration::from_millis(5),
+            std::time::Duration::from_millis(ms),
+            233,
+        );
+    }
+    metric.add_unsuccessful_transaction(std::time::Duration::from_millis(5), 233);
+    metric.set_total_gross_send_time(20.0);
+    metric.set_total_elapsed_time(1_000.0);
+    metric.finalize();
+    metric
+}
+
+#[test]
+fn csv_output_roundtrip() {
+    let metric = bench1_result();
+    let mut out = vec![];
+    write_metrics(
+        &mut out,
+        OutputFormat::Csv,
+        &CsvOptions::default(),
+        &[metric.clone()],
+        &metric,
+    )
+    .unwrap();
+
+    let mut reader = csv::Reader::from_reader(out.as_slice());
+    let headers = reader.headers().unwrap().clone();
+    let rows = reader.records().map(|r| r.unwrap()).collect::<Vec<_>>();
+    assert_eq!(rows.len(), 2);
+    let txs_confirmed = headers.iter().position(|h| h == "txs_confirmed").unwrap();
+    assert_eq!(&rows[0][txs_confirmed], "3");
+    let p50 = headers
+        .iter()
+        .position(|h| h == "p50_confirmation_time_ms")
+        .unwrap();
+    assert_eq!(rows[1][p50].parse::<f64>().unwrap(), 200.0);
+}
+
+#[test]
+fn json_output_roundtrip() {
+    let metric = bench1_result();
+    let mut out = vec![];
+    write_metrics(
+        &mut out,
+        OutputFormat::Json,
+        &CsvOptions::default(),
+        &[metric.clone()],
+        &metric,
+    )
+    .unwrap();
+
+    let json: serde_json::Value = serde_json::from_slice(&out).unwrap();
+    let expected = serde_json::to_value(&metric).unwrap();
+    assert_eq!(json["average"], expected);
+    assert_eq!(json["runs"][0], expected);
+    assert_eq!(json["average"]["txs_sent"], 4);
+    assert_eq!(json["average"]["confirmed_tps"], 3.0);
+}
+
+#[test]
+fn prometheus_output_roundtrip() {
+    let metric = bench1_result();
+    let mut out = vec![];
+    write_metrics(
+        &mut out,
+        OutputFormat::Prometheus,
+        &CsvOptions::default(),
+        &[],
+        &metric,
+    )
+    .unwrap();
+
+    let text = String::from_utf8(out).unwrap();
+    let samples = text
+        .lines()
+        .filter(|l| !l.starts_with('#'))
+        .map(|l| {
+            let (name, value) = l.split_once(' ').unwrap();
+            (name.to_string(), value.parse::<f64>().unwrap())
+        })
+        .collect::<std::collections::HashMap<_, _>>();
+    let serde_json::Value::Object(fields) = serde_json::to_value(&metric).unwrap() else {
+        unreachable!()
+    };
+    assert_eq!(samples.len(), fields.len());
+    assert_eq!(samples["literpc_bench_txs_sent"], 4.0);
+    assert_eq!(samples["literpc_bench_txs_un_confirmed"], 1.0);
+    assert_eq!(samples["literpc_bench_p90_confirmation_time_ms"], 300.0);
+}
+
+#[test]
+fn append_is_rejected_for_non_csv_formats() {
+    let metric = bench1_result();
+    let csv_options = CsvOptions {
+        has_headers: false,
+        append: true,
+        ..CsvOptions::default()
+    };
+    for format in [OutputFormat::Json, OutputFormat::Prometheus] {
+        let mut out = vec![];
+        let err = write_metrics(&mut out, format, &csv_options, &[], &metric).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "appending is only supported for the csv output format"
+        );
+        assert!(out.is_empty());
+    }
+
+    let mut out = vec![];
+    write_metrics(&mut out, OutputFormat::Csv, &csv_options, &[], &metric).unwrap();
+    assert_eq!(out.iter().filter(|b| **b == b'\n').count(), 1);
+}
+
+#[test]
+fn csv_tab_delimited_headerless_append() {
+    let path = std::env::temp_dir().join(format!(
+        "bench-csv-append-{}-{}.csv",
+        std::process::id(),
+        rand::random::<u64>()
+    ));
+    std::fs::write(&path, "signature\tsent_slot\nexisting\t1\n").unwrap();
+
+    let csv_options = CsvOptions {
+        delimiter: CsvDelimiter::Tab,
+        has_headers: false,
+        append: true,
+    };
+    let mut csv_writer = csv_options.create_writer(&path).unwrap();
+    csv_writer
+        .serialize(crate::metrics::TxMetricData {
+            signature: "sig".to_string(),
+            sent_slot: 2,
+            confirmed_slot: 3,
+            time_to_send_in_millis: 4,
+            time_to_confirm_in_millis: 5,
+        })
+        .unwrap();
+    csv_writer.flush().unwrap();
+    drop(csv_writer);
+
+    let written = std::fs::read(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!(
+        written,
+        b"signature\tsent_slot\nexisting\t1\nsig\t2\t3\t4\t5\n".to_vec()
+    );
+}
+
+#[test]
+fn csv_bad_path_is_an_error() {
+    let path = std::env::temp_dir()
+        .join("bench-csv-does-not-exist")
+        .join("metrics.csv");
+    let err = CsvOptions::default().create_writer(&path).unwrap_err();
+    assert!(err.to_string().contains("failed to open csv output file"));
+}
+
+#[test]
+fn failed_write_leaves_no_partial_output() {
+    let path = std::env::temp_dir().join(format!(
+        "bench-metrics-atomic-{}-{}.csv",
+        std::process::id(),
+        rand::random::<u64>()
+    ));
+
+    let err = CsvOptions::default()
+        .write_file_atomically(&path, |mut file| {
+            file.write_all(b"txs_sent,txs_confirmed\n4,")?;
+            anyhow::bail!("run failed")
+        })
+        .unwrap_err();
+    assert_eq!(err.to_string(), "run failed");
+    assert!(!path.exists());
+    assert!(!tmp_path(&path).exists());
+
+    // appending run fails, the existing output is kept as is
+    std::fs::write(&path, "txs_sent\n4\n").unwrap();
+    let csv_options = CsvOptions {
+        has_headers: false,
+        append: true,
+        ..CsvOptions::default()
+    };
+    csv_options
+        .write_file_atomically(&path, |mut file| {
+            file.write_all(b"5")?;
+            anyhow::bail!("run failed")
+        })
+        .unwrap_err();
+    assert_eq!(std::fs::read_to_string(&path).unwrap(), "txs_sent\n4\n");
+
+    csv_options
+        .write_file_atomically(&path, |mut file| Ok(file.write_all(b"5\n")?))
+        .unwrap();
+    let written = std::fs::read_to_string(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!(written, "txs_sent\n4\n5\n");
+    assert!(!tmp_path(&path).exists());
+}
+
+#[test]
+fn rows_written_atomically() {
+    let path = std::env::temp_dir().join(format!(
+        "bench-rows-atomic-{}-{}.csv",
+        std::process::id(),
+        rand::random::<u64>()
+    ));
+
+    CsvOptions::default()
+        .write_rows_atomically(&path, [bench1_result(), bench1_result()])
+        .unwrap();
+    let written = std::fs::read_to_string(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    // header + one line per row
+    assert_eq!(written.lines().count(), 3);
+    assert!(written.starts_with("txs_sent,"));
+    assert!(!tmp_path(&path).exists());
+}
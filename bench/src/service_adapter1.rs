@@ -1,7 +1,7 @@
 // adapter code for all from benchrunner-service
 
 use crate::bench1;
-use crate::bench1::TransactionSize;
+use crate::bench1::{BenchCommitment, TransactionSize};
 use crate::metrics::{Metric, TxMetricData};
 use crate::tx_size::TxSize;
 use log::debug;
@@ -96,6 +96,10 @@ pub async fn bench_servicerunner(
             false, // log_transactions
             transaction_size,
             bench_config.cu_price_micro_lamports,
+            Duration::ZERO,
+            None,
+            BenchCommitment::Confirmed,
+            None,
         )
         .await
     }
@@ -1,3 +1,4 @@
+use crate::bench1::BenchCommitment;
 use crate::benches::confirmation_rate;
 use crate::benches::confirmation_rate::send_bulk_txs_and_wait;
 use crate::service_adapter1::BenchConfig;
@@ -32,6 +33,7 @@ pub async fn benchnew_confirmation_rate_servicerunner(
         bench_config.tx_count,
         &tx_params,
         max_timeout,
+        BenchCommitment::Confirmed,
     )
     .await;
     result.unwrap_or_else(|err| {
@@ -0,0 +1,542 @@
+use std::collections::{BTreeMap, VecDeque};
+use std::future::Future;
+use std::ops::RangeInclusive;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use log::{debug, error, warn};
+use prometheus::{opts, register_int_counter, register_int_gauge, IntCounter, IntGauge};
+use solana_lite_rpc_core::structures::epoch::EpochCache;
+use solana_lite_rpc_core::structures::produced_block::ProducedBlock;
+use solana_lite_rpc_core::types::BlockStream;
+use solana_lite_rpc_core::AnyhowJoinHandle;
+use solana_sdk::slot_history::Slot;
+use tokio::sync::broadcast::{self, error::RecvError, Receiver};
+
+use crate::history::{backfill_blocks, BackfillBlockStore};
+
+lazy_static::lazy_static! {
+    static ref BLOCK_SAVE_RETRIES: IntCounter =
+        register_int_counter!(opts!("literpc_blockstore_block_save_retries", "Number of retried block saves")).unwrap();
+    static ref BLOCKS_DEAD_LETTERED: IntCounter =
+        register_int_counter!(opts!("literpc_blockstore_blocks_dead_lettered", "Number of blocks which could not be persisted")).unwrap();
+    static ref BLOCK_RETRY_QUEUE_SIZE: IntGauge =
+        register_int_gauge!(opts!("literpc_blockstore_block_retry_queue_size", "Number of blocks waiting for a retry")).unwrap();
+    static ref HISTORY_GAP_SLOTS: IntGauge =
+        register_int_gauge!(opts!("literpc_blockstore_history_gap_slots", "Number of slots missing from the persistent history")).unwrap();
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct BlockPersistRetryConfig {
+    /// including the first attempt
+    pub max_attempts: u32,
+    pub initial_backoff: Duration,
+    pub max_backoff: Duration,
+    /// the oldest block is dead-lettered if the queue is full
+    pub queue_capacity: usize,
+}
+
+impl Default for BlockPersistRetryConfig {
+    fn default() -> Self {
+        Self {
+            max_attempts: 5,
+            initial_backoff: Duration::from_millis(500),
+            max_backoff: Duration::from_secs(30),
+            queue_capacity: 256,
+        }
+    }
+}
+
+struct PendingBlock {
+    block: ProducedBlock,
+    attempts: u32,
+    retry_at: Instant,
+}
+
+/// Saves blocks and retries failed saves with exponential backoff, e.g. during a transient database outage.
+/// Blocks which still fail after `max_attempts` are sent to the dead-letter channel.
+pub struct BlockPersistRetryQueue {
+    writer: Arc<dyn BackfillBlockStore>,
+    config: BlockPersistRetryConfig,
+    pending: VecDeque<PendingBlock>,
+    dead_letter_sender: broadcast::Sender<ProducedBlock>,
+}
+
+impl BlockPersistRetryQueue {
+    pub fn new(
+        writer: Arc<dyn BackfillBlockStore>,
+        config: BlockPersistRetryConfig,
+    ) -> (Self, Receiver<ProducedBlock>) {
+        assert!(
+            config.max_attempts > 0,
+            "max_attempts must be greater than 0"
+        );
+        let (dead_letter_sender, dead_letter_receiver) = broadcast::channel(64);
+        (
+            Self {
+                writer,
+                config,
+                pending: VecDeque::with_capacity(config.queue_capacity),
+                dead_letter_sender,
+            },
+            dead_letter_receiver,
+        )
+    }
+
+    pub fn pending_len(&self) -> usize {
+        self.pending.len()
+    }
+
+    pub async fn save_block(&mut self, block: ProducedBlock, now: Instant) {
+        if let Err(err) = self.writer.save_block(&block).await {
+            warn!(
+                "Failed to save block {}@{} - will retry: {err:?}",
+                block.slot, block.commitment_config.commitment
+            );
+            self.schedule_retry(block, 1, now);
+        }
+    }
+
+    /// retries all blocks whose backoff elapsed
+    pub async fn retry_due(&mut self, now: Instant) {
+        let mut not_due = VecDeque::with_capacity(self.pending.len());
+        while let Some(pending) = self.pending.pop_front() {
+            if pending.retry_at > now {
+                not_due.push_back(pending);
+                continue;
+            }
+            BLOCK_SAVE_RETRIES.inc();
+            match self.writer.save_block(&pending.block).await {
+                Ok(()) => {
+                    debug!(
+                        "Saved block {} after {} attempts",
+                        pending.block.slot,
+                        pending.attempts + 1
+                    );
+                }
+                Err(err) => {
+                    warn!(
+                        "Retry {} of block {} failed: {err:?}",
+                        pending.attempts, pending.block.slot
+                    );
+                    let attempts = pending.attempts + 1;
+                    if attempts >= self.config.max_attempts {
+                        self.dead_letter(pending.block);
+                    } else {
+                        not_due.push_back(PendingBlock {
+                            block: pending.block,
+                            attempts,
+                            retry_at: now + self.backoff(attempts),
+                        });
+                    }
+                }
+            }
+        }
+        self.pending = not_due;
+        BLOCK_RETRY_QUEUE_SIZE.set(self.pending.len() as i64);
+    }
+
+    fn schedule_retry(&mut self, block: ProducedBlock, attempts: u32, now: Instant) {
+        if attempts >= self.config.max_attempts {
+            self.dead_letter(block);
+            return;
+        }
+        if self.pending.len() >= self.config.queue_capacity {
+            if let Some(oldest) = self.pending.pop_front() {
+                self.dead_letter(oldest.block);
+            }
+        }
+        self.pending.push_back(PendingBlock {
+            block,
+            attempts,
+            retry_at: now + self.backoff(attempts),
+        });
+        BLOCK_RETRY_QUEUE_SIZE.set(self.pending.len() as i64);
+    }
+
+    fn backoff(&self, attempts: u32) -> Duration {
+        self.config
+            .initial_backoff
+            .saturating_mul(2u32.saturating_pow(attempts.saturating_sub(1)))
+            .min(self.config.max_backoff)
+    }
+
+    fn dead_letter(&self, block: ProducedBlock) {
+        error!(
+            "Giving up on saving block {}@{} - persistent history has a gap",
+            block.slot, block.commitment_config.commitment
+        );
+        BLOCKS_DEAD_LETTERED.inc();
+        // no receivers is fine, the counter is still incremented
+        let _ = self.dead_letter_sender.send(block);
+    }
+}
+
+/// persists the confirmed blocks of the stream, returns the dead-letter channel
+pub fn start_block_persist_task(
+    mut blocks: BlockStream,
+    writer: Arc<dyn BackfillBlockStore>,
+    config: BlockPersistRetryConfig,
+) -> (Receiver<ProducedBlock>, AnyhowJoinHandle) {
+    let (mut retry_queue, dead_letter_receiver) = BlockPersistRetryQueue::new(writer, config);
+    let jh = tokio::spawn(async move {
+        let mut retry_tick = tokio::time::interval(config.initial_backoff);
+        loop {
+            tokio::select! {
+                block = blocks.recv() => {
+                    match block {
+                        Ok(block) => {
+                            if !block.commitment_config.is_confirmed() {
+                                continue;
+                            }
+                            retry_queue.save_block(block, Instant::now()).await;
+                        }
+                        Err(RecvError::Lagged(missed)) => {
+                            warn!("Block persist task lagged behind and missed {missed} blocks");
+                        }
+                        Err(RecvError::Closed) => {
+                            anyhow::bail!("block stream closed");
+                        }
+                    }
+                }
+                _ = retry_tick.tick() => {
+                    retry_queue.retry_due(Instant::now()).await;
+                }
+            }
+        }
+    });
+    (dead_letter_receiver, jh)
+}
+
+/// oldest ranges are dropped beyond this, e.g. during a long database outage
+const MAX_HISTORY_GAP_RANGES: usize = 1024;
+
+/// Slots missing from the persistent history, merged to ranges which can be passed to `backfill_blocks`
+#[derive(Debug)]
+pub struct HistoryGaps {
+    /// first slot -> last slot of the range
+    ranges: BTreeMap<Slot, Slot>,
+    max_ranges: usize,
+    slot_count: u64,
+}
+
+impl Default for HistoryGaps {
+    fn default() -> Self {
+        Self::with_max_ranges(MAX_HISTORY_GAP_RANGES)
+    }
+}
+
+impl HistoryGaps {
+    pub fn with_max_ranges(max_ranges: usize) -> Self {
+        assert!(max_ranges > 0, "max_ranges must be greater than 0");
+        Self {
+            ranges: BTreeMap::new(),
+            max_ranges,
+            slot_count: 0,
+        }
+    }
+
+    /// returns false if the slot is already a known gap
+    pub fn insert(&mut self, slot: Slot) -> bool {
+        let mut first = slot;
+        let mut last = slot;
+        if let Some((&prev_first, &prev_last)) = self.ranges.range(..=slot).next_back() {
+            if prev_last >= slot {
+                return false;
+            }
+            if prev_last + 1 == slot {
+                first = prev_first;
+            }
+        }
+        if let Some(next_last) = self.ranges.remove(&(slot + 1)) {
+            last = next_last;
+        }
+        self.ranges.insert(first, last);
+        self.slot_count += 1;
+
+        while self.ranges.len() > self.max_ranges {
+            let (dropped_first, dropped_last) =
+                self.ranges.pop_first().expect("ranges are not empty");
+            self.slot_count -= dropped_last - dropped_first + 1;
+            warn!(
+                "Too many gaps in the persistent history, forgetting slots {dropped_first}..={dropped_last}"
+            );
+        }
+        true
+    }
+
+    pub fn ranges(&self) -> Vec<RangeInclusive<Slot>> {
+        self.ranges
+            .iter()
+            .map(|(first, last)| *first..=*last)
+            .collect()
+    }
+
+    pub fn first_range(&self) -> Option<RangeInclusive<Slot>> {
+        self.ranges
+            .first_key_value()
+            .map(|(first, last)| *first..=*last)
+    }
+
+    /// removes the range starting at `first` once it was backfilled
+    pub fn remove_range(&mut self, first: Slot) {
+        if let Some(last) = self.ranges.remove(&first) {
+            self.slot_count -= last - first + 1;
+        }
+    }
+
+    pub fn range_count(&self) -> usize {
+        self.ranges.len()
+    }
+
+    /// number of missing slots in all ranges
+    pub fn slot_count(&self) -> u64 {
+        self.slot_count
+    }
+}
+
+/// drains the dead-letter channel and backfills the gaps of the persistent history from upstream
+/// once per `backfill_interval`, e.g. after the database outage which caused the gaps is over
+pub fn start_dead_letter_task<F, Fut>(
+    mut dead_letters: Receiver<ProducedBlock>,
+    block_store: Arc<dyn BackfillBlockStore>,
+    epoch_cache: EpochCache,
+    backfill_interval: Duration,
+    mut fetch_block: F,
+) -> AnyhowJoinHandle
+where
+    F: FnMut(Slot) -> Fut + Send + 'static,
+    Fut: Future<Output = anyhow::Result<Option<ProducedBlock>>> + Send,
+{
+    tokio::spawn(async move {
+        let mut gaps = HistoryGaps::default();
+        let mut backfill_tick = tokio::time::interval(backfill_interval);
+        loop {
+            tokio::select! {
+                block = dead_letters.recv() => {
+                    match block {
+                        Ok(block) => {
+                            if !gaps.insert(block.slot) {
+                                continue;
+                            }
+                            HISTORY_GAP_SLOTS.set(gaps.slot_count() as i64);
+                            error!(
+                                "Persistent history is missing slot {} - {} slots in {} ranges need a backfill",
+                                block.slot,
+                                gaps.slot_count(),
+                                gaps.range_count()
+                            );
+                        }
+                        Err(RecvError::Lagged(missed)) => {
+                            warn!("Dead-letter task lagged behind and missed {missed} blocks - gaps are incomplete");
+                        }
+                        Err(RecvError::Closed) => {
+                            anyhow::bail!("dead-letter channel closed");
+                        }
+                    }
+                }
+                _ = backfill_tick.tick() => {
+                    let Some(slots) = gaps.first_range() else {
+                        continue;
+                    };
+                    match backfill_blocks(block_store.as_ref(), &epoch_cache, slots.clone(), &mut fetch_block).await {
+                        Ok(_) => {
+                            gaps.remove_range(*slots.start());
+                            HISTORY_GAP_SLOTS.set(gaps.slot_count() as i64);
+                        }
+                        Err(err) => {
+                            warn!("Backfill of slots {slots:?} failed - will retry: {err:?}");
+                        }
+                    }
+                }
+            }
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Mutex;
+
+    use async_trait::async_trait;
+    use solana_lite_rpc_core::structures::epoch::EpochRef;
+    use solana_lite_rpc_core::structures::produced_block::{BlockStats, ProducedBlockInner};
+    use solana_sdk::commitment_config::CommitmentConfig;
+    use solana_sdk::hash::Hash;
+
+    use super::*;
+
+    /// fails the first `fail_first` saves
+    struct FlakyWriter {
+        fail_first: usize,
+        attempts: AtomicUsize,
+        saved: Mutex<Vec<Slot>>,
+    }
+
+    #[async_trait]
+    impl BackfillBlockStore for FlakyWriter {
+        async fn get_highest_slot_in_range(
+            &self,
+            _slots: &RangeInclusive<Slot>,
+        ) -> anyhow::Result<Option<Slot>> {
+            Ok(None)
+        }
+
+        async fn ensure_epoch_schema(&self, _epoch: EpochRef) -> anyhow::Result<()> {
+            Ok(())
+        }
+
+        async fn save_block(&self, block: &ProducedBlock) -> anyhow::Result<()> {
+            if self.attempts.fetch_add(1, Ordering::Relaxed) < self.fail_first {
+                anyhow::bail!("connection refused");
+            }
+            self.saved.lock().unwrap().push(block.slot);
+            Ok(())
+        }
+    }
+
+    fn create_test_block(slot: Slot) -> ProducedBlock {
+        ProducedBlock::new(
+            ProducedBlockInner {
+                transactions: vec![],
+                leader_id: None,
+                blockhash: Hash::new_unique(),
+                block_height: slot,
+                slot,
+                parent_slot: slot - 1,
+                block_time: 1_700_000_000,
+                previous_blockhash: Hash::new_unique(),
+                rewards: None,
+                stats: BlockStats::default(),
+            },
+            CommitmentConfig::confirmed(),
+        )
+    }
+
+    #[tokio::test]
+    async fn test_failed_save_is_retried() {
+        let writer = Arc::new(FlakyWriter {
+            fail_first: 1,
+            attempts: AtomicUsize::new(0),
+            saved: Mutex::new(vec![]),
+        });
+        let config = BlockPersistRetryConfig::default();
+        let (mut retry_queue, mut dead_letters) =
+            BlockPersistRetryQueue::new(writer.clone(), config);
+
+        let started = Instant::now();
+        retry_queue.save_block(create_test_block(42), started).await;
+        assert!(writer.saved.lock().unwrap().is_empty());
+        assert_eq!(retry_queue.pending_len(), 1);
+
+        // backoff not elapsed yet
+        retry_queue.retry_due(started).await;
+        assert_eq!(writer.attempts.load(Ordering::Relaxed), 1);
+
+        retry_queue
+            .retry_due(started + config.initial_backoff)
+            .await;
+        assert_eq!(*writer.saved.lock().unwrap(), vec![42]);
+        assert_eq!(retry_queue.pending_len(), 0);
+
+        // normal insertion resumes
+        retry_queue
+            .save_block(create_test_block(43), started + config.initial_backoff)
+            .await;
+        assert_eq!(*writer.saved.lock().unwrap(), vec![42, 43]);
+        assert!(dead_letters.try_recv().is_err());
+    }
+
+    #[tokio::test]
+    async fn test_dead_letter_after_max_attempts() {
+        let writer = Arc::new(FlakyWriter {
+            fail_first: usize::MAX,
+            attempts: AtomicUsize::new(0),
+            saved: Mutex::new(vec![]),
+        });
+        let config = BlockPersistRetryConfig {
+            max_attempts: 3,
+            ..Default::default()
+        };
+        let (mut retry_queue, mut dead_letters) = BlockPersistRetryQueue::new(writer, config);
+
+        let started = Instant::now();
+        retry_queue.save_block(create_test_block(42), started).await;
+        retry_queue
+            .retry_due(started + config.initial_backoff)
+            .await;
+        assert_eq!(retry_queue.pending_len(), 1);
+        retry_queue.retry_due(started + config.max_backoff).await;
+
+        assert_eq!(retry_queue.pending_len(), 0);
+        assert_eq!(dead_letters.try_recv().unwrap().slot, 42);
+    }
+
+    #[test]
+    fn test_history_gaps_merged_to_ranges() {
+        let mut gaps = HistoryGaps::default();
+        for slot in [10, 12, 20, 11, 12, 9, 21] {
+            gaps.insert(slot);
+        }
+        assert_eq!(gaps.ranges(), vec![9..=12, 20..=21]);
+        assert_eq!(gaps.slot_count(), 6);
+    }
+
+    #[test]
+    fn test_history_gaps_drop_oldest_ranges() {
+        let mut gaps = HistoryGaps::with_max_ranges(2);
+        assert!(gaps.insert(10));
+        assert!(gaps.insert(11));
+        assert!(gaps.insert(20));
+        assert!(!gaps.insert(20));
+        assert!(gaps.insert(30));
+        assert_eq!(gaps.ranges(), vec![20..=20, 30..=30]);
+        assert_eq!(gaps.slot_count(), 2);
+
+        // merging does not add a range
+        assert!(gaps.insert(21));
+        assert_eq!(gaps.ranges(), vec![20..=21, 30..=30]);
+        assert_eq!(gaps.slot_count(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_dead_lettered_slot_is_backfilled() {
+        let writer = Arc::new(FlakyWriter {
+            fail_first: 0,
+            attempts: AtomicUsize::new(0),
+            saved: Mutex::new(vec![]),
+        });
+        let (dead_letter_sender, dead_letters) = broadcast::channel(8);
+        let _dead_letter_task = start_dead_letter_task(
+            dead_letters,
+            writer.clone(),
+            EpochCache::new_for_tests(),
+            Duration::from_millis(10),
+            |slot| async move { Ok(Some(create_test_block(slot))) },
+        );
+
+        dead_letter_sender.send(create_test_block(42)).unwrap();
+
+        tokio::time::timeout(Duration::from_secs(5), async {
+            while writer.saved.lock().unwrap().is_empty() {
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+        })
+        .await
+        .expect("dead-lettered slot should be backfilled");
+        assert_eq!(*writer.saved.lock().unwrap(), vec![42]);
+    }
+
+    #[test]
+    fn test_history_gaps_remove_backfilled_range() {
+        let mut gaps = HistoryGaps::default();
+        for slot in [10, 11, 20] {
+            gaps.insert(slot);
+        }
+        assert_eq!(gaps.first_range(), Some(10..=11));
+        gaps.remove_range(10);
+        assert_eq!(gaps.first_range(), Some(20..=20));
+        assert_eq!(gaps.slot_count(), 1);
+    }
+}
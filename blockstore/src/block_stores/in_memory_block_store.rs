@@ -0,0 +1,63 @@
+use std::collections::BTreeMap;
+use std::sync::{Arc, RwLock};
+
+use log::trace;
+use solana_lite_rpc_core::structures::produced_block::ProducedBlock;
+use solana_sdk::slot_history::Slot;
+
+/// Keeps the blocks of the most recent `retention_slots` slots in memory, older slots are evicted
+/// and must be served from persistent storage.
+/// Only confirmed and finalized blocks are kept, a block is replaced when its commitment level is raised.
+#[derive(Clone)]
+pub struct InMemoryBlockStore {
+    retention_slots: u64,
+    blocks: Arc<RwLock<BTreeMap<Slot, ProducedBlock>>>,
+}
+
+impl InMemoryBlockStore {
+    pub fn new(retention_slots: u64) -> Self {
+        assert!(
+            retention_slots > 0,
+            "retention_slots must be greater than 0"
+        );
+        Self {
+            retention_slots,
+            blocks: Arc::new(RwLock::new(BTreeMap::new())),
+        }
+    }
+
+    pub fn add_block(&self, block: ProducedBlock) {
+        if !block.commitment_config.is_at_least_confirmed() {
+            return;
+        }
+        let mut blocks = self.blocks.write().unwrap();
+        let highest_slot = blocks
+            .last_key_value()
+            .map(|(slot, _)| *slot)
+            .unwrap_or_default()
+            .max(block.slot);
+        let lowest_retained_slot = (highest_slot + 1).saturating_sub(self.retention_slots);
+        if block.slot < lowest_retained_slot {
+            return;
+        }
+        blocks.insert(block.slot, block);
+
+        let retained = blocks.split_off(&lowest_retained_slot);
+        let cnt_evicted = std::mem::replace(&mut *blocks, retained).len();
+        if cnt_evicted > 0 {
+            trace!("evicted {cnt_evicted} blocks older than slot {lowest_retained_slot}");
+        }
+    }
+
+    pub fn get_block(&self, slot: Slot) -> Option<ProducedBlock> {
+        self.blocks.read().unwrap().get(&slot).cloned()
+    }
+
+    pub fn len(&self) -> usize {
+        self.blocks.read().unwrap().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
@@ -1,3 +1,6 @@
+pub mod block_persist_retry;
 pub mod faithful_history;
+pub mod in_memory_block_store;
 pub mod multiple_strategy_block_store;
 pub mod postgres;
+pub mod signature_slot_index;
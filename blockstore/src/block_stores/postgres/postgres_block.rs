@@ -1,16 +1,51 @@
 use super::postgres_epoch::PostgresEpoch;
 use super::postgres_session::PostgresSession;
+use anyhow::Context;
 use log::{debug, warn};
 use solana_lite_rpc_core::solana_utils::hash_from_str;
 use solana_lite_rpc_core::structures::epoch::EpochRef;
-use solana_lite_rpc_core::structures::produced_block::{ProducedBlockInner, TransactionInfo};
+use solana_lite_rpc_core::structures::produced_block::{
+    BlockStats, ProducedBlockInner, TransactionInfo,
+};
 use solana_lite_rpc_core::{encoding::BASE64, structures::produced_block::ProducedBlock};
 use solana_sdk::clock::Slot;
 use solana_sdk::commitment_config::CommitmentConfig;
 use solana_transaction_status::Reward;
+use std::ops::RangeInclusive;
 use std::time::Instant;
 use tokio_postgres::types::ToSql;
 
+// not part of the base64 alphabet, rows without the marker are plain base64 (legacy format)
+const ZSTD_REWARDS_MARKER: char = '~';
+// small reward sets (most blocks) are not worth compressing
+const COMPRESS_REWARDS_MIN_SIZE: usize = 1024;
+const ZSTD_COMPRESSION_LEVEL: i32 = 3;
+
+/// bincode serialized rewards, zstd compressed and prefixed with the marker if large
+fn serialize_rewards(rewards: &[Reward]) -> anyhow::Result<String> {
+    let bytes = bincode::serialize(rewards)?;
+    if bytes.len() < COMPRESS_REWARDS_MIN_SIZE {
+        return Ok(BASE64.encode(bytes));
+    }
+    let compressed =
+        zstd::encode_all(bytes.as_slice(), ZSTD_COMPRESSION_LEVEL).context("compress rewards")?;
+    Ok(format!(
+        "{ZSTD_REWARDS_MARKER}{}",
+        BASE64.encode(compressed)
+    ))
+}
+
+/// decodes both the legacy and the compressed format
+fn deserialize_rewards(rewards: &str) -> anyhow::Result<Vec<Reward>> {
+    let bytes = match rewards.strip_prefix(ZSTD_REWARDS_MARKER) {
+        Some(compressed) => {
+            zstd::decode_all(BASE64.decode(compressed)?.as_slice()).context("decompress rewards")?
+        }
+        None => BASE64.decode(rewards)?,
+    };
+    Ok(bincode::deserialize(&bytes)?)
+}
+
 #[derive(Debug)]
 pub struct PostgresBlock {
     pub slot: i64,
@@ -21,6 +56,8 @@ pub struct PostgresBlock {
     pub previous_blockhash: String,
     pub rewards: Option<String>,
     pub leader_id: Option<String>,
+    // in lamports, null for blocks saved before the fees were persisted
+    pub total_fees: Option<i64>,
 }
 
 impl From<&ProducedBlock> for PostgresBlock {
@@ -28,7 +65,7 @@ impl From<&ProducedBlock> for PostgresBlock {
         let rewards = value
             .rewards
             .as_ref()
-            .map(|x| BASE64.serialize::<Vec<Reward>>(x).ok())
+            .map(|x| serialize_rewards(x).ok())
             .unwrap_or(None);
 
         Self {
@@ -41,6 +78,7 @@ impl From<&ProducedBlock> for PostgresBlock {
             // TODO add leader_id, etc.
             rewards,
             leader_id: value.leader_id.clone(),
+            total_fees: Some(value.stats.total_fees as i64),
         }
     }
 }
@@ -54,10 +92,14 @@ impl PostgresBlock {
         let rewards_vec: Option<Vec<Reward>> = self
             .rewards
             .as_ref()
-            .map(|x| BASE64.deserialize::<Vec<Reward>>(x).ok())
+            .map(|x| deserialize_rewards(x).ok())
             .unwrap_or(None);
 
         let inner = ProducedBlockInner {
+            stats: BlockStats::new(
+                &transaction_infos,
+                self.total_fees.unwrap_or_default() as u64,
+            ),
             // TODO implement
             transactions: transaction_infos,
             leader_id: None,
@@ -87,6 +129,7 @@ impl PostgresBlock {
                 block_time BIGINT NOT NULL,
                 previous_blockhash TEXT NOT NULL,
                 rewards TEXT,
+                total_fees BIGINT,
                 CONSTRAINT pk_block_slot PRIMARY KEY(slot)
             ) WITH (FILLFACTOR=90);
             CLUSTER {schema}.blocks USING pk_block_slot;
@@ -95,11 +138,22 @@ impl PostgresBlock {
         )
     }
 
+    // schemas created before the fees were persisted lack the column
+    pub fn build_add_total_fees_column_statement(epoch: EpochRef) -> String {
+        let schema = PostgresEpoch::build_schema_name(epoch);
+        format!(
+            r#"
+            ALTER TABLE {schema}.blocks ADD COLUMN IF NOT EXISTS total_fees BIGINT;
+        "#,
+            schema = schema
+        )
+    }
+
     pub fn build_query_statement(epoch: EpochRef, slot: Slot) -> String {
         format!(
             r#"
                 SELECT
-                    slot, blockhash, block_height, parent_slot, block_time, previous_blockhash, rewards, leader_id,
+                    slot, blockhash, block_height, parent_slot, block_time, previous_blockhash, rewards, leader_id, total_fees,
                     {epoch}::bigint as _epoch, '{schema}'::text as _epoch_schema FROM {schema}.blocks
                 WHERE slot = {slot}
             "#,
@@ -109,13 +163,34 @@ impl PostgresBlock {
         )
     }
 
+    // the block with the lowest slot in the range, skipped slots have no block
+    pub fn build_query_first_in_range_statement(
+        epoch: EpochRef,
+        slots: &RangeInclusive<Slot>,
+    ) -> String {
+        format!(
+            r#"
+                SELECT
+                    slot, blockhash, block_height, parent_slot, block_time, previous_blockhash, rewards, leader_id, total_fees,
+                    {epoch}::bigint as _epoch, '{schema}'::text as _epoch_schema FROM {schema}.blocks
+                WHERE slot BETWEEN {first} AND {last}
+                ORDER BY slot
+                LIMIT 1
+            "#,
+            schema = PostgresEpoch::build_schema_name(epoch),
+            epoch = epoch,
+            first = slots.start(),
+            last = slots.end()
+        )
+    }
+
     // true is actually inserted; false if operation was noop
     pub async fn save(
         &self,
         postgres_session: &PostgresSession,
         epoch: EpochRef,
     ) -> anyhow::Result<bool> {
-        const NB_ARGUMENTS: usize = 8;
+        const NB_ARGUMENTS: usize = 9;
 
         let started = Instant::now();
         let schema = PostgresEpoch::build_schema_name(epoch);
@@ -123,7 +198,7 @@ impl PostgresBlock {
 
         let statement = format!(
             r#"
-                INSERT INTO {schema}.blocks (slot, blockhash, block_height, parent_slot, block_time, previous_blockhash, rewards, leader_id)
+                INSERT INTO {schema}.blocks (slot, blockhash, block_height, parent_slot, block_time, previous_blockhash, rewards, leader_id, total_fees)
                 VALUES {}
                 -- prevent updates
                 ON CONFLICT DO NOTHING
@@ -147,6 +222,7 @@ impl PostgresBlock {
         args.push(&self.previous_blockhash);
         args.push(&self.rewards);
         args.push(&self.leader_id);
+        args.push(&self.total_fees);
 
         let returning = postgres_session
             .execute_and_return(&statement, &args)
@@ -206,6 +282,7 @@ mod tests {
             previous_blockhash: Hash::new_unique().to_string(),
             rewards: None,
             leader_id: None,
+            total_fees: Some(10_000),
         };
 
         let transaction_infos = vec![create_tx_info(), create_tx_info()];
@@ -215,6 +292,62 @@ mod tests {
 
         assert_eq!(produced_block.slot, 5050505);
         assert_eq!(produced_block.transactions.len(), 2);
+        assert_eq!(produced_block.stats.total_fees, 10_000);
+        assert_eq!(
+            PostgresBlock::from(&produced_block).total_fees,
+            Some(10_000)
+        );
+
+        // blocks saved before the fees were persisted
+        let legacy_block = PostgresBlock {
+            total_fees: None,
+            ..block
+        };
+        let produced_block = legacy_block.to_produced_block(vec![], CommitmentConfig::confirmed());
+        assert_eq!(produced_block.stats.total_fees, 0);
+    }
+
+    fn create_rewards(count: usize) -> Vec<Reward> {
+        (0..count)
+            .map(|i| Reward {
+                pubkey: Pubkey::new_unique().to_string(),
+                lamports: 1000 + i as i64,
+                post_balance: 5_000_000,
+                reward_type: Some(solana_transaction_status::RewardType::Staking),
+                commission: Some(5),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_rewards_roundtrip_legacy_and_compressed() {
+        // rows written before compression was added
+        let rewards = create_rewards(3);
+        let legacy = BASE64.serialize::<Vec<Reward>>(&rewards).unwrap();
+        assert_eq!(deserialize_rewards(&legacy).unwrap(), rewards);
+        // small sets are still written in the legacy format
+        assert_eq!(serialize_rewards(&rewards).unwrap(), legacy);
+
+        // epoch boundary
+        let rewards = create_rewards(1000);
+        let compressed = serialize_rewards(&rewards).unwrap();
+        assert!(compressed.starts_with(ZSTD_REWARDS_MARKER));
+        assert!(compressed.len() < BASE64.serialize::<Vec<Reward>>(&rewards).unwrap().len());
+        assert_eq!(deserialize_rewards(&compressed).unwrap(), rewards);
+
+        let block = PostgresBlock {
+            slot: 5050505,
+            blockhash: Hash::new_unique().to_string(),
+            block_height: 4040404,
+            parent_slot: 5050500,
+            block_time: 12121212,
+            previous_blockhash: Hash::new_unique().to_string(),
+            rewards: Some(compressed),
+            leader_id: None,
+            total_fees: None,
+        };
+        let produced_block = block.to_produced_block(vec![], CommitmentConfig::confirmed());
+        assert_eq!(produced_block.rewards, Some(rewards));
     }
 
     fn create_tx_info() -> TransactionInfo {
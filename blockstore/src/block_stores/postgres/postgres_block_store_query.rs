@@ -55,20 +55,69 @@ impl PostgresQueryBlockStore {
     }
 
     pub async fn query_block(&self, slot: Slot) -> Result<ProducedBlock> {
-        let started_at = Instant::now();
         let epoch: EpochRef = self.epoch_schedule.get_epoch_at_slot(slot).into();
+        match self.query_block_in_epoch(epoch, slot).await? {
+            Some(produced_block) => Ok(produced_block),
+            None => bail!("Block {} in epoch {} not found in postgres", slot, epoch),
+        }
+    }
 
+    async fn epoch_schema_exists(&self, epoch: EpochRef) -> Result<bool> {
+        let row = self
+            .get_session()
+            .await
+            .query_opt(
+                "SELECT 1 FROM information_schema.schemata WHERE schema_name = $1",
+                &[&PostgresEpoch::build_schema_name(epoch)],
+            )
+            .await?;
+        Ok(row.is_some())
+    }
+
+    // none if the epoch schema does not exist (yet) or the block is not stored
+    pub async fn query_block_in_epoch(
+        &self,
+        epoch: EpochRef,
+        slot: Slot,
+    ) -> Result<Option<ProducedBlock>> {
         let statement = PostgresBlock::build_query_statement(epoch, slot);
+        self.query_block_with_statement(epoch, &statement).await
+    }
+
+    // the stored block with the lowest slot in the range, using one query for the whole range
+    // none if the epoch schema does not exist (yet) or no block in the range is stored
+    pub async fn query_first_block_in_range(
+        &self,
+        epoch: EpochRef,
+        slots: RangeInclusive<Slot>,
+    ) -> Result<Option<ProducedBlock>> {
+        let statement = PostgresBlock::build_query_first_in_range_statement(epoch, &slots);
+        self.query_block_with_statement(epoch, &statement).await
+    }
+
+    async fn query_block_with_statement(
+        &self,
+        epoch: EpochRef,
+        statement: &str,
+    ) -> Result<Option<ProducedBlock>> {
+        let started_at = Instant::now();
+        if !self.epoch_schema_exists(epoch).await? {
+            debug!("Epoch schema for epoch {} does not exist", epoch);
+            return Ok(None);
+        }
+
         let block_row = self
             .get_session()
             .await
-            .query_opt(&statement, &[])
+            .query_opt(statement, &[])
             .await
             .unwrap();
 
-        if block_row.is_none() {
-            bail!("Block {} in epoch {} not found in postgres", slot, epoch);
-        }
+        let Some(row) = block_row else {
+            return Ok(None);
+        };
+        let slot: i64 = row.get("slot");
+        let slot = slot as Slot;
 
         let statement = PostgresTransaction::build_query_statement(epoch, slot);
         let transaction_rows = self
@@ -101,7 +150,6 @@ impl PostgresQueryBlockStore {
             })
             .collect_vec();
 
-        let row = block_row.unwrap();
         // meta data
         let _epoch: i64 = row.get("_epoch");
         let epoch_schema: String = row.get("_epoch_schema");
@@ -114,6 +162,7 @@ impl PostgresQueryBlockStore {
         let previous_blockhash: String = row.get("previous_blockhash");
         let rewards: Option<String> = row.get("rewards");
         let leader_id: Option<String> = row.get("leader_id");
+        let total_fees: Option<i64> = row.get("total_fees");
 
         let postgres_block = PostgresBlock {
             slot,
@@ -124,6 +173,7 @@ impl PostgresQueryBlockStore {
             previous_blockhash,
             rewards,
             leader_id,
+            total_fees,
         };
 
         let produced_block = postgres_block.to_produced_block(
@@ -141,7 +191,7 @@ impl PostgresQueryBlockStore {
             produced_block.commitment_config.commitment
         );
 
-        Ok(produced_block)
+        Ok(Some(produced_block))
     }
 
     async fn check_query_role(session_cache: &PostgresSessionCache) {
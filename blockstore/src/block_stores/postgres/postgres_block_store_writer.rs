@@ -1,3 +1,4 @@
+use std::ops::RangeInclusive;
 use std::time::{Duration, Instant};
 
 use crate::block_stores::postgres::{LITERPC_QUERY_ROLE, LITERPC_ROLE};
@@ -7,6 +8,7 @@ use log::{debug, info, trace, warn};
 use solana_lite_rpc_core::structures::epoch::EpochRef;
 use solana_lite_rpc_core::structures::{epoch::EpochCache, produced_block::ProducedBlock};
 use solana_sdk::commitment_config::CommitmentLevel;
+use solana_sdk::signature::Signature;
 use solana_sdk::slot_history::Slot;
 use tokio_postgres::error::SqlState;
 
@@ -15,6 +17,7 @@ use super::postgres_config::*;
 use super::postgres_epoch::*;
 use super::postgres_session::*;
 use super::postgres_transaction::*;
+use super::postgres_transaction_slots::*;
 
 const PARALLEL_WRITE_SESSIONS: usize = 4;
 const MIN_WRITE_CHUNK_SIZE: usize = 500;
@@ -78,11 +81,19 @@ impl PostgresBlockStore {
         }
     }
 
-    // return true if schema was actually created
+    // return true if schema was actually created, the DDL is skipped for epochs ensured before
     async fn start_new_epoch_if_necessary(&self, epoch: EpochRef) -> Result<bool> {
+        // a reconnect invalidates the ensured epochs, so get the session first
+        let session = self.get_session().await;
+        self.session_cache
+            .ensured_epochs()
+            .ensure_once(epoch, || Self::create_epoch_schema(session, epoch))
+            .await
+    }
+
+    async fn create_epoch_schema(session: PostgresSession, epoch: EpochRef) -> Result<bool> {
         // create schema for new epoch
         let schema_name = PostgresEpoch::build_schema_name(epoch);
-        let session = self.get_session().await;
 
         let statement = PostgresEpoch::build_create_schema_statement(epoch);
         // note: requires GRANT CREATE ON DATABASE xyz
@@ -98,6 +109,17 @@ impl PostgresBlockStore {
                     "Schema {} for epoch {} already exists - data will be appended",
                     schema_name, epoch
                 );
+                // schemas created before the signature to slot lookup lack the table
+                let statement = PostgresTransactionSlots::build_create_table_statement(epoch);
+                session
+                    .execute_multiple(&statement)
+                    .await
+                    .context("create transaction slots table for existing epoch")?;
+                let statement = PostgresBlock::build_add_total_fees_column_statement(epoch);
+                session
+                    .execute_multiple(&statement)
+                    .await
+                    .context("add total fees column for existing epoch")?;
                 return Ok(false);
             } else {
                 return Err(err).context("create schema for new epoch");
@@ -125,6 +147,13 @@ impl PostgresBlockStore {
             .await
             .context("create transaction table for new epoch")?;
 
+        // create signature to slot lookup table
+        let statement = PostgresTransactionSlots::build_create_table_statement(epoch);
+        session
+            .execute_multiple(&statement)
+            .await
+            .context("create transaction slots table for new epoch")?;
+
         // add foreign key constraint between transactions and blocks
         let statement = PostgresTransaction::build_foreign_key_statement(epoch);
         session
@@ -273,6 +302,97 @@ impl PostgresBlockStore {
         Ok(created_current || created_next)
     }
 
+    // create only the given epoch, true if it was created
+    pub async fn ensure_epoch_schema(&self, epoch: EpochRef) -> anyhow::Result<bool> {
+        self.start_new_epoch_if_necessary(epoch).await
+    }
+
+    // highest slot stored within the range, epochs without schema are skipped
+    pub async fn get_highest_slot_in_range(
+        &self,
+        slots: &RangeInclusive<Slot>,
+    ) -> anyhow::Result<Option<Slot>> {
+        let first_epoch = self.epoch_schedule.get_epoch_at_slot(*slots.start()).epoch;
+        let last_epoch = self.epoch_schedule.get_epoch_at_slot(*slots.end()).epoch;
+        let session = self.get_session().await;
+
+        for epoch in (first_epoch..=last_epoch).rev() {
+            let schema = PostgresEpoch::build_schema_name(EpochRef::new(epoch));
+            let schema_exists = session
+                .query_opt(
+                    "SELECT 1 FROM information_schema.schemata WHERE schema_name = $1",
+                    &[&schema],
+                )
+                .await
+                .context("check if epoch schema exists")?
+                .is_some();
+            if !schema_exists {
+                continue;
+            }
+
+            let statement = format!(
+                r#"
+                    SELECT max(slot) AS slot_max FROM {schema}.blocks WHERE slot BETWEEN $1 AND $2
+                "#
+            );
+            let row = session
+                .query_one(
+                    &statement,
+                    &[&(*slots.start() as i64), &(*slots.end() as i64)],
+                )
+                .await
+                .context("query highest slot in epoch schema")?;
+            if let Some(slot_max) = row.get::<&str, Option<i64>>("slot_max") {
+                return Ok(Some(slot_max as Slot));
+            }
+        }
+        Ok(None)
+    }
+
+    // entries are grouped by the epoch of their slot
+    pub async fn save_transaction_slots(&self, entries: &[(Signature, Slot)]) -> Result<()> {
+        let by_epoch = entries.iter().copied().into_group_map_by(|(_, slot)| {
+            EpochRef::from(self.epoch_schedule.get_epoch_at_slot(*slot))
+        });
+        for (epoch, entries) in by_epoch {
+            self.start_new_epoch_if_necessary(epoch).await?;
+            let session = self.write_sessions[0].get_write_session().await;
+            PostgresTransactionSlots::save(&session, epoch, &entries).await?;
+        }
+        Ok(())
+    }
+
+    // looks up the signature in the epoch schemas which have a transaction_slots table, newest first
+    pub async fn query_transaction_slot(&self, signature: &Signature) -> Result<Option<Slot>> {
+        let session = self.get_session().await;
+        let schemas = session
+            .query_list(
+                "SELECT table_schema FROM information_schema.tables WHERE table_schema LIKE $1 AND table_name = 'transaction_slots'",
+                &[&format!("{EPOCH_SCHEMA_PREFIX}%")],
+            )
+            .await
+            .context("list epoch schemas")?;
+        let epochs = schemas
+            .iter()
+            .map(|row| PostgresEpoch::parse_epoch_from_schema_name(row.get("table_schema")))
+            .sorted()
+            .rev()
+            .collect_vec();
+
+        let signature = signature.to_string();
+        for epoch in epochs {
+            let statement = PostgresTransactionSlots::build_query_statement(epoch);
+            let row = session
+                .query_opt(&statement, &[&signature])
+                .await
+                .context("query transaction slot")?;
+            if let Some(row) = row {
+                return Ok(Some(row.get::<&str, i64>("slot") as Slot));
+            }
+        }
+        Ok(None)
+    }
+
     // used for testing only ATM
     pub async fn drop_epoch_schema(&self, epoch: EpochRef) -> anyhow::Result<()> {
         // create schema for new epoch
@@ -316,7 +436,9 @@ fn div_ceil(a: usize, b: usize) -> usize {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use solana_lite_rpc_core::structures::produced_block::{ProducedBlockInner, TransactionInfo};
+    use solana_lite_rpc_core::structures::produced_block::{
+        BlockStats, ProducedBlockInner, TransactionInfo,
+    };
     use solana_sdk::commitment_config::CommitmentConfig;
     use solana_sdk::message::{v0, MessageHeader, VersionedMessage};
     use solana_sdk::pubkey::Pubkey;
@@ -366,13 +488,15 @@ mod tests {
         let sig1 = Signature::from_str("5VBroA4MxsbZdZmaSEb618WRRwhWYW9weKhh3md1asGRx7nXDVFLua9c98voeiWdBE7A9isEoLL7buKyaVRSK1pV").unwrap();
         let sig2 = Signature::from_str("3d9x3rkVQEoza37MLJqXyadeTbEJGUB6unywK4pjeRLJc16wPsgw3dxPryRWw3UaLcRyuxEp1AXKGECvroYxAEf2").unwrap();
 
+        let transactions = vec![create_test_tx(sig1), create_test_tx(sig2)];
         let inner = ProducedBlockInner {
             block_height: 42,
             blockhash: solana_sdk::hash::Hash::new_unique(),
             previous_blockhash: solana_sdk::hash::Hash::new_unique(),
             parent_slot: 666,
             slot: 223555999,
-            transactions: vec![create_test_tx(sig1), create_test_tx(sig2)],
+            stats: BlockStats::new(&transactions, 10_000),
+            transactions,
             // TODO double if this is unix millis or seconds
             block_time: 1699260872000,
             leader_id: None,
@@ -1,10 +1,13 @@
-use std::sync::Arc;
+use std::collections::HashSet;
+use std::future::Future;
+use std::sync::{Arc, Mutex};
 
 use anyhow::Context;
 use log::debug;
 use native_tls::{Certificate, Identity, TlsConnector};
 use postgres_native_tls::MakeTlsConnector;
 use solana_lite_rpc_core::encoding::BinaryEncoding;
+use solana_lite_rpc_core::structures::epoch::EpochRef;
 use tokio::sync::RwLock;
 use tokio_postgres::{
     config::SslMode, tls::MakeTlsConnect, types::ToSql, Client, CopyInSink, Error, NoTls, Row,
@@ -227,6 +230,7 @@ impl PostgresSession {
 pub struct PostgresSessionCache {
     session: Arc<RwLock<PostgresSession>>,
     config: PostgresSessionConfig,
+    ensured_epochs: EnsuredEpochs,
 }
 
 impl PostgresSessionCache {
@@ -235,6 +239,7 @@ impl PostgresSessionCache {
         Ok(Self {
             session: Arc::new(RwLock::new(session)),
             config,
+            ensured_epochs: EnsuredEpochs::default(),
         })
     }
 
@@ -244,11 +249,47 @@ impl PostgresSessionCache {
             drop(session);
             let session = PostgresSession::new(self.config.clone()).await?;
             *self.session.write().await = session.clone();
+            // the schemas might have changed while disconnected
+            self.ensured_epochs.invalidate();
             Ok(session)
         } else {
             Ok(session.clone())
         }
     }
+
+    pub fn ensured_epochs(&self) -> &EnsuredEpochs {
+        &self.ensured_epochs
+    }
+}
+
+/// Epoch schemas known to exist, so the DDL is only issued once per epoch.
+#[derive(Clone, Default)]
+pub struct EnsuredEpochs {
+    epochs: Arc<Mutex<HashSet<EpochRef>>>,
+}
+
+impl EnsuredEpochs {
+    /// runs `ensure_schema` unless the epoch was ensured before, a failed run is not cached
+    pub async fn ensure_once<F, Fut>(
+        &self,
+        epoch: EpochRef,
+        ensure_schema: F,
+    ) -> anyhow::Result<bool>
+    where
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = anyhow::Result<bool>>,
+    {
+        if self.epochs.lock().unwrap().contains(&epoch) {
+            return Ok(false);
+        }
+        let created = ensure_schema().await?;
+        self.epochs.lock().unwrap().insert(epoch);
+        Ok(created)
+    }
+
+    pub fn invalidate(&self) {
+        self.epochs.lock().unwrap().clear();
+    }
 }
 
 #[derive(Clone)]
@@ -331,3 +372,39 @@ fn value_vec_test_types() {
     let values = PostgresSession::values_vec(3, &["text", "int", "int"]);
     assert_eq!(values, "(($1)::text,($2)::int,($3)::int)");
 }
+
+#[tokio::test]
+async fn ensure_epoch_schema_once() {
+    let ensured_epochs = &EnsuredEpochs::default();
+    let ddl_count = &std::sync::atomic::AtomicUsize::new(0);
+    let ensure = move |epoch| {
+        ensured_epochs.ensure_once(EpochRef::new(epoch), move || async move {
+            ddl_count.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+            Ok(true)
+        })
+    };
+
+    // many saves within two epochs
+    assert!(ensure(0).await.unwrap());
+    for _ in 0..100 {
+        assert!(!ensure(0).await.unwrap());
+    }
+    assert!(ensure(1).await.unwrap());
+    assert!(!ensure(1).await.unwrap());
+    assert_eq!(ddl_count.load(std::sync::atomic::Ordering::Relaxed), 2);
+
+    // a failed ddl is retried
+    assert!(ensured_epochs
+        .ensure_once(EpochRef::new(2), || async {
+            anyhow::bail!("connection lost")
+        })
+        .await
+        .is_err());
+    assert!(ensure(2).await.unwrap());
+    assert_eq!(ddl_count.load(std::sync::atomic::Ordering::Relaxed), 3);
+
+    // reconnect
+    ensured_epochs.invalidate();
+    assert!(ensure(0).await.unwrap());
+    assert_eq!(ddl_count.load(std::sync::atomic::Ordering::Relaxed), 4);
+}
@@ -0,0 +1,84 @@
+use anyhow::Context;
+use log::debug;
+use solana_lite_rpc_core::structures::epoch::EpochRef;
+use solana_sdk::signature::Signature;
+use solana_sdk::slot_history::Slot;
+use tokio::time::Instant;
+use tokio_postgres::types::ToSql;
+
+use super::postgres_epoch::*;
+use super::postgres_session::*;
+
+// stay well below the postgres limit of 65535 bind parameters
+const MAX_ROWS_PER_INSERT: usize = 10_000;
+
+/// signature to slot lookup for transactions which were evicted from the in-memory index
+pub struct PostgresTransactionSlots {}
+
+impl PostgresTransactionSlots {
+    pub fn build_create_table_statement(epoch: EpochRef) -> String {
+        let schema = PostgresEpoch::build_schema_name(epoch);
+        format!(
+            r#"
+                -- no updates or deletes, only INSERTs
+                CREATE TABLE IF NOT EXISTS {schema}.transaction_slots(
+                    -- never put sig on TOAST
+                    signature text STORAGE PLAIN NOT NULL,
+                    slot bigint NOT NULL,
+                    CONSTRAINT pk_transaction_slots_signature PRIMARY KEY(signature)
+                ) WITH (FILLFACTOR=90);
+            "#,
+            schema = schema
+        )
+    }
+
+    pub fn build_query_statement(epoch: EpochRef) -> String {
+        format!(
+            r#"
+                SELECT slot FROM {schema}.transaction_slots WHERE signature = $1
+            "#,
+            schema = PostgresEpoch::build_schema_name(epoch),
+        )
+    }
+
+    pub async fn save(
+        postgres_session: &PostgresSession,
+        epoch: EpochRef,
+        entries: &[(Signature, Slot)],
+    ) -> anyhow::Result<u64> {
+        const NB_ARGUMENTS: usize = 2;
+
+        let started_at = Instant::now();
+        let schema = PostgresEpoch::build_schema_name(epoch);
+        let mut num_rows = 0;
+        for chunk in entries.chunks(MAX_ROWS_PER_INSERT) {
+            let rows = chunk
+                .iter()
+                .map(|(signature, slot)| (signature.to_string(), *slot as i64))
+                .collect::<Vec<_>>();
+            let values = PostgresSession::values_vecvec(NB_ARGUMENTS, rows.len(), &[]);
+            let statement = format!(
+                r#"
+                    INSERT INTO {schema}.transaction_slots (signature, slot)
+                    VALUES {values}
+                    ON CONFLICT DO NOTHING
+                "#,
+            );
+            let mut args: Vec<&(dyn ToSql + Sync)> = Vec::with_capacity(NB_ARGUMENTS * rows.len());
+            for (signature, slot) in &rows {
+                args.push(signature);
+                args.push(slot);
+            }
+            num_rows += postgres_session
+                .execute(&statement, &args)
+                .await
+                .context("insert transaction slots")?;
+        }
+        debug!(
+            "inserted {} rows into transaction_slots table in {}ms",
+            num_rows,
+            started_at.elapsed().as_millis()
+        );
+        Ok(num_rows)
+    }
+}
@@ -0,0 +1,208 @@
+use std::collections::{HashMap, VecDeque};
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use log::trace;
+use solana_lite_rpc_core::structures::produced_block::ProducedBlock;
+use solana_sdk::signature::Signature;
+use solana_sdk::slot_history::Slot;
+use tokio::sync::Mutex;
+
+use crate::block_stores::postgres::postgres_block_store_writer::PostgresBlockStore;
+
+/// Persistent storage for the signatures evicted from the in-memory index
+#[async_trait]
+pub trait SignatureSlotSpill: Send + Sync {
+    async fn spill(&self, entries: &[(Signature, Slot)]) -> anyhow::Result<()>;
+    async fn lookup(&self, signature: &Signature) -> anyhow::Result<Option<Slot>>;
+}
+
+#[async_trait]
+impl SignatureSlotSpill for PostgresBlockStore {
+    async fn spill(&self, entries: &[(Signature, Slot)]) -> anyhow::Result<()> {
+        self.save_transaction_slots(entries).await
+    }
+
+    async fn lookup(&self, signature: &Signature) -> anyhow::Result<Option<Slot>> {
+        self.query_transaction_slot(signature).await
+    }
+}
+
+#[derive(Default)]
+struct RecentSignatures {
+    slots: HashMap<Signature, Slot>,
+    // insertion order, oldest first
+    order: VecDeque<Signature>,
+}
+
+/// Maps transaction signatures to the slot of their block.
+/// The most recent `memory_capacity` signatures are kept in memory, older ones are spilled to the persistent storage.
+#[derive(Clone)]
+pub struct SignatureSlotIndex {
+    memory_capacity: usize,
+    recent: Arc<Mutex<RecentSignatures>>,
+    spill: Arc<dyn SignatureSlotSpill>,
+}
+
+impl SignatureSlotIndex {
+    pub fn new(memory_capacity: usize, spill: Arc<dyn SignatureSlotSpill>) -> Self {
+        assert!(
+            memory_capacity > 0,
+            "memory_capacity must be greater than 0"
+        );
+        Self {
+            memory_capacity,
+            recent: Arc::new(Mutex::new(RecentSignatures::default())),
+            spill,
+        }
+    }
+
+    pub async fn insert_block(&self, block: &ProducedBlock) -> anyhow::Result<()> {
+        let entries = block
+            .transactions
+            .iter()
+            .map(|tx| (tx.signature, block.slot))
+            .collect::<Vec<_>>();
+        self.insert(&entries).await
+    }
+
+    pub async fn insert(&self, entries: &[(Signature, Slot)]) -> anyhow::Result<()> {
+        let evicted = {
+            let mut guard = self.recent.lock().await;
+            let recent = &mut *guard;
+            for (signature, slot) in entries {
+                if recent.slots.insert(*signature, *slot).is_none() {
+                    recent.order.push_back(*signature);
+                }
+            }
+
+            let overflow = recent.order.len().saturating_sub(self.memory_capacity);
+            // evicted entries stay in `slots` until spilled so that lookups keep finding them
+            recent
+                .order
+                .drain(..overflow)
+                .map(|signature| (signature, recent.slots[&signature]))
+                .collect::<Vec<_>>()
+        };
+        if evicted.is_empty() {
+            return Ok(());
+        }
+
+        // the lock is not held while writing to the persistent storage
+        if let Err(err) = self.spill.spill(&evicted).await {
+            // put the entries back in front so that the next insert retries them
+            let mut recent = self.recent.lock().await;
+            for (signature, _) in evicted.iter().rev() {
+                if recent.slots.contains_key(signature) {
+                    recent.order.push_front(*signature);
+                }
+            }
+            return Err(err);
+        }
+
+        let mut recent = self.recent.lock().await;
+        for (signature, _) in &evicted {
+            recent.slots.remove(signature);
+        }
+        trace!("spilled {} signatures to persistent storage", evicted.len());
+        Ok(())
+    }
+
+    /// checks the memory first, then the persistent storage
+    pub async fn get_slot(&self, signature: &Signature) -> anyhow::Result<Option<Slot>> {
+        {
+            let recent = self.recent.lock().await;
+            if let Some(slot) = recent.slots.get(signature) {
+                return Ok(Some(*slot));
+            }
+        }
+        self.spill.lookup(signature).await
+    }
+
+    pub async fn len_in_memory(&self) -> usize {
+        self.recent.lock().await.order.len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::RwLock;
+
+    use super::*;
+
+    #[derive(Default)]
+    struct MockSpill {
+        stored: RwLock<HashMap<Signature, Slot>>,
+        lookups: AtomicUsize,
+    }
+
+    #[async_trait]
+    impl SignatureSlotSpill for MockSpill {
+        async fn spill(&self, entries: &[(Signature, Slot)]) -> anyhow::Result<()> {
+            self.stored.write().unwrap().extend(entries.iter().copied());
+            Ok(())
+        }
+
+        async fn lookup(&self, signature: &Signature) -> anyhow::Result<Option<Slot>> {
+            self.lookups.fetch_add(1, Ordering::Relaxed);
+            Ok(self.stored.read().unwrap().get(signature).copied())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_lookup_across_memory_and_disk() {
+        let spill = Arc::new(MockSpill::default());
+        let index = SignatureSlotIndex::new(3, spill.clone());
+
+        let signatures = (0..5).map(|_| Signature::new_unique()).collect::<Vec<_>>();
+        for (slot, signature) in signatures.iter().enumerate() {
+            index.insert(&[(*signature, slot as Slot)]).await.unwrap();
+        }
+
+        assert_eq!(index.len_in_memory().await, 3);
+        assert_eq!(spill.stored.read().unwrap().len(), 2);
+
+        // served from memory
+        assert_eq!(index.get_slot(&signatures[4]).await.unwrap(), Some(4));
+        assert_eq!(index.get_slot(&signatures[2]).await.unwrap(), Some(2));
+        assert_eq!(spill.lookups.load(Ordering::Relaxed), 0);
+
+        // spilled to disk
+        assert_eq!(index.get_slot(&signatures[0]).await.unwrap(), Some(0));
+        assert_eq!(index.get_slot(&signatures[1]).await.unwrap(), Some(1));
+        assert_eq!(spill.lookups.load(Ordering::Relaxed), 2);
+
+        assert_eq!(
+            index.get_slot(&Signature::new_unique()).await.unwrap(),
+            None
+        );
+    }
+
+    struct FailingSpill;
+
+    #[async_trait]
+    impl SignatureSlotSpill for FailingSpill {
+        async fn spill(&self, _entries: &[(Signature, Slot)]) -> anyhow::Result<()> {
+            anyhow::bail!("postgres unavailable")
+        }
+
+        async fn lookup(&self, _signature: &Signature) -> anyhow::Result<Option<Slot>> {
+            Ok(None)
+        }
+    }
+
+    #[tokio::test]
+    async fn test_failed_spill_keeps_signatures_in_memory() {
+        let index = SignatureSlotIndex::new(1, Arc::new(FailingSpill));
+
+        let first = Signature::new_unique();
+        let second = Signature::new_unique();
+        index.insert(&[(first, 1)]).await.unwrap();
+        assert!(index.insert(&[(second, 2)]).await.is_err());
+
+        assert_eq!(index.len_in_memory().await, 2);
+        assert_eq!(index.get_slot(&first).await.unwrap(), Some(1));
+        assert_eq!(index.get_slot(&second).await.unwrap(), Some(2));
+    }
+}
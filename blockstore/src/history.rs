@@ -1,10 +1,300 @@
+use std::future::Future;
+use std::ops::RangeInclusive;
+use std::sync::Arc;
+
+use anyhow::bail;
+use async_trait::async_trait;
+use log::{debug, info, warn};
+use solana_lite_rpc_core::structures::epoch::{EpochCache, EpochRef};
+use solana_lite_rpc_core::structures::produced_block::ProducedBlock;
+use solana_lite_rpc_core::types::BlockStream;
+use solana_lite_rpc_core::AnyhowJoinHandle;
+use solana_rpc_client_api::response::RpcInflationReward;
+use solana_sdk::clock::Epoch;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::Signature;
+use solana_sdk::slot_history::Slot;
+use solana_transaction_status::{RewardType, TransactionConfirmationStatus, TransactionStatus};
+use tokio::sync::broadcast::error::RecvError;
+
+use crate::block_stores::in_memory_block_store::InMemoryBlockStore;
+use crate::block_stores::multiple_strategy_block_store::MultipleStrategyBlockStorage;
+use crate::block_stores::postgres::postgres_block_store_query::PostgresQueryBlockStore;
+use crate::block_stores::postgres::postgres_block_store_writer::PostgresBlockStore;
+use crate::block_stores::signature_slot_index::SignatureSlotIndex;
+
+const BACKFILL_PROGRESS_LOG_INTERVAL: u64 = 100;
+// the first slots of an epoch may be skipped by their leaders
+const MAX_SLOTS_TO_SCAN_FOR_REWARDS: u64 = 64;
+
+/// Persistent storage of blocks which are no longer in the in-memory caches
+#[async_trait]
+pub trait BlockTimeStore: Send + Sync {
+    async fn get_block_time(&self, slot: Slot) -> anyhow::Result<u64>;
+}
+
+#[async_trait]
+impl BlockTimeStore for MultipleStrategyBlockStorage {
+    async fn get_block_time(&self, slot: Slot) -> anyhow::Result<u64> {
+        Ok(self.query_block(slot).await?.block_time)
+    }
+}
+
+/// Blocks stored in one schema per epoch
+#[async_trait]
+pub trait EpochBlockStore: Send + Sync {
+    /// none if the epoch schema does not exist (yet) or the block is not stored
+    async fn query_block_in_epoch(
+        &self,
+        epoch: EpochRef,
+        slot: Slot,
+    ) -> anyhow::Result<Option<ProducedBlock>>;
+
+    /// the stored block with the lowest slot in `slots`, none if the epoch schema does not exist or no block is stored
+    async fn query_first_block_in_range(
+        &self,
+        epoch: EpochRef,
+        slots: RangeInclusive<Slot>,
+    ) -> anyhow::Result<Option<ProducedBlock>>;
+}
+
+#[async_trait]
+impl EpochBlockStore for PostgresQueryBlockStore {
+    async fn query_block_in_epoch(
+        &self,
+        epoch: EpochRef,
+        slot: Slot,
+    ) -> anyhow::Result<Option<ProducedBlock>> {
+        PostgresQueryBlockStore::query_block_in_epoch(self, epoch, slot).await
+    }
+
+    async fn query_first_block_in_range(
+        &self,
+        epoch: EpochRef,
+        slots: RangeInclusive<Slot>,
+    ) -> anyhow::Result<Option<ProducedBlock>> {
+        PostgresQueryBlockStore::query_first_block_in_range(self, epoch, slots).await
+    }
+}
+
+#[derive(Clone)]
+struct EpochBlocks {
+    epoch_data: EpochCache,
+    block_store: Arc<dyn EpochBlockStore>,
+}
+
+#[derive(Clone)]
 pub struct History {
-    // Placeholder
+    block_store: Option<Arc<dyn BlockTimeStore>>,
+    epoch_blocks: Option<EpochBlocks>,
+    recent_blocks: Option<InMemoryBlockStore>,
+    signature_slots: Option<SignatureSlotIndex>,
 }
 
 impl History {
     pub fn new() -> Self {
-        History {}
+        History {
+            block_store: None,
+            epoch_blocks: None,
+            recent_blocks: None,
+            signature_slots: None,
+        }
+    }
+
+    pub fn new_with_block_store(block_store: Arc<dyn BlockTimeStore>) -> Self {
+        History {
+            block_store: Some(block_store),
+            epoch_blocks: None,
+            recent_blocks: None,
+            signature_slots: None,
+        }
+    }
+
+    /// serve the blocks of the last `retention_slots` slots from memory
+    pub fn with_recent_blocks(mut self, retention_slots: u64) -> Self {
+        self.recent_blocks = Some(InMemoryBlockStore::new(retention_slots));
+        self
+    }
+
+    /// no-op if no in-memory retention is configured
+    pub fn add_block(&self, block: ProducedBlock) {
+        if let Some(recent_blocks) = &self.recent_blocks {
+            recent_blocks.add_block(block);
+        }
+    }
+
+    pub fn start_recent_blocks_task(&self, mut blocks: BlockStream) -> AnyhowJoinHandle {
+        let history = self.clone();
+        tokio::spawn(async move {
+            loop {
+                match blocks.recv().await {
+                    Ok(block) => history.add_block(block),
+                    Err(RecvError::Lagged(missed)) => {
+                        warn!("recent blocks task lagged behind and missed {missed} blocks");
+                    }
+                    Err(RecvError::Closed) => {
+                        bail!("block stream closed");
+                    }
+                }
+            }
+        })
+    }
+
+    /// resolve the slot of transactions which are no longer in the transaction cache
+    pub fn with_signature_slot_index(mut self, signature_slots: SignatureSlotIndex) -> Self {
+        self.signature_slots = Some(signature_slots);
+        self
+    }
+
+    /// indexes the signatures of confirmed blocks; no-op if no signature slot index is configured
+    pub fn start_signature_slot_index_task(&self, mut blocks: BlockStream) -> AnyhowJoinHandle {
+        let signature_slots = self.signature_slots.clone();
+        tokio::spawn(async move {
+            let Some(signature_slots) = signature_slots else {
+                std::future::pending::<()>().await;
+                unreachable!()
+            };
+            loop {
+                match blocks.recv().await {
+                    Ok(block) => {
+                        // processed blocks may still be dropped by a fork, finalized blocks were indexed when confirmed
+                        if !block.commitment_config.is_confirmed() {
+                            continue;
+                        }
+                        if let Err(err) = signature_slots.insert_block(&block).await {
+                            warn!(
+                                "failed to index the signatures of block {}: {err:?}",
+                                block.slot
+                            );
+                        }
+                    }
+                    Err(RecvError::Lagged(missed)) => {
+                        warn!("signature slot index task lagged behind and missed {missed} blocks");
+                    }
+                    Err(RecvError::Closed) => {
+                        bail!("block stream closed");
+                    }
+                }
+            }
+        })
+    }
+
+    pub fn with_epoch_block_store(
+        mut self,
+        epoch_data: EpochCache,
+        block_store: Arc<dyn EpochBlockStore>,
+    ) -> Self {
+        self.epoch_blocks = Some(EpochBlocks {
+            epoch_data,
+            block_store,
+        });
+        self
+    }
+
+    /// false if blocks are neither kept in memory nor in persistent storage
+    pub fn is_enabled(&self) -> bool {
+        self.block_store.is_some() || self.epoch_blocks.is_some() || self.recent_blocks.is_some()
+    }
+
+    /// serves recent slots from memory, otherwise looks up the epoch of the slot and queries the block from the schema of that epoch
+    /// none if no epoch block store is configured, the epoch schema does not exist or the block is not stored
+    pub async fn get_block(&self, slot: Slot) -> anyhow::Result<Option<ProducedBlock>> {
+        if let Some(block) = self
+            .recent_blocks
+            .as_ref()
+            .and_then(|recent_blocks| recent_blocks.get_block(slot))
+        {
+            return Ok(Some(block));
+        }
+        let Some(epoch_blocks) = &self.epoch_blocks else {
+            return Ok(None);
+        };
+        // the first slot of an epoch belongs to that epoch, warmup epochs are handled by the schedule
+        let epoch = EpochRef::new(epoch_blocks.epoch_data.get_epoch_schedule().get_epoch(slot));
+        epoch_blocks
+            .block_store
+            .query_block_in_epoch(epoch, slot)
+            .await
+    }
+
+    /// rewards of `epoch` are paid in the first block of the following epoch
+    /// none if no epoch block store is configured or none of the first slots of the following epoch is stored
+    pub async fn get_epoch_rewards_block(
+        &self,
+        epoch: Epoch,
+    ) -> anyhow::Result<Option<ProducedBlock>> {
+        let Some(epoch_blocks) = &self.epoch_blocks else {
+            return Ok(None);
+        };
+        let rewards_epoch = EpochRef::new(epoch + 1);
+        let first_slot = epoch_blocks
+            .epoch_data
+            .get_first_slot_in_epoch(rewards_epoch.get_epoch());
+        let slots = first_slot..=first_slot + MAX_SLOTS_TO_SCAN_FOR_REWARDS - 1;
+        if let Some(block) = epoch_blocks
+            .block_store
+            .query_first_block_in_range(rewards_epoch, slots)
+            .await?
+        {
+            return Ok(Some(block));
+        }
+        debug!("no block stored in the first {MAX_SLOTS_TO_SCAN_FOR_REWARDS} slots of epoch {rewards_epoch}");
+        Ok(None)
+    }
+
+    /// status of a transaction from the block it was included in
+    /// none if no signature slot index is configured, the signature is unknown or its block is not available
+    pub async fn get_transaction_status(
+        &self,
+        signature: &Signature,
+    ) -> anyhow::Result<Option<TransactionStatus>> {
+        let Some(signature_slots) = &self.signature_slots else {
+            return Ok(None);
+        };
+        let Some(slot) = signature_slots.get_slot(signature).await? else {
+            return Ok(None);
+        };
+        let Some(block) = self.get_block(slot).await? else {
+            debug!("block {slot} of transaction {signature} not found in history");
+            return Ok(None);
+        };
+        let Some(tx) = block
+            .transactions
+            .iter()
+            .find(|tx| tx.signature == *signature)
+        else {
+            return Ok(None);
+        };
+        let confirmation_status = if block.commitment_config.is_finalized() {
+            TransactionConfirmationStatus::Finalized
+        } else {
+            TransactionConfirmationStatus::Confirmed
+        };
+        Ok(Some(TransactionStatus {
+            slot,
+            confirmations: None,
+            status: tx.err.clone().map_or(Ok(()), Err),
+            err: tx.err.clone(),
+            confirmation_status: Some(confirmation_status),
+        }))
+    }
+
+    /// looks the block up like `get_block`, then queries the block time from the block store
+    /// none if the slot is not available
+    pub async fn get_block_time(&self, slot: Slot) -> Option<u64> {
+        match self.get_block(slot).await {
+            Ok(Some(block)) => return Some(block.block_time),
+            Ok(None) => {}
+            Err(err) => debug!("block {slot} not found in history: {err:?}"),
+        }
+        let block_store = self.block_store.as_ref()?;
+        match block_store.get_block_time(slot).await {
+            Ok(block_time) => Some(block_time),
+            Err(err) => {
+                debug!("block time for slot {slot} not found in history: {err:?}");
+                None
+            }
+        }
     }
 }
 
@@ -13,3 +303,546 @@ impl Default for History {
         Self::new()
     }
 }
+
+/// staking and voting rewards of `addresses` paid in `rewards_block`, none where an address got no reward
+pub fn inflation_rewards(
+    addresses: &[Pubkey],
+    epoch: Epoch,
+    rewards_block: &ProducedBlock,
+) -> Vec<Option<RpcInflationReward>> {
+    let rewards = rewards_block.rewards.as_deref().unwrap_or_default();
+    addresses
+        .iter()
+        .map(|address| {
+            let address = address.to_string();
+            rewards
+                .iter()
+                .find(|reward| {
+                    reward.pubkey == address
+                        && matches!(
+                            reward.reward_type,
+                            Some(RewardType::Staking) | Some(RewardType::Voting)
+                        )
+                })
+                .map(|reward| RpcInflationReward {
+                    epoch,
+                    effective_slot: rewards_block.slot,
+                    amount: reward.lamports.unsigned_abs(),
+                    post_balance: reward.post_balance,
+                    commission: reward.commission,
+                })
+        })
+        .collect()
+}
+
+/// Write path used to backfill historical blocks
+#[async_trait]
+pub trait BackfillBlockStore: Send + Sync {
+    async fn get_highest_slot_in_range(
+        &self,
+        slots: &RangeInclusive<Slot>,
+    ) -> anyhow::Result<Option<Slot>>;
+
+    async fn ensure_epoch_schema(&self, epoch: EpochRef) -> anyhow::Result<()>;
+
+    async fn save_block(&self, block: &ProducedBlock) -> anyhow::Result<()>;
+}
+
+#[async_trait]
+impl BackfillBlockStore for PostgresBlockStore {
+    async fn get_highest_slot_in_range(
+        &self,
+        slots: &RangeInclusive<Slot>,
+    ) -> anyhow::Result<Option<Slot>> {
+        PostgresBlockStore::get_highest_slot_in_range(self, slots).await
+    }
+
+    async fn ensure_epoch_schema(&self, epoch: EpochRef) -> anyhow::Result<()> {
+        PostgresBlockStore::ensure_epoch_schema(self, epoch).await?;
+        Ok(())
+    }
+
+    async fn save_block(&self, block: &ProducedBlock) -> anyhow::Result<()> {
+        PostgresBlockStore::save_block(self, block).await
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct BackfillProgress {
+    /// highest slot which was already stored before the backfill started
+    pub resumed_after: Option<Slot>,
+    pub blocks_saved: u64,
+    pub slots_skipped: u64,
+}
+
+/// Loads the blocks of `slots` from upstream into the block store, slot by slot.
+/// Resumes after the highest slot already stored in the range, missing epoch schemas are created on the way.
+/// `fetch_block` returns None for slots without a block.
+pub async fn backfill_blocks<F, Fut>(
+    block_store: &dyn BackfillBlockStore,
+    epoch_cache: &EpochCache,
+    slots: RangeInclusive<Slot>,
+    mut fetch_block: F,
+) -> anyhow::Result<BackfillProgress>
+where
+    F: FnMut(Slot) -> Fut,
+    Fut: Future<Output = anyhow::Result<Option<ProducedBlock>>>,
+{
+    let mut progress = BackfillProgress {
+        resumed_after: block_store.get_highest_slot_in_range(&slots).await?,
+        ..Default::default()
+    };
+    let first_slot = match progress.resumed_after {
+        Some(slot) => {
+            info!("resume backfill of slots {slots:?} after slot {slot}");
+            slot + 1
+        }
+        None => *slots.start(),
+    };
+
+    let mut prepared_epoch: Option<EpochRef> = None;
+    for slot in first_slot..=*slots.end() {
+        let epoch: EpochRef = epoch_cache.get_epoch_at_slot(slot).into();
+        if prepared_epoch != Some(epoch) {
+            block_store.ensure_epoch_schema(epoch).await?;
+            prepared_epoch = Some(epoch);
+        }
+
+        match fetch_block(slot).await? {
+            Some(block) => {
+                block_store.save_block(&block).await?;
+                progress.blocks_saved += 1;
+            }
+            None => {
+                progress.slots_skipped += 1;
+            }
+        }
+
+        if (slot - first_slot + 1) % BACKFILL_PROGRESS_LOG_INTERVAL == 0 {
+            info!(
+                "backfill at slot {slot} of {slots:?}: {} blocks saved, {} slots skipped",
+                progress.blocks_saved, progress.slots_skipped
+            );
+        }
+    }
+
+    info!("backfill of slots {slots:?} completed: {progress:?}");
+    Ok(progress)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::{BTreeSet, HashMap};
+    use std::sync::Mutex;
+
+    use anyhow::{bail, Context};
+    use solana_lite_rpc_core::structures::produced_block::{
+        BlockStats, ProducedBlockInner, TransactionInfo,
+    };
+    use solana_sdk::commitment_config::CommitmentConfig;
+    use solana_sdk::hash::Hash;
+    use solana_sdk::message::{Message, VersionedMessage};
+    use solana_sdk::transaction::TransactionError;
+    use solana_transaction_status::Reward;
+
+    use super::*;
+    use crate::block_stores::signature_slot_index::SignatureSlotSpill;
+
+    struct InMemoryBlockTimes(HashMap<Slot, u64>);
+
+    #[async_trait]
+    impl BlockTimeStore for InMemoryBlockTimes {
+        async fn get_block_time(&self, slot: Slot) -> anyhow::Result<u64> {
+            self.0.get(&slot).copied().context("block not found")
+        }
+    }
+
+    #[tokio::test]
+    async fn test_block_time_from_history() {
+        let history =
+            History::new_with_block_store(Arc::new(InMemoryBlockTimes(HashMap::from([(
+                1000,
+                1_700_000_000,
+            )]))));
+        assert_eq!(history.get_block_time(1000).await, Some(1_700_000_000));
+        assert_eq!(history.get_block_time(1001).await, None);
+
+        assert_eq!(History::new().get_block_time(1000).await, None);
+    }
+
+    #[tokio::test]
+    async fn test_block_time_from_recent_blocks() {
+        let history = History::new_with_block_store(Arc::new(InMemoryBlockTimes(HashMap::new())))
+            .with_recent_blocks(10);
+        history.add_block(create_test_block(1000));
+
+        // not in the block store
+        assert_eq!(history.get_block_time(1000).await, Some(1_700_000_000));
+        assert_eq!(history.get_block_time(1001).await, None);
+    }
+
+    struct InMemoryEpochSchemas {
+        epoch_cache: EpochCache,
+        schemas: Mutex<HashMap<EpochRef, BTreeSet<Slot>>>,
+    }
+
+    #[async_trait]
+    impl BackfillBlockStore for InMemoryEpochSchemas {
+        async fn get_highest_slot_in_range(
+            &self,
+            slots: &RangeInclusive<Slot>,
+        ) -> anyhow::Result<Option<Slot>> {
+            Ok(self
+                .schemas
+                .lock()
+                .unwrap()
+                .values()
+                .flat_map(|stored| stored.range(slots.clone()).last().copied())
+                .max())
+        }
+
+        async fn ensure_epoch_schema(&self, epoch: EpochRef) -> anyhow::Result<()> {
+            self.schemas.lock().unwrap().entry(epoch).or_default();
+            Ok(())
+        }
+
+        async fn save_block(&self, block: &ProducedBlock) -> anyhow::Result<()> {
+            let epoch: EpochRef = self.epoch_cache.get_epoch_at_slot(block.slot).into();
+            let mut schemas = self.schemas.lock().unwrap();
+            let Some(stored) = schemas.get_mut(&epoch) else {
+                bail!("schema for epoch {epoch} does not exist");
+            };
+            stored.insert(block.slot);
+            Ok(())
+        }
+    }
+
+    fn create_test_block(slot: Slot) -> ProducedBlock {
+        create_test_block_with_rewards(slot, None)
+    }
+
+    fn create_test_block_with_rewards(slot: Slot, rewards: Option<Vec<Reward>>) -> ProducedBlock {
+        ProducedBlock::new(
+            ProducedBlockInner {
+                transactions: vec![],
+                leader_id: None,
+                blockhash: Hash::new_unique(),
+                block_height: slot,
+                slot,
+                parent_slot: slot - 1,
+                block_time: 1_700_000_000,
+                previous_blockhash: Hash::new_unique(),
+                rewards,
+                stats: BlockStats::default(),
+            },
+            CommitmentConfig::finalized(),
+        )
+    }
+
+    #[async_trait]
+    impl EpochBlockStore for InMemoryEpochSchemas {
+        async fn query_block_in_epoch(
+            &self,
+            epoch: EpochRef,
+            slot: Slot,
+        ) -> anyhow::Result<Option<ProducedBlock>> {
+            let schemas = self.schemas.lock().unwrap();
+            Ok(schemas
+                .get(&epoch)
+                .filter(|stored| stored.contains(&slot))
+                .map(|_| create_test_block(slot)))
+        }
+
+        async fn query_first_block_in_range(
+            &self,
+            epoch: EpochRef,
+            slots: RangeInclusive<Slot>,
+        ) -> anyhow::Result<Option<ProducedBlock>> {
+            let schemas = self.schemas.lock().unwrap();
+            Ok(schemas
+                .get(&epoch)
+                .and_then(|stored| stored.range(slots).next().copied())
+                .map(create_test_block))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_get_block_across_epoch_schemas() {
+        // 1000 slots per epoch
+        let epoch_cache = EpochCache::new_for_tests();
+        let store = Arc::new(InMemoryEpochSchemas {
+            epoch_cache: epoch_cache.clone(),
+            schemas: Mutex::new(HashMap::from([
+                (EpochRef::new(0), BTreeSet::from([998, 999])),
+                (EpochRef::new(1), BTreeSet::from([1000, 1001])),
+            ])),
+        });
+        let history = History::new().with_epoch_block_store(epoch_cache, store);
+
+        // last slot of epoch 0 and first slot of epoch 1
+        assert_eq!(history.get_block(999).await.unwrap().unwrap().slot, 999);
+        assert_eq!(history.get_block(1000).await.unwrap().unwrap().slot, 1000);
+        assert!(history.get_block(1002).await.unwrap().is_none());
+        // schema of epoch 2 does not exist yet
+        assert!(history.get_block(2000).await.unwrap().is_none());
+
+        assert!(History::new().get_block(999).await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_recent_blocks_evicted_to_persistent_storage() {
+        // 1000 slots per epoch
+        let epoch_cache = EpochCache::new_for_tests();
+        // all blocks are persisted
+        let store = Arc::new(InMemoryEpochSchemas {
+            epoch_cache: epoch_cache.clone(),
+            schemas: Mutex::new(HashMap::from([
+                (EpochRef::new(0), (990..=999).collect()),
+                (EpochRef::new(1), (1000..=1009).collect()),
+            ])),
+        });
+        let history = History::new()
+            .with_epoch_block_store(epoch_cache, store)
+            .with_recent_blocks(10);
+
+        let blocks = (990..=1009).map(create_test_block).collect::<Vec<_>>();
+        for block in &blocks {
+            history.add_block(block.clone());
+        }
+
+        let recent_blocks = history.recent_blocks.as_ref().unwrap();
+        assert_eq!(recent_blocks.len(), 10);
+        // oldest slot was evicted from memory but is still served from persistent storage
+        assert!(recent_blocks.get_block(990).is_none());
+        let block = history.get_block(990).await.unwrap().unwrap();
+        assert_eq!(block.slot, 990);
+        assert_ne!(block.blockhash, blocks[0].blockhash);
+
+        // served from memory
+        assert!(recent_blocks.get_block(1000).is_some());
+        let block = history.get_block(1009).await.unwrap().unwrap();
+        assert_eq!(block.blockhash, blocks[19].blockhash);
+    }
+
+    #[tokio::test]
+    async fn test_backfill_across_epoch_schemas() {
+        // 1000 slots per epoch
+        let epoch_cache = EpochCache::new_for_tests();
+        let store = InMemoryEpochSchemas {
+            epoch_cache: epoch_cache.clone(),
+            schemas: Mutex::new(HashMap::new()),
+        };
+
+        let slots = 975..=1024;
+        let progress = backfill_blocks(&store, &epoch_cache, slots.clone(), |slot| async move {
+            Ok(Some(create_test_block(slot)))
+        })
+        .await
+        .unwrap();
+        assert_eq!(
+            progress,
+            BackfillProgress {
+                resumed_after: None,
+                blocks_saved: 50,
+                slots_skipped: 0,
+            }
+        );
+
+        {
+            let schemas = store.schemas.lock().unwrap();
+            assert_eq!(schemas.len(), 2);
+            assert_eq!(
+                schemas[&EpochRef::new(0)],
+                (975..=999).collect::<BTreeSet<_>>()
+            );
+            assert_eq!(
+                schemas[&EpochRef::new(1)],
+                (1000..=1024).collect::<BTreeSet<_>>()
+            );
+        }
+
+        // restart resumes from the highest stored slot
+        let progress = backfill_blocks(&store, &epoch_cache, slots, |slot| async move {
+            panic!("slot {slot} was already backfilled")
+        })
+        .await
+        .unwrap();
+        assert_eq!(progress.resumed_after, Some(1024));
+        assert_eq!(progress.blocks_saved, 0);
+    }
+
+    struct StoredBlocks(HashMap<Slot, ProducedBlock>);
+
+    #[async_trait]
+    impl EpochBlockStore for StoredBlocks {
+        async fn query_block_in_epoch(
+            &self,
+            _epoch: EpochRef,
+            slot: Slot,
+        ) -> anyhow::Result<Option<ProducedBlock>> {
+            Ok(self.0.get(&slot).cloned())
+        }
+
+        async fn query_first_block_in_range(
+            &self,
+            _epoch: EpochRef,
+            slots: RangeInclusive<Slot>,
+        ) -> anyhow::Result<Option<ProducedBlock>> {
+            Ok(self
+                .0
+                .iter()
+                .filter(|(slot, _)| slots.contains(slot))
+                .min_by_key(|(slot, _)| **slot)
+                .map(|(_, block)| block.clone()))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_inflation_reward_from_epoch_boundary_block() {
+        let staker = Pubkey::new_unique();
+        let voter = Pubkey::new_unique();
+        let unrewarded = Pubkey::new_unique();
+
+        // 1000 slots per epoch, rewards of epoch 4 are paid in the first block of epoch 5
+        // first slot 5000 was skipped
+        let boundary_block = create_test_block_with_rewards(
+            5001,
+            Some(vec![
+                Reward {
+                    pubkey: staker.to_string(),
+                    lamports: 42_000,
+                    post_balance: 1_042_000,
+                    reward_type: Some(RewardType::Staking),
+                    commission: Some(7),
+                },
+                Reward {
+                    pubkey: voter.to_string(),
+                    lamports: 5000,
+                    post_balance: 10_000,
+                    reward_type: Some(RewardType::Fee),
+                    commission: None,
+                },
+            ]),
+        );
+        let store = Arc::new(StoredBlocks(HashMap::from([(5001, boundary_block)])));
+        let history = History::new().with_epoch_block_store(EpochCache::new_for_tests(), store);
+
+        let rewards_block = history.get_epoch_rewards_block(4).await.unwrap().unwrap();
+        assert_eq!(rewards_block.slot, 5001);
+        let rewards = inflation_rewards(&[staker, voter, unrewarded], 4, &rewards_block);
+        assert_eq!(
+            rewards[0],
+            Some(RpcInflationReward {
+                epoch: 4,
+                effective_slot: 5001,
+                amount: 42_000,
+                post_balance: 1_042_000,
+                commission: Some(7),
+            })
+        );
+        // fee rewards are no inflation rewards
+        assert_eq!(rewards[1], None);
+        assert_eq!(rewards[2], None);
+
+        // epoch 5 is not stored
+        assert!(history.get_epoch_rewards_block(5).await.unwrap().is_none());
+    }
+
+    struct NoSpill;
+
+    #[async_trait]
+    impl SignatureSlotSpill for NoSpill {
+        async fn spill(&self, _entries: &[(Signature, Slot)]) -> anyhow::Result<()> {
+            bail!("spill not expected")
+        }
+
+        async fn lookup(&self, _signature: &Signature) -> anyhow::Result<Option<Slot>> {
+            Ok(None)
+        }
+    }
+
+    fn create_test_tx(signature: Signature, err: Option<TransactionError>) -> TransactionInfo {
+        TransactionInfo {
+            signature,
+            is_vote: false,
+            err,
+            cu_requested: None,
+            prioritization_fees: None,
+            cu_consumed: None,
+            recent_blockhash: Hash::new_unique(),
+            message: VersionedMessage::Legacy(Message::default()),
+            writable_accounts: vec![],
+            readable_accounts: vec![],
+            address_lookup_tables: vec![],
+        }
+    }
+
+    #[tokio::test]
+    async fn test_transaction_status_from_indexed_block() {
+        let history = History::new()
+            .with_recent_blocks(10)
+            .with_signature_slot_index(SignatureSlotIndex::new(100, Arc::new(NoSpill)));
+        let succeeded = Signature::new_unique();
+        let failed = Signature::new_unique();
+
+        let block = ProducedBlock::new(
+            ProducedBlockInner {
+                transactions: vec![
+                    create_test_tx(succeeded, None),
+                    create_test_tx(failed, Some(TransactionError::AccountInUse)),
+                ],
+                leader_id: None,
+                blockhash: Hash::new_unique(),
+                block_height: 1000,
+                slot: 1000,
+                parent_slot: 999,
+                block_time: 1_700_000_000,
+                previous_blockhash: Hash::new_unique(),
+                rewards: None,
+                stats: BlockStats::default(),
+            },
+            CommitmentConfig::confirmed(),
+        );
+        history.add_block(block.clone());
+        history
+            .signature_slots
+            .as_ref()
+            .unwrap()
+            .insert_block(&block)
+            .await
+            .unwrap();
+
+        let status = history
+            .get_transaction_status(&succeeded)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(status.slot, 1000);
+        assert_eq!(status.status, Ok(()));
+        assert_eq!(
+            status.confirmation_status,
+            Some(TransactionConfirmationStatus::Confirmed)
+        );
+
+        let status = history
+            .get_transaction_status(&failed)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(status.err, Some(TransactionError::AccountInUse));
+        assert_eq!(status.status, Err(TransactionError::AccountInUse));
+
+        assert!(history
+            .get_transaction_status(&Signature::new_unique())
+            .await
+            .unwrap()
+            .is_none());
+        // no index configured
+        assert!(History::new()
+            .with_recent_blocks(10)
+            .get_transaction_status(&succeeded)
+            .await
+            .unwrap()
+            .is_none());
+    }
+}
@@ -68,10 +68,13 @@ async fn storage_test() {
     let grpc_sources = vec![grpc_config];
 
     let (slot_notifier, _jh_multiplex_slotstream) =
-        create_grpc_multiplex_processed_slots_subscription(grpc_sources.clone());
+        create_grpc_multiplex_processed_slots_subscription(
+            grpc_sources.clone(),
+            CancellationToken::new(),
+        );
 
     let (blocks_notifier, _blockmeta_output_stream, _jh_multiplex_blockstream) =
-        create_grpc_multiplex_blocks_subscription(grpc_sources);
+        create_grpc_multiplex_blocks_subscription(grpc_sources, CancellationToken::new());
 
     let (epoch_cache, _) = EpochCache::bootstrap_epoch(&rpc_client).await.unwrap();
 
@@ -4,7 +4,9 @@ use solana_lite_rpc_blockstore::block_stores::postgres::postgres_block_store_que
 use solana_lite_rpc_blockstore::block_stores::postgres::postgres_block_store_writer::PostgresBlockStore;
 use solana_lite_rpc_blockstore::block_stores::postgres::PostgresSessionConfig;
 use solana_lite_rpc_core::structures::epoch::EpochCache;
-use solana_lite_rpc_core::structures::produced_block::{ProducedBlock, ProducedBlockInner};
+use solana_lite_rpc_core::structures::produced_block::{
+    BlockStats, ProducedBlock, ProducedBlockInner,
+};
 use solana_sdk::pubkey::Pubkey;
 use solana_sdk::reward_type::RewardType;
 use solana_sdk::{commitment_config::CommitmentConfig, hash::Hash};
@@ -27,6 +29,7 @@ pub fn create_test_block(slot: u64, commitment_config: CommitmentConfig) -> Prod
             reward_type: Some(RewardType::Voting),
             commission: None,
         }]),
+        stats: BlockStats::default(),
     };
     ProducedBlock::new(inner, commitment_config)
 }
@@ -1,7 +1,11 @@
+use anyhow::{anyhow, Context};
 use solana_lite_rpc_core::{
     structures::account_data::AccountStream,
     types::{BlockInfoStream, BlockStream, ClusterInfoStream, SlotStream, VoteAccountStream},
+    AnyhowJoinHandle,
 };
+use tokio::task::AbortHandle;
+use tokio_util::sync::CancellationToken;
 
 /// subscribers to broadcast channels should assume that channels are not getting closed unless the system is shutting down
 pub struct EndpointStreaming {
@@ -12,3 +16,109 @@ pub struct EndpointStreaming {
     pub cluster_info_notifier: ClusterInfoStream,
     pub processed_account_stream: Option<AccountStream>,
 }
+
+/// the streams of an endpoint together with the tasks feeding them
+pub struct EndpointSubscription {
+    pub streaming: EndpointStreaming,
+    pub tasks: EndpointTasks,
+}
+
+/// tasks feeding the endpoint streams, they are expected to run until shutdown
+pub struct EndpointTasks {
+    handles: Vec<AnyhowJoinHandle>,
+    cancel: CancellationToken,
+}
+
+impl EndpointTasks {
+    pub fn new(handles: Vec<AnyhowJoinHandle>, cancel: CancellationToken) -> Self {
+        Self { handles, cancel }
+    }
+
+    pub fn cancellation_token(&self) -> CancellationToken {
+        self.cancel.clone()
+    }
+
+    /// resolves with an error as soon as any task stops, the remaining tasks are then stopped as well;
+    /// resolves with Ok when the tasks are cancelled
+    pub async fn run(self) -> anyhow::Result<()> {
+        let Self { handles, cancel } = self;
+        if handles.is_empty() {
+            cancel.cancelled().await;
+            return Ok(());
+        }
+        let abort_handles: Vec<AbortHandle> =
+            handles.iter().map(|handle| handle.abort_handle()).collect();
+
+        let result = tokio::select! {
+            _ = cancel.cancelled() => Ok(()),
+            (res, index, _) = futures::future::select_all(handles) => {
+                match res {
+                    Ok(Ok(())) => Err(anyhow!("endpoint task {index} stopped unexpectedly")),
+                    Ok(Err(err)) => Err(err).with_context(|| format!("endpoint task {index} failed")),
+                    Err(join_error) => Err(join_error).with_context(|| format!("endpoint task {index} panicked")),
+                }
+            }
+        };
+
+        cancel.cancel();
+        abort_handles.iter().for_each(AbortHandle::abort);
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use tokio::sync::broadcast::{self, error::RecvError};
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_run_fails_when_a_channel_breaks() {
+        let (slot_sx, mut slot_rx) = broadcast::channel::<u64>(8);
+        let cancel = CancellationToken::new();
+
+        let forwarder: AnyhowJoinHandle = tokio::spawn(async move {
+            loop {
+                match slot_rx.recv().await {
+                    Ok(_) | Err(RecvError::Lagged(_)) => continue,
+                    Err(RecvError::Closed) => anyhow::bail!("slot channel closed"),
+                }
+            }
+        });
+        let poller_cancel = cancel.clone();
+        let poller: AnyhowJoinHandle = tokio::spawn(async move {
+            poller_cancel.cancelled().await;
+            Ok(())
+        });
+
+        let tasks = EndpointTasks::new(vec![forwarder, poller], cancel.clone());
+        let cancellation_token = tasks.cancellation_token();
+        let run = tokio::spawn(tasks.run());
+
+        slot_sx.send(42).unwrap();
+        drop(slot_sx);
+
+        let err = tokio::time::timeout(Duration::from_secs(5), run)
+            .await
+            .unwrap()
+            .unwrap()
+            .unwrap_err();
+        assert_eq!(err.root_cause().to_string(), "slot channel closed");
+        assert!(cancellation_token.is_cancelled());
+    }
+
+    #[tokio::test]
+    async fn test_run_stops_on_cancel() {
+        let cancel = CancellationToken::new();
+        let task: AnyhowJoinHandle = tokio::spawn(async {
+            tokio::time::sleep(Duration::from_secs(3600)).await;
+            Ok(())
+        });
+        let tasks = EndpointTasks::new(vec![task], cancel.clone());
+
+        cancel.cancel();
+        assert!(tasks.run().await.is_ok());
+    }
+}
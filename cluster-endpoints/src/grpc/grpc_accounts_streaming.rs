@@ -179,6 +179,7 @@ pub fn start_account_streaming_tasks(
                                         rent_epoch: account_data.rent_epoch,
                                     }),
                                     updated_slot: account.slot,
+                                    write_version: account_data.write_version,
                                 },
                                 // TODO update with processed commitment / check above
                                 commitment: Commitment::Processed,
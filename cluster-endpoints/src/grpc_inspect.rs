@@ -1,29 +1,358 @@
+use anyhow::bail;
 use chrono::{DateTime, Utc};
-use log::{debug, info, warn};
+use log::{debug, error, info, warn};
+use serde::{Deserialize, Serialize};
+use solana_lite_rpc_core::structures::epoch::EpochCache;
 use solana_lite_rpc_core::types::BlockStream;
 use solana_sdk::clock::Slot;
 use solana_sdk::commitment_config::CommitmentConfig;
 use solana_sdk::hash::Hash;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
+use std::str::FromStr;
 use std::sync::atomic::AtomicU64;
 use std::sync::Arc;
 use std::time::{Duration, SystemTime};
 use tokio::sync::broadcast::error::RecvError;
 use tokio::task::JoinHandle;
 use tokio::time::{sleep, Instant};
+use tokio_util::sync::CancellationToken;
+
+pub fn epoch_of_slot(epoch_cache: &EpochCache, slot: Slot) -> u64 {
+    epoch_cache.get_epoch_at_slot(slot).epoch
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SlotProgression {
+    /// first slot seen or first slot of a new epoch, nothing to compare with
+    Reset,
+    /// slot is higher than all slots seen in the epoch
+    Monotonic {
+        last_highest_slot: Slot,
+    },
+    OutOfOrder {
+        last_highest_slot: Slot,
+    },
+}
+
+/// Tracks the highest slot seen per epoch, the tracking starts over when the epoch changes
+/// so that slot gaps around the epoch boundary are not reported as out of order.
+pub struct SlotProgressionTracker {
+    epoch_cache: EpochCache,
+    current_epoch: Option<u64>,
+    last_highest_slot: Slot,
+}
+
+impl SlotProgressionTracker {
+    pub fn new(epoch_cache: EpochCache) -> Self {
+        Self {
+            epoch_cache,
+            current_epoch: None,
+            last_highest_slot: 0,
+        }
+    }
+
+    pub fn observe(&mut self, slot: Slot) -> SlotProgression {
+        let epoch = epoch_of_slot(&self.epoch_cache, slot);
+        if self.current_epoch != Some(epoch) {
+            if let Some(prev_epoch) = self.current_epoch {
+                debug!("epoch changed from {prev_epoch} to {epoch} at slot {slot} - reset slot tracking");
+            }
+            self.current_epoch = Some(epoch);
+            self.last_highest_slot = slot;
+            return SlotProgression::Reset;
+        }
+
+        let last_highest_slot = self.last_highest_slot;
+        if slot > last_highest_slot {
+            self.last_highest_slot = slot;
+            SlotProgression::Monotonic { last_highest_slot }
+        } else {
+            SlotProgression::OutOfOrder { last_highest_slot }
+        }
+    }
+}
+
+/// invariants of the commitment levels a block is seen with on the block stream
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ConfirmationRule {
+    /// a block is seen at most once per commitment level
+    NoDuplicates,
+    /// a confirmed or finalized block was seen as processed before
+    ProcessedFirst,
+    /// a finalized block was seen as confirmed before
+    ConfirmedBeforeFinalized,
+    /// a block is not seen at a lower commitment level after a higher one
+    NoDowngrade,
+}
+
+impl ConfirmationRule {
+    pub const ALL: [ConfirmationRule; 4] = [
+        ConfirmationRule::NoDuplicates,
+        ConfirmationRule::ProcessedFirst,
+        ConfirmationRule::ConfirmedBeforeFinalized,
+        ConfirmationRule::NoDowngrade,
+    ];
+}
+
+impl FromStr for ConfirmationRule {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "no-duplicates" => Ok(ConfirmationRule::NoDuplicates),
+            "processed-first" => Ok(ConfirmationRule::ProcessedFirst),
+            "confirmed-before-finalized" => Ok(ConfirmationRule::ConfirmedBeforeFinalized),
+            "no-downgrade" => Ok(ConfirmationRule::NoDowngrade),
+            _ => bail!("unknown confirmation rule '{s}'"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ViolationLevel {
+    #[default]
+    Warn,
+    Error,
+}
+
+impl FromStr for ViolationLevel {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "warn" => Ok(ViolationLevel::Warn),
+            "error" => Ok(ViolationLevel::Error),
+            _ => bail!("unknown violation level '{s}', expected warn or error"),
+        }
+    }
+}
+
+/// rules enforced by the confirmation sequence inspection and how violations are logged
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ConfirmationRuleSet {
+    pub rules: HashSet<ConfirmationRule>,
+    pub violation_level: ViolationLevel,
+}
+
+impl Default for ConfirmationRuleSet {
+    fn default() -> Self {
+        Self {
+            rules: ConfirmationRule::ALL.into_iter().collect(),
+            violation_level: ViolationLevel::default(),
+        }
+    }
+}
+
+impl ConfirmationRuleSet {
+    /// comma separated rules, e.g. "no-duplicates,processed-first"
+    pub fn parse_rules(rules: &str) -> anyhow::Result<HashSet<ConfirmationRule>> {
+        rules
+            .split(',')
+            .map(str::trim)
+            .filter(|rule| !rule.is_empty())
+            .map(ConfirmationRule::from_str)
+            .collect()
+    }
+
+    fn log_violation(&self, rule: ConfirmationRule, message: &str) {
+        match self.violation_level {
+            ViolationLevel::Warn => warn!("{message} [{rule:?}]"),
+            ViolationLevel::Error => error!("{message} [{rule:?}]"),
+        }
+    }
+}
+
+/// blocks seen per commitment level, checked against the enabled rules
+pub struct ConfirmationSequenceChecker {
+    rule_set: ConfirmationRuleSet,
+    // use blockhash as key instead of slot as for processed the slot is ambiguous
+    saw_processed_at: HashMap<String, (Slot, SystemTime)>,
+    saw_confirmed_at: HashMap<String, (Slot, SystemTime)>,
+    saw_finalized_at: HashMap<String, (Slot, SystemTime)>,
+}
+
+impl ConfirmationSequenceChecker {
+    pub fn new(rule_set: ConfirmationRuleSet) -> Self {
+        Self {
+            rule_set,
+            saw_processed_at: HashMap::new(),
+            saw_confirmed_at: HashMap::new(),
+            saw_finalized_at: HashMap::new(),
+        }
+    }
+
+    /// records the block and returns the violations of the enabled rules
+    pub fn check(
+        &mut self,
+        slot: Slot,
+        blockhash: &str,
+        commitment_config: CommitmentConfig,
+    ) -> Vec<(ConfirmationRule, String)> {
+        let mut violations = vec![];
+
+        if commitment_config.is_processed() {
+            if let Some(prev) = self
+                .saw_processed_at
+                .insert(blockhash.to_string(), (slot, SystemTime::now()))
+            {
+                violations.push((
+                    ConfirmationRule::NoDuplicates,
+                    format!(
+                        "should not see same processed slot twice ({}, {}) - saw at {:?}",
+                        slot,
+                        blockhash,
+                        format_timestamp(&prev.1)
+                    ),
+                ));
+            }
+        }
+        if commitment_config.is_confirmed() {
+            if let Some(prev) = self
+                .saw_confirmed_at
+                .insert(blockhash.to_string(), (slot, SystemTime::now()))
+            {
+                violations.push((
+                    ConfirmationRule::NoDuplicates,
+                    format!(
+                        "should not see same confirmed slot twice ({}) - saw at {:?}",
+                        blockhash,
+                        format_timestamp(&prev.1)
+                    ),
+                ));
+            }
+        }
+        if commitment_config.is_finalized() {
+            if let Some(prev) = self
+                .saw_finalized_at
+                .insert(blockhash.to_string(), (slot, SystemTime::now()))
+            {
+                violations.push((
+                    ConfirmationRule::NoDuplicates,
+                    format!(
+                        "should not see same finalized slot twice ({}) - saw at {:?}",
+                        slot,
+                        format_timestamp(&prev.1)
+                    ),
+                ));
+            }
+        }
+
+        // rule: if confirmed, we should have seen processed but not finalized
+        if commitment_config.is_confirmed() {
+            if !self.saw_processed_at.contains_key(blockhash) {
+                violations.push((
+                    ConfirmationRule::ProcessedFirst,
+                    format!(
+                        "should not see confirmed slot without seeing processed slot first ({})",
+                        blockhash
+                    ),
+                ));
+            }
+            if self.saw_finalized_at.contains_key(blockhash) {
+                violations.push((
+                    ConfirmationRule::NoDowngrade,
+                    format!(
+                        "should not see confirmed slot after seeing finalized slot ({})",
+                        blockhash
+                    ),
+                ));
+            }
+        }
+
+        // rule: if processed, we should have seen neither confirmed nor finalized
+        if commitment_config.is_processed() {
+            if self.saw_confirmed_at.contains_key(blockhash) {
+                violations.push((
+                    ConfirmationRule::NoDowngrade,
+                    format!(
+                        "should not see processed slot after seeing confirmed slot ({})",
+                        blockhash
+                    ),
+                ));
+            }
+            if self.saw_finalized_at.contains_key(blockhash) {
+                violations.push((
+                    ConfirmationRule::NoDowngrade,
+                    format!(
+                        "should not see processed slot after seeing finalized slot ({})",
+                        blockhash
+                    ),
+                ));
+            }
+        }
+
+        // rule: if finalized, we should have seen processed and confirmed
+        if commitment_config.is_finalized() {
+            if !self.saw_processed_at.contains_key(blockhash) {
+                violations.push((
+                    ConfirmationRule::ProcessedFirst,
+                    format!(
+                        "should not see finalized slot without seeing processed slot first ({})",
+                        blockhash
+                    ),
+                ));
+            }
+            if !self.saw_confirmed_at.contains_key(blockhash) {
+                violations.push((
+                    ConfirmationRule::ConfirmedBeforeFinalized,
+                    format!(
+                        "should not see finalized slot without seeing confirmed slot first ({})",
+                        blockhash
+                    ),
+                ));
+            }
+
+            if let (Some(processed), Some(confirmed)) = (
+                self.saw_processed_at.get(blockhash),
+                self.saw_confirmed_at.get(blockhash),
+            ) {
+                let finalized = self.saw_finalized_at.get(blockhash).unwrap();
+                debug!(
+                    "block sequence seen on channel for block {} (slot {}): {:?} -> {:?} -> {:?}",
+                    blockhash,
+                    slot,
+                    format_timestamp(&processed.1),
+                    format_timestamp(&confirmed.1),
+                    format_timestamp(&finalized.1)
+                );
+            }
+        }
+
+        violations.retain(|(rule, _)| self.rule_set.rules.contains(rule));
+        violations
+    }
+
+    pub fn cleanup(&mut self, cleanup_before_slot: Slot) {
+        self.saw_processed_at
+            .retain(|_blockhash, (slot, _instant)| *slot >= cleanup_before_slot);
+        self.saw_confirmed_at
+            .retain(|_blockhash, (slot, _instant)| *slot >= cleanup_before_slot);
+        self.saw_finalized_at
+            .retain(|_blockhash, (slot, _instant)| *slot >= cleanup_before_slot);
+        debug!(
+            "map sizes after cleanup: {} processed, {} confirmed, {} finalized",
+            self.saw_processed_at.len(),
+            self.saw_confirmed_at.len(),
+            self.saw_finalized_at.len()
+        );
+    }
+}
 
 // note: we assume that the invariants hold even right after startup
 pub fn debugtask_blockstream_confirmation_sequence(
     mut block_notifier: BlockStream,
+    epoch_cache: EpochCache,
+    rule_set: ConfirmationRuleSet,
 ) -> JoinHandle<()> {
     tokio::spawn(async move {
         let mut cleanup_before_slot = 0;
+        let mut current_epoch = None;
         // throttle cleanup
         let mut slots_since_last_cleanup = 0;
-        // use blockhash as key instead of slot as for processed the slot is ambiguous
-        let mut saw_processed_at: HashMap<String, (Slot, SystemTime)> = HashMap::new();
-        let mut saw_confirmed_at: HashMap<String, (Slot, SystemTime)> = HashMap::new();
-        let mut saw_finalized_at: HashMap<String, (Slot, SystemTime)> = HashMap::new();
+        let mut checker = ConfirmationSequenceChecker::new(rule_set.clone());
         'recv_loop: loop {
             match block_notifier.recv().await {
                 Ok(block) => {
@@ -40,142 +369,23 @@ pub fn debugtask_blockstream_confirmation_sequence(
                         block.transactions.len()
                     );
 
-                    if block.commitment_config.is_processed() {
-                        let prev_value =
-                            saw_processed_at.insert(blockhash.clone(), (slot, SystemTime::now()));
-                        match prev_value {
-                            None => {
-                                // okey
-                            }
-                            Some(prev) => {
-                                // this is actually fatal
-                                warn!(
-                                    "should not see same processed slot twice ({}, {}) - saw at {:?}",
-                                    slot,
-                                    blockhash,
-                                    format_timestamp(&prev.1)
-                                );
-                            }
-                        }
-                    }
-                    if block.commitment_config.is_confirmed() {
-                        let prev_value =
-                            saw_confirmed_at.insert(blockhash.clone(), (slot, SystemTime::now()));
-                        match prev_value {
-                            None => {
-                                // okey
-                            }
-                            Some(prev) => {
-                                // this is actually fatal
-                                warn!(
-                                    "should not see same confirmed slot twice ({}) - saw at {:?}",
-                                    blockhash,
-                                    format_timestamp(&prev.1)
-                                );
-                            }
-                        }
-                    }
-                    if block.commitment_config.is_finalized() {
-                        let prev_value =
-                            saw_finalized_at.insert(blockhash.clone(), (slot, SystemTime::now()));
-                        match prev_value {
-                            None => {
-                                // okey
-                            }
-                            Some(prev) => {
-                                // this is actually fatal
-                                warn!(
-                                    "should not see same finalized slot twice ({}) - saw at {:?}",
-                                    slot,
-                                    format_timestamp(&prev.1)
-                                );
-                            }
-                        }
-                    }
-
-                    // rule: if confirmed, we should have seen processed but not finalized
-                    if block.commitment_config.is_confirmed() {
-                        if saw_processed_at.contains_key(&blockhash) {
-                            // okey
-                        } else {
-                            warn!("should not see confirmed slot without seeing processed slot first ({})", blockhash);
-                        }
-                        if saw_finalized_at.contains_key(&blockhash) {
-                            warn!(
-                                "should not see confirmed slot after seeing finalized slot ({})",
-                                blockhash
-                            );
-                        } else {
-                            // okey
-                        }
+                    for (rule, message) in checker.check(slot, &blockhash, block.commitment_config)
+                    {
+                        rule_set.log_violation(rule, &message);
                     }
 
-                    // rule: if processed, we should have seen neither confirmed nor finalized
-                    if block.commitment_config.is_processed() {
-                        if saw_confirmed_at.contains_key(&blockhash) {
-                            warn!(
-                                "should not see processed slot after seeing confirmed slot ({})",
-                                blockhash
-                            );
-                        } else {
-                            // okey
-                        }
-                        if saw_finalized_at.contains_key(&blockhash) {
-                            warn!(
-                                "should not see processed slot after seeing finalized slot ({})",
-                                blockhash
-                            );
-                        } else {
-                            // okey
-                        }
-                    }
+                    // cleanup at each epoch boundary as the slot gap might be large
+                    let epoch = epoch_of_slot(&epoch_cache, slot);
+                    let epoch_changed = current_epoch.is_some_and(|current| current != epoch);
+                    current_epoch = Some(epoch);
 
-                    // rule: if finalized, we should have seen processed and confirmed
-                    if block.commitment_config.is_finalized() {
-                        if saw_processed_at.contains_key(&blockhash) {
-                            // okey
-                        } else {
-                            warn!("should not see finalized slot without seeing processed slot first ({})", blockhash);
-                        }
-                        if saw_confirmed_at.contains_key(&blockhash) {
-                            // okey
-                        } else {
-                            warn!("should not see finalized slot without seeing confirmed slot first ({})", blockhash);
-                        }
-
-                        if let (Some(processed), Some(confirmed)) = (
-                            saw_processed_at.get(&blockhash),
-                            saw_confirmed_at.get(&blockhash),
-                        ) {
-                            let finalized = saw_finalized_at.get(&blockhash).unwrap();
-                            debug!(
-                                "block sequence seen on channel for block {} (slot {}): {:?} -> {:?} -> {:?}",
-                                blockhash, slot,
-                                format_timestamp(&processed.1),
-                                format_timestamp(&confirmed.1),
-                                format_timestamp(&finalized.1)
-                            );
-                        }
-                    }
-
-                    if slots_since_last_cleanup < 500 {
+                    if slots_since_last_cleanup < 500 && !epoch_changed {
                         slots_since_last_cleanup += 1;
                     } else {
                         // perform cleanup, THEN update cleanup_before_slot
-                        saw_processed_at
-                            .retain(|_blockhash, (slot, _instant)| *slot >= cleanup_before_slot);
-                        saw_confirmed_at
-                            .retain(|_blockhash, (slot, _instant)| *slot >= cleanup_before_slot);
-                        saw_finalized_at
-                            .retain(|_blockhash, (slot, _instant)| *slot >= cleanup_before_slot);
-                        cleanup_before_slot = slot - 200;
+                        checker.cleanup(cleanup_before_slot);
+                        cleanup_before_slot = slot.saturating_sub(200);
                         debug!("move cleanup point to {}", cleanup_before_slot);
-                        debug!(
-                            "map sizes after cleanup: {} processed, {} confirmed, {} finalized",
-                            saw_processed_at.len(),
-                            saw_confirmed_at.len(),
-                            saw_finalized_at.len()
-                        );
                         slots_since_last_cleanup = 0;
                     }
                 } // -- Ok
@@ -198,12 +408,17 @@ pub fn debugtask_blockstream_confirmation_sequence(
 pub fn debugtask_blockstream_slot_progression(
     mut block_notifier: BlockStream,
     commitment_config: CommitmentConfig,
+    epoch_cache: EpochCache,
 ) -> JoinHandle<()> {
     let latest_slot_seen_shared = Arc::new(AtomicU64::new(0));
     const WARNING_THRESHOLD: Duration = Duration::from_secs(10);
 
+    // stops the liveness check once the block stream is closed
+    let stream_closed = CancellationToken::new();
+
     let latest_slot_seen = latest_slot_seen_shared.clone();
-    tokio::spawn(async move {
+    let liveness_stopped = stream_closed.clone();
+    let jh_liveness = tokio::spawn(async move {
         let mut prev_slot = 0;
         let mut last_changed_at = Instant::now();
         loop {
@@ -211,8 +426,10 @@ pub fn debugtask_blockstream_slot_progression(
             // startup
             if prev_slot == 0 {
                 prev_slot = latest_slot;
-                sleep(Duration::from_millis(1000)).await;
-                continue;
+                tokio::select! {
+                    _ = liveness_stopped.cancelled() => break,
+                    _ = sleep(Duration::from_millis(1000)) => continue,
+                }
             }
 
             if latest_slot != prev_slot {
@@ -230,13 +447,16 @@ pub fn debugtask_blockstream_slot_progression(
                 }
             }
             prev_slot = latest_slot;
-            sleep(Duration::from_millis(5000)).await;
+            tokio::select! {
+                _ = liveness_stopped.cancelled() => break,
+                _ = sleep(Duration::from_millis(5000)) => {}
+            }
         }
     });
 
     let last_slot_seen = latest_slot_seen_shared.clone();
     tokio::spawn(async move {
-        let mut last_highest_slot_number = 0;
+        let mut slot_tracker = SlotProgressionTracker::new(epoch_cache);
         let mut last_blockhash: Option<Hash> = None;
 
         'recv_loop: loop {
@@ -255,29 +475,34 @@ pub fn debugtask_blockstream_slot_progression(
 
                     last_slot_seen.store(block.slot, std::sync::atomic::Ordering::Relaxed);
 
-                    if last_highest_slot_number != 0 {
-                        if block.parent_slot == last_highest_slot_number {
+                    match slot_tracker.observe(block.slot) {
+                        SlotProgression::Reset => {
                             debug!(
-                                "parent block@{} is correct ({} -> {})",
-                                commitment_config.commitment, block.slot, block.parent_slot
+                                "slot tracking@{} (re)started at block {}",
+                                commitment_config.commitment, block.slot
                             );
-                        } else {
-                            warn!(
-                                "parent block@{} not correct ({} -> {}, last_highest_slot_number={})",
-                                commitment_config.commitment, block.slot, block.parent_slot, last_highest_slot_number
+                        }
+                        SlotProgression::Monotonic { last_highest_slot } => {
+                            if block.parent_slot == last_highest_slot {
+                                debug!(
+                                    "parent block@{} is correct ({} -> {})",
+                                    commitment_config.commitment, block.slot, block.parent_slot
+                                );
+                            } else {
+                                warn!(
+                                    "parent block@{} not correct ({} -> {}, last_highest_slot_number={})",
+                                    commitment_config.commitment, block.slot, block.parent_slot, last_highest_slot
+                                );
+                            }
+                        }
+                        SlotProgression::OutOfOrder { last_highest_slot } => {
+                            // note: ATM this fails very often (using the RPC poller)
+                            warn!("monotonic check failed - block {} is out of order, last highest was {}",
+                                block.slot, last_highest_slot
                             );
                         }
                     }
 
-                    if block.slot > last_highest_slot_number {
-                        last_highest_slot_number = block.slot;
-                    } else {
-                        // note: ATM this fails very often (using the RPC poller)
-                        warn!("monotonic check failed - block {} is out of order, last highest was {}",
-                            block.slot, last_highest_slot_number
-                        );
-                    }
-
                     if let Some(last_blockhash) = last_blockhash {
                         if block.previous_blockhash == last_blockhash {
                             debug!(
@@ -306,6 +531,8 @@ pub fn debugtask_blockstream_slot_progression(
                 }
             }
         } // -- END receiver loop
+        stream_closed.cancel();
+        let _ = jh_liveness.await;
         info!("Geyser channel debug task for slot progression shutting down.")
     })
 }
@@ -315,3 +542,99 @@ fn format_timestamp(d: &SystemTime) -> String {
     let datetime = DateTime::<Utc>::from(*d);
     datetime.format("%Y-%m-%d %H:%M:%S.%f").to_string()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_slot_progression_across_epoch_boundary() {
+        // 1000 slots per epoch
+        let mut tracker = SlotProgressionTracker::new(EpochCache::new_for_tests());
+
+        assert_eq!(tracker.observe(997), SlotProgression::Reset);
+        assert_eq!(
+            tracker.observe(998),
+            SlotProgression::Monotonic {
+                last_highest_slot: 997
+            }
+        );
+        // slots 999..=1001 skipped, the new epoch starts over
+        assert_eq!(tracker.observe(1002), SlotProgression::Reset);
+        assert_eq!(
+            tracker.observe(1003),
+            SlotProgression::Monotonic {
+                last_highest_slot: 1002
+            }
+        );
+
+        // out of order within the epoch is still detected
+        assert_eq!(
+            tracker.observe(1001),
+            SlotProgression::OutOfOrder {
+                last_highest_slot: 1003
+            }
+        );
+        assert_eq!(epoch_of_slot(&EpochCache::new_for_tests(), 999), 0);
+        assert_eq!(epoch_of_slot(&EpochCache::new_for_tests(), 1000), 1);
+    }
+
+    #[tokio::test]
+    async fn test_debug_tasks_terminate_when_sender_is_dropped() {
+        let (block_sender, block_notifier) = tokio::sync::broadcast::channel(8);
+        let confirmation_sequence = debugtask_blockstream_confirmation_sequence(
+            block_notifier.resubscribe(),
+            EpochCache::new_for_tests(),
+            ConfirmationRuleSet::default(),
+        );
+        let slot_progression = debugtask_blockstream_slot_progression(
+            block_notifier,
+            CommitmentConfig::confirmed(),
+            EpochCache::new_for_tests(),
+        );
+        drop(block_sender);
+
+        for task in [confirmation_sequence, slot_progression] {
+            tokio::time::timeout(Duration::from_secs(1), task)
+                .await
+                .expect("debug task must terminate once the sender is gone")
+                .unwrap();
+        }
+    }
+
+    #[test]
+    fn test_disabled_rule_reports_no_violation() {
+        let blockhash = Hash::new_unique().to_string();
+        // a source which does not emit processed blocks
+        let mut checker = ConfirmationSequenceChecker::new(ConfirmationRuleSet::default());
+        let violations = checker.check(42, &blockhash, CommitmentConfig::confirmed());
+        assert_eq!(
+            violations.iter().map(|(rule, _)| *rule).collect::<Vec<_>>(),
+            vec![ConfirmationRule::ProcessedFirst]
+        );
+
+        let rule_set = ConfirmationRuleSet {
+            rules: ConfirmationRuleSet::parse_rules(
+                "no-duplicates, confirmed-before-finalized, no-downgrade",
+            )
+            .unwrap(),
+            violation_level: ViolationLevel::Error,
+        };
+        let mut checker = ConfirmationSequenceChecker::new(rule_set);
+        assert!(checker
+            .check(42, &blockhash, CommitmentConfig::confirmed())
+            .is_empty());
+        assert!(checker
+            .check(42, &blockhash, CommitmentConfig::finalized())
+            .is_empty());
+
+        // the enabled rules are still checked
+        let violations = checker.check(42, &blockhash, CommitmentConfig::processed());
+        assert_eq!(
+            violations.iter().map(|(rule, _)| *rule).collect::<Vec<_>>(),
+            vec![ConfirmationRule::NoDowngrade, ConfirmationRule::NoDowngrade]
+        );
+
+        assert!(ConfirmationRuleSet::parse_rules("processed-first,unknown").is_err());
+    }
+}
@@ -3,53 +3,91 @@ use geyser_grpc_connector::grpc_subscription_autoreconnect_tasks::create_geyser_
 use geyser_grpc_connector::{GeyserFilter, GrpcSourceConfig, Message};
 use itertools::Itertools;
 use log::{debug, info, trace, warn};
+use prometheus::{
+    histogram_opts, opts, register_gauge_vec, register_histogram_vec, register_int_counter_vec,
+    GaugeVec, HistogramVec, IntCounterVec,
+};
+use solana_lite_rpc_core::commitment_utils::Commitment;
 use solana_lite_rpc_core::structures::produced_block::ProducedBlock;
 use solana_lite_rpc_core::structures::slot_notification::SlotNotification;
 use solana_lite_rpc_core::AnyhowJoinHandle;
 use solana_sdk::clock::Slot;
-use solana_sdk::commitment_config::CommitmentConfig;
+use solana_sdk::commitment_config::{CommitmentConfig, CommitmentLevel};
+use solana_sdk::hash::Hash;
 
+use rand::Rng;
 use solana_lite_rpc_core::solana_utils::hash_from_str;
 use solana_lite_rpc_core::structures::block_info::BlockInfo;
-use std::collections::{BTreeSet, HashMap, HashSet};
-use std::time::Duration;
+use solana_lite_rpc_util::obfuscate_rpcurl;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet, VecDeque};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 use tokio::sync::broadcast::{self, Receiver};
 use tokio::task::JoinHandle;
 use tokio::time::{sleep, Instant};
+use tokio_util::sync::CancellationToken;
 use tracing::debug_span;
 use yellowstone_grpc_proto::geyser::subscribe_update::UpdateOneof;
 use yellowstone_grpc_proto::geyser::SubscribeUpdate;
 
-use crate::grpc_subscription::from_grpc_block_update;
+use crate::grpc_source_mapper::{BlockSourceKind, FromYellowstoneMapper, MultiplexSource};
+
+lazy_static::lazy_static! {
+    static ref MULTIPLEX_SOURCE_BLOCKS_WON: IntCounterVec =
+        register_int_counter_vec!(opts!("literpc_multiplex_source_blocks_won", "Number of processed blocks first delivered by a grpc source"), &["source"]).unwrap();
+    static ref MULTIPLEX_SOURCE_WIN_RATIO: GaugeVec =
+        register_gauge_vec!(opts!("literpc_multiplex_source_win_ratio", "Fraction of the recent processed blocks first delivered by a grpc source"), &["source"]).unwrap();
+    static ref BLOCK_INGESTION_LAG: HistogramVec =
+        register_histogram_vec!(histogram_opts!("literpc_block_ingestion_lag_seconds", "Time from the block time of a block until it is forwarded by the multiplexer", vec![0.25, 0.5, 1.0, 2.0, 5.0, 10.0, 30.0, 60.0]), &["commitment"]).unwrap();
+}
 
 /// connect to all sources provided using transparent autoconnection task
+/// each source is mapped with the mapper of its kind, e.g. one source provides blocks and another one transactions
 /// shutdown handling:
 /// - task will shutdown of the receiver side of block_sender gets closed
 /// - will also shutdown the grpc autoconnection task(s)
 fn create_grpc_multiplex_processed_block_task(
-    grpc_sources: &Vec<GrpcSourceConfig>,
+    sources: &[MultiplexSource],
     block_sender: tokio::sync::mpsc::Sender<ProducedBlock>,
     mut exit_notify: broadcast::Receiver<()>,
 ) -> Vec<JoinHandle<()>> {
     const COMMITMENT_CONFIG: CommitmentConfig = CommitmentConfig::processed();
 
-    let (autoconnect_tx, mut blocks_rx) = tokio::sync::mpsc::channel(10);
+    let (autoconnect_tx, mut blocks_rx) = tokio::sync::mpsc::channel::<(usize, Message)>(10);
     let mut tasks = vec![];
 
-    for grpc_source in grpc_sources {
+    for (source_index, source) in sources.iter().enumerate() {
+        let (source_tx, source_rx) = tokio::sync::mpsc::channel(10);
         let task = create_geyser_autoconnection_task_with_mpsc(
-            grpc_source.clone(),
-            GeyserFilter(COMMITMENT_CONFIG).blocks_and_txs(),
-            autoconnect_tx.clone(),
+            source.grpc_source.clone(),
+            source.kind.subscribe_request(COMMITMENT_CONFIG),
+            source_tx,
             exit_notify.resubscribe(),
         );
         tasks.push(task);
+
+        // tag the messages with the source to map them and to track which source delivered a block first
+        tasks.push(spawn_tag_source(
+            source_index,
+            source_rx,
+            autoconnect_tx.clone(),
+        ));
     }
 
+    let source_labels = sources
+        .iter()
+        .map(|source| obfuscate_rpcurl(&source.grpc_source.grpc_addr))
+        .collect_vec();
+    let mut merger = ProcessedBlockMerger::new(
+        source_labels
+            .iter()
+            .cloned()
+            .zip(sources.iter().map(|source| source.kind))
+            .collect(),
+    );
     let jh_merging_streams = tokio::task::spawn(async move {
-        let mut slots_processed = BTreeSet::<u64>::new();
+        let mut reconnect_log = ReconnectLogThrottle::new();
         let mut last_tick = Instant::now();
-        'recv_loop: loop {
+        loop {
             // recv loop
             if last_tick.elapsed() > Duration::from_millis(800) {
                 warn!(
@@ -59,7 +97,6 @@ fn create_grpc_multiplex_processed_block_task(
             }
             last_tick = Instant::now();
 
-            const MAX_SIZE: usize = 1024;
             let blocks_rx_result = tokio::select! {
                 res = blocks_rx.recv() => {
                     res
@@ -69,54 +106,39 @@ fn create_grpc_multiplex_processed_block_task(
                 }
             };
             match blocks_rx_result {
-                Some(Message::GeyserSubscribeUpdate(subscribe_update)) => {
-                    // note: avoid mapping of full block as long as possible
-                    let extracted_slot = extract_slot_from_yellowstone_update(&subscribe_update);
-                    if let Some(slot) = extracted_slot {
-                        // check if the slot is in the map, if not check if the container is half full and the slot in question is older than the lowest value
-                        // it means that the slot is too old to process
-                        if slots_processed.contains(&slot) {
-                            continue 'recv_loop;
-                        }
-                        if slots_processed.len() >= MAX_SIZE / 2
-                            && slot <= slots_processed.first().cloned().unwrap_or_default()
-                        {
-                            continue 'recv_loop;
-                        }
-
-                        let mapfilter =
-                            map_block_from_yellowstone_update(*subscribe_update, COMMITMENT_CONFIG);
-                        if let Some((_slot, produced_block)) = mapfilter {
-                            let send_started_at = Instant::now();
-                            let send_result = block_sender
-                                .send(produced_block)
-                                .await
-                                .context("Send block to channel");
-                            if send_result.is_err() {
-                                warn!("Block channel receiver is closed - aborting");
-                                break;
-                            }
-
-                            trace!(
-                                "emitted block #{}@{} from multiplexer took {:?}",
-                                slot,
-                                COMMITMENT_CONFIG.commitment,
-                                send_started_at.elapsed()
-                            );
-
-                            slots_processed.insert(slot);
-                            if slots_processed.len() > MAX_SIZE {
-                                slots_processed.pop_first();
-                            }
-                        }
+                Some((source_index, Message::GeyserSubscribeUpdate(subscribe_update))) => {
+                    let Some(produced_block) =
+                        merger.on_update(source_index, *subscribe_update, COMMITMENT_CONFIG)
+                    else {
+                        continue;
+                    };
+                    let slot = produced_block.slot;
+                    let send_started_at = Instant::now();
+                    let send_result = block_sender
+                        .send(produced_block)
+                        .await
+                        .context("Send block to channel");
+                    if send_result.is_err() {
+                        warn!("Block channel receiver is closed - aborting");
+                        break;
                     }
+
+                    trace!(
+                        "emitted block #{}@{} from multiplexer took {:?}",
+                        slot,
+                        COMMITMENT_CONFIG.commitment,
+                        send_started_at.elapsed()
+                    );
                 }
-                Some(Message::Connecting(attempt)) => {
+                Some((source_index, Message::Connecting(attempt))) => {
+                    let source = &source_labels[source_index];
                     if attempt > 1 {
-                        warn!(
-                            "Multiplexed geyser stream performs reconnect attempt {}",
-                            attempt
-                        );
+                        if let Some(suppressed) = reconnect_log.should_log(source, Instant::now()) {
+                            warn!(
+                                "Multiplexed geyser stream of {} performs reconnect attempt {} ({} attempts not logged)",
+                                source, attempt, suppressed
+                            );
+                        }
                     }
                 }
                 None => {
@@ -130,6 +152,67 @@ fn create_grpc_multiplex_processed_block_task(
     tasks
 }
 
+/// combines the updates of heterogeneous sources to processed blocks, the first source to complete a slot wins
+struct ProcessedBlockMerger {
+    mappers: Vec<Box<dyn FromYellowstoneMapper>>,
+    source_labels: Vec<String>,
+    source_win_ratio: SourceWinRatio,
+    slots_processed: BTreeSet<Slot>,
+}
+
+impl ProcessedBlockMerger {
+    const MAX_SLOTS_PROCESSED: usize = 1024;
+
+    fn new(sources: Vec<(String, BlockSourceKind)>) -> Self {
+        let (source_labels, mappers): (Vec<_>, Vec<_>) = sources
+            .into_iter()
+            .map(|(label, kind)| (label, kind.create_mapper()))
+            .unzip();
+        Self {
+            mappers,
+            source_win_ratio: SourceWinRatio::new(
+                SourceWinRatio::DEFAULT_WINDOW,
+                source_labels.clone(),
+            ),
+            source_labels,
+            slots_processed: BTreeSet::new(),
+        }
+    }
+
+    /// the slot was already emitted or is too old to process
+    fn is_processed(&self, slot: Slot) -> bool {
+        self.slots_processed.contains(&slot)
+            || self.slots_processed.len() >= Self::MAX_SLOTS_PROCESSED / 2
+                && slot <= self.slots_processed.first().cloned().unwrap_or_default()
+    }
+
+    fn on_update(
+        &mut self,
+        source_index: usize,
+        update: SubscribeUpdate,
+        commitment_config: CommitmentConfig,
+    ) -> Option<ProducedBlock> {
+        // note: avoid mapping of full block as long as possible
+        if let Some(slot) = extract_slot_from_yellowstone_update(&update) {
+            if self.is_processed(slot) {
+                return None;
+            }
+        }
+        let produced_block = self.mappers[source_index].map_update(update, commitment_config)?;
+        if self.is_processed(produced_block.slot) {
+            return None;
+        }
+
+        self.source_win_ratio
+            .record_win(&self.source_labels[source_index]);
+        self.slots_processed.insert(produced_block.slot);
+        if self.slots_processed.len() > Self::MAX_SLOTS_PROCESSED {
+            self.slots_processed.pop_first();
+        }
+        Some(produced_block)
+    }
+}
+
 // backpressure: the mpsc sender will block grpc stream until capacity is available
 fn create_grpc_multiplex_block_info_task(
     grpc_sources: &Vec<GrpcSourceConfig>,
@@ -139,17 +222,28 @@ fn create_grpc_multiplex_block_info_task(
 ) -> Vec<JoinHandle<()>> {
     let (autoconnect_tx, mut blocks_rx) = tokio::sync::mpsc::channel(10);
     let mut tasks = vec![];
-    for grpc_source in grpc_sources {
+    for (source_index, grpc_source) in grpc_sources.iter().enumerate() {
+        let (source_tx, source_rx) = tokio::sync::mpsc::channel(10);
         let task = create_geyser_autoconnection_task_with_mpsc(
             grpc_source.clone(),
             GeyserFilter(commitment_config).blocks_meta(),
-            autoconnect_tx.clone(),
+            source_tx,
             exit_notify.resubscribe(),
         );
         tasks.push(task);
+        tasks.push(spawn_tag_source(
+            source_index,
+            source_rx,
+            autoconnect_tx.clone(),
+        ));
     }
+    let source_labels = grpc_sources
+        .iter()
+        .map(|grpc_source| obfuscate_rpcurl(&grpc_source.grpc_addr))
+        .collect_vec();
 
     let jh_merging_streams = tokio::task::spawn(async move {
+        let mut reconnect_log = ReconnectLogThrottle::new();
         let mut tip: Slot = 0;
         loop {
             let blocks_rx_result = tokio::select! {
@@ -161,7 +255,7 @@ fn create_grpc_multiplex_block_info_task(
                 }
             };
             match blocks_rx_result {
-                Some(Message::GeyserSubscribeUpdate(subscribe_update)) => {
+                Some((_source_index, Message::GeyserSubscribeUpdate(subscribe_update))) => {
                     if let Some(update) = subscribe_update.update_oneof {
                         match update {
                             UpdateOneof::BlockMeta(block_meta) => {
@@ -209,12 +303,15 @@ fn create_grpc_multiplex_block_info_task(
                         }
                     }
                 }
-                Some(Message::Connecting(attempt)) => {
+                Some((source_index, Message::Connecting(attempt))) => {
+                    let source = &source_labels[source_index];
                     if attempt > 1 {
-                        warn!(
-                            "Multiplexed geyser stream performs reconnect attempt {}",
-                            attempt
-                        );
+                        if let Some(suppressed) = reconnect_log.should_log(source, Instant::now()) {
+                            warn!(
+                                "Multiplexed geyser block meta stream of {} performs reconnect attempt {} ({} attempts not logged)",
+                                source, attempt, suppressed
+                            );
+                        }
                     }
                 }
                 None => {
@@ -231,20 +328,45 @@ fn create_grpc_multiplex_block_info_task(
 /// connect to multiple grpc sources to consume processed blocks and block status update
 /// emits full blocks for commitment levels processed, confirmed, finalized in that order
 /// the channel must never be closed
+/// on cancellation the blocks already received are flushed to the channel and the task returns Ok
 pub fn create_grpc_multiplex_blocks_subscription(
     grpc_sources: Vec<GrpcSourceConfig>,
+    cancel: CancellationToken,
+) -> (
+    Receiver<ProducedBlock>,
+    Receiver<BlockInfo>,
+    AnyhowJoinHandle,
+) {
+    create_grpc_multiplex_blocks_subscription_with_sources(
+        grpc_sources
+            .into_iter()
+            .map(MultiplexSource::from)
+            .collect(),
+        cancel,
+    )
+}
+
+/// like create_grpc_multiplex_blocks_subscription but the processed blocks of each source are mapped according to its kind
+/// all sources must provide block meta updates
+pub fn create_grpc_multiplex_blocks_subscription_with_sources(
+    sources: Vec<MultiplexSource>,
+    cancel: CancellationToken,
 ) -> (
     Receiver<ProducedBlock>,
     Receiver<BlockInfo>,
     AnyhowJoinHandle,
 ) {
     info!("Setup grpc multiplexed blocks connection...");
-    if grpc_sources.is_empty() {
+    if sources.is_empty() {
         info!("- no grpc connection configured");
     }
-    for grpc_source in &grpc_sources {
-        info!("- connection to {}", grpc_source);
+    for source in &sources {
+        info!("- connection to {} ({:?})", source.grpc_source, source.kind);
     }
+    let grpc_sources = sources
+        .iter()
+        .map(|source| source.grpc_source.clone())
+        .collect_vec();
 
     // return value is the broadcast receiver
     // must NEVER be closed from inside this method
@@ -256,9 +378,16 @@ pub fn create_grpc_multiplex_blocks_subscription(
         tokio::sync::broadcast::channel::<BlockInfo>(32);
 
     let mut reconnect_attempts = 0;
+    let mut reconnect_log = ReconnectLogThrottle::new();
+    let all_sources_label = grpc_sources
+        .iter()
+        .map(|source| obfuscate_rpcurl(&source.grpc_addr))
+        .join(",");
 
-    // task MUST not terminate but might be aborted from outside
+    // task MUST not terminate but might be aborted or cancelled from outside
     let jh_block_emitter_task = tokio::task::spawn(async move {
+        // survives reconnects as the sources might replay blocks which were already emitted
+        let mut producedblock_sender = DedupBlockSender::new(producedblock_sender);
         loop {
             // channels must NEVER GET CLOSED (unless full restart of multiplexer)
             let (processed_block_sender, mut processed_block_reciever) =
@@ -274,17 +403,22 @@ pub fn create_grpc_multiplex_blocks_subscription(
             let processed_block_sender = processed_block_sender.clone();
             reconnect_attempts += 1;
             if reconnect_attempts > 1 {
-                warn!(
-                    "Multiplexed geyser stream performs reconnect attempt {}",
-                    reconnect_attempts
-                );
+                // all sources are reconnected together
+                if let Some(suppressed) =
+                    reconnect_log.should_log(&all_sources_label, Instant::now())
+                {
+                    warn!(
+                        "Multiplexed geyser stream of {} performs reconnect attempt {} ({} attempts not logged)",
+                        all_sources_label, reconnect_attempts, suppressed
+                    );
+                }
             }
 
             // tasks which should be cleaned up uppon reconnect
             let mut task_list: Vec<JoinHandle<()>> = vec![];
 
             let processed_blocks_tasks = create_grpc_multiplex_processed_block_task(
-                &grpc_sources,
+                &sources,
                 processed_block_sender.clone(),
                 exit_notify.resubscribe(),
             );
@@ -331,6 +465,7 @@ pub fn create_grpc_multiplex_blocks_subscription(
             //  start logging errors when we recieve first finalized block
             let mut startup_completed = false;
             const MAX_ALLOWED_CLEANUP_WITHOUT_RECV: u8 = 12; // 12*5 = 60s without recving data
+            let mut cancelled = false;
             'recv_loop: loop {
                 debug!("channel capacities: processed_block_sender={}, block_info_sender_confirmed={}, block_info_sender_finalized={}",
                     processed_block_sender.capacity(),
@@ -338,6 +473,12 @@ pub fn create_grpc_multiplex_blocks_subscription(
                     block_info_sender_finalized.capacity()
                 );
                 tokio::select! {
+                    _ = cancel.cancelled() => {
+                        let cnt_flushed = flush_pending_blocks(&mut processed_block_reciever, &mut producedblock_sender);
+                        info!("multiplexer cancelled - flushed {} pending blocks", cnt_flushed);
+                        cancelled = true;
+                        break 'recv_loop;
+                    },
                     processed_block = processed_block_reciever.recv() => {
                             cleanup_without_recv_full_blocks = 0;
 
@@ -431,7 +572,7 @@ pub fn create_grpc_multiplex_blocks_subscription(
                             log::error!("block or block info geyser stream stopped - restarting multiplexer ({}-{}-{})",
                             cleanup_without_recv_full_blocks, cleanup_without_confirmed_recv_blocks_meta, cleanup_without_finalized_recv_blocks_meta,);
                             // throttle a bit
-                            sleep(Duration::from_millis(200)).await;
+                            throttle_restart(Duration::from_millis(200)).await;
                             break 'recv_loop;
                         }
                         cleanup_without_recv_full_blocks += 1;
@@ -454,6 +595,9 @@ pub fn create_grpc_multiplex_blocks_subscription(
                 log::error!("Problem sending exit signal");
                 task_list.iter().for_each(|x| x.abort());
             }
+            if cancelled {
+                return Ok(());
+            }
         } // -- END reconnect loop
     });
 
@@ -464,8 +608,10 @@ pub fn create_grpc_multiplex_blocks_subscription(
     )
 }
 
+/// on cancellation the task shuts down the grpc sources and returns Ok
 pub fn create_grpc_multiplex_processed_slots_subscription(
     grpc_sources: Vec<GrpcSourceConfig>,
+    cancel: CancellationToken,
 ) -> (Receiver<SlotNotification>, AnyhowJoinHandle) {
     const COMMITMENT_CONFIG: CommitmentConfig = CommitmentConfig::processed();
     info!("Setup grpc multiplexed slots connection...");
@@ -480,30 +626,47 @@ pub fn create_grpc_multiplex_processed_slots_subscription(
     let (multiplexed_messages_sender, multiplexed_messages_rx) =
         tokio::sync::broadcast::channel(32);
 
-    // task MUST not terminate but might be aborted from outside
+    // task MUST not terminate but might be aborted or cancelled from outside
+    let source_labels = grpc_sources
+        .iter()
+        .map(|grpc_source| obfuscate_rpcurl(&grpc_source.grpc_addr))
+        .collect_vec();
     let jh_multiplex_task = tokio::spawn(async move {
+        let mut reconnect_log = ReconnectLogThrottle::new();
         loop {
             let (autoconnect_tx, mut slots_rx) = tokio::sync::mpsc::channel(10);
             let (exit_sender, exit_notify) = broadcast::channel(1);
 
             let task_list = grpc_sources
-                .clone()
                 .iter()
-                .map(|grpc_source| {
-                    create_geyser_autoconnection_task_with_mpsc(
-                        grpc_source.clone(),
-                        GeyserFilter(COMMITMENT_CONFIG).slots(),
-                        autoconnect_tx.clone(),
-                        exit_notify.resubscribe(),
-                    )
+                .enumerate()
+                .flat_map(|(source_index, grpc_source)| {
+                    let (source_tx, source_rx) = tokio::sync::mpsc::channel(10);
+                    [
+                        create_geyser_autoconnection_task_with_mpsc(
+                            grpc_source.clone(),
+                            GeyserFilter(COMMITMENT_CONFIG).slots(),
+                            source_tx,
+                            exit_notify.resubscribe(),
+                        ),
+                        spawn_tag_source(source_index, source_rx, autoconnect_tx.clone()),
+                    ]
                 })
                 .collect_vec();
 
             let mut last_slot = 0;
+            let mut cancelled = false;
             'recv_loop: loop {
-                let next = tokio::time::timeout(Duration::from_secs(30), slots_rx.recv()).await;
+                let next = tokio::select! {
+                    next = tokio::time::timeout(Duration::from_secs(30), slots_rx.recv()) => next,
+                    _ = cancel.cancelled() => {
+                        info!("slot multiplexer cancelled");
+                        cancelled = true;
+                        break 'recv_loop;
+                    }
+                };
                 match next {
-                    Ok(Some(Message::GeyserSubscribeUpdate(slot_update))) => {
+                    Ok(Some((_source_index, Message::GeyserSubscribeUpdate(slot_update)))) => {
                         let mapfilter = map_slot_from_yellowstone_update(*slot_update);
                         if let Some(slot) = mapfilter {
                             if last_slot > slot {
@@ -533,12 +696,17 @@ pub fn create_grpc_multiplex_processed_slots_subscription(
                             );
                         }
                     }
-                    Ok(Some(Message::Connecting(attempt))) => {
+                    Ok(Some((source_index, Message::Connecting(attempt)))) => {
+                        let source = &source_labels[source_index];
                         if attempt > 1 {
-                            warn!(
-                                "Multiplexed geyser slot stream performs reconnect attempt {}",
-                                attempt
-                            );
+                            if let Some(suppressed) =
+                                reconnect_log.should_log(source, Instant::now())
+                            {
+                                warn!(
+                                    "Multiplexed geyser slot stream of {} performs reconnect attempt {} ({} attempts not logged)",
+                                    source, attempt, suppressed
+                                );
+                            }
                         }
                     }
                     Ok(None) => {
@@ -548,7 +716,7 @@ pub fn create_grpc_multiplex_processed_slots_subscription(
                     Err(_elapsed) => {
                         warn!("Multiplexed geyser slot stream timeout - reconnect");
                         // throttle
-                        sleep(Duration::from_millis(1500)).await;
+                        throttle_restart(Duration::from_millis(1500)).await;
                         break 'recv_loop;
                     }
                 }
@@ -560,12 +728,247 @@ pub fn create_grpc_multiplex_processed_slots_subscription(
                 log::error!("Problem sending exit signal");
                 task_list.iter().for_each(|x| x.abort());
             }
+            if cancelled {
+                return Ok(());
+            }
         } // -- END reconnect loop
     });
 
     (multiplexed_messages_rx, jh_multiplex_task)
 }
 
+/// forwards the messages of one source tagged with its index
+fn spawn_tag_source(
+    source_index: usize,
+    mut source_rx: tokio::sync::mpsc::Receiver<Message>,
+    tagged_tx: tokio::sync::mpsc::Sender<(usize, Message)>,
+) -> JoinHandle<()> {
+    tokio::task::spawn(async move {
+        while let Some(message) = source_rx.recv().await {
+            if tagged_tx.send((source_index, message)).await.is_err() {
+                break;
+            }
+        }
+    })
+}
+
+/// rate limits the reconnect warnings while a source is flapping, each source is throttled on its own
+/// so a flapping source does not hide the reconnects of another one
+struct ReconnectLogThrottle {
+    sources: HashMap<String, SourceReconnectLog>,
+}
+
+impl ReconnectLogThrottle {
+    fn new() -> Self {
+        Self {
+            sources: HashMap::new(),
+        }
+    }
+
+    /// number of attempts of the source which were not logged since its last warning, none if this attempt should not be logged
+    fn should_log(&mut self, source: &str, now: Instant) -> Option<u64> {
+        if let Some(source_log) = self.sources.get_mut(source) {
+            return source_log.should_log(now);
+        }
+        self.sources
+            .entry(source.to_string())
+            .or_insert_with(SourceReconnectLog::new)
+            .should_log(now)
+    }
+}
+
+/// the interval between two warnings of a source doubles up to MAX_INTERVAL and is jittered
+struct SourceReconnectLog {
+    interval: Duration,
+    next_log_at: Option<Instant>,
+    suppressed: u64,
+}
+
+impl SourceReconnectLog {
+    const INITIAL_INTERVAL: Duration = Duration::from_secs(1);
+    const MAX_INTERVAL: Duration = Duration::from_secs(60);
+
+    fn new() -> Self {
+        Self {
+            interval: Self::INITIAL_INTERVAL,
+            next_log_at: None,
+            suppressed: 0,
+        }
+    }
+
+    fn should_log(&mut self, now: Instant) -> Option<u64> {
+        match self.next_log_at {
+            Some(next_log_at) if now < next_log_at => {
+                self.suppressed += 1;
+                return None;
+            }
+            // source was stable for a while, start over
+            Some(next_log_at) if now > next_log_at + Self::MAX_INTERVAL => {
+                self.interval = Self::INITIAL_INTERVAL;
+            }
+            _ => {}
+        }
+        self.next_log_at = Some(now + with_jitter(self.interval));
+        self.interval = (self.interval * 2).min(Self::MAX_INTERVAL);
+        Some(std::mem::take(&mut self.suppressed))
+    }
+}
+
+/// adds up to 25% of the duration
+fn with_jitter(duration: Duration) -> Duration {
+    let max_jitter_ms = duration.as_millis() as u64 / 4;
+    duration + Duration::from_millis(rand::thread_rng().gen_range(0..=max_jitter_ms))
+}
+
+/// must not block the runtime as the other multiplexer tasks share it
+async fn throttle_restart(duration: Duration) {
+    sleep(with_jitter(duration)).await;
+}
+
+/// rolling fraction of the last `window` processed blocks which were delivered first by each source
+/// e.g. "green wins 80%, blue 20%"; exported as literpc_multiplex_source_win_ratio
+struct SourceWinRatio {
+    window: usize,
+    winners: VecDeque<String>,
+    wins: HashMap<String, usize>,
+}
+
+impl SourceWinRatio {
+    const DEFAULT_WINDOW: usize = 100;
+
+    /// sources are registered upfront so a source which never wins is reported with 0
+    fn new(window: usize, sources: Vec<String>) -> Self {
+        Self {
+            window,
+            winners: VecDeque::with_capacity(window + 1),
+            wins: sources.into_iter().map(|source| (source, 0)).collect(),
+        }
+    }
+
+    fn record_win(&mut self, source: &str) {
+        MULTIPLEX_SOURCE_BLOCKS_WON
+            .with_label_values(&[source])
+            .inc();
+        *self.wins.entry(source.to_string()).or_default() += 1;
+        self.winners.push_back(source.to_string());
+        if self.winners.len() > self.window {
+            if let Some(evicted) = self.winners.pop_front() {
+                if let Some(wins) = self.wins.get_mut(&evicted) {
+                    *wins = wins.saturating_sub(1);
+                }
+            }
+        }
+
+        for source in self.wins.keys() {
+            MULTIPLEX_SOURCE_WIN_RATIO
+                .with_label_values(&[source])
+                .set(self.ratio(source));
+        }
+    }
+
+    fn ratio(&self, source: &str) -> f64 {
+        if self.winners.is_empty() {
+            return 0.0;
+        }
+        self.wins.get(source).copied().unwrap_or_default() as f64 / self.winners.len() as f64
+    }
+}
+
+/// drops blocks which were already forwarded with the same commitment level
+/// e.g. if a source replays blocks after a reconnect
+/// and blocks with a lower commitment than already forwarded for the slot, e.g. confirmed after finalized from out of order sources
+struct DedupBlockSender {
+    sender: broadcast::Sender<ProducedBlock>,
+    forwarded: HashMap<CommitmentLevel, BTreeSet<(Slot, Hash)>>,
+    highest_commitment: BTreeMap<Slot, Commitment>,
+}
+
+impl DedupBlockSender {
+    const MAX_FORWARDED_PER_COMMITMENT: usize = 1024;
+
+    fn new(sender: broadcast::Sender<ProducedBlock>) -> Self {
+        Self {
+            sender,
+            forwarded: HashMap::new(),
+            highest_commitment: BTreeMap::new(),
+        }
+    }
+
+    fn send(
+        &mut self,
+        block: ProducedBlock,
+    ) -> Result<(), broadcast::error::SendError<ProducedBlock>> {
+        let commitment = Commitment::from(block.commitment_config);
+        if let Some(highest) = self
+            .highest_commitment
+            .get(&block.slot)
+            .filter(|highest| commitment < **highest)
+        {
+            debug!(
+                "drop block #{}@{} as it was already forwarded with {highest:?}",
+                block.slot, block.commitment_config.commitment
+            );
+            return Ok(());
+        }
+
+        let forwarded = self
+            .forwarded
+            .entry(block.commitment_config.commitment)
+            .or_default();
+        // older than anything we remember, must have been forwarded already
+        let too_old = forwarded.len() >= Self::MAX_FORWARDED_PER_COMMITMENT
+            && forwarded
+                .first()
+                .is_some_and(|(oldest_slot, _)| block.slot < *oldest_slot);
+        if too_old || !forwarded.insert((block.slot, block.blockhash)) {
+            debug!(
+                "drop duplicate block #{}@{} with blockhash {}",
+                block.slot, block.commitment_config.commitment, block.blockhash
+            );
+            return Ok(());
+        }
+        if forwarded.len() > Self::MAX_FORWARDED_PER_COMMITMENT {
+            forwarded.pop_first();
+        }
+        self.highest_commitment.insert(block.slot, commitment);
+        if self.highest_commitment.len() > Self::MAX_FORWARDED_PER_COMMITMENT {
+            self.highest_commitment.pop_first();
+        }
+        observe_ingestion_lag(&block, SystemTime::now());
+        self.sender.send(block).map(|_| ())
+    }
+}
+
+/// block time has a resolution of seconds, blocks without block time are not observed
+fn observe_ingestion_lag(block: &ProducedBlock, forwarded_at: SystemTime) {
+    if block.block_time == 0 {
+        return;
+    }
+    let block_time = UNIX_EPOCH + Duration::from_secs(block.block_time);
+    let lag = forwarded_at
+        .duration_since(block_time)
+        .unwrap_or_default()
+        .as_secs_f64();
+    BLOCK_INGESTION_LAG
+        .with_label_values(&[&block.commitment_config.commitment.to_string()])
+        .observe(lag);
+}
+
+/// forward the processed blocks which are already buffered in the channel, used on shutdown
+fn flush_pending_blocks(
+    processed_block_reciever: &mut tokio::sync::mpsc::Receiver<ProducedBlock>,
+    producedblock_sender: &mut DedupBlockSender,
+) -> usize {
+    let mut cnt_flushed = 0;
+    while let Ok(processed_block) = processed_block_reciever.try_recv() {
+        if let Err(e) = producedblock_sender.send(processed_block) {
+            warn!("produced block channel has no receivers while flushing {e:?}");
+        }
+        cnt_flushed += 1;
+    }
+    cnt_flushed
+}
+
 fn extract_slot_from_yellowstone_update(update: &SubscribeUpdate) -> Option<Slot> {
     match &update.update_oneof {
         // list is not exhaustive
@@ -583,16 +986,314 @@ fn map_slot_from_yellowstone_update(update: SubscribeUpdate) -> Option<Slot> {
     }
 }
 
-fn map_block_from_yellowstone_update(
-    update: SubscribeUpdate,
-    commitment_config: CommitmentConfig,
-) -> Option<(Slot, ProducedBlock)> {
-    let _span = debug_span!("map_block_from_yellowstone_update").entered();
-    match update.update_oneof {
-        Some(UpdateOneof::Block(update_block_message)) => {
-            let block = from_grpc_block_update(update_block_message, commitment_config);
-            Some((block.slot, block))
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solana_lite_rpc_core::structures::produced_block::{BlockStats, ProducedBlockInner};
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::sync::Arc;
+
+    fn create_test_block(slot: Slot) -> ProducedBlock {
+        create_test_block_with_time(slot, 0)
+    }
+
+    fn create_test_block_with_time(slot: Slot, block_time: u64) -> ProducedBlock {
+        ProducedBlock::new(
+            ProducedBlockInner {
+                transactions: vec![],
+                leader_id: None,
+                blockhash: Hash::new_unique(),
+                block_height: slot,
+                slot,
+                parent_slot: slot - 1,
+                block_time,
+                previous_blockhash: Hash::new_unique(),
+                rewards: None,
+                stats: BlockStats::default(),
+            },
+            CommitmentConfig::processed(),
+        )
+    }
+
+    #[tokio::test]
+    async fn test_flush_pending_blocks_on_cancel() {
+        let (processed_block_sender, mut processed_block_reciever) =
+            tokio::sync::mpsc::channel::<ProducedBlock>(10);
+        let (producedblock_sender, mut blocks_output_stream) =
+            broadcast::channel::<ProducedBlock>(32);
+        let mut producedblock_sender = DedupBlockSender::new(producedblock_sender);
+
+        processed_block_sender
+            .send(create_test_block(41))
+            .await
+            .unwrap();
+        processed_block_sender
+            .send(create_test_block(42))
+            .await
+            .unwrap();
+
+        let cnt_flushed =
+            flush_pending_blocks(&mut processed_block_reciever, &mut producedblock_sender);
+        assert_eq!(cnt_flushed, 2);
+        assert_eq!(blocks_output_stream.recv().await.unwrap().slot, 41);
+        // last block must not get lost
+        assert_eq!(blocks_output_stream.recv().await.unwrap().slot, 42);
+    }
+
+    #[tokio::test]
+    async fn test_cancel_multiplex_tasks() {
+        let cancel = CancellationToken::new();
+        let (_blocks, _blockinfos, jh_blocks) =
+            create_grpc_multiplex_blocks_subscription(vec![], cancel.clone());
+        let (_slots, jh_slots) =
+            create_grpc_multiplex_processed_slots_subscription(vec![], cancel.clone());
+
+        cancel.cancel();
+
+        let res = tokio::time::timeout(Duration::from_secs(5), jh_blocks)
+            .await
+            .expect("blocks multiplexer exits after cancel");
+        assert!(res.expect("no panic").is_ok());
+        let res = tokio::time::timeout(Duration::from_secs(5), jh_slots)
+            .await
+            .expect("slots multiplexer exits after cancel");
+        assert!(res.expect("no panic").is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_duplicate_block_forwarded_once() {
+        let (producedblock_sender, mut blocks_output_stream) =
+            broadcast::channel::<ProducedBlock>(32);
+        let mut producedblock_sender = DedupBlockSender::new(producedblock_sender);
+
+        let block = create_test_block(42);
+        producedblock_sender.send(block.clone()).unwrap();
+        // replayed after reconnect
+        producedblock_sender.send(block.clone()).unwrap();
+        // same block with higher commitment is not a duplicate
+        producedblock_sender
+            .send(block.to_confirmed_block())
+            .unwrap();
+        producedblock_sender.send(create_test_block(43)).unwrap();
+
+        let forwarded = [
+            blocks_output_stream.recv().await.unwrap(),
+            blocks_output_stream.recv().await.unwrap(),
+            blocks_output_stream.recv().await.unwrap(),
+        ];
+        assert_eq!(
+            forwarded
+                .iter()
+                .map(|b| (b.slot, b.commitment_config.commitment))
+                .collect_vec(),
+            vec![
+                (42, CommitmentLevel::Processed),
+                (42, CommitmentLevel::Confirmed),
+                (43, CommitmentLevel::Processed)
+            ]
+        );
+        assert!(blocks_output_stream.try_recv().is_err());
+    }
+
+    #[tokio::test]
+    async fn test_no_commitment_downgrade_for_slot() {
+        let (producedblock_sender, mut blocks_output_stream) =
+            broadcast::channel::<ProducedBlock>(32);
+        let mut producedblock_sender = DedupBlockSender::new(producedblock_sender);
+
+        let block = create_test_block(42);
+        // finalized arrives first from a faster source
+        producedblock_sender
+            .send(block.to_finalized_block())
+            .unwrap();
+        producedblock_sender
+            .send(block.to_confirmed_block())
+            .unwrap();
+        producedblock_sender.send(block.clone()).unwrap();
+        producedblock_sender.send(create_test_block(43)).unwrap();
+
+        let forwarded = [
+            blocks_output_stream.recv().await.unwrap(),
+            blocks_output_stream.recv().await.unwrap(),
+        ];
+        assert_eq!(
+            forwarded
+                .iter()
+                .map(|b| (b.slot, b.commitment_config.commitment))
+                .collect_vec(),
+            vec![
+                (42, CommitmentLevel::Finalized),
+                (43, CommitmentLevel::Processed)
+            ]
+        );
+        assert!(blocks_output_stream.try_recv().is_err());
+    }
+
+    #[test]
+    fn test_ingestion_lag_observed() {
+        let forwarded_at = UNIX_EPOCH + Duration::from_secs(1_700_000_003);
+        let histogram = BLOCK_INGESTION_LAG.with_label_values(&["processed"]);
+        let (count_before, sum_before) = (histogram.get_sample_count(), histogram.get_sample_sum());
+
+        // block time unknown
+        observe_ingestion_lag(&create_test_block(42), forwarded_at);
+        assert_eq!(histogram.get_sample_count(), count_before);
+
+        observe_ingestion_lag(
+            &create_test_block_with_time(43, 1_700_000_000),
+            forwarded_at,
+        );
+        assert_eq!(histogram.get_sample_count(), count_before + 1);
+        assert_eq!(histogram.get_sample_sum() - sum_before, 3.0);
+    }
+
+    fn block_update(slot: Slot) -> SubscribeUpdate {
+        SubscribeUpdate {
+            update_oneof: Some(UpdateOneof::Block(
+                yellowstone_grpc_proto::geyser::SubscribeUpdateBlock {
+                    slot,
+                    blockhash: Hash::new_unique().to_string(),
+                    parent_slot: slot - 1,
+                    parent_blockhash: Hash::new_unique().to_string(),
+                    block_height: Some(yellowstone_grpc_proto::prelude::BlockHeight {
+                        block_height: slot,
+                    }),
+                    block_time: Some(yellowstone_grpc_proto::prelude::UnixTimestamp {
+                        timestamp: 1_700_000_000,
+                    }),
+                    ..Default::default()
+                },
+            )),
+            ..Default::default()
         }
-        _ => None,
+    }
+
+    fn slot_update(slot: Slot) -> SubscribeUpdate {
+        SubscribeUpdate {
+            update_oneof: Some(UpdateOneof::Slot(
+                yellowstone_grpc_proto::geyser::SubscribeUpdateSlot {
+                    slot,
+                    parent: Some(slot - 1),
+                    ..Default::default()
+                },
+            )),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_merge_block_source_and_slot_only_source() {
+        let commitment_config = CommitmentConfig::processed();
+        let mut merger = ProcessedBlockMerger::new(vec![
+            ("test-merge-blocks".to_string(), BlockSourceKind::Blocks),
+            ("test-merge-slots".to_string(), BlockSourceKind::Slots),
+        ]);
+
+        // slot-only source does not contribute blocks
+        assert!(merger
+            .on_update(1, slot_update(42), commitment_config)
+            .is_none());
+        let block = merger
+            .on_update(0, block_update(42), commitment_config)
+            .expect("block from block source");
+        assert_eq!(block.slot, 42);
+        // slot updates for emitted slots are ignored
+        assert!(merger
+            .on_update(1, slot_update(42), commitment_config)
+            .is_none());
+        assert!(merger
+            .on_update(0, block_update(42), commitment_config)
+            .is_none());
+        assert_eq!(
+            merger
+                .on_update(0, block_update(43), commitment_config)
+                .map(|block| block.slot),
+            Some(43)
+        );
+
+        assert_eq!(merger.source_win_ratio.ratio("test-merge-blocks"), 1.0);
+        assert_eq!(merger.source_win_ratio.ratio("test-merge-slots"), 0.0);
+    }
+
+    #[test]
+    fn test_source_win_ratio() {
+        let (green, blue) = ("test-win-ratio-green", "test-win-ratio-blue");
+        let mut win_ratio = SourceWinRatio::new(10, vec![green.to_string(), blue.to_string()]);
+        assert_eq!(win_ratio.ratio(green), 0.0);
+
+        for i in 0..10 {
+            win_ratio.record_win(if i % 5 == 0 { blue } else { green });
+        }
+        assert_eq!(win_ratio.ratio(green), 0.8);
+        assert_eq!(win_ratio.ratio(blue), 0.2);
+        assert_eq!(
+            MULTIPLEX_SOURCE_WIN_RATIO.with_label_values(&[green]).get(),
+            0.8
+        );
+        assert_eq!(
+            MULTIPLEX_SOURCE_WIN_RATIO.with_label_values(&[blue]).get(),
+            0.2
+        );
+
+        // green falls behind, old wins leave the window
+        for _ in 0..10 {
+            win_ratio.record_win(blue);
+        }
+        assert_eq!(win_ratio.ratio(green), 0.0);
+        assert_eq!(
+            MULTIPLEX_SOURCE_WIN_RATIO.with_label_values(&[green]).get(),
+            0.0
+        );
+        assert_eq!(
+            MULTIPLEX_SOURCE_WIN_RATIO.with_label_values(&[blue]).get(),
+            1.0
+        );
+        assert_eq!(
+            MULTIPLEX_SOURCE_BLOCKS_WON.with_label_values(&[blue]).get(),
+            12
+        );
+    }
+
+    #[test]
+    fn test_reconnect_logging_is_rate_limited() {
+        let mut reconnect_log = ReconnectLogThrottle::new();
+        let started = Instant::now();
+        let at = |ms| started + Duration::from_millis(ms);
+        let (green, blue) = ("green", "blue");
+
+        assert_eq!(reconnect_log.should_log(green, at(0)), Some(0));
+        // flapping source
+        assert_eq!(reconnect_log.should_log(green, at(100)), None);
+        assert_eq!(reconnect_log.should_log(green, at(200)), None);
+        // another source is not throttled by the flapping one
+        assert_eq!(reconnect_log.should_log(blue, at(250)), Some(0));
+        assert_eq!(reconnect_log.should_log(blue, at(300)), None);
+        // after 1s + 25% jitter
+        assert_eq!(reconnect_log.should_log(green, at(1_300)), Some(2));
+        // interval doubled to 2s
+        assert_eq!(reconnect_log.should_log(green, at(3_200)), None);
+        assert_eq!(reconnect_log.should_log(green, at(3_900)), Some(1));
+        // the suppressed attempts are counted per source
+        assert_eq!(reconnect_log.should_log(blue, at(3_900)), Some(1));
+        // stable for a long time, next attempt is logged again
+        assert_eq!(reconnect_log.should_log(green, at(600_000)), Some(0));
+        assert_eq!(
+            reconnect_log.sources[green].interval,
+            Duration::from_secs(2)
+        );
+    }
+
+    #[tokio::test(flavor = "current_thread")]
+    async fn test_throttle_restart_does_not_block_runtime() {
+        let ticked = Arc::new(AtomicBool::new(false));
+        let ticker = tokio::spawn({
+            let ticked = ticked.clone();
+            async move { ticked.store(true, Ordering::Relaxed) }
+        });
+
+        // a blocking sleep would keep the single runtime thread from running the ticker
+        throttle_restart(Duration::from_millis(50)).await;
+        assert!(ticked.load(Ordering::Relaxed));
+        ticker.await.unwrap();
     }
 }
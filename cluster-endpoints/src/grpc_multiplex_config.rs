@@ -0,0 +1,296 @@
+use std::collections::{HashMap, HashSet};
+use std::fmt::{Display, Formatter};
+use std::time::Duration;
+
+use anyhow::{bail, Context};
+use geyser_grpc_connector::{GrpcConnectionTimeouts, GrpcSourceConfig};
+use solana_lite_rpc_util::obfuscate_rpcurl;
+use solana_lite_rpc_util::secrets::obfuscate_token;
+
+use crate::grpc_source_mapper::{BlockSourceKind, MultiplexSource};
+
+const DEFAULT_GRPC_TIMEOUT_MS: u64 = 15_000;
+const DEFAULT_ACCOUNT_CHANNEL_CAPACITY: usize = 1024;
+// GRPC_ADDR is the first source, GRPC_ADDR2 to GRPC_ADDR4 are optional
+const MAX_NUMBERED_SOURCES: usize = 4;
+
+#[derive(Clone)]
+pub struct GrpcMultiplexSource {
+    /// used in logs and metrics
+    pub name: String,
+    pub addr: String,
+    pub x_token: Option<String>,
+    /// subscribe filter of the source, e.g. slots only for a cheap source next to a full block source
+    pub kind: BlockSourceKind,
+}
+
+impl Display for GrpcMultiplexSource {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} {} (x-token {}, {:?})",
+            self.name,
+            obfuscate_rpcurl(&self.addr),
+            obfuscate_token(&self.x_token),
+            self.kind
+        )
+    }
+}
+
+/// Sources and connection settings of the grpc multiplexer
+#[derive(Clone)]
+pub struct GrpcMultiplexConfig {
+    pub sources: Vec<GrpcMultiplexSource>,
+    pub timeouts: GrpcConnectionTimeouts,
+    /// capacity of the processed account notification channel
+    pub account_channel_capacity: usize,
+}
+
+impl GrpcMultiplexConfig {
+    pub fn new(
+        sources: Vec<GrpcMultiplexSource>,
+        timeouts: GrpcConnectionTimeouts,
+        account_channel_capacity: usize,
+    ) -> anyhow::Result<Self> {
+        if sources.is_empty() {
+            bail!("at least one grpc source must be configured");
+        }
+        let mut names = HashSet::new();
+        for source in &sources {
+            if !names.insert(source.name.as_str()) {
+                bail!("duplicate grpc source name {}", source.name);
+            }
+            let Some((scheme, host)) = source.addr.split_once("://") else {
+                bail!("grpc source {} has no scheme", source.name);
+            };
+            if !matches!(scheme, "http" | "https") || host.is_empty() {
+                bail!("grpc source {} must be a http(s) url", source.name);
+            }
+        }
+        if account_channel_capacity == 0 {
+            bail!("account channel capacity must be greater than 0");
+        }
+        Ok(Self {
+            sources,
+            timeouts,
+            account_channel_capacity,
+        })
+    }
+
+    pub fn from_env() -> anyhow::Result<Self> {
+        Self::from_vars(&std::env::vars().collect())
+    }
+
+    /// GRPC_ADDR, GRPC_X_TOKEN, GRPC_SOURCE_KIND and GRPC_ADDR2 to GRPC_ADDR4 with their
+    /// GRPC_X_TOKEN2 to GRPC_X_TOKEN4 and GRPC_SOURCE_KIND2 to GRPC_SOURCE_KIND4 (blocks, transactions or slots),
+    /// timeouts GRPC_CONNECT_TIMEOUT_MS, GRPC_REQUEST_TIMEOUT_MS, GRPC_SUBSCRIBE_TIMEOUT_MS, GRPC_RECEIVE_TIMEOUT_MS
+    /// and GRPC_ACCOUNT_CHANNEL_CAPACITY
+    pub fn from_vars(vars: &HashMap<String, String>) -> anyhow::Result<Self> {
+        if vars.contains_key("GRPC_ADDR1") {
+            bail!("use GRPC_ADDR instead of GRPC_ADDR1");
+        }
+        if vars.contains_key("GRPC_X_TOKEN1") {
+            bail!("use GRPC_X_TOKEN instead of GRPC_X_TOKEN1");
+        }
+
+        let mut sources = vec![];
+        for number in 1..=MAX_NUMBERED_SOURCES {
+            let suffix = if number == 1 {
+                String::new()
+            } else {
+                number.to_string()
+            };
+            let addr_var = format!("GRPC_ADDR{suffix}");
+            let x_token_var = format!("GRPC_X_TOKEN{suffix}");
+            let kind_var = format!("GRPC_SOURCE_KIND{suffix}");
+            let x_token = vars.get(&x_token_var).cloned();
+            let kind = vars.get(&kind_var);
+            match vars.get(&addr_var) {
+                Some(addr) => sources.push(GrpcMultiplexSource {
+                    name: format!("grpc{number}"),
+                    addr: addr.clone(),
+                    x_token,
+                    kind: match kind {
+                        Some(kind) => kind
+                            .parse()
+                            .with_context(|| format!("invalid {kind_var}"))?,
+                        None => BlockSourceKind::default(),
+                    },
+                }),
+                None if x_token.is_some() => bail!("{x_token_var} is set without {addr_var}"),
+                None if kind.is_some() => bail!("{kind_var} is set without {addr_var}"),
+                None => {}
+            }
+        }
+
+        let timeouts = GrpcConnectionTimeouts {
+            connect_timeout: parse_timeout(vars, "GRPC_CONNECT_TIMEOUT_MS")?,
+            request_timeout: parse_timeout(vars, "GRPC_REQUEST_TIMEOUT_MS")?,
+            subscribe_timeout: parse_timeout(vars, "GRPC_SUBSCRIBE_TIMEOUT_MS")?,
+            receive_timeout: parse_timeout(vars, "GRPC_RECEIVE_TIMEOUT_MS")?,
+        };
+        let account_channel_capacity = match vars.get("GRPC_ACCOUNT_CHANNEL_CAPACITY") {
+            Some(capacity) => capacity
+                .parse()
+                .with_context(|| format!("invalid GRPC_ACCOUNT_CHANNEL_CAPACITY {capacity}"))?,
+            None => DEFAULT_ACCOUNT_CHANNEL_CAPACITY,
+        };
+
+        Self::new(sources, timeouts, account_channel_capacity)
+    }
+
+    pub fn grpc_source_configs(&self) -> Vec<GrpcSourceConfig> {
+        self.sources
+            .iter()
+            .map(|source| {
+                GrpcSourceConfig::new(
+                    source.addr.clone(),
+                    source.x_token.clone(),
+                    None,
+                    self.timeouts.clone(),
+                )
+            })
+            .collect()
+    }
+
+    /// the grpc sources with their subscribe filter
+    pub fn multiplex_sources(&self) -> Vec<MultiplexSource> {
+        self.sources
+            .iter()
+            .zip(self.grpc_source_configs())
+            .map(|(source, grpc_source)| MultiplexSource {
+                grpc_source,
+                kind: source.kind,
+            })
+            .collect()
+    }
+
+    /// required by callers consuming the block stream, slot only sources do not contribute blocks
+    pub fn validate_block_sources(&self) -> anyhow::Result<()> {
+        if !self
+            .sources
+            .iter()
+            .any(|source| source.kind.provides_blocks())
+        {
+            bail!("at least one grpc source must provide blocks or transactions");
+        }
+        Ok(())
+    }
+}
+
+fn parse_timeout(vars: &HashMap<String, String>, var: &str) -> anyhow::Result<Duration> {
+    let millis = match vars.get(var) {
+        Some(millis) => millis
+            .parse()
+            .with_context(|| format!("invalid {var} {millis}"))?,
+        None => DEFAULT_GRPC_TIMEOUT_MS,
+    };
+    if millis == 0 {
+        bail!("{var} must be greater than 0");
+    }
+    Ok(Duration::from_millis(millis))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn vars(vars: &[(&str, &str)]) -> HashMap<String, String> {
+        vars.iter()
+            .map(|(name, value)| (name.to_string(), value.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn test_config_from_vars() {
+        let config = GrpcMultiplexConfig::from_vars(&vars(&[
+            ("GRPC_ADDR", "http://localhost:10000"),
+            ("GRPC_X_TOKEN", "secret"),
+            ("GRPC_ADDR3", "https://grpc.example.com"),
+            ("GRPC_RECEIVE_TIMEOUT_MS", "5000"),
+        ]))
+        .unwrap();
+
+        assert_eq!(
+            config
+                .sources
+                .iter()
+                .map(|source| (source.name.as_str(), source.x_token.is_some()))
+                .collect::<Vec<_>>(),
+            vec![("grpc1", true), ("grpc3", false)]
+        );
+        assert_eq!(config.timeouts.receive_timeout, Duration::from_secs(5));
+        assert_eq!(config.timeouts.connect_timeout, Duration::from_secs(15));
+        assert_eq!(
+            config.account_channel_capacity,
+            DEFAULT_ACCOUNT_CHANNEL_CAPACITY
+        );
+        assert_eq!(config.grpc_source_configs().len(), 2);
+    }
+
+    #[test]
+    fn test_sources_with_different_filters() {
+        let config = GrpcMultiplexConfig::from_vars(&vars(&[
+            ("GRPC_ADDR", "http://localhost:10000"),
+            ("GRPC_SOURCE_KIND", "slots"),
+            ("GRPC_ADDR2", "http://localhost:10001"),
+        ]))
+        .unwrap();
+        config.validate_block_sources().unwrap();
+
+        let sources = config.multiplex_sources();
+        assert_eq!(
+            sources.iter().map(|source| source.kind).collect::<Vec<_>>(),
+            vec![BlockSourceKind::Slots, BlockSourceKind::Blocks]
+        );
+        assert_eq!(sources[1].grpc_source.grpc_addr, "http://localhost:10001");
+        let processed = solana_sdk::commitment_config::CommitmentConfig::processed();
+        let slots_request = sources[0].kind.subscribe_request(processed);
+        let blocks_request = sources[1].kind.subscribe_request(processed);
+        assert!(!slots_request.slots.is_empty() && slots_request.blocks.is_empty());
+        assert!(!blocks_request.blocks.is_empty());
+
+        // no source provides blocks
+        let slots_only = GrpcMultiplexConfig::from_vars(&vars(&[
+            ("GRPC_ADDR", "http://localhost:10000"),
+            ("GRPC_SOURCE_KIND", "slots"),
+        ]))
+        .unwrap();
+        assert!(slots_only.validate_block_sources().is_err());
+    }
+
+    #[test]
+    fn test_invalid_config_is_an_error() {
+        let invalid = [
+            vec![],
+            vec![("GRPC_ADDR", "localhost:10000")],
+            vec![("GRPC_ADDR1", "http://localhost:10000")],
+            vec![
+                ("GRPC_ADDR", "http://localhost:10000"),
+                ("GRPC_X_TOKEN2", "secret"),
+            ],
+            vec![
+                ("GRPC_ADDR", "http://localhost:10000"),
+                ("GRPC_CONNECT_TIMEOUT_MS", "soon"),
+            ],
+            vec![
+                ("GRPC_ADDR", "http://localhost:10000"),
+                ("GRPC_ACCOUNT_CHANNEL_CAPACITY", "0"),
+            ],
+            vec![
+                ("GRPC_ADDR", "http://localhost:10000"),
+                ("GRPC_SOURCE_KIND", "accounts"),
+            ],
+            vec![
+                ("GRPC_ADDR", "http://localhost:10000"),
+                ("GRPC_SOURCE_KIND2", "slots"),
+            ],
+        ];
+        for invalid in invalid {
+            assert!(
+                GrpcMultiplexConfig::from_vars(&vars(&invalid)).is_err(),
+                "{invalid:?} must be rejected"
+            );
+        }
+    }
+}
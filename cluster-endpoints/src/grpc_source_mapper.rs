@@ -0,0 +1,323 @@
+use std::collections::{BTreeMap, HashMap};
+use std::str::FromStr;
+
+use geyser_grpc_connector::{GeyserFilter, GrpcSourceConfig};
+use log::{debug, trace};
+use solana_lite_rpc_core::structures::produced_block::ProducedBlock;
+use solana_sdk::clock::Slot;
+use solana_sdk::commitment_config::{CommitmentConfig, CommitmentLevel};
+use yellowstone_grpc_proto::geyser::subscribe_update::UpdateOneof;
+use yellowstone_grpc_proto::geyser::{
+    SubscribeRequest, SubscribeRequestFilterBlocksMeta, SubscribeRequestFilterTransactions,
+    SubscribeUpdate, SubscribeUpdateBlock, SubscribeUpdateBlockMeta,
+    SubscribeUpdateTransactionInfo,
+};
+
+use crate::grpc_subscription::from_grpc_block_update;
+
+/// Maps the updates of one grpc source to produced blocks
+pub trait FromYellowstoneMapper: Send {
+    /// none if the update does not complete a block (yet)
+    fn map_update(
+        &mut self,
+        update: SubscribeUpdate,
+        commitment_config: CommitmentConfig,
+    ) -> Option<ProducedBlock>;
+}
+
+/// What a grpc source of the block multiplexer delivers
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum BlockSourceKind {
+    /// full blocks including the transactions
+    #[default]
+    Blocks,
+    /// individual transactions, assembled to a block once the block meta is complete
+    Transactions,
+    /// slot updates only, does not contribute blocks
+    Slots,
+}
+
+impl FromStr for BlockSourceKind {
+    type Err = anyhow::Error;
+
+    fn from_str(kind: &str) -> Result<Self, Self::Err> {
+        match kind {
+            "blocks" => Ok(BlockSourceKind::Blocks),
+            "transactions" => Ok(BlockSourceKind::Transactions),
+            "slots" => Ok(BlockSourceKind::Slots),
+            _ => anyhow::bail!(
+                "unknown grpc source kind {kind}, expected blocks, transactions or slots"
+            ),
+        }
+    }
+}
+
+impl BlockSourceKind {
+    /// the source contributes blocks to the block stream
+    pub fn provides_blocks(&self) -> bool {
+        !matches!(self, BlockSourceKind::Slots)
+    }
+
+    pub fn subscribe_request(&self, commitment_config: CommitmentConfig) -> SubscribeRequest {
+        match self {
+            BlockSourceKind::Blocks => GeyserFilter(commitment_config).blocks_and_txs(),
+            BlockSourceKind::Transactions => SubscribeRequest {
+                transactions: HashMap::from([(
+                    "client".to_string(),
+                    SubscribeRequestFilterTransactions::default(),
+                )]),
+                blocks_meta: HashMap::from([(
+                    "client".to_string(),
+                    SubscribeRequestFilterBlocksMeta::default(),
+                )]),
+                commitment: Some(map_commitment_level(commitment_config).into()),
+                ..Default::default()
+            },
+            BlockSourceKind::Slots => GeyserFilter(commitment_config).slots(),
+        }
+    }
+
+    pub fn create_mapper(&self) -> Box<dyn FromYellowstoneMapper> {
+        match self {
+            BlockSourceKind::Blocks => Box::new(BlockMapper),
+            BlockSourceKind::Transactions => Box::<TransactionAssembler>::default(),
+            BlockSourceKind::Slots => Box::new(SlotOnlyMapper),
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct MultiplexSource {
+    pub grpc_source: GrpcSourceConfig,
+    pub kind: BlockSourceKind,
+}
+
+impl From<GrpcSourceConfig> for MultiplexSource {
+    fn from(grpc_source: GrpcSourceConfig) -> Self {
+        Self {
+            grpc_source,
+            kind: BlockSourceKind::Blocks,
+        }
+    }
+}
+
+fn map_commitment_level(
+    commitment_config: CommitmentConfig,
+) -> yellowstone_grpc_proto::geyser::CommitmentLevel {
+    match commitment_config.commitment {
+        CommitmentLevel::Finalized => yellowstone_grpc_proto::geyser::CommitmentLevel::Finalized,
+        CommitmentLevel::Confirmed => yellowstone_grpc_proto::geyser::CommitmentLevel::Confirmed,
+        _ => yellowstone_grpc_proto::geyser::CommitmentLevel::Processed,
+    }
+}
+
+pub struct BlockMapper;
+
+impl FromYellowstoneMapper for BlockMapper {
+    fn map_update(
+        &mut self,
+        update: SubscribeUpdate,
+        commitment_config: CommitmentConfig,
+    ) -> Option<ProducedBlock> {
+        match update.update_oneof {
+            Some(UpdateOneof::Block(update_block_message)) => Some(from_grpc_block_update(
+                update_block_message,
+                commitment_config,
+            )),
+            _ => None,
+        }
+    }
+}
+
+pub struct SlotOnlyMapper;
+
+impl FromYellowstoneMapper for SlotOnlyMapper {
+    fn map_update(
+        &mut self,
+        _update: SubscribeUpdate,
+        _commitment_config: CommitmentConfig,
+    ) -> Option<ProducedBlock> {
+        None
+    }
+}
+
+#[derive(Default)]
+struct PendingBlock {
+    transactions: Vec<SubscribeUpdateTransactionInfo>,
+    block_meta: Option<SubscribeUpdateBlockMeta>,
+}
+
+/// collects the transactions per slot until the block meta arrived and all executed transactions were received
+#[derive(Default)]
+pub struct TransactionAssembler {
+    pending: BTreeMap<Slot, PendingBlock>,
+}
+
+impl TransactionAssembler {
+    /// incomplete blocks further behind the latest slot are dropped
+    const MAX_PENDING_SLOTS: u64 = 32;
+
+    fn try_complete(
+        &mut self,
+        slot: Slot,
+        commitment_config: CommitmentConfig,
+    ) -> Option<ProducedBlock> {
+        let pending = self.pending.get(&slot)?;
+        let block_meta = pending.block_meta.as_ref()?;
+        if (pending.transactions.len() as u64) < block_meta.executed_transaction_count {
+            return None;
+        }
+        let PendingBlock {
+            mut transactions,
+            block_meta,
+        } = self.pending.remove(&slot)?;
+        let block_meta = block_meta?;
+        transactions.sort_by_key(|tx| tx.index);
+        trace!(
+            "assembled block {} from {} transactions",
+            slot,
+            transactions.len()
+        );
+        Some(from_grpc_block_update(
+            SubscribeUpdateBlock {
+                slot: block_meta.slot,
+                blockhash: block_meta.blockhash,
+                rewards: block_meta.rewards,
+                block_time: block_meta.block_time,
+                block_height: block_meta.block_height,
+                parent_slot: block_meta.parent_slot,
+                parent_blockhash: block_meta.parent_blockhash,
+                executed_transaction_count: block_meta.executed_transaction_count,
+                transactions,
+                ..Default::default()
+            },
+            commitment_config,
+        ))
+    }
+
+    fn prune(&mut self, latest_slot: Slot) {
+        let lowest_slot = latest_slot.saturating_sub(Self::MAX_PENDING_SLOTS);
+        let retained = self.pending.split_off(&lowest_slot);
+        let dropped = std::mem::replace(&mut self.pending, retained);
+        if !dropped.is_empty() {
+            debug!(
+                "dropped {} incomplete blocks before slot {}",
+                dropped.len(),
+                lowest_slot
+            );
+        }
+    }
+}
+
+impl FromYellowstoneMapper for TransactionAssembler {
+    fn map_update(
+        &mut self,
+        update: SubscribeUpdate,
+        commitment_config: CommitmentConfig,
+    ) -> Option<ProducedBlock> {
+        let slot = match update.update_oneof {
+            Some(UpdateOneof::Transaction(update_transaction)) => {
+                let slot = update_transaction.slot;
+                let transaction = update_transaction.transaction?;
+                self.pending
+                    .entry(slot)
+                    .or_default()
+                    .transactions
+                    .push(transaction);
+                slot
+            }
+            Some(UpdateOneof::BlockMeta(block_meta)) => {
+                let slot = block_meta.slot;
+                self.pending.entry(slot).or_default().block_meta = Some(block_meta);
+                slot
+            }
+            _ => return None,
+        };
+        self.prune(slot);
+        self.try_complete(slot, commitment_config)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solana_sdk::hash::Hash;
+    use solana_sdk::pubkey::Pubkey;
+    use solana_sdk::signature::Signature;
+    use yellowstone_grpc_proto::prelude as proto;
+
+    fn create_grpc_tx(index: u64) -> SubscribeUpdateTransactionInfo {
+        SubscribeUpdateTransactionInfo {
+            signature: Signature::new_unique().as_ref().to_vec(),
+            transaction: Some(proto::Transaction {
+                signatures: vec![],
+                message: Some(proto::Message {
+                    header: Some(proto::MessageHeader {
+                        num_required_signatures: 1,
+                        num_readonly_signed_accounts: 0,
+                        num_readonly_unsigned_accounts: 1,
+                    }),
+                    account_keys: vec![
+                        Pubkey::new_unique().to_bytes().to_vec(),
+                        solana_sdk::system_program::id().to_bytes().to_vec(),
+                    ],
+                    recent_blockhash: Hash::new_unique().to_bytes().to_vec(),
+                    ..Default::default()
+                }),
+            }),
+            meta: Some(proto::TransactionStatusMeta::default()),
+            index,
+            ..Default::default()
+        }
+    }
+
+    fn transaction_update(slot: Slot, index: u64) -> SubscribeUpdate {
+        SubscribeUpdate {
+            update_oneof: Some(UpdateOneof::Transaction(
+                proto::SubscribeUpdateTransaction {
+                    transaction: Some(create_grpc_tx(index)),
+                    slot,
+                },
+            )),
+            ..Default::default()
+        }
+    }
+
+    fn block_meta_update(slot: Slot, executed_transaction_count: u64) -> SubscribeUpdate {
+        SubscribeUpdate {
+            update_oneof: Some(UpdateOneof::BlockMeta(SubscribeUpdateBlockMeta {
+                slot,
+                blockhash: Hash::new_unique().to_string(),
+                parent_slot: slot - 1,
+                parent_blockhash: Hash::new_unique().to_string(),
+                block_height: Some(proto::BlockHeight { block_height: slot }),
+                block_time: Some(proto::UnixTimestamp {
+                    timestamp: 1_700_000_000,
+                }),
+                executed_transaction_count,
+                ..Default::default()
+            })),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_assemble_block_from_transactions() {
+        let commitment_config = CommitmentConfig::processed();
+        let mut mapper = BlockSourceKind::Transactions.create_mapper();
+
+        assert!(mapper
+            .map_update(transaction_update(42, 1), commitment_config)
+            .is_none());
+        // meta arrives before the last transaction
+        assert!(mapper
+            .map_update(block_meta_update(42, 2), commitment_config)
+            .is_none());
+        let block = mapper
+            .map_update(transaction_update(42, 0), commitment_config)
+            .expect("block is complete");
+
+        assert_eq!(block.slot, 42);
+        assert_eq!(block.transactions.len(), 2);
+    }
+}
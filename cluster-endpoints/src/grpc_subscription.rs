@@ -1,18 +1,16 @@
-use crate::endpoint_stremers::EndpointStreaming;
+use crate::endpoint_stremers::{EndpointStreaming, EndpointSubscription, EndpointTasks};
 use crate::grpc::grpc_accounts_streaming::create_grpc_account_streaming;
 use crate::grpc_multiplex::{
-    create_grpc_multiplex_blocks_subscription, create_grpc_multiplex_processed_slots_subscription,
+    create_grpc_multiplex_blocks_subscription_with_sources,
+    create_grpc_multiplex_processed_slots_subscription,
 };
-use geyser_grpc_connector::GrpcSourceConfig;
+use crate::grpc_multiplex_config::GrpcMultiplexConfig;
 use itertools::Itertools;
 use log::trace;
 use solana_client::nonblocking::rpc_client::RpcClient;
 use solana_lite_rpc_core::structures::account_data::AccountNotificationMessage;
 use solana_lite_rpc_core::structures::account_filter::AccountFilters;
-use solana_lite_rpc_core::{
-    structures::produced_block::{ProducedBlock, TransactionInfo},
-    AnyhowJoinHandle,
-};
+use solana_lite_rpc_core::structures::produced_block::{ProducedBlock, TransactionInfo};
 use solana_sdk::program_utils::limited_deserialize;
 use solana_sdk::vote::instruction::VoteInstruction;
 use solana_sdk::{
@@ -32,13 +30,14 @@ use solana_transaction_status::{Reward, RewardType};
 use std::cell::OnceCell;
 use std::sync::Arc;
 use tokio::sync::Notify;
+use tokio_util::sync::CancellationToken;
 use tracing::trace_span;
 
 use crate::rpc_polling::vote_accounts_and_cluster_info_polling::{
     poll_cluster_info, poll_vote_accounts,
 };
 use solana_lite_rpc_core::solana_utils::hash_from_str;
-use solana_lite_rpc_core::structures::produced_block::ProducedBlockInner;
+use solana_lite_rpc_core::structures::produced_block::{BlockStats, ProducedBlockInner};
 use yellowstone_grpc_proto::prelude::SubscribeUpdateBlock;
 
 /// grpc version of ProducedBlock mapping
@@ -48,6 +47,7 @@ pub fn from_grpc_block_update(
 ) -> ProducedBlock {
     let num_transactions = block.transactions.len();
     let _span = trace_span!("from_grpc_block_update", ?block.slot, ?num_transactions).entered();
+    let mut total_fees: u64 = 0;
     let txs: Vec<TransactionInfo> = block
         .transactions
         .into_iter()
@@ -56,6 +56,8 @@ pub fn from_grpc_block_update(
 
             let transaction = tx.transaction?;
 
+            total_fees = total_fees.saturating_add(meta.fee);
+
             let message = transaction.message?;
 
             let header = message.header?;
@@ -187,6 +189,7 @@ pub fn from_grpc_block_update(
         None
     };
 
+    let stats = BlockStats::new(&txs, total_fees);
     let inner = ProducedBlockInner {
         transactions: txs,
         block_height: block
@@ -200,6 +203,7 @@ pub fn from_grpc_block_update(
         parent_slot: block.parent_slot,
         slot: block.slot,
         rewards,
+        stats,
     };
     ProducedBlock::new(inner, commitment_config)
 }
@@ -244,25 +248,32 @@ fn map_compute_budget_instructions(message: &VersionedMessage) -> (Option<u32>,
 
 pub fn create_grpc_subscription(
     rpc_client: Arc<RpcClient>,
-    grpc_sources: Vec<GrpcSourceConfig>,
+    grpc_config: GrpcMultiplexConfig,
     accounts_filter: AccountFilters,
-) -> anyhow::Result<(EndpointStreaming, Vec<AnyhowJoinHandle>)> {
+    cancel: CancellationToken,
+) -> anyhow::Result<EndpointSubscription> {
+    grpc_config.validate_block_sources()?;
+    let grpc_sources = grpc_config.grpc_source_configs();
     let (cluster_info_sx, cluster_info_notifier) = tokio::sync::broadcast::channel(10);
     let (va_sx, vote_account_notifier) = tokio::sync::broadcast::channel(10);
 
     // processed slot is required to keep up with leader schedule
     let (slot_multiplex_channel, jh_multiplex_slotstream) =
-        create_grpc_multiplex_processed_slots_subscription(grpc_sources.clone());
+        create_grpc_multiplex_processed_slots_subscription(grpc_sources.clone(), cancel.clone());
 
     let (block_multiplex_channel, blockmeta_channel, jh_multiplex_blockstream) =
-        create_grpc_multiplex_blocks_subscription(grpc_sources.clone());
+        create_grpc_multiplex_blocks_subscription_with_sources(
+            grpc_config.multiplex_sources(),
+            cancel.clone(),
+        );
 
     let cluster_info_polling = poll_cluster_info(rpc_client.clone(), cluster_info_sx);
     let vote_accounts_polling = poll_vote_accounts(rpc_client.clone(), va_sx);
     // accounts
     if !accounts_filter.is_empty() {
-        let (account_sender, accounts_stream) =
-            tokio::sync::broadcast::channel::<AccountNotificationMessage>(1024);
+        let (account_sender, accounts_stream) = tokio::sync::broadcast::channel::<
+            AccountNotificationMessage,
+        >(grpc_config.account_channel_capacity);
         let account_jh = create_grpc_account_streaming(
             grpc_sources,
             accounts_filter,
@@ -285,7 +296,10 @@ pub fn create_grpc_subscription(
             vote_accounts_polling,
             account_jh,
         ];
-        Ok((streamers, endpoint_tasks))
+        Ok(EndpointSubscription {
+            streaming: streamers,
+            tasks: EndpointTasks::new(endpoint_tasks, cancel),
+        })
     } else {
         let streamers = EndpointStreaming {
             blocks_notifier: block_multiplex_channel,
@@ -302,6 +316,89 @@ pub fn create_grpc_subscription(
             cluster_info_polling,
             vote_accounts_polling,
         ];
-        Ok((streamers, endpoint_tasks))
+        Ok(EndpointSubscription {
+            streaming: streamers,
+            tasks: EndpointTasks::new(endpoint_tasks, cancel),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solana_sdk::vote::state::Vote;
+    use yellowstone_grpc_proto::prelude as proto;
+
+    fn create_grpc_tx(
+        program_id: Pubkey,
+        data: Vec<u8>,
+        fee: u64,
+    ) -> proto::SubscribeUpdateTransactionInfo {
+        let account_keys = vec![Pubkey::new_unique(), Pubkey::new_unique(), program_id];
+        proto::SubscribeUpdateTransactionInfo {
+            signature: Signature::new_unique().as_ref().to_vec(),
+            transaction: Some(proto::Transaction {
+                signatures: vec![],
+                message: Some(proto::Message {
+                    header: Some(proto::MessageHeader {
+                        num_required_signatures: 1,
+                        num_readonly_signed_accounts: 0,
+                        num_readonly_unsigned_accounts: 1,
+                    }),
+                    account_keys: account_keys
+                        .iter()
+                        .map(|key| key.to_bytes().to_vec())
+                        .collect(),
+                    recent_blockhash: Hash::new_unique().to_bytes().to_vec(),
+                    instructions: vec![proto::CompiledInstruction {
+                        program_id_index: 2,
+                        accounts: vec![1, 0],
+                        data,
+                    }],
+                    ..Default::default()
+                }),
+            }),
+            meta: Some(proto::TransactionStatusMeta {
+                fee,
+                ..Default::default()
+            }),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_block_stats_vote_and_non_vote() {
+        let vote_data = bincode::serialize(&VoteInstruction::Vote(Vote::new(
+            vec![41],
+            Hash::new_unique(),
+        )))
+        .unwrap();
+        let block = SubscribeUpdateBlock {
+            slot: 42,
+            blockhash: Hash::new_unique().to_string(),
+            parent_slot: 41,
+            parent_blockhash: Hash::new_unique().to_string(),
+            block_height: Some(proto::BlockHeight { block_height: 40 }),
+            block_time: Some(proto::UnixTimestamp {
+                timestamp: 1_700_000_000,
+            }),
+            transactions: vec![
+                create_grpc_tx(solana_sdk::vote::program::id(), vote_data, 5_000),
+                create_grpc_tx(solana_sdk::system_program::id(), vec![2, 0, 0, 0], 7_500),
+            ],
+            ..Default::default()
+        };
+
+        let produced_block = from_grpc_block_update(block, CommitmentConfig::processed());
+
+        assert_eq!(
+            produced_block.stats,
+            BlockStats {
+                transaction_count: 2,
+                vote_transaction_count: 1,
+                non_vote_transaction_count: 1,
+                total_fees: 12_500,
+            }
+        );
     }
 }
@@ -2,18 +2,18 @@ use crate::rpc_polling::vote_accounts_and_cluster_info_polling::{
     poll_cluster_info, poll_vote_accounts,
 };
 use crate::{
-    endpoint_stremers::EndpointStreaming,
+    endpoint_stremers::{EndpointStreaming, EndpointSubscription, EndpointTasks},
     rpc_polling::{poll_blocks::poll_block, poll_slots::poll_slots},
 };
 use solana_client::nonblocking::rpc_client::RpcClient;
-use solana_lite_rpc_core::AnyhowJoinHandle;
 use solana_sdk::commitment_config::CommitmentConfig;
 use std::sync::Arc;
+use tokio_util::sync::CancellationToken;
 
 pub fn create_json_rpc_polling_subscription(
     rpc_client: Arc<RpcClient>,
     num_parallel_tasks: usize,
-) -> anyhow::Result<(EndpointStreaming, Vec<AnyhowJoinHandle>)> {
+) -> anyhow::Result<EndpointSubscription> {
     let (slot_sx, slot_notifier) = tokio::sync::broadcast::channel(16);
     let (block_sx, blocks_notifier) = tokio::sync::broadcast::channel(16);
     let (blockinfo_sx, blockinfo_notifier) = tokio::sync::broadcast::channel(16);
@@ -48,5 +48,9 @@ pub fn create_json_rpc_polling_subscription(
         // does not support accounts support with rpc polling
         processed_account_stream: None,
     };
-    Ok((streamers, endpoint_tasks))
+    // the polling tasks do not observe the token, they are aborted once the tasks stop running
+    Ok(EndpointSubscription {
+        streaming: streamers,
+        tasks: EndpointTasks::new(endpoint_tasks, CancellationToken::new()),
+    })
 }
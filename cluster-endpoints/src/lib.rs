@@ -3,6 +3,8 @@ pub mod grpc;
 pub mod grpc_inspect;
 pub mod grpc_leaders_getter;
 pub mod grpc_multiplex;
+pub mod grpc_multiplex_config;
+pub mod grpc_source_mapper;
 pub mod grpc_stream_utils;
 pub mod grpc_subscription;
 pub mod json_rpc_leaders_getter;
@@ -2,7 +2,9 @@ use anyhow::{bail, Context};
 use solana_client::nonblocking::rpc_client::RpcClient;
 use solana_lite_rpc_core::solana_utils::hash_from_str;
 use solana_lite_rpc_core::structures::block_info::BlockInfo;
-use solana_lite_rpc_core::structures::produced_block::{ProducedBlockInner, TransactionInfo};
+use solana_lite_rpc_core::structures::produced_block::{
+    BlockStats, ProducedBlockInner, TransactionInfo,
+};
 use solana_lite_rpc_core::{
     structures::{
         produced_block::ProducedBlock,
@@ -10,7 +12,12 @@ use solana_lite_rpc_core::{
     },
     AnyhowJoinHandle,
 };
+use solana_rpc_client_api::client_error::ErrorKind as ClientErrorKind;
 use solana_rpc_client_api::config::RpcBlockConfig;
+use solana_rpc_client_api::custom_error::{
+    JSON_RPC_SERVER_ERROR_LONG_TERM_STORAGE_SLOT_SKIPPED, JSON_RPC_SERVER_ERROR_SLOT_SKIPPED,
+};
+use solana_rpc_client_api::request::RpcError;
 use solana_sdk::borsh1::try_from_slice_unchecked;
 use solana_sdk::compute_budget::ComputeBudgetInstruction;
 use solana_sdk::program_utils::limited_deserialize;
@@ -30,28 +37,52 @@ use tokio::sync::broadcast::{Receiver, Sender};
 
 pub const NUM_PARALLEL_TASKS_DEFAULT: usize = 16;
 
+fn block_config(commitment_config: CommitmentConfig) -> RpcBlockConfig {
+    RpcBlockConfig {
+        transaction_details: Some(TransactionDetails::Full),
+        commitment: Some(commitment_config),
+        max_supported_transaction_version: Some(0),
+        encoding: Some(UiTransactionEncoding::Base64),
+        rewards: Some(true),
+    }
+}
+
 pub async fn process_block(
     rpc_client: &RpcClient,
     slot: Slot,
     commitment_config: CommitmentConfig,
 ) -> Option<ProducedBlock> {
     let block = rpc_client
-        .get_block_with_config(
-            slot,
-            RpcBlockConfig {
-                transaction_details: Some(TransactionDetails::Full),
-                commitment: Some(commitment_config),
-                max_supported_transaction_version: Some(0),
-                encoding: Some(UiTransactionEncoding::Base64),
-                rewards: Some(true),
-            },
-        )
+        .get_block_with_config(slot, block_config(commitment_config))
         .await;
     block
         .ok()
         .map(|block| from_ui_block(block, slot, commitment_config))
 }
 
+/// like `process_block` but returns None only for skipped slots, other rpc errors are returned
+pub async fn fetch_block(
+    rpc_client: &RpcClient,
+    slot: Slot,
+    commitment_config: CommitmentConfig,
+) -> anyhow::Result<Option<ProducedBlock>> {
+    match rpc_client
+        .get_block_with_config(slot, block_config(commitment_config))
+        .await
+    {
+        Ok(block) => Ok(Some(from_ui_block(block, slot, commitment_config))),
+        Err(err) => match &err.kind {
+            ClientErrorKind::RpcError(RpcError::RpcResponseError { code, .. })
+                if *code == JSON_RPC_SERVER_ERROR_SLOT_SKIPPED
+                    || *code == JSON_RPC_SERVER_ERROR_LONG_TERM_STORAGE_SLOT_SKIPPED =>
+            {
+                Ok(None)
+            }
+            _ => Err(err).with_context(|| format!("failed to fetch block {slot}")),
+        },
+    }
+}
+
 pub fn poll_block(
     rpc_client: Arc<RpcClient>,
     block_notification_sender: Sender<ProducedBlock>,
@@ -196,11 +227,13 @@ pub fn from_ui_block(
     let parent_slot = block.parent_slot;
     let rewards = block.rewards.clone();
 
-    let txs = txs
+    let mut total_fees: u64 = 0;
+    let txs: Vec<TransactionInfo> = txs
         .into_iter()
         .filter_map(|tx| {
             let Some(UiTransactionStatusMeta {
                 err,
+                fee,
                 compute_units_consumed,
                 ..
             }) = tx.meta
@@ -215,6 +248,7 @@ pub fn from_ui_block(
                 log::info!("Tx could not be decoded");
                 return None;
             };
+            total_fees = total_fees.saturating_add(fee);
 
             let signature = tx.signatures[0];
             let cu_consumed = match compute_units_consumed {
@@ -303,6 +337,7 @@ pub fn from_ui_block(
 
     let block_time = block.block_time.unwrap_or(0) as u64;
 
+    let stats = BlockStats::new(&txs, total_fees);
     let inner = ProducedBlockInner {
         transactions: txs,
         block_height,
@@ -313,6 +348,7 @@ pub fn from_ui_block(
         block_time,
         slot,
         rewards,
+        stats,
     };
     ProducedBlock::new(inner, commitment_config)
 }
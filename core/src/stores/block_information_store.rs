@@ -1,16 +1,28 @@
 use dashmap::DashMap;
-use log::info;
+use log::{debug, info};
 
 use solana_sdk::commitment_config::{CommitmentConfig, CommitmentLevel};
-use solana_sdk::{clock::MAX_RECENT_BLOCKHASHES, slot_history::Slot};
+use solana_sdk::{
+    clock::{DEFAULT_MS_PER_SLOT, MAX_RECENT_BLOCKHASHES},
+    slot_history::Slot,
+};
+use std::collections::{BTreeMap, HashSet};
+use std::ops::RangeInclusive;
 use std::sync::atomic::AtomicU64;
-use std::sync::Arc;
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
 use tokio::sync::RwLock;
 
+use crate::commitment_utils::Commitment;
 use crate::structures::block_info::BlockInfo;
 use crate::structures::produced_block::ProducedBlock;
 use solana_sdk::hash::Hash;
 
+/// unix timestamp of `slot` extrapolated from a block at `base_slot`, assuming the default slot duration
+pub fn estimate_slot_time(base_slot: Slot, base_block_time: u64, slot: Slot) -> u64 {
+    base_block_time + slot.saturating_sub(base_slot) * DEFAULT_MS_PER_SLOT / 1000
+}
+
 #[derive(Clone, Debug)]
 pub struct BlockInformation {
     pub slot: u64,
@@ -20,6 +32,9 @@ pub struct BlockInformation {
     pub blockhash: Hash,
     pub commitment_config: CommitmentConfig,
     pub block_time: u64,
+    pub parent_slot: Slot,
+    /// block_time was interpolated from the parent block, the validator did not provide it yet
+    pub block_time_estimated: bool,
 }
 
 impl BlockInformation {
@@ -32,6 +47,8 @@ impl BlockInformation {
             blockhash: block.blockhash,
             commitment_config: block.commitment_config,
             block_time: block.block_time,
+            parent_slot: block.parent_slot,
+            block_time_estimated: false,
         }
     }
     pub fn from_block_info(block_info: &BlockInfo) -> Self {
@@ -43,6 +60,8 @@ impl BlockInformation {
             blockhash: block_info.blockhash,
             commitment_config: block_info.commitment_config,
             block_time: block_info.block_time,
+            parent_slot: block_info.parent,
+            block_time_estimated: false,
         }
     }
 }
@@ -55,9 +74,13 @@ impl BlockInformation {
 pub struct BlockInformationStore {
     // maps Block Hash -> Block information
     blocks: Arc<DashMap<Hash, BlockInformation>>,
+    // maps Slot -> Block Hashes of the forks at that slot, locked before a block entry when both are needed
+    slot_index: Arc<Mutex<BTreeMap<Slot, HashSet<Hash>>>>,
     last_blockheight: Arc<AtomicU64>,
     latest_confirmed_block: Arc<RwLock<BlockInformation>>,
     latest_finalized_block: Arc<RwLock<BlockInformation>>,
+    // highest slot added and when, by commitment rank (processed, confirmed, finalized)
+    tips: Arc<Mutex<[Option<(Slot, Instant)>; 3]>>,
 }
 
 impl BlockInformationStore {
@@ -68,12 +91,18 @@ impl BlockInformationStore {
             latest_finalized_block.blockhash,
             latest_finalized_block.clone(),
         );
+        let slot_index = BTreeMap::from([(
+            latest_finalized_block.slot,
+            HashSet::from([latest_finalized_block.blockhash]),
+        )]);
 
         Self {
             last_blockheight: Arc::new(AtomicU64::new(latest_finalized_block.block_height)),
             latest_confirmed_block: Arc::new(RwLock::new(latest_finalized_block.clone())),
             latest_finalized_block: Arc::new(RwLock::new(latest_finalized_block)),
             blocks,
+            slot_index: Arc::new(Mutex::new(slot_index)),
+            tips: Arc::new(Mutex::new([None; 3])),
         }
     }
 
@@ -111,10 +140,14 @@ impl BlockInformationStore {
             .clone()
     }
 
-    pub async fn add_block(&self, block_info: BlockInformation) -> bool {
+    pub async fn add_block(&self, mut block_info: BlockInformation) -> bool {
+        if block_info.block_time == 0 {
+            self.estimate_block_time(&mut block_info);
+        }
         // save slot copy to avoid borrow issues
         let slot = block_info.slot;
         let commitment_config = block_info.commitment_config;
+        self.advance_tip(slot, commitment_config);
         if self
             .last_blockheight
             .load(std::sync::atomic::Ordering::Relaxed)
@@ -127,15 +160,8 @@ impl BlockInformationStore {
             );
         }
 
-        // update latest block
-        {
-            let latest_block = self.get_latest_block_arc(commitment_config);
-            if slot > latest_block.read().await.slot {
-                *latest_block.write().await = block_info.clone();
-            }
-        }
-
-        match self.blocks.entry(block_info.blockhash) {
+        // the block must be known before it becomes the latest block, see is_blockhash_valid
+        let updated = match self.blocks.entry(block_info.blockhash) {
             dashmap::mapref::entry::Entry::Occupied(entry) => {
                 let should_update = match entry.get().commitment_config.commitment {
                     CommitmentLevel::Finalized => false, // should never update blocks of finalized commitment
@@ -147,16 +173,118 @@ impl BlockInformationStore {
                             || commitment_config == CommitmentConfig::finalized()
                     }
                 };
+                // a real block time replaces an estimate, an estimate never replaces a real block time
+                let stored = entry.get();
+                let is_real_block_time =
+                    block_info.block_time != 0 && !block_info.block_time_estimated;
+                let replaces_estimate = stored.block_time_estimated && is_real_block_time;
                 if should_update {
-                    entry.replace_entry(block_info);
+                    if !is_real_block_time && !stored.block_time_estimated {
+                        block_info.block_time = stored.block_time;
+                        block_info.block_time_estimated = false;
+                    }
+                    entry.replace_entry(block_info.clone());
+                } else if replaces_estimate {
+                    let mut stored = stored.clone();
+                    stored.block_time = block_info.block_time;
+                    stored.block_time_estimated = false;
+                    entry.replace_entry(stored);
                 }
-                should_update
+                should_update || replaces_estimate
             }
             dashmap::mapref::entry::Entry::Vacant(entry) => {
-                entry.insert(block_info);
+                entry.insert(block_info.clone());
                 true
             }
+        };
+        self.slot_index
+            .lock()
+            .unwrap()
+            .entry(slot)
+            .or_default()
+            .insert(block_info.blockhash);
+
+        // a block of a higher commitment replaces the blocks of an orphaned fork at the same slot
+        let replaced_fork = commitment_config.is_at_least_confirmed()
+            && self.remove_orphaned_blocks(slot, &block_info.blockhash, commitment_config);
+
+        // update latest block
+        {
+            let latest_block = self.get_latest_block_arc(commitment_config);
+            let should_replace_latest = {
+                let latest = latest_block.read().await;
+                slot > latest.slot
+                    || (slot == latest.slot
+                        && latest.blockhash != block_info.blockhash
+                        && Commitment::from(commitment_config)
+                            > Commitment::from(latest.commitment_config))
+            };
+            if should_replace_latest {
+                *latest_block.write().await = block_info;
+            }
         }
+
+        updated || replaced_fork
+    }
+
+    fn advance_tip(&self, slot: Slot, commitment_config: CommitmentConfig) {
+        let mut tips = self.tips.lock().unwrap();
+        let tip = &mut tips[Commitment::from(commitment_config) as usize];
+        if tip.map_or(true, |(tip_slot, _)| slot > tip_slot) {
+            *tip = Some((slot, Instant::now()));
+        }
+    }
+
+    /// when a block of a higher slot was last added at the commitment, none if none was added since startup
+    pub fn last_update(&self, commitment_config: CommitmentConfig) -> Option<Instant> {
+        self.tips.lock().unwrap()[Commitment::from(commitment_config) as usize]
+            .map(|(_, updated_at)| updated_at)
+    }
+
+    /// interpolates a missing block time from the parent block, assuming the default slot duration
+    fn estimate_block_time(&self, block_info: &mut BlockInformation) {
+        let Some(parent) = self.get_block_info_by_slot(block_info.parent_slot) else {
+            return;
+        };
+        if parent.block_time == 0 {
+            return;
+        }
+        block_info.block_time = estimate_slot_time(parent.slot, parent.block_time, block_info.slot);
+        block_info.block_time_estimated = true;
+        debug!(
+            "estimated block time {} of slot {} from parent slot {}",
+            block_info.block_time, block_info.slot, parent.slot
+        );
+    }
+
+    /// removes the blocks at `slot` with a different blockhash and a lower commitment, returns true if any was removed
+    fn remove_orphaned_blocks(
+        &self,
+        slot: Slot,
+        blockhash: &Hash,
+        commitment_config: CommitmentConfig,
+    ) -> bool {
+        let commitment = Commitment::from(commitment_config);
+        let mut slot_index = self.slot_index.lock().unwrap();
+        let Some(blockhashes) = slot_index.get_mut(&slot) else {
+            return false;
+        };
+        let orphaned = blockhashes
+            .iter()
+            .filter(|other| *other != blockhash)
+            .filter(|other| {
+                self.blocks.get(*other).map_or(false, |block| {
+                    Commitment::from(block.commitment_config) < commitment
+                })
+            })
+            .copied()
+            .collect::<Vec<_>>();
+        for orphaned_blockhash in &orphaned {
+            info!("replacing orphaned block {orphaned_blockhash} at slot {slot} with {blockhash}");
+            self.blocks.remove(orphaned_blockhash);
+            blockhashes.remove(orphaned_blockhash);
+        }
+        !orphaned.is_empty()
     }
 
     pub async fn clean(&self) {
@@ -166,6 +294,10 @@ impl BlockInformationStore {
         let before_length = self.blocks.len();
         self.blocks
             .retain(|_, v| v.last_valid_blockheight >= finalized_block_information.block_height);
+        self.slot_index.lock().unwrap().retain(|_, blockhashes| {
+            blockhashes.retain(|blockhash| self.blocks.contains_key(blockhash));
+            !blockhashes.is_empty()
+        });
 
         info!(
             "Cleaned {} block info",
@@ -177,34 +309,269 @@ impl BlockInformationStore {
         self.blocks.len()
     }
 
+    /// Checks the blockhash against the latest block height at the given commitment.
+    ///
+    /// A blockhash just issued by getLatestBlockhash is never reported as invalid:
+    /// - blocks are stored before they become the latest block of their commitment level
+    /// - a blockhash of a block ahead of the requested commitment (e.g. confirmed blockhash checked with finalized)
+    ///   is valid as the finalized tip has not caught up yet
+    ///
+    /// The returned context slot is the latest slot at the given commitment but never lower than the slot of the blockhash.
     pub async fn is_blockhash_valid(
         &self,
         blockhash: &Hash,
         commitment_config: CommitmentConfig,
     ) -> (bool, Slot) {
         let latest_block = self.get_latest_block_information(commitment_config).await;
-        match self.blocks.get(blockhash) {
+        let stored = self.blocks.get(blockhash).map(|info| info.value().clone());
+        let block_information = match stored {
+            Some(block_information) => Some(block_information),
+            None => {
+                // the latest confirmed block is the most recent blockhash we hand out
+                let latest_confirmed = self.latest_confirmed_block.read().await;
+                (latest_confirmed.blockhash == *blockhash).then(|| latest_confirmed.clone())
+            }
+        };
+        match block_information {
             Some(block_information) => (
                 latest_block.block_height <= block_information.last_valid_blockheight,
-                latest_block.slot,
+                latest_block.slot.max(block_information.slot),
             ),
             None => (false, latest_block.slot),
         }
     }
 
     pub fn get_block_info_by_slot(&self, slot: u64) -> Option<BlockInformation> {
-        self.blocks.iter().find_map(|iter| {
-            // should be fine for now, may be could be optimized in future if necessary
-            if iter.slot == slot {
-                Some(iter.value().clone())
-            } else {
-                None
-            }
+        let slot_index = self.slot_index.lock().unwrap();
+        slot_index.get(&slot)?.iter().find_map(|blockhash| {
+            self.blocks
+                .get(blockhash)
+                .map(|block_info| block_info.value().clone())
         })
     }
 
+    /// slots of the blocks in the store of at least `commitment_config`, older blocks are already cleaned up
+    pub fn get_slots_in_range(
+        &self,
+        slots: RangeInclusive<Slot>,
+        commitment_config: CommitmentConfig,
+    ) -> HashSet<Slot> {
+        let min_commitment = Commitment::from(commitment_config);
+        let slot_index = self.slot_index.lock().unwrap();
+        slot_index
+            .range(slots)
+            .filter(|(_, blockhashes)| {
+                blockhashes.iter().any(|blockhash| {
+                    self.blocks.get(blockhash).map_or(false, |block_info| {
+                        Commitment::from(block_info.commitment_config) >= min_commitment
+                    })
+                })
+            })
+            .map(|(slot, _)| *slot)
+            .collect()
+    }
+
+    /// blocks before this slot were cleaned up or never seen, none if the store is empty
+    pub fn get_oldest_slot(&self) -> Option<Slot> {
+        self.slot_index
+            .lock()
+            .unwrap()
+            .first_key_value()
+            .map(|(slot, _)| *slot)
+    }
+
     pub fn get_last_blockheight(&self) -> u64 {
         self.last_blockheight
             .load(std::sync::atomic::Ordering::Relaxed)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn block_information(slot: Slot, commitment_config: CommitmentConfig) -> BlockInformation {
+        BlockInformation {
+            slot,
+            block_height: slot,
+            last_valid_blockheight: slot + MAX_RECENT_BLOCKHASHES as u64,
+            cleanup_slot: slot + 1000,
+            blockhash: Hash::new_unique(),
+            commitment_config,
+            block_time: 0,
+            parent_slot: slot - 1,
+            block_time_estimated: false,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_just_issued_blockhash_is_valid() {
+        let store =
+            BlockInformationStore::new(block_information(100, CommitmentConfig::finalized()));
+        store
+            .add_block(block_information(132, CommitmentConfig::confirmed()))
+            .await;
+
+        for commitment_config in [CommitmentConfig::confirmed(), CommitmentConfig::finalized()] {
+            let issued = store.get_latest_block_information(commitment_config).await;
+            let (is_valid, slot) = store
+                .is_blockhash_valid(&issued.blockhash, commitment_config)
+                .await;
+            assert!(is_valid);
+            assert_eq!(slot, issued.slot);
+        }
+
+        // confirmed blockhash checked before finalized caught up
+        let issued = store
+            .get_latest_block_information(CommitmentConfig::confirmed())
+            .await;
+        let (is_valid, slot) = store
+            .is_blockhash_valid(&issued.blockhash, CommitmentConfig::finalized())
+            .await;
+        assert!(is_valid);
+        assert_eq!(slot, 132);
+
+        let (is_valid, _) = store
+            .is_blockhash_valid(&Hash::new_unique(), CommitmentConfig::confirmed())
+            .await;
+        assert!(!is_valid);
+    }
+
+    #[tokio::test]
+    async fn test_processed_block_replaced_by_confirmed_fork() {
+        let store =
+            BlockInformationStore::new(block_information(100, CommitmentConfig::finalized()));
+        let processed = block_information(132, CommitmentConfig::processed());
+        assert!(store.add_block(processed.clone()).await);
+
+        // the processed block got orphaned, the cluster confirmed a different block at the same slot
+        let confirmed = block_information(132, CommitmentConfig::confirmed());
+        assert!(store.add_block(confirmed.clone()).await);
+
+        let latest = store
+            .get_latest_block_information(CommitmentConfig::confirmed())
+            .await;
+        assert_eq!(latest.slot, 132);
+        assert_eq!(latest.blockhash, confirmed.blockhash);
+        assert!(store.get_block_info(&processed.blockhash).is_none());
+        assert_eq!(
+            store.get_block_info_by_slot(132).unwrap().blockhash,
+            confirmed.blockhash
+        );
+
+        // a late processed block of the orphaned fork does not replace the confirmed block
+        let late_processed = block_information(132, CommitmentConfig::processed());
+        store.add_block(late_processed).await;
+        assert_eq!(
+            store
+                .get_latest_blockhash(CommitmentConfig::confirmed())
+                .await,
+            confirmed.blockhash
+        );
+    }
+
+    #[tokio::test]
+    async fn test_missing_block_time_interpolated_from_parent() {
+        let store =
+            BlockInformationStore::new(block_information(100, CommitmentConfig::finalized()));
+        let parent = BlockInformation {
+            block_time: 1_700_000_000,
+            ..block_information(130, CommitmentConfig::confirmed())
+        };
+        store.add_block(parent).await;
+
+        // slots 131 to 134 were skipped
+        let zero_time_block = BlockInformation {
+            parent_slot: 130,
+            ..block_information(135, CommitmentConfig::processed())
+        };
+        store.add_block(zero_time_block.clone()).await;
+        let estimated = store.get_block_info(&zero_time_block.blockhash).unwrap();
+        // 5 slots of 400ms
+        assert_eq!(estimated.block_time, 1_700_000_002);
+        assert!(estimated.block_time_estimated);
+
+        // the real block time replaces the estimate
+        store
+            .add_block(BlockInformation {
+                block_time: 1_700_000_003,
+                ..zero_time_block.clone()
+            })
+            .await;
+        let stored = store.get_block_info(&zero_time_block.blockhash).unwrap();
+        assert_eq!(stored.block_time, 1_700_000_003);
+        assert!(!stored.block_time_estimated);
+
+        // a confirmed update without block time keeps the real block time
+        store
+            .add_block(BlockInformation {
+                commitment_config: CommitmentConfig::confirmed(),
+                ..zero_time_block.clone()
+            })
+            .await;
+        let stored = store.get_block_info(&zero_time_block.blockhash).unwrap();
+        assert_eq!(stored.block_time, 1_700_000_003);
+        assert_eq!(stored.commitment_config, CommitmentConfig::confirmed());
+    }
+
+    #[tokio::test]
+    async fn test_last_update_per_commitment() {
+        let store =
+            BlockInformationStore::new(block_information(100, CommitmentConfig::finalized()));
+        assert_eq!(store.last_update(CommitmentConfig::finalized()), None);
+
+        store
+            .add_block(block_information(101, CommitmentConfig::finalized()))
+            .await;
+        let finalized_update = store.last_update(CommitmentConfig::finalized()).unwrap();
+
+        // only processed advances
+        for slot in 102..110 {
+            store
+                .add_block(block_information(slot, CommitmentConfig::processed()))
+                .await;
+        }
+        let processed_update = store.last_update(CommitmentConfig::processed()).unwrap();
+        assert!(processed_update >= finalized_update);
+        assert_eq!(
+            store.last_update(CommitmentConfig::finalized()),
+            Some(finalized_update)
+        );
+        assert_eq!(store.last_update(CommitmentConfig::confirmed()), None);
+
+        // the same finalized block again does not advance the tip
+        store
+            .add_block(block_information(101, CommitmentConfig::finalized()))
+            .await;
+        assert_eq!(
+            store.last_update(CommitmentConfig::finalized()),
+            Some(finalized_update)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_slots_in_range_by_commitment() {
+        let store =
+            BlockInformationStore::new(block_information(100, CommitmentConfig::finalized()));
+        store
+            .add_block(block_information(101, CommitmentConfig::confirmed()))
+            .await;
+        store
+            .add_block(block_information(102, CommitmentConfig::processed()))
+            .await;
+
+        assert_eq!(store.get_oldest_slot(), Some(100));
+        assert_eq!(
+            store.get_slots_in_range(100..=102, CommitmentConfig::processed()),
+            HashSet::from([100, 101, 102])
+        );
+        assert_eq!(
+            store.get_slots_in_range(100..=102, CommitmentConfig::confirmed()),
+            HashSet::from([100, 101])
+        );
+        assert_eq!(
+            store.get_slots_in_range(100..=102, CommitmentConfig::finalized()),
+            HashSet::from([100])
+        );
+    }
+}
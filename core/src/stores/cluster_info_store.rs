@@ -1,13 +1,19 @@
 use dashmap::DashMap;
 use solana_rpc_client_api::response::RpcContactInfo;
 use solana_sdk::pubkey::Pubkey;
-use std::{str::FromStr, sync::Arc};
+use std::{
+    str::FromStr,
+    sync::Arc,
+    time::{Duration, Instant},
+};
 
 use crate::types::ClusterInfoStream;
 
 #[derive(Debug, Clone, Default)]
 pub struct ClusterInfo {
     pub cluster_nodes: Arc<DashMap<Pubkey, Arc<RpcContactInfo>>>,
+    // when a node was last part of a cluster info update
+    last_seen: Arc<DashMap<Pubkey, Instant>>,
 }
 
 impl ClusterInfo {
@@ -19,11 +25,107 @@ impl ClusterInfo {
             .recv()
             .await
             .expect("Failed to recieve on broadcast channel");
-        cluster_nodes.iter().for_each(|x| {
-            if let Ok(pubkey) = Pubkey::from_str(x.pubkey.as_str()) {
-                self.cluster_nodes.insert(pubkey, Arc::new(x.clone()));
-            }
-        });
+        cluster_nodes
+            .iter()
+            .for_each(|x| self.insert_contact_info(x));
         Ok(())
     }
+
+    /// keeps version, feature_set and shred_version of a known node if the update does not contain them
+    pub fn insert_contact_info(&self, contact_info: &RpcContactInfo) {
+        let Ok(pubkey) = Pubkey::from_str(contact_info.pubkey.as_str()) else {
+            return;
+        };
+        let mut contact_info = contact_info.clone();
+        if let Some(known) = self.cluster_nodes.get(&pubkey) {
+            contact_info.version = contact_info.version.or_else(|| known.version.clone());
+            contact_info.feature_set = contact_info.feature_set.or(known.feature_set);
+            contact_info.shred_version = contact_info.shred_version.or(known.shred_version);
+        }
+        self.cluster_nodes.insert(pubkey, Arc::new(contact_info));
+        self.last_seen.insert(pubkey, Instant::now());
+    }
+
+    /// with `only_with_tpu` nodes without a tpu address are left out as transactions cannot be sent to them,
+    /// with `ttl` nodes not refreshed within the ttl are left out as they probably left the cluster
+    pub fn get_cluster_nodes(
+        &self,
+        only_with_tpu: bool,
+        ttl: Option<Duration>,
+    ) -> Vec<RpcContactInfo> {
+        self.cluster_nodes
+            .iter()
+            .filter(|node| !only_with_tpu || node.tpu.is_some())
+            .filter(|node| ttl.map_or(true, |ttl| self.is_fresh(node.key(), ttl)))
+            .map(|node| node.value().as_ref().clone())
+            .collect()
+    }
+
+    fn is_fresh(&self, pubkey: &Pubkey, ttl: Duration) -> bool {
+        self.last_seen
+            .get(pubkey)
+            .is_some_and(|last_seen| last_seen.elapsed() <= ttl)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn contact_info(pubkey: &Pubkey, tpu: Option<&str>, version: Option<&str>) -> RpcContactInfo {
+        serde_json::from_value(serde_json::json!({
+            "pubkey": pubkey.to_string(),
+            "tpu": tpu,
+            "version": version,
+            "featureSet": version.map(|_| 4_215_500_110_u32),
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_filter_nodes_without_tpu() {
+        let cluster_info = ClusterInfo::default();
+        let with_tpu = Pubkey::new_unique();
+        let without_tpu = Pubkey::new_unique();
+        cluster_info.insert_contact_info(&contact_info(&with_tpu, Some("10.0.0.1:8003"), None));
+        cluster_info.insert_contact_info(&contact_info(&without_tpu, None, None));
+
+        assert_eq!(cluster_info.get_cluster_nodes(false, None).len(), 2);
+
+        let nodes = cluster_info.get_cluster_nodes(true, None);
+        assert_eq!(nodes.len(), 1);
+        assert_eq!(nodes[0].pubkey, with_tpu.to_string());
+    }
+
+    #[test]
+    fn test_keep_known_version() {
+        let cluster_info = ClusterInfo::default();
+        let pubkey = Pubkey::new_unique();
+        cluster_info.insert_contact_info(&contact_info(&pubkey, None, Some("1.18.15")));
+        cluster_info.insert_contact_info(&contact_info(&pubkey, Some("10.0.0.1:8003"), None));
+
+        let node = cluster_info.cluster_nodes.get(&pubkey).unwrap();
+        assert!(node.tpu.is_some());
+        assert_eq!(node.version.as_deref(), Some("1.18.15"));
+        assert_eq!(node.feature_set, Some(4_215_500_110));
+    }
+
+    #[test]
+    fn test_filter_stale_nodes() {
+        let cluster_info = ClusterInfo::default();
+        let stale = Pubkey::new_unique();
+        let fresh = Pubkey::new_unique();
+        cluster_info.insert_contact_info(&contact_info(&stale, Some("10.0.0.1:8003"), None));
+        cluster_info.insert_contact_info(&contact_info(&fresh, Some("10.0.0.2:8003"), None));
+        let Some(two_minutes_ago) = Instant::now().checked_sub(Duration::from_secs(120)) else {
+            return;
+        };
+        cluster_info.last_seen.insert(stale, two_minutes_ago);
+
+        assert_eq!(cluster_info.get_cluster_nodes(false, None).len(), 2);
+
+        let nodes = cluster_info.get_cluster_nodes(false, Some(Duration::from_secs(60)));
+        assert_eq!(nodes.len(), 1);
+        assert_eq!(nodes[0].pubkey, fresh.to_string());
+    }
 }
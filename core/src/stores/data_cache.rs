@@ -1,15 +1,21 @@
 use crate::structures::leaderschedule::CalculatedSchedule;
 use dashmap::DashMap;
+use itertools::Itertools;
 use solana_sdk::hash::Hash;
+use solana_sdk::signature::Signature;
 use solana_sdk::slot_history::Slot;
 use solana_sdk::{commitment_config::CommitmentConfig, pubkey::Pubkey};
+use solana_transaction_status::TransactionStatus;
+use std::net::SocketAddr;
 use std::sync::{atomic::AtomicU64, Arc};
+use std::time::{Duration, Instant};
 use tokio::sync::RwLock;
 
 use crate::{
     stores::{
         block_information_store::BlockInformationStore, cluster_info_store::ClusterInfo,
-        subscription_store::SubscriptionStore, tx_store::TxStore,
+        latest_block_cache::LatestBlockCache, subscription_store::SubscriptionStore,
+        transaction_count_store::TransactionCountStore, tx_store::TxStore,
     },
     structures::{
         epoch::{Epoch, EpochCache},
@@ -26,6 +32,8 @@ pub type TxSubKey = (String, CommitmentConfig);
 pub struct SlotCache {
     current_slot: AtomicSlot,
     estimated_slot: AtomicSlot,
+    // highest processed slot seen, current_slot can go back on forks
+    highest_processed_slot: AtomicSlot,
 }
 
 /// The central data store for all data from the cluster.
@@ -39,6 +47,8 @@ pub struct DataCache {
     pub cluster_info: ClusterInfo,
     pub epoch_data: EpochCache,
     pub leader_schedule: Arc<RwLock<CalculatedSchedule>>,
+    pub latest_block_cache: LatestBlockCache,
+    pub transaction_count: TransactionCountStore,
 }
 
 impl DataCache {
@@ -64,6 +74,43 @@ impl DataCache {
                 .is_transaction_confirmed(&sent_transaction_info.signature)
     }
 
+    /// waits until the transaction status reaches the commitment,
+    /// none if the block height passed `last_valid_block_height` before (transaction dropped)
+    pub async fn wait_for_transaction_commitment(
+        &self,
+        signature: &Signature,
+        commitment_config: CommitmentConfig,
+        last_valid_block_height: u64,
+        poll_interval: Duration,
+    ) -> Option<TransactionStatus> {
+        loop {
+            if let Some(status) = self.txs.get(signature).and_then(|props| props.status) {
+                if status.satisfies_commitment(commitment_config) {
+                    return Some(status);
+                }
+            }
+            if self.block_information_store.get_last_blockheight() > last_valid_block_height {
+                return None;
+            }
+            tokio::time::sleep(poll_interval).await;
+        }
+    }
+
+    /// when the tip of the commitment last advanced, a stalled finalized tip shows while processed keeps moving
+    pub fn last_update(&self, commitment_config: CommitmentConfig) -> Option<Instant> {
+        self.block_information_store.last_update(commitment_config)
+    }
+
+    /// latest block information, cached until the next slot notification
+    pub async fn get_latest_block_information(
+        &self,
+        commitment_config: CommitmentConfig,
+    ) -> BlockInformation {
+        self.latest_block_cache
+            .get_or_load(commitment_config, &self.block_information_store)
+            .await
+    }
+
     pub async fn get_current_epoch(&self, commitment: CommitmentConfig) -> Epoch {
         let BlockInformation { slot, .. } = self
             .block_information_store
@@ -72,6 +119,44 @@ impl DataCache {
         self.epoch_data.get_epoch_at_slot(slot)
     }
 
+    /// tpu addresses of the leaders of the next `num_slots` slots after the latest block of the commitment,
+    /// each leader once in schedule order, leaders without a known tpu address are skipped
+    pub async fn tpu_peers(
+        &self,
+        commitment: CommitmentConfig,
+        num_slots: u64,
+    ) -> anyhow::Result<Vec<(Pubkey, SocketAddr)>> {
+        let BlockInformation { slot, .. } = self
+            .block_information_store
+            .get_latest_block_information(commitment)
+            .await;
+        let leaders = self
+            .leader_schedule
+            .read()
+            .await
+            .get_slot_leaders(slot, num_slots, self.epoch_data.get_epoch_schedule())
+            .await
+            .map_err(|err| anyhow::anyhow!(err))?;
+
+        Ok(self.tpu_peers_of_leaders(leaders))
+    }
+
+    /// tpu addresses of the given leaders, each leader once in the given order,
+    /// leaders without a known tpu address are skipped
+    pub fn tpu_peers_of_leaders(
+        &self,
+        leaders: impl IntoIterator<Item = Pubkey>,
+    ) -> Vec<(Pubkey, SocketAddr)> {
+        leaders
+            .into_iter()
+            .unique()
+            .filter_map(|leader| {
+                let tpu = self.cluster_info.cluster_nodes.get(&leader)?.tpu?;
+                Some((leader, tpu))
+            })
+            .collect()
+    }
+
     pub fn new_for_tests() -> Self {
         Self {
             block_information_store: BlockInformationStore::new(BlockInformation {
@@ -82,6 +167,8 @@ impl DataCache {
                 last_valid_blockheight: 300,
                 slot: 0,
                 block_time: 0,
+                parent_slot: 0,
+                block_time_estimated: false,
             }),
             cluster_info: ClusterInfo::default(),
             identity_stakes: IdentityStakes::new(Pubkey::new_unique()),
@@ -92,6 +179,8 @@ impl DataCache {
             },
             epoch_data: EpochCache::new_for_tests(),
             leader_schedule: Arc::new(RwLock::new(CalculatedSchedule::default())),
+            latest_block_cache: LatestBlockCache::default(),
+            transaction_count: TransactionCountStore::default(),
         }
     }
 }
@@ -101,6 +190,7 @@ impl SlotCache {
         Self {
             current_slot: Arc::new(AtomicU64::new(slot)),
             estimated_slot: Arc::new(AtomicU64::new(slot)),
+            highest_processed_slot: Arc::new(AtomicU64::new(slot)),
         }
     }
     pub fn get_current_slot(&self) -> Slot {
@@ -112,6 +202,11 @@ impl SlotCache {
             .load(std::sync::atomic::Ordering::Relaxed)
     }
 
+    pub fn get_highest_processed_slot(&self) -> Slot {
+        self.highest_processed_slot
+            .load(std::sync::atomic::Ordering::Relaxed)
+    }
+
     pub fn update(&self, slot_notification: SlotNotification) {
         self.current_slot.store(
             slot_notification.processed_slot,
@@ -121,5 +216,167 @@ impl SlotCache {
             slot_notification.estimated_processed_slot,
             std::sync::atomic::Ordering::Relaxed,
         );
+        self.highest_processed_slot.fetch_max(
+            slot_notification.processed_slot,
+            std::sync::atomic::Ordering::Relaxed,
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::structures::leaderschedule::LeaderScheduleData;
+    use solana_transaction_status::TransactionConfirmationStatus;
+
+    fn status(confirmation_status: TransactionConfirmationStatus) -> TransactionStatus {
+        TransactionStatus {
+            slot: 1,
+            confirmations: None,
+            status: Ok(()),
+            err: None,
+            confirmation_status: Some(confirmation_status),
+        }
+    }
+
+    #[test]
+    fn test_highest_processed_slot() {
+        let slot_cache = SlotCache::new(100);
+        assert_eq!(slot_cache.get_highest_processed_slot(), 100);
+
+        slot_cache.update(SlotNotification {
+            processed_slot: 105,
+            estimated_processed_slot: 106,
+        });
+        assert_eq!(slot_cache.get_highest_processed_slot(), 105);
+
+        // a lower processed slot (fork) does not move the highest slot back
+        slot_cache.update(SlotNotification {
+            processed_slot: 104,
+            estimated_processed_slot: 106,
+        });
+        assert_eq!(slot_cache.get_current_slot(), 104);
+        assert_eq!(slot_cache.get_highest_processed_slot(), 105);
+    }
+
+    #[tokio::test]
+    async fn test_wait_for_transaction_commitment() {
+        let data_cache = DataCache::new_for_tests();
+        let signature = Signature::new_unique();
+
+        let updater = {
+            let data_cache = data_cache.clone();
+            tokio::spawn(async move {
+                tokio::time::sleep(Duration::from_millis(50)).await;
+                data_cache.txs.update_status(
+                    signature,
+                    status(TransactionConfirmationStatus::Processed),
+                    300,
+                );
+                tokio::time::sleep(Duration::from_millis(50)).await;
+                data_cache.txs.update_status(
+                    signature,
+                    status(TransactionConfirmationStatus::Confirmed),
+                    300,
+                );
+            })
+        };
+
+        let reached = data_cache
+            .wait_for_transaction_commitment(
+                &signature,
+                CommitmentConfig::confirmed(),
+                300,
+                Duration::from_millis(5),
+            )
+            .await
+            .expect("transaction is confirmed");
+        assert_eq!(
+            reached.confirmation_status,
+            Some(TransactionConfirmationStatus::Confirmed)
+        );
+        updater.await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_wait_for_dropped_transaction_times_out() {
+        let data_cache = DataCache::new_for_tests();
+
+        let block_producer = {
+            let data_cache = data_cache.clone();
+            tokio::spawn(async move {
+                tokio::time::sleep(Duration::from_millis(50)).await;
+                data_cache
+                    .block_information_store
+                    .add_block(BlockInformation {
+                        slot: 10,
+                        block_height: 10,
+                        last_valid_blockheight: 310,
+                        cleanup_slot: 1010,
+                        blockhash: Hash::new_unique(),
+                        commitment_config: CommitmentConfig::confirmed(),
+                        block_time: 0,
+                        parent_slot: 9,
+                        block_time_estimated: false,
+                    })
+                    .await;
+            })
+        };
+
+        let reached = data_cache
+            .wait_for_transaction_commitment(
+                &Signature::new_unique(),
+                CommitmentConfig::confirmed(),
+                5,
+                Duration::from_millis(5),
+            )
+            .await;
+        assert!(reached.is_none());
+        block_producer.await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_tpu_peers_from_schedule() {
+        let data_cache = DataCache::new_for_tests();
+        let with_tpu = Pubkey::new_unique();
+        let without_tpu = Pubkey::new_unique();
+        let unknown = Pubkey::new_unique();
+        let other_with_tpu = Pubkey::new_unique();
+        for (pubkey, tpu) in [
+            (with_tpu, Some("10.0.0.1:8003")),
+            (without_tpu, None),
+            (other_with_tpu, Some("10.0.0.2:8003")),
+        ] {
+            data_cache.cluster_info.insert_contact_info(
+                &serde_json::from_value(serde_json::json!({
+                    "pubkey": pubkey.to_string(),
+                    "tpu": tpu,
+                }))
+                .unwrap(),
+            );
+        }
+        // latest block of new_for_tests is at slot 0 of epoch 0
+        let schedule_by_slot = [with_tpu, without_tpu, unknown, other_with_tpu, with_tpu]
+            .iter()
+            .flat_map(|leader| std::iter::repeat(*leader).take(4))
+            .chain(std::iter::repeat(Pubkey::new_unique()).take(980))
+            .collect_vec();
+        data_cache.leader_schedule.write().await.current = Some(LeaderScheduleData {
+            schedule_by_node: Default::default(),
+            schedule_by_slot,
+            epoch: 0,
+        });
+
+        let tpu_peers = data_cache
+            .tpu_peers(CommitmentConfig::finalized(), 20)
+            .await
+            .unwrap();
+        assert_eq!(
+            tpu_peers,
+            vec![
+                (with_tpu, "10.0.0.1:8003".parse().unwrap()),
+                (other_with_tpu, "10.0.0.2:8003".parse().unwrap()),
+            ]
+        );
     }
 }
@@ -0,0 +1,130 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, RwLock};
+
+use solana_sdk::commitment_config::CommitmentConfig;
+
+use super::block_information_store::{BlockInformation, BlockInformationStore};
+
+/// Caches the latest block information per commitment until the next slot notification,
+/// repeated calls within a slot do not need to await the block information store.
+#[derive(Clone, Default)]
+pub struct LatestBlockCache {
+    // bumped on each slot notification, entries of older generations are stale
+    generation: Arc<AtomicU64>,
+    confirmed: Arc<RwLock<Option<(u64, BlockInformation)>>>,
+    finalized: Arc<RwLock<Option<(u64, BlockInformation)>>>,
+    misses: Arc<AtomicU64>,
+}
+
+impl LatestBlockCache {
+    // same mapping as the block information store: processed is served from confirmed
+    fn entry(
+        &self,
+        commitment_config: CommitmentConfig,
+    ) -> &RwLock<Option<(u64, BlockInformation)>> {
+        if commitment_config.is_finalized() {
+            &self.finalized
+        } else {
+            &self.confirmed
+        }
+    }
+
+    pub async fn get_or_load(
+        &self,
+        commitment_config: CommitmentConfig,
+        block_information_store: &BlockInformationStore,
+    ) -> BlockInformation {
+        let generation = self.generation.load(Ordering::Acquire);
+        let entry = self.entry(commitment_config);
+        if let Some((cached_generation, block_info)) = entry.read().unwrap().as_ref() {
+            if *cached_generation == generation {
+                return block_info.clone();
+            }
+        }
+
+        self.misses.fetch_add(1, Ordering::Relaxed);
+        let block_info = block_information_store
+            .get_latest_block_information(commitment_config)
+            .await;
+        // do not cache if the slot changed while loading
+        if self.generation.load(Ordering::Acquire) == generation {
+            *entry.write().unwrap() = Some((generation, block_info.clone()));
+        }
+        block_info
+    }
+
+    /// called on each slot notification
+    pub fn invalidate(&self) {
+        self.generation.fetch_add(1, Ordering::Release);
+    }
+
+    pub fn number_of_misses(&self) -> u64 {
+        self.misses.load(Ordering::Relaxed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use solana_sdk::hash::Hash;
+
+    use super::*;
+
+    fn block_information(slot: u64, commitment_config: CommitmentConfig) -> BlockInformation {
+        BlockInformation {
+            slot,
+            block_height: slot,
+            last_valid_blockheight: slot + 300,
+            cleanup_slot: slot + 1000,
+            blockhash: Hash::new_unique(),
+            commitment_config,
+            block_time: 0,
+            parent_slot: slot.saturating_sub(1),
+            block_time_estimated: false,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_rapid_calls_within_slot_hit_store_once() {
+        let store =
+            BlockInformationStore::new(block_information(10, CommitmentConfig::finalized()));
+        let cache = LatestBlockCache::default();
+
+        for _ in 0..100 {
+            let block_info = cache
+                .get_or_load(CommitmentConfig::confirmed(), &store)
+                .await;
+            assert_eq!(block_info.slot, 10);
+        }
+        assert_eq!(cache.number_of_misses(), 1);
+
+        // a new block is only visible after the next slot notification
+        store
+            .add_block(block_information(11, CommitmentConfig::confirmed()))
+            .await;
+        assert_eq!(
+            cache
+                .get_or_load(CommitmentConfig::confirmed(), &store)
+                .await
+                .slot,
+            10
+        );
+        cache.invalidate();
+        for _ in 0..100 {
+            let block_info = cache
+                .get_or_load(CommitmentConfig::confirmed(), &store)
+                .await;
+            assert_eq!(block_info.slot, 11);
+        }
+        assert_eq!(cache.number_of_misses(), 2);
+
+        // cached per commitment
+        assert_eq!(
+            cache
+                .get_or_load(CommitmentConfig::finalized(), &store)
+                .await
+                .slot,
+            10
+        );
+        assert_eq!(cache.number_of_misses(), 3);
+    }
+}
@@ -3,5 +3,7 @@
 pub mod block_information_store;
 pub mod cluster_info_store;
 pub mod data_cache;
+pub mod latest_block_cache;
 pub mod subscription_store;
+pub mod transaction_count_store;
 pub mod tx_store;
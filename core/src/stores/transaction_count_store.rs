@@ -0,0 +1,115 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+
+use solana_sdk::commitment_config::CommitmentConfig;
+use solana_sdk::slot_history::Slot;
+
+use crate::structures::produced_block::ProducedBlock;
+
+/// Cumulative number of transactions in the blocks ingested by this node.
+/// The counts are node-relative: they start at 0 when lite-rpc starts and do not
+/// match the cluster-absolute count a validator reports for getTransactionCount.
+#[derive(Clone, Default)]
+pub struct TransactionCountStore {
+    confirmed: Arc<Counter>,
+    finalized: Arc<Counter>,
+}
+
+#[derive(Default)]
+struct Counter {
+    count: AtomicU64,
+    // highest slot counted, blocks at or below it are not counted twice
+    last_counted_slot: Mutex<Option<Slot>>,
+}
+
+impl TransactionCountStore {
+    // same mapping as the block information store: processed is served from confirmed
+    fn counter(&self, commitment_config: CommitmentConfig) -> &Counter {
+        if commitment_config.is_finalized() {
+            &self.finalized
+        } else {
+            &self.confirmed
+        }
+    }
+
+    /// adds the transactions of a confirmed or finalized block, returns false if the block was not counted
+    pub fn add_block(&self, block: &ProducedBlock) -> bool {
+        if !block.commitment_config.is_at_least_confirmed() {
+            return false;
+        }
+        let counter = self.counter(block.commitment_config);
+        let mut last_counted_slot = counter.last_counted_slot.lock().unwrap();
+        if last_counted_slot.is_some_and(|last| block.slot <= last) {
+            return false;
+        }
+        *last_counted_slot = Some(block.slot);
+        counter
+            .count
+            .fetch_add(block.stats.transaction_count, Ordering::Relaxed);
+        true
+    }
+
+    pub fn get_transaction_count(&self, commitment_config: CommitmentConfig) -> u64 {
+        self.counter(commitment_config)
+            .count
+            .load(Ordering::Relaxed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::structures::produced_block::{BlockStats, ProducedBlockInner};
+    use solana_sdk::hash::Hash;
+
+    fn block(
+        slot: Slot,
+        transaction_count: u64,
+        commitment_config: CommitmentConfig,
+    ) -> ProducedBlock {
+        ProducedBlock::new(
+            ProducedBlockInner {
+                transactions: vec![],
+                leader_id: None,
+                blockhash: Hash::new_unique(),
+                block_height: slot,
+                slot,
+                parent_slot: slot - 1,
+                block_time: 0,
+                previous_blockhash: Hash::new_unique(),
+                rewards: None,
+                stats: BlockStats {
+                    transaction_count,
+                    ..BlockStats::default()
+                },
+            },
+            commitment_config,
+        )
+    }
+
+    #[test]
+    fn test_transaction_count_grows_with_ingested_blocks() {
+        let store = TransactionCountStore::default();
+        let confirmed = CommitmentConfig::confirmed();
+        assert_eq!(store.get_transaction_count(confirmed), 0);
+
+        assert!(store.add_block(&block(10, 5, confirmed)));
+        let first = store.get_transaction_count(confirmed);
+        assert!(store.add_block(&block(11, 3, confirmed)));
+        let second = store.get_transaction_count(confirmed);
+        assert_eq!((first, second), (5, 8));
+
+        // replayed block is not counted twice, processed blocks are ignored
+        assert!(!store.add_block(&block(11, 3, confirmed)));
+        assert!(!store.add_block(&block(12, 7, CommitmentConfig::processed())));
+        assert_eq!(store.get_transaction_count(confirmed), 8);
+        assert_eq!(
+            store.get_transaction_count(CommitmentConfig::processed()),
+            8
+        );
+        assert_eq!(
+            store.get_transaction_count(CommitmentConfig::finalized()),
+            0
+        );
+    }
+}
@@ -11,9 +11,16 @@ pub struct AccountData {
     pub pubkey: Pubkey,
     pub account: Arc<Account>,
     pub updated_slot: Slot,
+    /// orders the updates of an account within the same slot
+    pub write_version: u64,
 }
 
 impl AccountData {
+    /// updates are ordered by slot, then by write version within a slot
+    pub fn is_newer_than(&self, other: &AccountData) -> bool {
+        (self.updated_slot, self.write_version) > (other.updated_slot, other.write_version)
+    }
+
     pub fn allows(&self, filter: &RpcFilterType) -> bool {
         match filter {
             RpcFilterType::DataSize(size) => self.account.data.len() as u64 == *size,
@@ -31,6 +38,7 @@ impl PartialEq for AccountData {
         self.pubkey == other.pubkey
             && *self.account == *other.account
             && self.updated_slot == other.updated_slot
+            && self.write_version == other.write_version
     }
 }
 
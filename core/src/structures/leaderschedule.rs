@@ -6,7 +6,7 @@ use solana_sdk::pubkey::ParsePubkeyError;
 use solana_sdk::pubkey::Pubkey;
 use solana_sdk::slot_history::Slot;
 use solana_sdk::sysvar::epoch_schedule::EpochSchedule;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::str::FromStr;
 
 #[derive(Clone, Default)]
@@ -35,13 +35,83 @@ impl TryFrom<RpcGetVoteAccountsConfig> for GetVoteAccountsConfig {
     }
 }
 
+/// slots of the next epoch precomputed with the current one so getSlotLeaders requests at the epoch boundary are served by a slice
+pub const DEFAULT_LEADER_SCHEDULE_LOOKAHEAD_SLOTS: u64 =
+    solana_rpc_client_api::request::MAX_GET_SLOT_LEADERS as u64;
+
 #[derive(Clone, Default, Debug)]
 pub struct CalculatedSchedule {
     pub current: Option<LeaderScheduleData>,
     pub next: Option<LeaderScheduleData>,
+    /// number of slots of the next epoch in the lookahead, 0 disables it
+    pub lookahead_slots: u64,
+    pub lookahead: Option<SlotLeadersLookahead>,
+}
+
+/// slot leaders of the current epoch followed by the first slots of the next epoch
+#[derive(Clone, Default, Debug)]
+pub struct SlotLeadersLookahead {
+    pub first_slot: Slot,
+    pub leaders: Vec<Pubkey>,
 }
 
 impl CalculatedSchedule {
+    pub fn with_lookahead_slots(lookahead_slots: u64) -> Self {
+        Self {
+            lookahead_slots,
+            ..Default::default()
+        }
+    }
+
+    /// replaces the current and next schedules, e.g. after bootstrap, keeping the lookahead configuration
+    pub fn replace(&mut self, schedule: CalculatedSchedule, epoch_schedule: &EpochSchedule) {
+        self.current = schedule.current;
+        self.next = schedule.next;
+        self.update_lookahead(epoch_schedule);
+    }
+
+    /// the next epoch becomes the current one, the lookahead is rebuilt for the new epochs
+    pub fn rotate(&mut self, next: LeaderScheduleData, epoch_schedule: &EpochSchedule) {
+        self.current = self.next.take();
+        self.next = Some(next);
+        self.update_lookahead(epoch_schedule);
+    }
+
+    fn update_lookahead(&mut self, epoch_schedule: &EpochSchedule) {
+        self.lookahead = match (&self.current, &self.next) {
+            (Some(current), Some(next))
+                if self.lookahead_slots > 0 && next.epoch == current.epoch + 1 =>
+            {
+                Some(SlotLeadersLookahead {
+                    first_slot: epoch_schedule.get_first_slot_in_epoch(current.epoch),
+                    leaders: current
+                        .schedule_by_slot
+                        .iter()
+                        .chain(
+                            next.schedule_by_slot
+                                .iter()
+                                .take(self.lookahead_slots as usize),
+                        )
+                        .copied()
+                        .collect(),
+                })
+            }
+            _ => None,
+        };
+    }
+
+    /// none if the slots are not all in the lookahead
+    pub fn get_cached_slot_leaders(&self, start_slot: Slot, limit: usize) -> Option<Vec<Pubkey>> {
+        let lookahead = self.lookahead.as_ref()?;
+        let start = start_slot.checked_sub(lookahead.first_slot)? as usize;
+        lookahead
+            .leaders
+            .get(start..start.checked_add(limit)?)
+            .map(<[Pubkey]>::to_vec)
+    }
+
+    /// A slot of none resolves to the current epoch, i.e. the latest processed slot or the latest block at the given commitment.
+    /// None if the epoch of the slot is neither the current nor the next epoch.
     pub async fn get_leader_schedule_for_slot(
         &self,
         slot: Option<u64>,
@@ -54,10 +124,10 @@ impl CalculatedSchedule {
             self.next.clone().unwrap_or_default()
         );
 
-        let commitment = commitment.unwrap_or_default();
-        let slot = match slot {
-            Some(slot) => slot,
-            None => {
+        let slot = match (slot, commitment) {
+            (Some(slot), _) => slot,
+            (None, None) => data_cache.slot_cache.get_highest_processed_slot(),
+            (None, Some(commitment)) => {
                 let BlockInformation { slot, .. } = data_cache
                     .block_information_store
                     .get_latest_block_information(commitment)
@@ -89,31 +159,22 @@ impl CalculatedSchedule {
         pub const MAX_GET_SLOT_LEADERS: usize =
             solana_rpc_client_api::request::MAX_GET_SLOT_LEADERS;
 
-        let mut limit = limit as usize;
+        let limit = limit as usize;
         if limit > MAX_GET_SLOT_LEADERS {
             return Err(format!(
                 "Invalid Params: Invalid limit; max {MAX_GET_SLOT_LEADERS}"
             ));
         }
 
-        let (epoch, slot_index) = epock_schedule.get_epoch_and_slot_index(start_slot);
-        let mut slot_leaders = Vec::with_capacity(limit);
-
-        let mut extend_slot_from_epoch = |leader_schedule: &[Pubkey], slot_index: usize| {
-            let take = limit.saturating_sub(slot_leaders.len());
-            slot_leaders.extend(leader_schedule.iter().skip(slot_index).take(take));
-            limit -= slot_leaders.len();
-        };
+        if let Some(slot_leaders) = self.get_cached_slot_leaders(start_slot, limit) {
+            return Ok(slot_leaders);
+        }
 
-        // log::info!(
-        //     "get_slot_leaders  epoch:{epoch} current:{:?} next:{:?} ",
-        //     self.current.clone().unwrap_or_default(),
-        //     self.next.clone().unwrap_or_default()
-        // );
+        let (epoch, slot_index) = epock_schedule.get_epoch_and_slot_index(start_slot);
 
         //TODO manage more leader schedule data in storage.
         //Here  only search  on current and next epoch
-        let res = [
+        let epochs_from_start_slot = [
             (&self.current, slot_index as usize, epoch),
             (&self.next, slot_index as usize, epoch),
             (&self.next, 0, epoch + 1),
@@ -124,16 +185,61 @@ impl CalculatedSchedule {
                 (epoch_data.epoch == epoch).then_some((epoch_data, slot_index))
             })
         })
-        .map(|(epoch_data, slot_index)| {
-            extend_slot_from_epoch(&epoch_data.schedule_by_slot, slot_index);
-        })
-        .collect::<Vec<()>>();
-        match res.is_empty()  {
-            true => Err(format!(
+        .collect::<Vec<_>>();
+
+        // start slot before the first available schedule or after the last one
+        if epochs_from_start_slot.is_empty() {
+            return Err(format!(
                 "Invalid Params: Invalid slot range: leader schedule for epoch {epoch} is unavailable"
-            )),
-            false => Ok(slot_leaders),
+            ));
+        }
+
+        let mut slot_leaders = Vec::with_capacity(limit);
+        for (epoch_data, slot_index) in epochs_from_start_slot {
+            let take = limit.saturating_sub(slot_leaders.len());
+            slot_leaders.extend(
+                epoch_data
+                    .schedule_by_slot
+                    .iter()
+                    .skip(slot_index)
+                    .take(take),
+            );
+        }
+        Ok(slot_leaders)
+    }
+
+    /// (leader slots, blocks produced) per identity for the slots in `first_slot..=last_slot`
+    /// a leader slot which is not in `produced_slots` counts as skipped
+    pub fn get_block_production(
+        &self,
+        first_slot: Slot,
+        last_slot: Slot,
+        produced_slots: &HashSet<Slot>,
+        identity: Option<&Pubkey>,
+        epoch_schedule: &EpochSchedule,
+    ) -> Result<HashMap<String, (usize, usize)>, String> {
+        let mut by_identity: HashMap<String, (usize, usize)> = HashMap::new();
+        for slot in first_slot..=last_slot {
+            let (epoch, slot_index) = epoch_schedule.get_epoch_and_slot_index(slot);
+            let leader = [&self.current, &self.next]
+                .into_iter()
+                .flatten()
+                .find(|epoch_data| epoch_data.epoch == epoch)
+                .and_then(|epoch_data| epoch_data.schedule_by_slot.get(slot_index as usize))
+                .ok_or_else(|| {
+                    format!("Invalid Params: Invalid slot range: leader schedule for epoch {epoch} is unavailable")
+                })?;
+            if identity.is_some_and(|identity| identity != leader) {
+                continue;
+            }
+            let (leader_slots, blocks_produced) =
+                by_identity.entry(leader.to_string()).or_default();
+            *leader_slots += 1;
+            if produced_slots.contains(&slot) {
+                *blocks_produced += 1;
+            }
         }
+        Ok(by_identity)
     }
 }
 
@@ -143,3 +249,283 @@ pub struct LeaderScheduleData {
     pub schedule_by_slot: Vec<Pubkey>,
     pub epoch: u64,
 }
+
+/// keeps only the slot indices of `identity`, none if it is not a leader in the epoch
+pub fn filter_schedule_by_identity(
+    mut schedule_by_node: HashMap<String, Vec<usize>>,
+    identity: &Pubkey,
+) -> Option<HashMap<String, Vec<usize>>> {
+    let identity = identity.to_string();
+    schedule_by_node
+        .remove(&identity)
+        .map(|slot_indices| HashMap::from([(identity, slot_indices)]))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::structures::slot_notification::SlotNotification;
+
+    #[test]
+    fn test_filter_schedule_by_identity() {
+        let leader_a = Pubkey::new_unique();
+        let leader_b = Pubkey::new_unique();
+        // 4 slots per leader rotation
+        let schedule_by_slot = [leader_a, leader_b, leader_a]
+            .iter()
+            .flat_map(|leader| std::iter::repeat(*leader).take(4))
+            .collect::<Vec<_>>();
+        let mut schedule_by_node: HashMap<String, Vec<usize>> = HashMap::new();
+        for (slot_index, leader) in schedule_by_slot.iter().enumerate() {
+            schedule_by_node
+                .entry(leader.to_string())
+                .or_default()
+                .push(slot_index);
+        }
+
+        let filtered = filter_schedule_by_identity(schedule_by_node.clone(), &leader_a).unwrap();
+        assert_eq!(
+            filtered,
+            HashMap::from([(leader_a.to_string(), vec![0, 1, 2, 3, 8, 9, 10, 11])])
+        );
+
+        assert_eq!(
+            filter_schedule_by_identity(schedule_by_node, &Pubkey::new_unique()),
+            None
+        );
+    }
+
+    fn schedule(epoch: u64, slots_in_epoch: usize) -> LeaderScheduleData {
+        LeaderScheduleData {
+            schedule_by_node: HashMap::new(),
+            schedule_by_slot: (0..slots_in_epoch).map(|_| Pubkey::new_unique()).collect(),
+            epoch,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_leader_schedule_for_slot() {
+        // 1000 slots per epoch
+        let data_cache = DataCache::new_for_tests();
+        data_cache.slot_cache.update(SlotNotification {
+            processed_slot: 1500,
+            estimated_processed_slot: 1500,
+        });
+        let mut current = schedule(1, 1000);
+        current.schedule_by_node = HashMap::from([(Pubkey::new_unique().to_string(), vec![0])]);
+        let calculated = CalculatedSchedule {
+            current: Some(current.clone()),
+            next: Some(schedule(2, 1000)),
+            ..Default::default()
+        };
+
+        // the epoch of the latest processed slot
+        assert_eq!(
+            calculated
+                .get_leader_schedule_for_slot(None, None, &data_cache)
+                .await,
+            Some(current.schedule_by_node)
+        );
+        // the finalized block of the test cache is in epoch 0
+        assert_eq!(
+            calculated
+                .get_leader_schedule_for_slot(
+                    None,
+                    Some(CommitmentConfig::finalized()),
+                    &data_cache
+                )
+                .await,
+            None
+        );
+        assert!(calculated
+            .get_leader_schedule_for_slot(Some(2500), None, &data_cache)
+            .await
+            .is_some());
+        // too far in the future
+        assert_eq!(
+            calculated
+                .get_leader_schedule_for_slot(Some(5000), None, &data_cache)
+                .await,
+            None
+        );
+    }
+
+    #[tokio::test]
+    async fn test_slot_leaders_over_limit() {
+        let epoch_schedule = EpochSchedule::custom(32, 32, false);
+        let calculated = CalculatedSchedule {
+            current: Some(schedule(3, 32)),
+            next: Some(schedule(4, 32)),
+            ..Default::default()
+        };
+
+        let err = calculated
+            .get_slot_leaders(3 * 32, 5001, &epoch_schedule)
+            .await
+            .unwrap_err();
+        assert!(err.contains("Invalid limit; max 5000"), "{err}");
+
+        // spans into the next epoch
+        let leaders = calculated
+            .get_slot_leaders(3 * 32 + 30, 5, &epoch_schedule)
+            .await
+            .unwrap();
+        let current = calculated.current.as_ref().unwrap();
+        let next = calculated.next.as_ref().unwrap();
+        assert_eq!(
+            leaders,
+            [&current.schedule_by_slot[30..], &next.schedule_by_slot[..3]].concat()
+        );
+    }
+
+    #[test]
+    fn test_block_production_with_skipped_slot() {
+        let epoch_schedule = EpochSchedule::custom(32, 32, false);
+        let leader_a = Pubkey::new_unique();
+        let leader_b = Pubkey::new_unique();
+        let calculated = CalculatedSchedule {
+            current: Some(LeaderScheduleData {
+                schedule_by_node: HashMap::new(),
+                // 4 slots per leader rotation
+                schedule_by_slot: [leader_a, leader_b]
+                    .iter()
+                    .cycle()
+                    .flat_map(|leader| std::iter::repeat(*leader).take(4))
+                    .take(32)
+                    .collect(),
+                epoch: 3,
+            }),
+            next: None,
+            ..Default::default()
+        };
+        // leader_b skipped slot 3 * 32 + 5
+        let first_slot = 3 * 32;
+        let produced_slots = (first_slot..first_slot + 8)
+            .filter(|slot| *slot != first_slot + 5)
+            .collect::<HashSet<_>>();
+
+        let by_identity = calculated
+            .get_block_production(
+                first_slot,
+                first_slot + 7,
+                &produced_slots,
+                None,
+                &epoch_schedule,
+            )
+            .unwrap();
+        assert_eq!(
+            by_identity,
+            HashMap::from([
+                (leader_a.to_string(), (4, 4)),
+                (leader_b.to_string(), (4, 3))
+            ])
+        );
+
+        let by_identity = calculated
+            .get_block_production(
+                first_slot,
+                first_slot + 7,
+                &produced_slots,
+                Some(&leader_b),
+                &epoch_schedule,
+            )
+            .unwrap();
+        assert_eq!(by_identity, HashMap::from([(leader_b.to_string(), (4, 3))]));
+
+        // schedule of epoch 4 is unavailable
+        assert!(calculated
+            .get_block_production(first_slot, 4 * 32, &produced_slots, None, &epoch_schedule)
+            .is_err());
+    }
+
+    #[tokio::test]
+    async fn test_slot_leaders_out_of_range() {
+        let epoch_schedule = EpochSchedule::custom(32, 32, false);
+        let calculated = CalculatedSchedule {
+            current: Some(schedule(3, 32)),
+            next: Some(schedule(4, 32)),
+            ..Default::default()
+        };
+
+        // before the first slot of the current epoch
+        let err = calculated
+            .get_slot_leaders(3 * 32 - 1, 10, &epoch_schedule)
+            .await
+            .unwrap_err();
+        assert!(
+            err.contains("leader schedule for epoch 2 is unavailable"),
+            "{err}"
+        );
+
+        // after the next epoch
+        assert!(calculated
+            .get_slot_leaders(5 * 32, 10, &epoch_schedule)
+            .await
+            .is_err());
+    }
+
+    #[tokio::test]
+    async fn test_slot_leaders_served_from_lookahead() {
+        let epoch_schedule = EpochSchedule::custom(32, 32, false);
+        let mut calculated = CalculatedSchedule::with_lookahead_slots(8);
+        calculated.replace(
+            CalculatedSchedule {
+                current: Some(schedule(3, 32)),
+                next: Some(schedule(4, 32)),
+                ..Default::default()
+            },
+            &epoch_schedule,
+        );
+        let current = calculated.current.clone().unwrap();
+        let next = calculated.next.clone().unwrap();
+
+        // spans into the next epoch
+        let expected = [&current.schedule_by_slot[30..], &next.schedule_by_slot[..3]].concat();
+        assert_eq!(
+            calculated.get_cached_slot_leaders(3 * 32 + 30, 5),
+            Some(expected.clone())
+        );
+        assert_eq!(
+            calculated
+                .get_slot_leaders(3 * 32 + 30, 5, &epoch_schedule)
+                .await
+                .unwrap(),
+            expected
+        );
+
+        // beyond the lookahead the schedules are still used
+        assert_eq!(calculated.get_cached_slot_leaders(3 * 32 + 30, 20), None);
+        assert_eq!(
+            calculated
+                .get_slot_leaders(3 * 32 + 30, 20, &epoch_schedule)
+                .await
+                .unwrap(),
+            [
+                &current.schedule_by_slot[30..],
+                &next.schedule_by_slot[..18]
+            ]
+            .concat()
+        );
+
+        // the lookahead of epoch 3 is invalidated by the rotation
+        calculated.rotate(schedule(5, 32), &epoch_schedule);
+        let after = calculated.next.clone().unwrap();
+        assert_eq!(calculated.get_cached_slot_leaders(3 * 32 + 30, 5), None);
+        assert_eq!(
+            calculated.get_cached_slot_leaders(4 * 32 + 30, 5),
+            Some([&next.schedule_by_slot[30..], &after.schedule_by_slot[..3]].concat())
+        );
+
+        // disabled
+        let mut calculated = CalculatedSchedule::default();
+        calculated.replace(
+            CalculatedSchedule {
+                current: Some(schedule(3, 32)),
+                next: Some(schedule(4, 32)),
+                ..Default::default()
+            },
+            &epoch_schedule,
+        );
+        assert_eq!(calculated.get_cached_slot_leaders(3 * 32 + 30, 5), None);
+    }
+}
@@ -70,6 +70,30 @@ pub struct ProducedBlockInner {
     pub block_time: u64,
     pub previous_blockhash: Hash,
     pub rewards: Option<Vec<Reward>>,
+    pub stats: BlockStats,
+}
+
+/// aggregates over the transactions of a block, computed once when the block is mapped
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct BlockStats {
+    pub transaction_count: u64,
+    pub vote_transaction_count: u64,
+    pub non_vote_transaction_count: u64,
+    /// in lamports, 0 for blocks saved to postgres before the fees were persisted
+    pub total_fees: u64,
+}
+
+impl BlockStats {
+    pub fn new(transactions: &[TransactionInfo], total_fees: u64) -> Self {
+        let vote_transaction_count = transactions.iter().filter(|tx| tx.is_vote).count() as u64;
+        let transaction_count = transactions.len() as u64;
+        Self {
+            transaction_count,
+            vote_transaction_count,
+            non_vote_transaction_count: transaction_count - vote_transaction_count,
+            total_fees,
+        }
+    }
 }
 
 impl ProducedBlock {
@@ -8,7 +8,10 @@ use rand::{
     SeedableRng,
 };
 use solana_lite_rpc_cluster_endpoints::{
-    geyser_grpc_connector::{GrpcConnectionTimeouts, GrpcSourceConfig},
+    endpoint_stremers::EndpointSubscription,
+    geyser_grpc_connector::GrpcConnectionTimeouts,
+    grpc_multiplex_config::{GrpcMultiplexConfig, GrpcMultiplexSource},
+    grpc_source_mapper::BlockSourceKind,
     grpc_subscription::create_grpc_subscription,
     json_rpc_leaders_getter::JsonRpcLeaderGetter,
     json_rpc_subscription::create_json_rpc_polling_subscription,
@@ -19,7 +22,9 @@ use solana_lite_rpc_core::{
         block_information_store::{BlockInformation, BlockInformationStore},
         cluster_info_store::ClusterInfo,
         data_cache::{DataCache, SlotCache},
+        latest_block_cache::LatestBlockCache,
         subscription_store::SubscriptionStore,
+        transaction_count_store::TransactionCountStore,
         tx_store::TxStore,
     },
     structures::{
@@ -29,7 +34,7 @@ use solana_lite_rpc_core::{
     utils::wait_till_block_of_commitment_is_recieved,
 };
 use solana_lite_rpc_services::{
-    data_caching_service::DataCachingService,
+    data_caching_service::{DataCachingService, DEFAULT_SLOT_DRIFT_ALARM_THRESHOLD},
     quic_connection_utils::QuicConnectionParameters,
     tpu_utils::{
         tpu_connection_path::TpuConnectionPath,
@@ -53,6 +58,7 @@ use solana_sdk::{
     transaction::Transaction,
 };
 use tokio::sync::{Mutex, RwLock};
+use tokio_util::sync::CancellationToken;
 
 use crate::cli::Args;
 use solana_rpc_client::nonblocking::rpc_client::RpcClient;
@@ -259,22 +265,31 @@ async fn main() -> anyhow::Result<()> {
     // START ALL SERVICES REQUIRED BY LITE_RPC
     // setup endpoint, GRPC/RPC Polling
     println!("Setting up lite-rpc tpu service");
-    let (endpoints, _handles) = if let Some(grpc_addr) = args.grpc_url {
+    let EndpointSubscription {
+        streaming: endpoints,
+        tasks: _tasks,
+    } = if let Some(grpc_addr) = args.grpc_url {
         let timeouts = GrpcConnectionTimeouts {
             connect_timeout: Duration::from_secs(10),
             request_timeout: Duration::from_secs(10),
             subscribe_timeout: Duration::from_secs(10),
             receive_timeout: Duration::from_secs(10),
         };
+        let grpc_config = GrpcMultiplexConfig::new(
+            vec![GrpcMultiplexSource {
+                name: "grpc".to_string(),
+                addr: grpc_addr,
+                x_token: args.x_token.clone(),
+                kind: BlockSourceKind::Blocks,
+            }],
+            timeouts,
+            1024,
+        )?;
         create_grpc_subscription(
             rpc_client.clone(),
-            vec![GrpcSourceConfig::new(
-                grpc_addr,
-                args.x_token.clone(),
-                None,
-                timeouts,
-            )],
+            grpc_config,
             vec![],
+            CancellationToken::new(),
         )?
     } else {
         create_json_rpc_polling_subscription(rpc_client.clone(), 100)?
@@ -313,6 +328,8 @@ async fn main() -> anyhow::Result<()> {
         blockhash: finalized_block_information.blockhash,
         commitment_config: CommitmentConfig::finalized(),
         block_time: 0,
+        parent_slot: finalized_block_information.parent,
+        block_time_estimated: false,
     };
 
     let block_information_store = BlockInformationStore::new(finalized_block_information);
@@ -328,11 +345,14 @@ async fn main() -> anyhow::Result<()> {
         },
         epoch_data: EpochCache::new_for_tests(),
         leader_schedule: Arc::new(RwLock::new(CalculatedSchedule::default())),
+        latest_block_cache: LatestBlockCache::default(),
+        transaction_count: TransactionCountStore::default(),
     };
 
     let data_cache_service = DataCachingService {
         data_cache: data_cache.clone(),
         clean_duration: Duration::from_secs(120),
+        slot_drift_alarm_threshold: DEFAULT_SLOT_DRIFT_ALARM_THRESHOLD,
     };
 
     // start listning the cluster data and filling the cache
@@ -385,6 +405,7 @@ async fn main() -> anyhow::Result<()> {
         None,
         data_cache.block_information_store.clone(),
         10,
+        10,
         endpoints.slot_notifier,
     );
 
@@ -1,49 +1,93 @@
 use itertools::Itertools;
 use jsonrpsee::core::RpcResult;
-use prometheus::{opts, register_int_counter, IntCounter};
-use solana_account_decoder::UiAccount;
+use prometheus::{
+    histogram_opts, opts, register_histogram_vec, register_int_counter, register_int_counter_vec,
+    HistogramTimer, HistogramVec, IntCounter, IntCounterVec,
+};
+use solana_account_decoder::{parse_token::UiTokenAmount, UiAccount, MAX_BASE58_BYTES};
 use solana_lite_rpc_accounts::account_service::AccountService;
+use solana_lite_rpc_accounts::account_store_interface::AccountLoadingError;
+use solana_lite_rpc_accounts::program_accounts_page::RpcProgramAccountsPage;
 use solana_lite_rpc_core::encoding::{BASE58, BASE64};
 use solana_lite_rpc_prioritization_fees::account_prio_service::AccountPrioService;
 use solana_lite_rpc_prioritization_fees::prioritization_fee_calculation_method::PrioritizationFeeCalculationMethod;
 use solana_rpc_client::nonblocking::rpc_client::RpcClient;
-use solana_rpc_client_api::config::{RpcAccountInfoConfig, RpcSendTransactionConfig};
+use solana_rpc_client_api::config::{
+    RpcAccountInfoConfig, RpcBlockConfig, RpcEncodingConfigWrapper, RpcSendTransactionConfig,
+    RpcTokenAccountsFilter,
+};
+use solana_rpc_client_api::filter::RpcFilterType;
+use solana_rpc_client_api::request::{
+    RpcRequest, TokenAccountsFilter, MAX_GET_CONFIRMED_BLOCKS_RANGE,
+    MAX_GET_PROGRAM_ACCOUNT_FILTERS,
+};
 use solana_rpc_client_api::response::{OptionalContext, RpcKeyedAccount};
 use solana_rpc_client_api::{
     config::{
-        RpcBlocksConfigWrapper, RpcContextConfig, RpcGetVoteAccountsConfig,
-        RpcLeaderScheduleConfig, RpcProgramAccountsConfig, RpcRequestAirdropConfig,
-        RpcSignatureStatusConfig, RpcSignaturesForAddressConfig,
+        RpcBlockProductionConfig, RpcBlocksConfigWrapper, RpcContextConfig, RpcEpochConfig,
+        RpcGetVoteAccountsConfig, RpcLeaderScheduleConfig, RpcProgramAccountsConfig,
+        RpcRequestAirdropConfig, RpcSignatureStatusConfig, RpcSignaturesForAddressConfig,
+        RpcSupplyConfig,
     },
     response::{
-        Response as RpcResponse, RpcBlockhash, RpcConfirmedTransactionStatusWithSignature,
-        RpcContactInfo, RpcPerfSample, RpcPrioritizationFee, RpcResponseContext, RpcVersionInfo,
-        RpcVoteAccountStatus,
+        Response as RpcResponse, RpcBlockProduction, RpcBlockProductionRange, RpcBlockhash,
+        RpcConfirmedTransactionStatusWithSignature, RpcContactInfo, RpcIdentity,
+        RpcInflationReward, RpcPerfSample, RpcPrioritizationFee, RpcResponseContext, RpcSupply,
+        RpcVersionInfo, RpcVoteAccountStatus,
     },
 };
+use solana_sdk::clock::MAX_PROCESSING_AGE;
 use solana_sdk::epoch_info::EpochInfo;
+use solana_sdk::epoch_schedule::EpochSchedule;
+use solana_sdk::hash::Hash;
 use solana_sdk::packet::PACKET_DATA_SIZE;
+use solana_sdk::rent::Rent;
 use solana_sdk::signature::Signature;
-use solana_sdk::{commitment_config::CommitmentConfig, pubkey::Pubkey, slot_history::Slot};
+use solana_sdk::system_instruction::MAX_PERMITTED_DATA_LENGTH;
+use solana_sdk::transaction::VersionedTransaction;
+use solana_sdk::{
+    commitment_config::{CommitmentConfig, CommitmentLevel},
+    pubkey::Pubkey,
+    slot_history::Slot,
+};
 use solana_transaction_status::{
-    TransactionBinaryEncoding, TransactionStatus, UiConfirmedBlock, UiTransactionEncoding,
+    TransactionBinaryEncoding, TransactionDetails, TransactionStatus, UiConfirmedBlock,
+    UiTransactionEncoding,
 };
 use std::collections::HashMap;
 use std::str::FromStr;
 use std::sync::Arc;
+use tracing::{field, info_span, Instrument, Span};
 
-use solana_lite_rpc_blockstore::history::History;
+use solana_lite_rpc_blockstore::history::{inflation_rewards, History};
 use solana_lite_rpc_core::solana_utils::hash_from_str;
 use solana_lite_rpc_core::stores::{
-    block_information_store::BlockInformation, data_cache::DataCache,
+    block_information_store::{estimate_slot_time, BlockInformation},
+    data_cache::DataCache,
 };
+use solana_lite_rpc_core::structures::leaderschedule::filter_schedule_by_identity;
+use solana_lite_rpc_core::structures::produced_block::ProducedBlock;
 use solana_lite_rpc_services::{
-    transaction_service::TransactionService, tx_sender::TXS_IN_CHANNEL,
+    transaction_service::{BlockhashError, TransactionService},
+    tx_sender::TXS_IN_CHANNEL,
 };
 
-use crate::rpc_errors::RpcErrors;
-use crate::{configs::IsBlockHashValidConfig, rpc::LiteRpcServer};
-use solana_lite_rpc_prioritization_fees::rpc_data::{AccountPrioFeesStats, PrioFeesStats};
+use crate::bundle::{submit_bundle, validate_bundle, BundleError, MAX_BUNDLE_SIZE};
+use crate::rpc_errors::{RpcErrors, SolanaRpcError};
+use crate::{
+    configs::{
+        AccountPrioFeesHistoryConfig, BlockTimeConfig, ClusterNodesConfig, IsBlockHashValidConfig,
+        RpcBlockTime, RpcCapabilities, SendTransactionConfig,
+    },
+    rent::RentCache,
+    rpc::LiteRpcServer,
+    stake_minimum_delegation::StakeMinimumDelegation,
+    supply::SupplyCache,
+    upstream::{Upstream, UpstreamConfig, UpstreamError},
+};
+use solana_lite_rpc_prioritization_fees::rpc_data::{
+    AccountPrioFeesWithHistory, LatestPrioFees, PrioFeeAtPercentile, PrioFeesStats,
+};
 use solana_lite_rpc_prioritization_fees::PrioFeesService;
 
 lazy_static::lazy_static! {
@@ -59,18 +103,66 @@ lazy_static::lazy_static! {
     register_int_counter!(opts!("literpc_rpc_get_version", "RPC call to version")).unwrap();
     static ref RPC_REQUEST_AIRDROP: IntCounter =
     register_int_counter!(opts!("literpc_rpc_airdrop", "RPC call to request airdrop")).unwrap();
+    static ref RPC_GET_HEALTH: IntCounter =
+    register_int_counter!(opts!("literpc_rpc_get_health", "RPC call to get health")).unwrap();
+    static ref RPC_GET_IDENTITY: IntCounter =
+    register_int_counter!(opts!("literpc_rpc_get_identity", "RPC call to get identity")).unwrap();
+    static ref RPC_METHOD_CALLS: IntCounterVec =
+    register_int_counter_vec!(opts!("literpc_rpc_method_calls", "RPC calls by method"), &["method"]).unwrap();
+    static ref RPC_METHOD_LATENCY: HistogramVec =
+    register_histogram_vec!(histogram_opts!("literpc_rpc_method_latency", "RPC call latency in seconds by method"), &["method"]).unwrap();
+    static ref RPC_GET_GENESIS_HASH: IntCounter =
+    register_int_counter!(opts!("literpc_rpc_get_genesis_hash", "RPC call to get genesis hash")).unwrap();
 }
 
 /// A bridge between clients and tpu
 #[allow(dead_code)]
 pub struct LiteBridge {
     rpc_client: Arc<RpcClient>,
+    // rate limits the requests proxied to rpc_client
+    upstream: Upstream,
     data_cache: DataCache,
     transaction_service: TransactionService,
     history: History,
     prio_fees_service: PrioFeesService,
     account_priofees_service: AccountPrioService,
     accounts_service: Option<AccountService>,
+    // max distance between processed slot and estimated cluster slot to be healthy
+    health_check_slot_distance: u64,
+    // identity used for forwarding transactions, lite-rpc has no vote identity
+    identity: Pubkey,
+    // resolved once at startup
+    genesis_hash: Option<Hash>,
+    // used when a request does not specify a commitment
+    default_commitment: CommitmentConfig,
+    stake_minimum_delegation: StakeMinimumDelegation,
+    rent: RentCache,
+    // getClusterNodes leaves out nodes not refreshed within the ttl
+    cluster_nodes_ttl: Option<std::time::Duration>,
+    supply_cache: SupplyCache,
+    // sendBundle is only served with a relay, the tpu cannot land a bundle atomically
+    bundle_relay: Option<Arc<RpcClient>>,
+}
+
+/// Settings of the rpc methods served by the bridge
+pub struct LiteBridgeConfig {
+    pub history: History,
+    // max distance between processed slot and estimated cluster slot to be healthy
+    pub health_check_slot_distance: u64,
+    // identity used for forwarding transactions, lite-rpc has no vote identity
+    pub identity: Pubkey,
+    // resolved once at startup
+    pub genesis_hash: Option<Hash>,
+    // used when a request does not specify a commitment
+    pub default_commitment: CommitmentConfig,
+    pub upstream: UpstreamConfig,
+    pub upstream_passthrough_methods: Vec<String>,
+    pub stake_minimum_delegation: Option<u64>,
+    pub rent_lamports_per_byte_year: Option<u64>,
+    pub cluster_nodes_ttl: Option<std::time::Duration>,
+    pub supply_cache_interval: std::time::Duration,
+    // jito style block engine receiving the bundles of sendBundle
+    pub bundle_relay_url: Option<String>,
 }
 
 impl LiteBridge {
@@ -78,86 +170,318 @@ impl LiteBridge {
         rpc_client: Arc<RpcClient>,
         data_cache: DataCache,
         transaction_service: TransactionService,
-        history: History,
         prio_fees_service: PrioFeesService,
         account_priofees_service: AccountPrioService,
         accounts_service: Option<AccountService>,
+        config: LiteBridgeConfig,
     ) -> Self {
         Self {
             rpc_client,
+            upstream: Upstream::new(config.upstream)
+                .with_passthrough_methods(&config.upstream_passthrough_methods),
             data_cache,
             transaction_service,
-            history,
+            history: config.history,
             prio_fees_service,
             account_priofees_service,
             accounts_service,
+            health_check_slot_distance: config.health_check_slot_distance,
+            identity: config.identity,
+            genesis_hash: config.genesis_hash,
+            default_commitment: config.default_commitment,
+            stake_minimum_delegation: StakeMinimumDelegation::new(config.stake_minimum_delegation),
+            rent: RentCache::new(config.rent_lamports_per_byte_year),
+            cluster_nodes_ttl: config.cluster_nodes_ttl,
+            supply_cache: SupplyCache::new(config.supply_cache_interval),
+            bundle_relay: config
+                .bundle_relay_url
+                .map(|url| Arc::new(RpcClient::new(url))),
+        }
+    }
+
+    /// runs within the span of the transaction
+    async fn send_and_wait_for_commitment(
+        &self,
+        wire_output: Vec<u8>,
+        transaction: Option<VersionedTransaction>,
+        max_retries: Option<u16>,
+        wait_for_commitment: Option<CommitmentLevel>,
+    ) -> RpcResult<String> {
+        // the blockhash is looked up before sending, the transaction service rejects unknown blockhashes
+        let last_valid_block_height = transaction
+            .and_then(|tx| {
+                self.data_cache
+                    .block_information_store
+                    .get_block_info(tx.message.recent_blockhash())
+            })
+            .map(|block_info| block_info.last_valid_blockheight);
+        let sig = match self
+            .transaction_service
+            .send_wire_transaction(wire_output, max_retries)
+            .await
+        {
+            Ok(sig) => {
+                TXS_IN_CHANNEL.inc();
+                sig
+            }
+            Err(err) => {
+                log::debug!("transaction not sent: {err}");
+                // a transaction with an unknown or expired blockhash is accepted by the cluster but never lands
+                if err.downcast_ref::<BlockhashError>().is_some() {
+                    return Err(SolanaRpcError::BlockhashNotFound.into());
+                }
+                return Err(jsonrpsee::types::error::ErrorCode::InternalError.into());
+            }
+        };
+
+        let (Some(commitment), Some(last_valid_block_height)) =
+            (wait_for_commitment, last_valid_block_height)
+        else {
+            return Ok(sig);
+        };
+        let signature = Signature::from_str(&sig)
+            .map_err(|_| jsonrpsee::types::error::ErrorCode::InternalError)?;
+        log::debug!("waiting for {commitment:?} commitment");
+        match self
+            .data_cache
+            .wait_for_transaction_commitment(
+                &signature,
+                CommitmentConfig { commitment },
+                last_valid_block_height,
+                WAIT_FOR_COMMITMENT_POLL_INTERVAL,
+            )
+            .await
+        {
+            Some(_) => {
+                log::debug!("transaction reached {commitment:?} commitment");
+                Ok(sig)
+            }
+            None => Err(jsonrpsee::types::error::ErrorObject::owned(
+                jsonrpsee::types::error::ErrorCode::InternalError.code(),
+                format!("transaction {sig} expired before reaching {commitment:?} commitment"),
+                None::<()>,
+            )),
+        }
+    }
+
+    fn commitment_or_default(&self, commitment: Option<CommitmentConfig>) -> CommitmentConfig {
+        commitment.unwrap_or(self.default_commitment)
+    }
+
+    /// blocks older than the block information store are only known to the upstream rpc
+    async fn older_blocks_from_upstream(
+        &self,
+        method: &'static str,
+        start_slot: Slot,
+        params: serde_json::Value,
+    ) -> RpcResult<Vec<Slot>> {
+        if !self.upstream.is_passthrough(method) {
+            return Err(SolanaRpcError::BlockNotAvailable { slot: start_slot }.into());
         }
+        self.upstream
+            .passthrough(&self.rpc_client, method, params)
+            .await
+    }
+
+    fn account_config_or_default(
+        &self,
+        config: Option<RpcAccountInfoConfig>,
+    ) -> Option<RpcAccountInfoConfig> {
+        let mut config = config.unwrap_or_default();
+        config.commitment = Some(self.commitment_or_default(config.commitment));
+        Some(config)
+    }
+
+    fn program_accounts_config_or_default(
+        &self,
+        config: Option<RpcProgramAccountsConfig>,
+    ) -> Option<RpcProgramAccountsConfig> {
+        let mut config = config.unwrap_or_default();
+        config.account_config = self
+            .account_config_or_default(Some(config.account_config))
+            .unwrap_or_default();
+        Some(config)
     }
 }
 
 #[jsonrpsee::core::async_trait]
 impl LiteRpcServer for LiteBridge {
-    async fn get_block(&self, _slot: u64) -> RpcResult<Option<UiConfirmedBlock>> {
-        // let block = self.blockstore.block_storage.query_block(slot).await;
-        // if block.is_ok() {
-        //     // TO DO Convert to UIConfirmed Block
-        //     Err(jsonrpsee::core::Error::HttpNotImplemented)
-        // } else {
-        //     Ok(None)
-        // }
+    async fn get_block(
+        &self,
+        slot: u64,
+        config: Option<RpcEncodingConfigWrapper<RpcBlockConfig>>,
+    ) -> RpcResult<Option<UiConfirmedBlock>> {
+        let _timer = record_rpc_call("getBlock");
 
-        // TODO get_block might deserve different implementation based on whether we serve from "blockstore module" vs. from "send tx module"
+        let config = config.map(|config| config.convert_to_current());
+        let block = self.history.get_block(slot).await.map_err(|err| {
+            log::error!("failed to query block {slot} from history: {err:?}");
+            jsonrpsee::types::error::ErrorCode::InternalError
+        })?;
+        if let Some(ui_block) = block
+            .as_ref()
+            .and_then(|block| ui_block_from_history(block, &config.unwrap_or_default()))
+        {
+            return Ok(Some(ui_block));
+        }
 
-        // under progress
-        Err(jsonrpsee::types::error::ErrorCode::MethodNotFound.into())
+        let params = match config {
+            Some(config) => serde_json::json!([slot, config]),
+            None => serde_json::json!([slot]),
+        };
+        self.upstream
+            .passthrough(&self.rpc_client, "getBlock", params)
+            .await
     }
 
     async fn get_blocks(
         &self,
-        _start_slot: Slot,
-        _config: Option<RpcBlocksConfigWrapper>,
-        _commitment: Option<CommitmentConfig>,
+        start_slot: Slot,
+        config: Option<RpcBlocksConfigWrapper>,
+        commitment: Option<CommitmentConfig>,
     ) -> RpcResult<Vec<Slot>> {
-        // under progress
-        Err(jsonrpsee::types::error::ErrorCode::MethodNotFound.into())
+        let _timer = record_rpc_call("getBlocks");
+
+        let (end_slot, context_config) = config.map(|config| config.unzip()).unwrap_or_default();
+        let commitment_config = self.commitment_or_default(
+            commitment.or(context_config.and_then(|config| config.commitment)),
+        );
+        match blocks_in_range(&self.data_cache, start_slot, end_slot, commitment_config).await? {
+            Some(slots) => Ok(slots),
+            None => {
+                self.older_blocks_from_upstream(
+                    "getBlocks",
+                    start_slot,
+                    serde_json::json!([start_slot, end_slot, commitment_config]),
+                )
+                .await
+            }
+        }
+    }
+
+    async fn get_blocks_with_limit(
+        &self,
+        start_slot: Slot,
+        limit: usize,
+        config: Option<RpcContextConfig>,
+    ) -> RpcResult<Vec<Slot>> {
+        let _timer = record_rpc_call("getBlocksWithLimit");
+
+        let commitment_config = self.commitment_or_default(config.and_then(|x| x.commitment));
+        match blocks_with_limit(&self.data_cache, start_slot, limit, commitment_config).await? {
+            Some(slots) => Ok(slots),
+            None => {
+                self.older_blocks_from_upstream(
+                    "getBlocksWithLimit",
+                    start_slot,
+                    serde_json::json!([start_slot, limit, commitment_config]),
+                )
+                .await
+            }
+        }
     }
 
     async fn get_signatures_for_address(
         &self,
-        _address: String,
-        _config: Option<RpcSignaturesForAddressConfig>,
+        address: String,
+        config: Option<RpcSignaturesForAddressConfig>,
     ) -> RpcResult<Vec<RpcConfirmedTransactionStatusWithSignature>> {
+        let _timer = record_rpc_call("getSignaturesForAddress");
+
         // under progress
-        Err(jsonrpsee::types::error::ErrorCode::MethodNotFound.into())
+        self.upstream
+            .passthrough(
+                &self.rpc_client,
+                "getSignaturesForAddress",
+                serde_json::json!([address, config]),
+            )
+            .await
     }
 
-    async fn get_cluster_nodes(&self) -> RpcResult<Vec<RpcContactInfo>> {
+    async fn get_cluster_nodes(
+        &self,
+        config: Option<ClusterNodesConfig>,
+    ) -> RpcResult<Vec<RpcContactInfo>> {
+        let _timer = record_rpc_call("getClusterNodes");
+
+        let only_with_tpu = config
+            .map(|config| config.only_with_tpu)
+            .unwrap_or_default();
         Ok(self
             .data_cache
             .cluster_info
-            .cluster_nodes
-            .iter()
-            .map(|v| v.value().as_ref().clone())
-            .collect_vec())
+            .get_cluster_nodes(only_with_tpu, self.cluster_nodes_ttl))
     }
 
     async fn get_slot(&self, config: Option<RpcContextConfig>) -> RpcResult<Slot> {
-        let commitment_config = config
-            .map(|config| config.commitment.unwrap_or_default())
-            .unwrap_or_default();
+        let _timer = record_rpc_call("getSlot");
 
-        let BlockInformation { slot, .. } = self
+        Ok(latest_slot(&self.data_cache, config, self.default_commitment).await)
+    }
+
+    async fn get_max_retransmit_slot(&self) -> RpcResult<Slot> {
+        let _timer = record_rpc_call("getMaxRetransmitSlot");
+
+        Ok(self.data_cache.slot_cache.get_highest_processed_slot())
+    }
+
+    async fn get_max_shred_insert_slot(&self) -> RpcResult<Slot> {
+        let _timer = record_rpc_call("getMaxShredInsertSlot");
+
+        Ok(self.data_cache.slot_cache.get_highest_processed_slot())
+    }
+
+    async fn get_health(&self) -> RpcResult<String> {
+        let _timer = record_rpc_call("getHealth");
+        RPC_GET_HEALTH.inc();
+
+        let BlockInformation {
+            slot: processed_slot,
+            ..
+        } = self
             .data_cache
             .block_information_store
-            .get_latest_block_information(commitment_config)
+            .get_latest_block_information(CommitmentConfig::processed())
             .await;
-        Ok(slot)
+        let estimated_slot = self.data_cache.slot_cache.get_estimated_slot();
+
+        match check_health(
+            processed_slot,
+            estimated_slot,
+            self.health_check_slot_distance,
+        ) {
+            Ok(()) => Ok("ok".to_string()),
+            Err(num_slots_behind) => Err(SolanaRpcError::NodeUnhealthy { num_slots_behind }.into()),
+        }
+    }
+
+    fn get_identity(&self) -> RpcResult<RpcIdentity> {
+        let _timer = record_rpc_call("getIdentity");
+        RPC_GET_IDENTITY.inc();
+
+        Ok(RpcIdentity {
+            identity: self.identity.to_string(),
+        })
+    }
+
+    fn get_genesis_hash(&self) -> RpcResult<String> {
+        let _timer = record_rpc_call("getGenesisHash");
+        RPC_GET_GENESIS_HASH.inc();
+
+        match &self.genesis_hash {
+            Some(genesis_hash) => Ok(genesis_hash.to_string()),
+            None => Err(jsonrpsee::types::error::ErrorObject::owned(
+                jsonrpsee::types::error::ErrorCode::InternalError.code(),
+                "genesis hash is not available",
+                None::<()>,
+            )),
+        }
     }
 
     async fn get_block_height(&self, config: Option<RpcContextConfig>) -> RpcResult<u64> {
-        let commitment_config = config.map_or(CommitmentConfig::finalized(), |x| {
-            x.commitment.unwrap_or_default()
-        });
+        let _timer = record_rpc_call("getBlockHeight");
+
+        let commitment_config = self.commitment_or_default(config.and_then(|x| x.commitment));
         let block_info = self
             .data_cache
             .block_information_store
@@ -166,31 +490,235 @@ impl LiteRpcServer for LiteBridge {
         Ok(block_info.block_height)
     }
 
-    async fn get_block_time(&self, slot: u64) -> RpcResult<u64> {
+    fn get_transaction_count(&self, config: Option<RpcContextConfig>) -> RpcResult<u64> {
+        let _timer = record_rpc_call("getTransactionCount");
+
+        let commitment_config = self.commitment_or_default(config.and_then(|x| x.commitment));
+        Ok(self
+            .data_cache
+            .transaction_count
+            .get_transaction_count(commitment_config))
+    }
+
+    async fn get_block_time(
+        &self,
+        slot: u64,
+        config: Option<BlockTimeConfig>,
+    ) -> RpcResult<RpcBlockTime> {
+        let _timer = record_rpc_call("getBlockTime");
+
+        let estimate_future_slots = config.unwrap_or_default().estimate_future_slots;
+        let actual = |block_time, estimated| {
+            if estimate_future_slots {
+                RpcBlockTime::WithEstimate {
+                    block_time,
+                    estimated,
+                }
+            } else {
+                RpcBlockTime::Actual(block_time)
+            }
+        };
+
         let block_info = self
             .data_cache
             .block_information_store
             .get_block_info_by_slot(slot);
-        match block_info {
-            Some(info) => Ok(info.block_time),
-            None => Err(jsonrpsee::types::error::ErrorCode::InvalidParams.into()),
+        if let Some(info) = block_info {
+            return Ok(actual(info.block_time, info.block_time_estimated));
+        }
+
+        if estimate_future_slots {
+            let latest = self
+                .data_cache
+                .block_information_store
+                .get_latest_block_information(CommitmentConfig::confirmed())
+                .await;
+            if slot > latest.slot {
+                return Ok(RpcBlockTime::WithEstimate {
+                    block_time: estimate_future_block_time(&latest, slot)?,
+                    estimated: true,
+                });
+            }
+        }
+
+        // older slots are served from the retained blocks or the persistent block store
+        match self.history.get_block_time(slot).await {
+            Some(block_time) => Ok(actual(block_time, false)),
+            None => Err(SolanaRpcError::BlockNotAvailable { slot }.into()),
+        }
+    }
+
+    async fn get_inflation_reward(
+        &self,
+        address_strs: Vec<String>,
+        config: Option<RpcEpochConfig>,
+    ) -> RpcResult<Vec<Option<RpcInflationReward>>> {
+        let _timer = record_rpc_call("getInflationReward");
+
+        if address_strs.len() > MAX_INFLATION_REWARD_ADDRESSES {
+            return Err(jsonrpsee::types::error::ErrorObject::owned(
+                jsonrpsee::types::error::ErrorCode::InvalidParams.code(),
+                format!("Too many inputs provided; max {MAX_INFLATION_REWARD_ADDRESSES}"),
+                None::<()>,
+            ));
+        }
+        let addresses = address_strs
+            .iter()
+            .map(|address| Pubkey::from_str(address))
+            .collect_vec();
+        if let Some(position) = addresses.iter().position(|res| res.is_err()) {
+            return Err(SolanaRpcError::InvalidPubkey(address_strs[position].clone()).into());
+        };
+        let addresses = addresses
+            .into_iter()
+            .map(|pubkey| pubkey.unwrap())
+            .collect_vec();
+
+        let commitment_config =
+            self.commitment_or_default(config.as_ref().and_then(|x| x.commitment));
+        let current_epoch = self
+            .data_cache
+            .get_current_epoch(commitment_config)
+            .await
+            .epoch;
+        // rewards of the previous epoch by default, the current epoch is not rewarded yet
+        let epoch = config
+            .and_then(|x| x.epoch)
+            .unwrap_or(current_epoch.saturating_sub(1));
+        if epoch >= current_epoch {
+            return Err(jsonrpsee::types::error::ErrorObject::owned(
+                jsonrpsee::types::error::ErrorCode::InvalidParams.code(),
+                format!("Invalid param: epoch {epoch} has not been rewarded yet"),
+                None::<()>,
+            ));
+        }
+
+        let rewards_block = self
+            .history
+            .get_epoch_rewards_block(epoch)
+            .await
+            .map_err(|err| {
+                log::error!("failed to query rewards block of epoch {epoch}: {err:?}");
+                jsonrpsee::types::error::ErrorCode::InternalError
+            })?;
+        match rewards_block {
+            Some(rewards_block) => Ok(inflation_rewards(&addresses, epoch, &rewards_block)),
+            // epoch is not within the retained history
+            None => Err(SolanaRpcError::BlockNotAvailable {
+                slot: self
+                    .data_cache
+                    .epoch_data
+                    .get_first_slot_in_epoch(epoch + 1),
+            }
+            .into()),
         }
     }
 
+    async fn get_stake_minimum_delegation(
+        &self,
+        config: Option<RpcContextConfig>,
+    ) -> RpcResult<RpcResponse<u64>> {
+        let _timer = record_rpc_call("getStakeMinimumDelegation");
+
+        Ok(stake_minimum_delegation_response(
+            &self.data_cache,
+            &self.stake_minimum_delegation,
+            config,
+            self.default_commitment,
+            || async {
+                self.upstream
+                    .call("getStakeMinimumDelegation", || {
+                        self.rpc_client.get_stake_minimum_delegation()
+                    })
+                    .await
+            },
+        )
+        .await?)
+    }
+
+    async fn get_minimum_balance_for_rent_exemption(
+        &self,
+        data_len: usize,
+        _commitment: Option<CommitmentConfig>,
+    ) -> RpcResult<u64> {
+        let _timer = record_rpc_call("getMinimumBalanceForRentExemption");
+
+        // the rent parameters do not change with the commitment
+        let rent = self
+            .rent
+            .get_or_fetch(|| async {
+                let account = self
+                    .upstream
+                    .call("getAccountInfo", || {
+                        self.rpc_client.get_account(&solana_sdk::sysvar::rent::id())
+                    })
+                    .await?;
+                solana_sdk::account::from_account::<Rent, _>(&account).ok_or_else(|| {
+                    jsonrpsee::types::error::ErrorObject::owned(
+                        jsonrpsee::types::error::ErrorCode::InternalError.code(),
+                        "invalid rent sysvar",
+                        None::<()>,
+                    )
+                })
+            })
+            .await?;
+
+        minimum_balance_for_rent_exemption(&rent, data_len)
+    }
+
+    async fn get_supply(
+        &self,
+        config: Option<RpcSupplyConfig>,
+    ) -> RpcResult<RpcResponse<RpcSupply>> {
+        let _timer = record_rpc_call("getSupply");
+
+        let config = config.unwrap_or_default();
+        let commitment = self.commitment_or_default(config.commitment);
+        let exclude_non_circulating_accounts_list = config.exclude_non_circulating_accounts_list;
+        let upstream_config = RpcSupplyConfig {
+            commitment: Some(commitment),
+            exclude_non_circulating_accounts_list,
+        };
+        Ok(self
+            .supply_cache
+            .get_or_fetch(
+                commitment.commitment,
+                exclude_non_circulating_accounts_list,
+                || async {
+                    self.upstream
+                        .call("getSupply", || {
+                            self.rpc_client.send::<RpcResponse<RpcSupply>>(
+                                RpcRequest::GetSupply,
+                                serde_json::json!([upstream_config]),
+                            )
+                        })
+                        .await
+                },
+            )
+            .await?)
+    }
+
     async fn get_first_available_block(&self) -> RpcResult<u64> {
+        let _timer = record_rpc_call("getFirstAvailableBlock");
+
         // under progress
-        Err(jsonrpsee::types::error::ErrorCode::MethodNotFound.into())
+        self.upstream
+            .passthrough(
+                &self.rpc_client,
+                "getFirstAvailableBlock",
+                serde_json::json!([]),
+            )
+            .await
     }
 
     async fn get_latest_blockhash(
         &self,
         config: Option<RpcContextConfig>,
     ) -> RpcResult<RpcResponse<RpcBlockhash>> {
+        let _timer = record_rpc_call("getLatestBlockhash");
         RPC_GET_LATEST_BLOCKHASH.inc();
 
-        let commitment_config = config
-            .map(|config| config.commitment.unwrap_or(CommitmentConfig::confirmed()))
-            .unwrap_or_default();
+        let commitment_config = self.commitment_or_default(config.and_then(|x| x.commitment));
 
         let BlockInformation {
             slot,
@@ -199,7 +727,6 @@ impl LiteRpcServer for LiteBridge {
             ..
         } = self
             .data_cache
-            .block_information_store
             .get_latest_block_information(commitment_config)
             .await;
 
@@ -222,18 +749,23 @@ impl LiteRpcServer for LiteBridge {
         blockhash: String,
         config: Option<IsBlockHashValidConfig>,
     ) -> RpcResult<RpcResponse<bool>> {
+        let _timer = record_rpc_call("isBlockhashValid");
         RPC_IS_BLOCKHASH_VALID.inc();
 
-        let commitment = config.unwrap_or_default().commitment.unwrap_or_default();
-        let commitment = CommitmentConfig { commitment };
+        let commitment = self.commitment_or_default(
+            config
+                .and_then(|x| x.commitment)
+                .map(|commitment| CommitmentConfig { commitment }),
+        );
 
+        let Ok(blockhash) = hash_from_str(&blockhash) else {
+            return Err(jsonrpsee::types::error::ErrorCode::InvalidParams.into());
+        };
+        // a just-issued blockhash is always valid, see BlockInformationStore::is_blockhash_valid
         let (is_valid, slot) = self
             .data_cache
             .block_information_store
-            .is_blockhash_valid(
-                &hash_from_str(&blockhash).expect("valid blockhash"),
-                commitment,
-            )
+            .is_blockhash_valid(&blockhash, commitment)
             .await;
 
         Ok(RpcResponse {
@@ -246,9 +778,9 @@ impl LiteRpcServer for LiteBridge {
     }
 
     async fn get_epoch_info(&self, config: Option<RpcContextConfig>) -> RpcResult<EpochInfo> {
-        let commitment_config = config
-            .map(|config| config.commitment.unwrap_or_default())
-            .unwrap_or_default();
+        let _timer = record_rpc_call("getEpochInfo");
+
+        let commitment_config = self.commitment_or_default(config.and_then(|x| x.commitment));
         let block_info = self
             .data_cache
             .block_information_store
@@ -264,37 +796,64 @@ impl LiteRpcServer for LiteBridge {
         Ok(epoch_info)
     }
 
+    async fn get_epoch_schedule(&self) -> RpcResult<EpochSchedule> {
+        let _timer = record_rpc_call("getEpochSchedule");
+
+        // the epoch schedule does not change after genesis
+        Ok(self.data_cache.epoch_data.get_epoch_schedule().clone())
+    }
+
     async fn get_recent_performance_samples(
         &self,
         limit: Option<usize>,
     ) -> RpcResult<Vec<RpcPerfSample>> {
+        let _timer = record_rpc_call("getRecentPerformanceSamples");
+
         // TODO: implement our own perofmrance samples from blockstream and slot stream
         // For now just use normal rpc to get the data
-        self.rpc_client
-            .get_recent_performance_samples(limit)
-            .await
-            .map_err(|_| jsonrpsee::types::error::ErrorCode::InternalError.into())
+        Ok(self
+            .upstream
+            .call("getRecentPerformanceSamples", || {
+                self.rpc_client.get_recent_performance_samples(limit)
+            })
+            .await?)
     }
 
     async fn get_signature_statuses(
         &self,
         sigs: Vec<String>,
-        _config: Option<RpcSignatureStatusConfig>,
+        config: Option<RpcSignatureStatusConfig>,
     ) -> RpcResult<RpcResponse<Vec<Option<TransactionStatus>>>> {
+        let _timer = record_rpc_call("getSignatureStatuses");
         RPC_GET_SIGNATURE_STATUSES.inc();
 
-        let sig_statuses = sigs
-            .iter()
-            .map(|sig| Signature::from_str(sig).expect("signature must be valid"))
-            .map(|sig| self.data_cache.txs.get(&sig).and_then(|v| v.status))
-            .collect();
+        let search_transaction_history = config
+            .map(|config| config.search_transaction_history)
+            .unwrap_or_default();
+        let mut sig_statuses = Vec::with_capacity(sigs.len());
+        for sig in &sigs {
+            let sig = Signature::from_str(sig).expect("signature must be valid");
+            let mut status = self.data_cache.txs.get(&sig).and_then(|v| v.status);
+            // transactions evicted from the cache are resolved through the signature slot index
+            if status.is_none() && search_transaction_history {
+                status = self
+                    .history
+                    .get_transaction_status(&sig)
+                    .await
+                    .map_err(|err| {
+                        log::error!("failed to query status of {sig} from history: {err:?}");
+                        jsonrpsee::types::error::ErrorCode::InternalError
+                    })?;
+            }
+            sig_statuses.push(status);
+        }
 
         Ok(RpcResponse {
             context: RpcResponseContext {
                 slot: self
                     .data_cache
                     .block_information_store
-                    .get_latest_block_information(CommitmentConfig::finalized())
+                    .get_latest_block_information(self.default_commitment)
                     .await
                     .slot,
                 api_version: None,
@@ -306,9 +865,12 @@ impl LiteRpcServer for LiteBridge {
     async fn get_recent_prioritization_fees(
         &self,
         pubkey_strs: Vec<String>,
+        percentile: Option<f32>,
     ) -> RpcResult<Vec<RpcPrioritizationFee>> {
-        // This method will get the latest global and account prioritization fee stats and then send the maximum p75
-        const PERCENTILE: f32 = 0.75;
+        let _timer = record_rpc_call("getRecentPrioritizationFees");
+
+        // This method will get the latest global and account prioritization fee stats and then send the maximum at the percentile (p75 by default)
+        let percentile = validate_percentile(percentile)?;
         let accounts = pubkey_strs
             .iter()
             .filter_map(|pubkey| Pubkey::from_str(pubkey).ok())
@@ -318,10 +880,21 @@ impl LiteRpcServer for LiteBridge {
             return Err(jsonrpsee::types::error::ErrorCode::InvalidParams.into());
         }
 
+        if accounts.is_empty() {
+            // no accounts: the global fees of the recent slots, like the solana rpc
+            let recent_prio_fees = self
+                .prio_fees_service
+                .get_recent_priofees(MAX_RECENT_PRIORITIZATION_FEE_SLOTS)
+                .await;
+            return Ok(global_prioritization_fees(recent_prio_fees, percentile));
+        }
+
         let global_prio_fees = self.prio_fees_service.get_latest_priofees().await;
-        let max_p75 = global_prio_fees
+        let max_global = global_prio_fees
             .map(|(_, fees)| {
-                let fees = fees.get_percentile(PERCENTILE).unwrap_or_default();
+                let fees = fees
+                    .get_interpolated_percentile(percentile)
+                    .unwrap_or_default();
                 std::cmp::max(fees.0, fees.1)
             })
             .unwrap_or_default();
@@ -332,11 +905,11 @@ impl LiteRpcServer for LiteBridge {
                 let (slot, stats) = self.account_priofees_service.get_latest_stats(account);
                 let stat = stats
                     .all_stats
-                    .get_percentile(PERCENTILE)
+                    .get_interpolated_percentile(percentile)
                     .unwrap_or_default();
                 RpcPrioritizationFee {
                     slot,
-                    prioritization_fee: std::cmp::max(max_p75, std::cmp::max(stat.0, stat.1)),
+                    prioritization_fee: std::cmp::max(max_global, std::cmp::max(stat.0, stat.1)),
                 }
             })
             .collect_vec();
@@ -347,71 +920,103 @@ impl LiteRpcServer for LiteBridge {
     async fn send_transaction(
         &self,
         tx: String,
-        send_transaction_config: Option<RpcSendTransactionConfig>,
+        send_transaction_config: Option<SendTransactionConfig>,
     ) -> RpcResult<String> {
+        let _timer = record_rpc_call("sendTransaction");
         RPC_SEND_TX.inc();
 
-        // Copied these constants from solana labs code
-        const MAX_BASE58_SIZE: usize = 1683;
-        const MAX_BASE64_SIZE: usize = 1644;
-
-        let RpcSendTransactionConfig {
-            encoding,
-            max_retries,
-            ..
+        let SendTransactionConfig {
+            rpc_config:
+                RpcSendTransactionConfig {
+                    encoding,
+                    max_retries,
+                    ..
+                },
+            wait_for_commitment,
         } = send_transaction_config.unwrap_or_default();
 
-        let encoding = encoding.unwrap_or(UiTransactionEncoding::Base58);
-        let expected_size = match encoding {
-            UiTransactionEncoding::Base58 => MAX_BASE58_SIZE,
-            UiTransactionEncoding::Base64 => MAX_BASE64_SIZE,
-            _ => usize::MAX,
-        };
-        if tx.len() > expected_size {
-            return Err(jsonrpsee::types::error::ErrorCode::OversizedRequest.into());
-        }
+        let wire_output = decode_wire_transaction(&tx, encoding)?;
+        let transaction = bincode::deserialize::<VersionedTransaction>(&wire_output).ok();
+        let span = transaction_span(
+            "sendTransaction",
+            transaction.as_ref().and_then(|tx| tx.signatures.first()),
+        );
+        self.send_and_wait_for_commitment(
+            wire_output,
+            transaction,
+            max_retries.map(|x| x as u16),
+            wait_for_commitment,
+        )
+        .instrument(span)
+        .await
+    }
 
-        let binary_encoding = encoding
-            .into_binary_encoding()
-            .ok_or(jsonrpsee::types::error::ErrorCode::InvalidParams)?;
+    async fn send_bundle(
+        &self,
+        txs: Vec<String>,
+        send_transaction_config: Option<RpcSendTransactionConfig>,
+    ) -> RpcResult<String> {
+        let _timer = record_rpc_call("sendBundle");
 
-        let wire_output = match binary_encoding {
-            TransactionBinaryEncoding::Base58 => {
-                if tx.len() > MAX_BASE58_SIZE {
-                    return Err(jsonrpsee::types::error::ErrorCode::OversizedRequest.into());
-                }
-                BASE58
-                    .decode(tx)
-                    .map_err(|_| jsonrpsee::types::error::ErrorCode::InvalidParams)?
-            }
-            TransactionBinaryEncoding::Base64 => {
-                if tx.len() > MAX_BASE64_SIZE {
-                    return Err(jsonrpsee::types::error::ErrorCode::OversizedRequest.into());
-                }
-                BASE64
-                    .decode(tx)
-                    .map_err(|_| jsonrpsee::types::error::ErrorCode::InvalidParams)?
-            }
+        let Some(bundle_relay) = &self.bundle_relay else {
+            return Err(jsonrpsee::types::error::ErrorObject::owned(
+                jsonrpsee::types::error::ErrorCode::MethodNotFound.code(),
+                "sendBundle requires a bundle relay, BUNDLE_RELAY_URL is not set",
+                None::<()>,
+            ));
         };
-        if wire_output.len() > PACKET_DATA_SIZE {
-            return Err(jsonrpsee::types::error::ErrorCode::OversizedRequest.into());
+
+        if txs.is_empty() || txs.len() > MAX_BUNDLE_SIZE {
+            return Err(jsonrpsee::types::error::ErrorObject::owned(
+                jsonrpsee::types::error::ErrorCode::InvalidParams.code(),
+                format!("bundle must contain between 1 and {MAX_BUNDLE_SIZE} transactions"),
+                None::<()>,
+            ));
         }
-        let max_retries = max_retries.map(|x| x as u16);
-        match self
-            .transaction_service
-            .send_wire_transaction(wire_output, max_retries)
+
+        let RpcSendTransactionConfig { encoding, .. } = send_transaction_config.unwrap_or_default();
+
+        // the whole bundle is rejected if one transaction cannot be decoded
+        let wire_txs = txs
+            .iter()
+            .enumerate()
+            .map(|(index, tx)| {
+                decode_wire_transaction(tx, encoding).map_err(|err| {
+                    jsonrpsee::types::error::ErrorObject::owned(
+                        err.code(),
+                        err.message().to_string(),
+                        Some(serde_json::json!({ "index": index })),
+                    )
+                })
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        validate_bundle(&wire_txs, |wire_tx| {
+            self.transaction_service.validate_wire_transaction(wire_tx)
+        })
+        .map_err(|BundleError { index, error }| {
+            jsonrpsee::types::error::ErrorObject::owned(
+                jsonrpsee::types::error::ErrorCode::InvalidParams.code(),
+                format!("bundle transaction {index} is invalid: {error}"),
+                Some(serde_json::json!({ "index": index })),
+            )
+        })?;
+
+        // the relay lands the bundle atomically, none of the transactions is sent over the tpu
+        let bundle_id = submit_bundle(bundle_relay, &wire_txs)
             .await
-        {
-            Ok(sig) => {
-                TXS_IN_CHANNEL.inc();
+            .map_err(UpstreamError::Client)?;
 
-                Ok(sig)
-            }
-            Err(_) => Err(jsonrpsee::types::error::ErrorCode::InternalError.into()),
-        }
+        Ok(bundle_id)
+    }
+
+    fn get_capabilities(&self) -> RpcResult<RpcCapabilities> {
+        let _timer = record_rpc_call("getCapabilities");
+        Ok(rpc_capabilities(&self.accounts_service, &self.history))
     }
 
     fn get_version(&self) -> RpcResult<RpcVersionInfo> {
+        let _timer = record_rpc_call("getVersion");
         RPC_GET_VERSION.inc();
 
         let version = solana_version::Version::default();
@@ -423,12 +1028,29 @@ impl LiteRpcServer for LiteBridge {
 
     async fn request_airdrop(
         &self,
-        _pubkey_str: String,
-        _lamports: u64,
-        _config: Option<RpcRequestAirdropConfig>,
+        pubkey_str: String,
+        lamports: u64,
+        config: Option<RpcRequestAirdropConfig>,
     ) -> RpcResult<String> {
+        let _timer = record_rpc_call("requestAirdrop");
         RPC_REQUEST_AIRDROP.inc();
-        Err(jsonrpsee::types::error::ErrorCode::MethodNotFound.into())
+
+        // lite-rpc has no faucet, the upstream rpc serves the airdrop if configured
+        if !self.upstream.is_passthrough("requestAirdrop") {
+            return Err(jsonrpsee::types::error::ErrorCode::MethodNotFound.into());
+        }
+        let Ok(pubkey) = Pubkey::from_str(&pubkey_str) else {
+            return Err(SolanaRpcError::InvalidPubkey(pubkey_str).into());
+        };
+        let config = config.unwrap_or_default();
+        let signature = self
+            .upstream
+            .call("requestAirdrop", || {
+                self.rpc_client
+                    .request_airdrop_with_config(&pubkey, lamports, config.clone())
+            })
+            .await?;
+        Ok(signature.to_string())
     }
 
     async fn get_leader_schedule(
@@ -436,18 +1058,35 @@ impl LiteRpcServer for LiteBridge {
         slot: Option<u64>,
         config: Option<RpcLeaderScheduleConfig>,
     ) -> RpcResult<Option<HashMap<String, Vec<usize>>>> {
-        //TODO verify leader identity.
+        let _timer = record_rpc_call("getLeaderSchedule");
+
+        // without slot and commitment the schedule of the latest processed slot is returned
+        let (commitment, identity) = config
+            .map(|config| (config.commitment, config.identity))
+            .unwrap_or_default();
+        let identity = identity
+            .map(|identity| Pubkey::from_str(&identity))
+            .transpose()
+            .map_err(|_| jsonrpsee::types::error::ErrorCode::InvalidParams)?;
+
         let schedule = self
             .data_cache
             .leader_schedule
             .read()
             .await
-            .get_leader_schedule_for_slot(slot, config.and_then(|c| c.commitment), &self.data_cache)
+            .get_leader_schedule_for_slot(slot, commitment, &self.data_cache)
             .await;
-        Ok(schedule)
+        Ok(match identity {
+            Some(identity) => {
+                schedule.and_then(|schedule| filter_schedule_by_identity(schedule, &identity))
+            }
+            None => schedule,
+        })
     }
 
     async fn get_slot_leaders(&self, start_slot: u64, limit: u64) -> RpcResult<Vec<Pubkey>> {
+        let _timer = record_rpc_call("getSlotLeaders");
+
         let epock_schedule = self.data_cache.epoch_data.get_epoch_schedule();
 
         self.data_cache
@@ -457,24 +1096,122 @@ impl LiteRpcServer for LiteBridge {
             .get_slot_leaders(start_slot, limit, epock_schedule)
             .await
             .map_err(|err| {
-                log::error!("Error processing get leader schedule : {err:?}");
-                jsonrpsee::types::error::ErrorCode::InternalError.into()
+                log::debug!("Invalid get slot leaders request : {err:?}");
+                jsonrpsee::types::error::ErrorObject::owned(
+                    jsonrpsee::types::error::ErrorCode::InvalidParams.code(),
+                    err,
+                    None::<()>,
+                )
             })
     }
 
-    async fn get_vote_accounts(
+    async fn get_block_production(
         &self,
-        _config: Option<RpcGetVoteAccountsConfig>,
-    ) -> RpcResult<RpcVoteAccountStatus> {
-        // under progress
-        Err(jsonrpsee::types::error::ErrorCode::MethodNotFound.into())
-    }
+        config: Option<RpcBlockProductionConfig>,
+    ) -> RpcResult<RpcResponse<RpcBlockProduction>> {
+        let _timer = record_rpc_call("getBlockProduction");
 
-    async fn get_latest_block_priofees(
-        &self,
-        method: Option<PrioritizationFeeCalculationMethod>,
-    ) -> RpcResult<RpcResponse<PrioFeesStats>> {
-        let method = method.unwrap_or_default();
+        let config = config.unwrap_or_default();
+        let commitment_config = self.commitment_or_default(config.commitment);
+        let identity = config
+            .identity
+            .map(|identity| Pubkey::from_str(&identity))
+            .transpose()
+            .map_err(|_| jsonrpsee::types::error::ErrorCode::InvalidParams)?;
+        let BlockInformation { slot, .. } = self
+            .data_cache
+            .block_information_store
+            .get_latest_block_information(commitment_config)
+            .await;
+        let epoch_schedule = self.data_cache.epoch_data.get_epoch_schedule();
+
+        // defaults to the current epoch up to the latest slot
+        let (first_slot, last_slot) = match config.range {
+            Some(range) => (range.first_slot, range.last_slot.unwrap_or(slot)),
+            None => (
+                epoch_schedule.get_first_slot_in_epoch(epoch_schedule.get_epoch(slot)),
+                slot,
+            ),
+        };
+        if last_slot < first_slot || last_slot > slot {
+            return Err(jsonrpsee::types::error::ErrorObject::owned(
+                jsonrpsee::types::error::ErrorCode::InvalidParams.code(),
+                format!("lastSlot, {last_slot}, must be between firstSlot, {first_slot}, and the current slot, {slot}"),
+                None::<()>,
+            ));
+        }
+        // older slots are not retained, their leaders would be counted as having skipped them
+        let block_information_store = &self.data_cache.block_information_store;
+        let first_slot = first_slot.max(block_information_store.get_oldest_slot().unwrap_or(slot));
+        if first_slot > last_slot {
+            return Err(SolanaRpcError::BlockNotAvailable { slot: last_slot }.into());
+        }
+
+        let produced_slots = block_information_store
+            .get_slots_in_range(first_slot..=last_slot, CommitmentConfig::processed());
+        let by_identity = self
+            .data_cache
+            .leader_schedule
+            .read()
+            .await
+            .get_block_production(
+                first_slot,
+                last_slot,
+                &produced_slots,
+                identity.as_ref(),
+                epoch_schedule,
+            )
+            .map_err(|err| {
+                jsonrpsee::types::error::ErrorObject::owned(
+                    jsonrpsee::types::error::ErrorCode::InvalidParams.code(),
+                    err,
+                    None::<()>,
+                )
+            })?;
+
+        Ok(RpcResponse {
+            context: RpcResponseContext {
+                slot,
+                api_version: None,
+            },
+            value: RpcBlockProduction {
+                by_identity,
+                range: RpcBlockProductionRange {
+                    first_slot,
+                    last_slot,
+                },
+            },
+        })
+    }
+
+    async fn get_vote_accounts(
+        &self,
+        config: Option<RpcGetVoteAccountsConfig>,
+    ) -> RpcResult<RpcVoteAccountStatus> {
+        let _timer = record_rpc_call("getVoteAccounts");
+
+        // under progress
+        self.upstream
+            .passthrough(
+                &self.rpc_client,
+                "getVoteAccounts",
+                serde_json::json!([config]),
+            )
+            .await
+    }
+
+    async fn get_latest_block_priofees(
+        &self,
+        method: Option<PrioritizationFeeCalculationMethod>,
+        percentile: Option<f32>,
+    ) -> RpcResult<RpcResponse<LatestPrioFees>> {
+        let _timer = record_rpc_call("getLatestBlockPrioFees");
+
+        let percentile = percentile
+            .map(|percentile| validate_percentile(Some(percentile)))
+            .transpose()?;
+
+        let method = method.unwrap_or_default();
         let res = match method {
             PrioritizationFeeCalculationMethod::Latest => {
                 self.prio_fees_service.get_latest_priofees().await
@@ -484,6 +1221,11 @@ impl LiteRpcServer for LiteBridge {
                     .get_last_n_priofees_aggregate(nb)
                     .await
             }
+            PrioritizationFeeCalculationMethod::LastNSeconds(window_secs) if window_secs > 0 => {
+                self.prio_fees_service
+                    .get_time_window_priofees_aggregate(window_secs)
+                    .await
+            }
             _ => {
                 // method is invalid
                 return Err(jsonrpsee::types::error::ErrorCode::InvalidParams.into());
@@ -496,7 +1238,7 @@ impl LiteRpcServer for LiteBridge {
                     slot: confirmation_slot,
                     api_version: None,
                 },
-                value: priofees,
+                value: latest_priofees(priofees, percentile),
             }),
             None => Err(jsonrpsee::types::error::ErrorCode::InternalError.into()),
         }
@@ -506,10 +1248,23 @@ impl LiteRpcServer for LiteBridge {
         &self,
         account: String,
         method: Option<PrioritizationFeeCalculationMethod>,
-    ) -> RpcResult<RpcResponse<AccountPrioFeesStats>> {
+        history: Option<AccountPrioFeesHistoryConfig>,
+    ) -> RpcResult<RpcResponse<AccountPrioFeesWithHistory>> {
+        let _timer = record_rpc_call("getLatestAccountPrioFees");
+
         if let Ok(account) = Pubkey::from_str(&account) {
+            if history
+                .as_ref()
+                .is_some_and(|history| history.bucket_secs == 0 || history.window_secs == 0)
+            {
+                return Err(jsonrpsee::types::error::ErrorObject::owned(
+                    jsonrpsee::types::error::ErrorCode::InvalidParams.code(),
+                    "bucketSecs and windowSecs must be greater than 0",
+                    None::<()>,
+                ));
+            }
             let method = method.unwrap_or_default();
-            let (slot, value) = match method {
+            let (slot, latest) = match method {
                 PrioritizationFeeCalculationMethod::Latest => {
                     self.account_priofees_service.get_latest_stats(&account)
                 }
@@ -518,12 +1273,19 @@ impl LiteRpcServer for LiteBridge {
                 }
                 _ => return Err(jsonrpsee::types::error::ErrorCode::InternalError.into()),
             };
+            let history = history.map(|history| {
+                self.account_priofees_service.get_bucketed_stats(
+                    &account,
+                    history.bucket_secs,
+                    history.window_secs,
+                )
+            });
             Ok(RpcResponse {
                 context: RpcResponseContext {
                     slot,
                     api_version: None,
                 },
-                value,
+                value: AccountPrioFeesWithHistory { latest, history },
             })
         } else {
             // Account key is invalid
@@ -536,21 +1298,26 @@ impl LiteRpcServer for LiteBridge {
         pubkey_str: String,
         config: Option<RpcAccountInfoConfig>,
     ) -> RpcResult<RpcResponse<Option<UiAccount>>> {
+        let _timer = record_rpc_call("getAccountInfo");
+
         let Ok(pubkey) = Pubkey::from_str(&pubkey_str) else {
-            // pubkey is invalid
-            return Err(jsonrpsee::types::error::ErrorCode::InvalidParams.into());
+            return Err(SolanaRpcError::InvalidPubkey(pubkey_str).into());
         };
         if let Some(account_service) = &self.accounts_service {
+            ensure_accounts_ready(account_service)?;
             let commitment = config
                 .as_ref()
                 .and_then(|x| x.commitment)
-                .unwrap_or_default();
+                .unwrap_or(self.default_commitment);
             let current_block_info = self
                 .data_cache
                 .block_information_store
                 .get_latest_block_information(commitment)
                 .await;
-            match account_service.get_account(pubkey, config).await {
+            match account_service
+                .get_account(pubkey, self.account_config_or_default(config))
+                .await
+            {
                 Ok((_, ui_account)) => Ok(RpcResponse {
                     context: RpcResponseContext {
                         slot: current_block_info.slot,
@@ -558,6 +1325,10 @@ impl LiteRpcServer for LiteBridge {
                     },
                     value: ui_account,
                 }),
+                Err(AccountLoadingError::DataSliceOutOfRange) => {
+                    Err(data_slice_out_of_range_error())
+                }
+                Err(AccountLoadingError::Base58DataTooLarge) => Err(base58_data_too_large_error()),
                 Err(_) => {
                     // account not found
                     Err(jsonrpsee::types::error::ErrorCode::ServerError(
@@ -577,33 +1348,38 @@ impl LiteRpcServer for LiteBridge {
         pubkey_strs: Vec<String>,
         config: Option<RpcAccountInfoConfig>,
     ) -> RpcResult<RpcResponse<Vec<Option<UiAccount>>>> {
+        let _timer = record_rpc_call("getMultipleAccounts");
+
         let pubkeys = pubkey_strs
             .iter()
             .map(|key| Pubkey::from_str(key))
             .collect_vec();
-        if pubkeys.iter().any(|res| res.is_err()) {
-            return Err(jsonrpsee::types::error::ErrorCode::InternalError.into());
+        if let Some(position) = pubkeys.iter().position(|res| res.is_err()) {
+            return Err(SolanaRpcError::InvalidPubkey(pubkey_strs[position].clone()).into());
         };
 
         if let Some(account_service) = &self.accounts_service {
-            let mut ui_accounts = vec![];
-            for pubkey in pubkeys {
-                match account_service
-                    .get_account(pubkey.unwrap(), config.clone())
-                    .await
-                {
-                    Ok((_, ui_account)) => {
-                        ui_accounts.push(ui_account);
-                    }
-                    Err(_) => {
-                        ui_accounts.push(None);
-                    }
+            ensure_accounts_ready(account_service)?;
+            let pubkeys = pubkeys.into_iter().map(|pubkey| pubkey.unwrap()).collect();
+            let (max_slot, ui_accounts) = match account_service
+                .get_multiple_accounts(pubkeys, self.account_config_or_default(config.clone()))
+                .await
+            {
+                Ok(res) => res,
+                Err(AccountLoadingError::DataSliceOutOfRange) => {
+                    return Err(data_slice_out_of_range_error());
                 }
-            }
+                Err(AccountLoadingError::Base58DataTooLarge) => {
+                    return Err(base58_data_too_large_error());
+                }
+                Err(_) => {
+                    return Err(jsonrpsee::types::error::ErrorCode::InternalError.into());
+                }
+            };
             let commitment = config
                 .as_ref()
                 .and_then(|x| x.commitment)
-                .unwrap_or_default();
+                .unwrap_or(self.default_commitment);
             let current_block_info = self
                 .data_cache
                 .block_information_store
@@ -612,7 +1388,7 @@ impl LiteRpcServer for LiteBridge {
             assert_eq!(ui_accounts.len(), pubkey_strs.len());
             Ok(RpcResponse {
                 context: RpcResponseContext {
-                    slot: current_block_info.slot,
+                    slot: current_block_info.slot.max(max_slot),
                     api_version: None,
                 },
                 value: ui_accounts,
@@ -628,9 +1404,12 @@ impl LiteRpcServer for LiteBridge {
         program_id_str: String,
         config: Option<RpcProgramAccountsConfig>,
     ) -> RpcResult<OptionalContext<Vec<RpcKeyedAccount>>> {
+        let _timer = record_rpc_call("getProgramAccounts");
+
         let Ok(program_id) = Pubkey::from_str(&program_id_str) else {
-            return Err(jsonrpsee::types::error::ErrorCode::InternalError.into());
+            return Err(SolanaRpcError::InvalidPubkey(program_id_str).into());
         };
+        verify_program_accounts_filters(config.as_ref())?;
         let with_context = config
             .as_ref()
             .map(|value| value.with_context.unwrap_or_default())
@@ -639,7 +1418,7 @@ impl LiteRpcServer for LiteBridge {
         let commitment: CommitmentConfig = config
             .as_ref()
             .and_then(|x| x.account_config.commitment)
-            .unwrap_or_default();
+            .unwrap_or(self.default_commitment);
         let current_block_info = self
             .data_cache
             .block_information_store
@@ -647,8 +1426,9 @@ impl LiteRpcServer for LiteBridge {
             .await;
 
         if let Some(account_service) = &self.accounts_service {
+            ensure_accounts_ready(account_service)?;
             match account_service
-                .get_program_accounts(program_id, config)
+                .get_program_accounts(program_id, self.program_accounts_config_or_default(config))
                 .await
             {
                 Ok((_, ui_account)) => {
@@ -677,52 +1457,1859 @@ impl LiteRpcServer for LiteBridge {
         }
     }
 
+    async fn get_program_accounts_paginated(
+        &self,
+        program_id_str: String,
+        config: Option<RpcProgramAccountsConfig>,
+        limit: usize,
+        cursor: Option<String>,
+    ) -> RpcResult<RpcResponse<RpcProgramAccountsPage>> {
+        let _timer = record_rpc_call("getProgramAccountsPaginated");
+
+        let Ok(program_id) = Pubkey::from_str(&program_id_str) else {
+            return Err(SolanaRpcError::InvalidPubkey(program_id_str).into());
+        };
+        verify_program_accounts_filters(config.as_ref())?;
+
+        if let Some(account_service) = &self.accounts_service {
+            ensure_accounts_ready(account_service)?;
+            match account_service
+                .get_program_accounts_page(
+                    program_id,
+                    self.program_accounts_config_or_default(config),
+                    limit,
+                    cursor,
+                    self.data_cache.slot_cache.get_current_slot(),
+                )
+                .await
+            {
+                Ok((slot, page)) => Ok(RpcResponse {
+                    context: RpcResponseContext {
+                        slot,
+                        api_version: None,
+                    },
+                    value: page,
+                }),
+                Err(err) => Err(jsonrpsee::types::error::ErrorObject::owned(
+                    jsonrpsee::types::error::ErrorCode::InvalidParams.code(),
+                    format!("Invalid param: {err}"),
+                    None::<()>,
+                )),
+            }
+        } else {
+            // accounts are disabled
+            Err(jsonrpsee::types::error::ErrorCode::MethodNotFound.into())
+        }
+    }
+
     async fn get_balance(
         &self,
         pubkey_str: String,
         config: Option<RpcContextConfig>,
     ) -> RpcResult<RpcResponse<u64>> {
+        let _timer = record_rpc_call("getBalance");
+
         let Ok(pubkey) = Pubkey::from_str(&pubkey_str) else {
-            // pubkey is invalid
-            return Err(jsonrpsee::types::error::ErrorCode::InvalidParams.into());
+            return Err(SolanaRpcError::InvalidPubkey(pubkey_str).into());
         };
+        let min_context_slot = config.as_ref().and_then(|x| x.min_context_slot);
+        // min_context_slot is checked against the node, not against the slot the account was last updated
         let config = config.map(|x| RpcAccountInfoConfig {
             encoding: None,
             data_slice: None,
             commitment: x.commitment,
-            min_context_slot: x.min_context_slot,
+            min_context_slot: None,
         });
 
         let commitment = config
             .as_ref()
             .and_then(|x| x.commitment)
-            .unwrap_or_default();
+            .unwrap_or(self.default_commitment);
         let current_block_info = self
             .data_cache
             .block_information_store
             .get_latest_block_information(commitment)
             .await;
+        check_min_context_slot(min_context_slot, current_block_info.slot)?;
 
         if let Some(account_service) = &self.accounts_service {
-            match account_service.get_account(pubkey, config).await {
-                Ok((_, ui_account)) => Ok(RpcResponse {
+            ensure_accounts_ready(account_service)?;
+            match account_service
+                .get_balance(pubkey, self.account_config_or_default(config))
+                .await
+            {
+                Ok(lamports) => Ok(RpcResponse {
                     context: RpcResponseContext {
                         slot: current_block_info.slot,
                         api_version: None,
                     },
-                    value: ui_account.map(|x| x.lamports).unwrap_or_default(),
+                    value: lamports,
                 }),
-                Err(_) => {
-                    // account not found
-                    Err(jsonrpsee::types::error::ErrorCode::ServerError(
-                        RpcErrors::AccountNotFound as i32,
-                    )
-                    .into())
-                }
+                Err(_) => Err(jsonrpsee::types::error::ErrorCode::ServerError(
+                    RpcErrors::AccountNotFound as i32,
+                )
+                .into()),
             }
         } else {
             // accounts are disabled
-            Err(jsonrpsee::types::error::ErrorCode::MethodNotFound.into())
+            Err(jsonrpsee::types::error::ErrorObject::owned(
+                jsonrpsee::types::error::ErrorCode::MethodNotFound.code(),
+                "account filters are not configured",
+                None::<()>,
+            ))
+        }
+    }
+
+    async fn get_token_accounts_by_owner(
+        &self,
+        owner_str: String,
+        filter: RpcTokenAccountsFilter,
+        config: Option<RpcAccountInfoConfig>,
+    ) -> RpcResult<RpcResponse<Vec<RpcKeyedAccount>>> {
+        let _timer = record_rpc_call("getTokenAccountsByOwner");
+
+        let Ok(owner) = Pubkey::from_str(&owner_str) else {
+            return Err(SolanaRpcError::InvalidPubkey(owner_str).into());
+        };
+        let filter = match filter {
+            RpcTokenAccountsFilter::Mint(mint_str) => match Pubkey::from_str(&mint_str) {
+                Ok(mint) => TokenAccountsFilter::Mint(mint),
+                Err(_) => return Err(SolanaRpcError::InvalidPubkey(mint_str).into()),
+            },
+            RpcTokenAccountsFilter::ProgramId(program_id_str) => {
+                match Pubkey::from_str(&program_id_str) {
+                    Ok(program_id) => TokenAccountsFilter::ProgramId(program_id),
+                    Err(_) => return Err(SolanaRpcError::InvalidPubkey(program_id_str).into()),
+                }
+            }
+        };
+        let Some(account_service) = &self.accounts_service else {
+            // accounts are disabled
+            return Err(jsonrpsee::types::error::ErrorObject::owned(
+                jsonrpsee::types::error::ErrorCode::MethodNotFound.code(),
+                "account filters are not configured",
+                None::<()>,
+            ));
+        };
+        ensure_accounts_ready(account_service)?;
+
+        match account_service
+            .get_token_accounts_by_owner(owner, filter, self.account_config_or_default(config))
+            .await
+        {
+            Ok((slot, token_accounts)) => Ok(RpcResponse {
+                context: RpcResponseContext {
+                    slot,
+                    api_version: None,
+                },
+                value: token_accounts,
+            }),
+            Err(err) => {
+                log::debug!("getTokenAccountsByOwner failed for {owner}: {err:?}");
+                Err(jsonrpsee::types::error::ErrorCode::ServerError(
+                    RpcErrors::AccountNotFound as i32,
+                )
+                .into())
+            }
+        }
+    }
+
+    async fn get_token_account_balance(
+        &self,
+        pubkey_str: String,
+        commitment: Option<CommitmentConfig>,
+    ) -> RpcResult<RpcResponse<UiTokenAmount>> {
+        let _timer = record_rpc_call("getTokenAccountBalance");
+
+        let Ok(pubkey) = Pubkey::from_str(&pubkey_str) else {
+            return Err(SolanaRpcError::InvalidPubkey(pubkey_str).into());
+        };
+        let Some(account_service) = &self.accounts_service else {
+            // accounts are disabled
+            return Err(jsonrpsee::types::error::ErrorObject::owned(
+                jsonrpsee::types::error::ErrorCode::MethodNotFound.code(),
+                "account filters are not configured",
+                None::<()>,
+            ));
+        };
+        ensure_accounts_ready(account_service)?;
+
+        match account_service
+            .get_token_account_balance(pubkey, self.commitment_or_default(commitment))
+            .await
+        {
+            Ok((slot, token_amount)) => Ok(RpcResponse {
+                context: RpcResponseContext {
+                    slot,
+                    api_version: None,
+                },
+                value: token_amount,
+            }),
+            Err(AccountLoadingError::NotATokenAccount) => {
+                Err(jsonrpsee::types::error::ErrorObject::owned(
+                    jsonrpsee::types::error::ErrorCode::InvalidParams.code(),
+                    "Invalid param: not a Token account",
+                    None::<()>,
+                ))
+            }
+            Err(_) => Err(jsonrpsee::types::error::ErrorObject::owned(
+                jsonrpsee::types::error::ErrorCode::InvalidParams.code(),
+                "Invalid param: could not find account",
+                None::<()>,
+            )),
+        }
+    }
+}
+
+const DEFAULT_PRIORITIZATION_FEE_PERCENTILE: f32 = 0.75;
+
+// getBlockTime estimates slots up to the expiry of the latest blockhash
+const MAX_BLOCK_TIME_ESTIMATE_SLOTS: u64 = MAX_PROCESSING_AGE as u64;
+
+// number of slots in the prioritization fee cache of the solana rpc
+const MAX_RECENT_PRIORITIZATION_FEE_SLOTS: usize = 150;
+
+// same limit as getMultipleAccounts of the solana rpc
+const MAX_INFLATION_REWARD_ADDRESSES: usize = 100;
+
+// how often sendTransaction with waitForCommitment checks the transaction status
+const WAIT_FOR_COMMITMENT_POLL_INTERVAL: std::time::Duration =
+    std::time::Duration::from_millis(100);
+
+/// percentile must be within (0, 1]
+fn validate_percentile(percentile: Option<f32>) -> Result<f32, jsonrpsee::types::ErrorObjectOwned> {
+    match percentile {
+        None => Ok(DEFAULT_PRIORITIZATION_FEE_PERCENTILE),
+        Some(percentile) if percentile > 0.0 && percentile <= 1.0 => Ok(percentile),
+        Some(percentile) => Err(jsonrpsee::types::error::ErrorObject::owned(
+            jsonrpsee::types::error::ErrorCode::InvalidParams.code(),
+            format!("Invalid param: percentile {percentile} must be within (0, 1]"),
+            None::<()>,
+        )),
+    }
+}
+
+/// collapses the stats to the fee at the percentile if one was requested
+fn latest_priofees(stats: PrioFeesStats, percentile: Option<f32>) -> LatestPrioFees {
+    match percentile {
+        Some(percentile) => {
+            let fees = stats
+                .get_interpolated_percentile(percentile)
+                .unwrap_or_default();
+            LatestPrioFees::AtPercentile(PrioFeeAtPercentile {
+                percentile,
+                fee: std::cmp::max(fees.0, fees.1),
+            })
+        }
+        None => LatestPrioFees::Stats(stats),
+    }
+}
+
+/// fee of each slot at the percentile, the higher of the by tx and by cu fees
+fn global_prioritization_fees(
+    recent_prio_fees: Vec<(Slot, PrioFeesStats)>,
+    percentile: f32,
+) -> Vec<RpcPrioritizationFee> {
+    recent_prio_fees
+        .into_iter()
+        .map(|(slot, stats)| {
+            let fees = stats
+                .get_interpolated_percentile(percentile)
+                .unwrap_or_default();
+            RpcPrioritizationFee {
+                slot,
+                prioritization_fee: std::cmp::max(fees.0, fees.1),
+            }
+        })
+        .collect()
+}
+
+fn data_slice_out_of_range_error() -> jsonrpsee::types::ErrorObjectOwned {
+    jsonrpsee::types::error::ErrorObject::owned(
+        jsonrpsee::types::error::ErrorCode::InvalidParams.code(),
+        "Invalid param: dataSlice offset + length exceeds the account data length",
+        None::<()>,
+    )
+}
+
+fn base58_data_too_large_error() -> jsonrpsee::types::ErrorObjectOwned {
+    jsonrpsee::types::error::ErrorObject::owned(
+        jsonrpsee::types::error::ErrorCode::InvalidRequest.code(),
+        format!(
+            "Encoded binary (base 58) data should be less than {MAX_BASE58_BYTES} bytes, please use Base64 encoding."
+        ),
+        None::<()>,
+    )
+}
+
+/// same checks as the solana rpc before the filters reach the account store, errors name the offending filter
+#[allow(deprecated)]
+fn verify_program_accounts_filters(
+    config: Option<&RpcProgramAccountsConfig>,
+) -> Result<(), SolanaRpcError> {
+    let Some(filters) = config.and_then(|config| config.filters.as_ref()) else {
+        return Ok(());
+    };
+    if filters.len() > MAX_GET_PROGRAM_ACCOUNT_FILTERS {
+        return Err(SolanaRpcError::TooManyFilters {
+            max_filters: MAX_GET_PROGRAM_ACCOUNT_FILTERS,
+        });
+    }
+    for (index, filter) in filters.iter().enumerate() {
+        filter
+            .verify()
+            .map_err(|err| SolanaRpcError::InvalidFilter {
+                index,
+                reason: err.to_string(),
+            })?;
+        if let RpcFilterType::Memcmp(memcmp) = filter {
+            if memcmp.offset as u64 >= MAX_PERMITTED_DATA_LENGTH {
+                return Err(SolanaRpcError::InvalidFilter {
+                    index,
+                    reason: format!(
+                        "memcmp offset {} is beyond the maximum account data length {MAX_PERMITTED_DATA_LENGTH}",
+                        memcmp.offset
+                    ),
+                });
+            }
         }
     }
+    Ok(())
+}
+
+async fn latest_slot(
+    data_cache: &DataCache,
+    config: Option<RpcContextConfig>,
+    default_commitment: CommitmentConfig,
+) -> Slot {
+    let commitment_config = config
+        .and_then(|config| config.commitment)
+        .unwrap_or(default_commitment);
+
+    let BlockInformation { slot, .. } = data_cache
+        .get_latest_block_information(commitment_config)
+        .await;
+    slot
+}
+
+/// up to `limit` slots of confirmed or finalized blocks from `start_slot` to the latest slot of the commitment,
+/// none if `start_slot` is older than the blocks in the block information store
+async fn blocks_with_limit(
+    data_cache: &DataCache,
+    start_slot: Slot,
+    limit: usize,
+    commitment_config: CommitmentConfig,
+) -> RpcResult<Option<Vec<Slot>>> {
+    if limit > MAX_GET_CONFIRMED_BLOCKS_RANGE as usize {
+        return Err(jsonrpsee::types::error::ErrorObject::owned(
+            jsonrpsee::types::error::ErrorCode::InvalidParams.code(),
+            format!("Limit too large; max {MAX_GET_CONFIRMED_BLOCKS_RANGE}"),
+            None::<()>,
+        ));
+    }
+    let BlockInformation { slot, .. } = data_cache
+        .get_latest_block_information(commitment_config)
+        .await;
+    Ok(
+        retained_blocks(data_cache, start_slot, slot, commitment_config)?
+            .map(|slots| slots.into_iter().take(limit).collect()),
+    )
+}
+
+/// slots of confirmed or finalized blocks from `start_slot` to `end_slot`, capped at the latest slot of the commitment,
+/// none if `start_slot` is older than the blocks in the block information store
+async fn blocks_in_range(
+    data_cache: &DataCache,
+    start_slot: Slot,
+    end_slot: Option<Slot>,
+    commitment_config: CommitmentConfig,
+) -> RpcResult<Option<Vec<Slot>>> {
+    let BlockInformation { slot, .. } = data_cache
+        .get_latest_block_information(commitment_config)
+        .await;
+    let end_slot = end_slot.unwrap_or(slot).min(slot);
+    if end_slot.saturating_sub(start_slot) > MAX_GET_CONFIRMED_BLOCKS_RANGE {
+        return Err(jsonrpsee::types::error::ErrorObject::owned(
+            jsonrpsee::types::error::ErrorCode::InvalidParams.code(),
+            format!("Slot range too large; max {MAX_GET_CONFIRMED_BLOCKS_RANGE}"),
+            None::<()>,
+        ));
+    }
+    retained_blocks(data_cache, start_slot, end_slot, commitment_config)
+}
+
+fn retained_blocks(
+    data_cache: &DataCache,
+    start_slot: Slot,
+    end_slot: Slot,
+    commitment_config: CommitmentConfig,
+) -> RpcResult<Option<Vec<Slot>>> {
+    if !commitment_config.is_at_least_confirmed() {
+        return Err(jsonrpsee::types::error::ErrorObject::owned(
+            jsonrpsee::types::error::ErrorCode::InvalidParams.code(),
+            "Method does not support commitment below `confirmed`",
+            None::<()>,
+        ));
+    }
+    let block_information_store = &data_cache.block_information_store;
+    if block_information_store
+        .get_oldest_slot()
+        .map_or(true, |oldest_slot| start_slot < oldest_slot)
+    {
+        return Ok(None);
+    }
+    if start_slot > end_slot {
+        return Ok(Some(vec![]));
+    }
+    Ok(Some(
+        block_information_store
+            .get_slots_in_range(start_slot..=end_slot, commitment_config)
+            .into_iter()
+            .sorted()
+            .collect(),
+    ))
+}
+
+async fn stake_minimum_delegation_response<F, Fut, E>(
+    data_cache: &DataCache,
+    stake_minimum_delegation: &StakeMinimumDelegation,
+    config: Option<RpcContextConfig>,
+    default_commitment: CommitmentConfig,
+    fetch_from_rpc: F,
+) -> Result<RpcResponse<u64>, E>
+where
+    F: FnOnce() -> Fut,
+    Fut: std::future::Future<Output = Result<u64, E>>,
+{
+    let lamports = stake_minimum_delegation
+        .get_or_fetch(fetch_from_rpc)
+        .await?;
+    Ok(RpcResponse {
+        context: RpcResponseContext {
+            slot: latest_slot(data_cache, config, default_commitment).await,
+            api_version: None,
+        },
+        value: lamports,
+    })
+}
+
+/// time of a slot after the latest block, slots too far ahead cannot be estimated reliably
+fn estimate_future_block_time(
+    latest: &BlockInformation,
+    slot: Slot,
+) -> Result<u64, SolanaRpcError> {
+    let slots_ahead = slot.saturating_sub(latest.slot);
+    if slots_ahead > MAX_BLOCK_TIME_ESTIMATE_SLOTS || latest.block_time == 0 {
+        return Err(SolanaRpcError::BlockNotAvailable { slot });
+    }
+    Ok(estimate_slot_time(latest.slot, latest.block_time, slot))
+}
+
+/// before the initial snapshot is loaded a missing account might exist
+fn ensure_accounts_ready(account_service: &AccountService) -> Result<(), SolanaRpcError> {
+    if account_service.is_ready() {
+        Ok(())
+    } else {
+        Err(SolanaRpcError::AccountsWarmingUp)
+    }
+}
+
+/// same as solana, the node must have processed min_context_slot at the requested commitment
+fn check_min_context_slot(
+    min_context_slot: Option<Slot>,
+    context_slot: Slot,
+) -> Result<(), SolanaRpcError> {
+    match min_context_slot {
+        Some(min_context_slot) if min_context_slot > context_slot => {
+            Err(SolanaRpcError::MinContextSlotNotReached { context_slot })
+        }
+        _ => Ok(()),
+    }
+}
+
+/// rejects accounts larger than an account can be allocated
+fn minimum_balance_for_rent_exemption(
+    rent: &Rent,
+    data_len: usize,
+) -> Result<u64, jsonrpsee::types::ErrorObjectOwned> {
+    if data_len as u64 > MAX_PERMITTED_DATA_LENGTH {
+        return Err(jsonrpsee::types::error::ErrorObject::owned(
+            jsonrpsee::types::error::ErrorCode::InvalidParams.code(),
+            format!("Invalid param: data length {data_len} exceeds {MAX_PERMITTED_DATA_LENGTH}"),
+            None::<()>,
+        ));
+    }
+    Ok(rent.minimum_balance(data_len))
+}
+
+/// history blocks keep the signatures but not the transaction metas,
+/// none if the config asks for full transactions or a commitment the block has not reached
+fn ui_block_from_history(
+    block: &ProducedBlock,
+    config: &RpcBlockConfig,
+) -> Option<UiConfirmedBlock> {
+    let commitment = config.commitment.unwrap_or_default();
+    if commitment.is_finalized() && !block.commitment_config.is_finalized() {
+        return None;
+    }
+    let signatures = match config.transaction_details.unwrap_or_default() {
+        TransactionDetails::Signatures => Some(
+            block
+                .transactions
+                .iter()
+                .map(|tx| tx.signature.to_string())
+                .collect_vec(),
+        ),
+        TransactionDetails::None => None,
+        TransactionDetails::Full | TransactionDetails::Accounts => return None,
+    };
+    let rewards = config
+        .rewards
+        .unwrap_or(true)
+        .then(|| block.rewards.clone().unwrap_or_default());
+    Some(UiConfirmedBlock {
+        previous_blockhash: block.previous_blockhash.to_string(),
+        blockhash: block.blockhash.to_string(),
+        parent_slot: block.parent_slot,
+        transactions: None,
+        signatures,
+        rewards,
+        block_time: Some(block.block_time as i64),
+        block_height: Some(block.block_height),
+    })
+}
+
+/// counts the call, the latency is observed when the returned timer is dropped
+fn record_rpc_call(method: &str) -> HistogramTimer {
+    RPC_METHOD_CALLS.with_label_values(&[method]).inc();
+    RPC_METHOD_LATENCY
+        .with_label_values(&[method])
+        .start_timer()
+}
+
+/// decodes a transaction of sendTransaction or sendBundle, applies the solana size limits
+fn decode_wire_transaction(
+    tx: &str,
+    encoding: Option<UiTransactionEncoding>,
+) -> Result<Vec<u8>, jsonrpsee::types::ErrorObjectOwned> {
+    // Copied these constants from solana labs code
+    const MAX_BASE58_SIZE: usize = 1683;
+    const MAX_BASE64_SIZE: usize = 1644;
+
+    let encoding = encoding.unwrap_or(UiTransactionEncoding::Base58);
+    let expected_size = match encoding {
+        UiTransactionEncoding::Base58 => MAX_BASE58_SIZE,
+        UiTransactionEncoding::Base64 => MAX_BASE64_SIZE,
+        _ => usize::MAX,
+    };
+    if tx.len() > expected_size {
+        return Err(SolanaRpcError::TransactionTooLarge {
+            size: tx.len(),
+            max_size: expected_size,
+        }
+        .into());
+    }
+
+    let binary_encoding = encoding
+        .into_binary_encoding()
+        .ok_or(jsonrpsee::types::error::ErrorCode::InvalidParams)?;
+
+    let wire_output = match binary_encoding {
+        TransactionBinaryEncoding::Base58 => {
+            if tx.len() > MAX_BASE58_SIZE {
+                return Err(jsonrpsee::types::error::ErrorCode::OversizedRequest.into());
+            }
+            BASE58
+                .decode(tx)
+                .map_err(|_| jsonrpsee::types::error::ErrorCode::InvalidParams)?
+        }
+        TransactionBinaryEncoding::Base64 => {
+            if tx.len() > MAX_BASE64_SIZE {
+                return Err(jsonrpsee::types::error::ErrorCode::OversizedRequest.into());
+            }
+            BASE64
+                .decode(tx)
+                .map_err(|_| jsonrpsee::types::error::ErrorCode::InvalidParams)?
+        }
+    };
+    if wire_output.len() > PACKET_DATA_SIZE {
+        return Err(SolanaRpcError::TransactionTooLarge {
+            size: wire_output.len(),
+            max_size: PACKET_DATA_SIZE,
+        }
+        .into());
+    }
+    Ok(wire_output)
+}
+
+/// Err with the number of slots behind if the processed slot lags the estimated cluster slot by more than `slot_distance`
+fn check_health(processed_slot: Slot, estimated_slot: Slot, slot_distance: u64) -> Result<(), u64> {
+    let num_slots_behind = estimated_slot.saturating_sub(processed_slot);
+    if num_slots_behind > slot_distance {
+        Err(num_slots_behind)
+    } else {
+        Ok(())
+    }
+}
+
+/// correlates the log lines of a transaction from sending to confirmation
+pub(crate) fn transaction_span(method: &'static str, signature: Option<&Signature>) -> Span {
+    let span = info_span!("transaction", method, signature = field::Empty);
+    if let Some(signature) = signature {
+        span.record("signature", field::display(signature));
+    }
+    span
+}
+
+/// prioritization fees are always tracked from the block stream
+fn rpc_capabilities(
+    accounts_service: &Option<AccountService>,
+    history: &History,
+) -> RpcCapabilities {
+    RpcCapabilities {
+        accounts: accounts_service.is_some(),
+        prioritization_fees: true,
+        history: history.is_enabled(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solana_lite_rpc_accounts::inmemory_account_store::InmemoryAccountStore;
+    use solana_lite_rpc_prioritization_fees::prioritization_fee_data::{
+        BlockPrioData, PrioFeesData,
+    };
+    use solana_rpc_client_api::filter::{Memcmp, MemcmpEncodedBytes};
+    use solana_sdk::message::{Message, VersionedMessage};
+
+    fn test_bridge_config() -> LiteBridgeConfig {
+        LiteBridgeConfig {
+            history: History::new(),
+            health_check_slot_distance: 128,
+            identity: Pubkey::new_unique(),
+            genesis_hash: None,
+            default_commitment: CommitmentConfig::confirmed(),
+            upstream: UpstreamConfig::default(),
+            upstream_passthrough_methods: vec![],
+            stake_minimum_delegation: None,
+            rent_lamports_per_byte_year: None,
+            cluster_nodes_ttl: None,
+            supply_cache_interval: std::time::Duration::from_secs(60),
+            bundle_relay_url: None,
+        }
+    }
+
+    /// the block information store starts at a finalized block of `slot`
+    fn test_data_cache_at_slot(slot: Slot) -> DataCache {
+        let finalized = BlockInformation {
+            slot,
+            block_height: slot,
+            last_valid_blockheight: slot + 300,
+            cleanup_slot: slot + 1000,
+            blockhash: Hash::new_unique(),
+            commitment_config: CommitmentConfig::finalized(),
+            block_time: 0,
+            parent_slot: slot - 1,
+            block_time_estimated: false,
+        };
+        DataCache {
+            block_information_store:
+                solana_lite_rpc_core::stores::block_information_store::BlockInformationStore::new(
+                    finalized,
+                ),
+            ..DataCache::new_for_tests()
+        }
+    }
+
+    /// the transaction and block channels of the bridge are closed
+    fn test_bridge(
+        rpc_client: Arc<RpcClient>,
+        data_cache: DataCache,
+        accounts_service: Option<AccountService>,
+        config: LiteBridgeConfig,
+    ) -> LiteBridge {
+        let (transaction_channel, _) = tokio::sync::mpsc::channel(1);
+        let (replay_channel, _) = tokio::sync::mpsc::unbounded_channel();
+        let transaction_service = TransactionService {
+            transaction_channel,
+            replay_channel,
+            block_information_store: data_cache.block_information_store.clone(),
+            max_retries: 0,
+            max_allowed_retries: 0,
+            replay_offset: std::time::Duration::ZERO,
+            recent_signatures: None,
+        };
+        let (blocks_sender, _) = tokio::sync::broadcast::channel(1);
+        let (_, prio_fees_service) = solana_lite_rpc_prioritization_fees::start_block_priofees_task(
+            blocks_sender.subscribe(),
+            10,
+        );
+        let (_, account_priofees_service) =
+            AccountPrioService::start_account_priofees_task(blocks_sender.subscribe(), 10, None);
+        LiteBridge::new(
+            rpc_client,
+            data_cache,
+            transaction_service,
+            prio_fees_service,
+            account_priofees_service,
+            accounts_service,
+            config,
+        )
+    }
+
+    #[test]
+    fn test_health_within_threshold() {
+        assert_eq!(check_health(1000, 1000, 128), Ok(()));
+        assert_eq!(check_health(1000, 1128, 128), Ok(()));
+        // processed slot ahead of the estimate
+        assert_eq!(check_health(1010, 1000, 128), Ok(()));
+    }
+
+    #[test]
+    fn test_health_lagging() {
+        assert_eq!(check_health(1000, 1129, 128), Err(129));
+
+        let error: jsonrpsee::types::ErrorObjectOwned = SolanaRpcError::NodeUnhealthy {
+            num_slots_behind: 129,
+        }
+        .into();
+        assert_eq!(error.code(), -32005);
+        assert_eq!(error.message(), "Node is behind by 129 slots");
+        assert_eq!(
+            error.data().map(|data| data.get()),
+            Some(r#"{"numSlotsBehind":129}"#)
+        );
+    }
+
+    #[test]
+    fn test_verify_program_accounts_filters() {
+        let config = |filters: Vec<RpcFilterType>| RpcProgramAccountsConfig {
+            filters: Some(filters),
+            ..Default::default()
+        };
+        assert_eq!(verify_program_accounts_filters(None), Ok(()));
+        assert_eq!(
+            verify_program_accounts_filters(Some(&config(vec![
+                RpcFilterType::DataSize(165),
+                RpcFilterType::Memcmp(Memcmp::new_base58_encoded(32, &[1, 2, 3])),
+            ]))),
+            Ok(())
+        );
+
+        // 0 and l are not in the base58 alphabet
+        let invalid_encoding = config(vec![
+            RpcFilterType::DataSize(165),
+            RpcFilterType::Memcmp(Memcmp::new(0, MemcmpEncodedBytes::Base58("0l".to_string()))),
+        ]);
+        let error: jsonrpsee::types::ErrorObjectOwned =
+            verify_program_accounts_filters(Some(&invalid_encoding))
+                .unwrap_err()
+                .into();
+        assert_eq!(error.code(), -32602);
+        assert!(
+            error.message().starts_with("Invalid param: filter 1: "),
+            "{}",
+            error.message()
+        );
+
+        let beyond_data_length = config(vec![RpcFilterType::Memcmp(Memcmp::new_base58_encoded(
+            MAX_PERMITTED_DATA_LENGTH as usize,
+            &[1],
+        ))]);
+        assert!(matches!(
+            verify_program_accounts_filters(Some(&beyond_data_length)),
+            Err(SolanaRpcError::InvalidFilter { index: 0, .. })
+        ));
+
+        let too_many = config(vec![
+            RpcFilterType::DataSize(165);
+            MAX_GET_PROGRAM_ACCOUNT_FILTERS + 1
+        ]);
+        let error: jsonrpsee::types::ErrorObjectOwned =
+            verify_program_accounts_filters(Some(&too_many))
+                .unwrap_err()
+                .into();
+        assert_eq!(error.code(), -32602);
+        assert_eq!(
+            error.message(),
+            format!("Too many filters provided; max {MAX_GET_PROGRAM_ACCOUNT_FILTERS}")
+        );
+    }
+
+    #[test]
+    fn test_validate_percentile() {
+        assert_eq!(validate_percentile(None).ok(), Some(0.75));
+        assert_eq!(validate_percentile(Some(0.5)).ok(), Some(0.5));
+        assert_eq!(validate_percentile(Some(1.0)).ok(), Some(1.0));
+        assert!(validate_percentile(Some(0.0)).is_err());
+        assert!(validate_percentile(Some(1.1)).is_err());
+        assert!(validate_percentile(Some(f32::NAN)).is_err());
+    }
+
+    #[test]
+    fn test_global_prioritization_fees_per_slot() {
+        use solana_lite_rpc_prioritization_fees::prioritization_fee_data::{
+            BlockPrioData, PrioFeesData,
+        };
+
+        let recent_prio_fees = [(100, 10), (101, 30)]
+            .into_iter()
+            .map(|(slot, priority)| {
+                let block = BlockPrioData {
+                    transaction_data: vec![PrioFeesData::from((priority, 1000))],
+                    nb_non_vote_tx: 1,
+                    nb_total_tx: 1,
+                    non_vote_cu_consumed: 1000,
+                    total_cu_consumed: 1000,
+                    block_time: 0,
+                };
+                (slot, block.calculate_stats())
+            })
+            .collect();
+
+        let fees = global_prioritization_fees(recent_prio_fees, 0.75);
+        assert_eq!(
+            fees.iter()
+                .map(|fee| (fee.slot, fee.prioritization_fee))
+                .collect::<Vec<_>>(),
+            vec![(100, 10), (101, 30)]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_get_identity_returns_configured_identity() {
+        let identity = Pubkey::new_unique();
+        let bridge = test_bridge(
+            Arc::new(RpcClient::new_mock("succeeds".to_string())),
+            DataCache::new_for_tests(),
+            None,
+            LiteBridgeConfig {
+                identity,
+                ..test_bridge_config()
+            },
+        );
+
+        assert_eq!(
+            serde_json::to_value(bridge.get_identity().unwrap()).unwrap(),
+            serde_json::json!({ "identity": identity.to_string() })
+        );
+    }
+
+    #[tokio::test]
+    async fn test_send_bundle_without_relay_is_method_not_found() {
+        let bridge = test_bridge(
+            Arc::new(RpcClient::new_mock("succeeds".to_string())),
+            DataCache::new_for_tests(),
+            None,
+            test_bridge_config(),
+        );
+
+        let err = bridge
+            .send_bundle(vec!["tx".to_string()], None)
+            .await
+            .unwrap_err();
+
+        assert_eq!(
+            err.code(),
+            jsonrpsee::types::error::ErrorCode::MethodNotFound.code()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_request_airdrop_only_with_passthrough() {
+        let pubkey = Pubkey::new_unique().to_string();
+        let bridge = test_bridge(
+            Arc::new(RpcClient::new_mock("succeeds".to_string())),
+            DataCache::new_for_tests(),
+            None,
+            test_bridge_config(),
+        );
+        let err = bridge
+            .request_airdrop(pubkey.clone(), 1, None)
+            .await
+            .unwrap_err();
+        assert_eq!(
+            err.code(),
+            jsonrpsee::types::error::ErrorCode::MethodNotFound.code()
+        );
+
+        let bridge = test_bridge(
+            Arc::new(RpcClient::new_mock("succeeds".to_string())),
+            DataCache::new_for_tests(),
+            None,
+            LiteBridgeConfig {
+                upstream_passthrough_methods: vec!["requestAirdrop".to_string()],
+                ..test_bridge_config()
+            },
+        );
+        assert!(bridge.request_airdrop(pubkey, 1, None).await.is_ok());
+    }
+
+    #[test]
+    fn test_transaction_span_carries_signature() {
+        use std::sync::Mutex;
+        use tracing::span::{Attributes, Id, Record};
+        use tracing::Subscriber;
+        use tracing_subscriber::layer::{Context, SubscriberExt};
+        use tracing_subscriber::registry::LookupSpan;
+        use tracing_subscriber::Layer;
+
+        /// fields recorded on the spans, by field name
+        #[derive(Clone, Default)]
+        struct RecordedFields(Arc<Mutex<HashMap<String, String>>>);
+
+        impl field::Visit for RecordedFields {
+            fn record_debug(&mut self, field: &field::Field, value: &dyn std::fmt::Debug) {
+                self.0
+                    .lock()
+                    .unwrap()
+                    .insert(field.name().to_string(), format!("{value:?}"));
+            }
+            fn record_str(&mut self, field: &field::Field, value: &str) {
+                self.0
+                    .lock()
+                    .unwrap()
+                    .insert(field.name().to_string(), value.to_string());
+            }
+        }
+
+        impl<S: Subscriber + for<'a> LookupSpan<'a>> Layer<S> for RecordedFields {
+            fn on_new_span(&self, attrs: &Attributes<'_>, _: &Id, _: Context<'_, S>) {
+                attrs.record(&mut self.clone());
+            }
+            fn on_record(&self, _: &Id, values: &Record<'_>, _: Context<'_, S>) {
+                values.record(&mut self.clone());
+            }
+        }
+
+        let recorded = RecordedFields::default();
+        let subscriber = tracing_subscriber::registry().with(recorded.clone());
+        let signature = Signature::new_unique();
+        tracing::subscriber::with_default(subscriber, || {
+            let _span = transaction_span("sendTransaction", Some(&signature)).entered();
+        });
+
+        let fields = recorded.0.lock().unwrap();
+        assert_eq!(fields.get("signature"), Some(&signature.to_string()));
+        assert_eq!(
+            fields.get("method").map(String::as_str),
+            Some("sendTransaction")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_get_epoch_schedule_of_mainnet() {
+        // the genesis epoch schedule of mainnet-beta, with warmup epochs
+        let bridge = test_bridge(
+            Arc::new(RpcClient::new_mock("succeeds".to_string())),
+            DataCache {
+                epoch_data: solana_lite_rpc_core::structures::epoch::EpochCache::new(
+                    EpochSchedule::custom(432_000, 432_000, true),
+                ),
+                ..DataCache::new_for_tests()
+            },
+            None,
+            test_bridge_config(),
+        );
+
+        let epoch_schedule = bridge.get_epoch_schedule().await.unwrap();
+        assert_eq!(
+            serde_json::to_value(epoch_schedule).unwrap(),
+            serde_json::json!({
+                "slotsPerEpoch": 432_000,
+                "leaderScheduleSlotOffset": 432_000,
+                "warmup": true,
+                "firstNormalEpoch": 14,
+                "firstNormalSlot": 524_256,
+            })
+        );
+    }
+
+    #[test]
+    fn test_capabilities_without_accounts_service() {
+        let capabilities = rpc_capabilities(&None, &History::new().with_recent_blocks(100));
+        assert_eq!(
+            capabilities,
+            RpcCapabilities {
+                accounts: false,
+                prioritization_fees: true,
+                history: true,
+            }
+        );
+        assert_eq!(
+            serde_json::to_value(capabilities).unwrap(),
+            serde_json::json!({ "accounts": false, "prioritizationFees": true, "history": true })
+        );
+        assert!(!rpc_capabilities(&None, &History::new()).history);
+    }
+
+    #[test]
+    fn test_record_rpc_call() {
+        // labels not used by any rpc method, tests running in parallel do not touch them
+        let method = "testRecordRpcCall";
+        let other_method = "testRecordRpcCallOther";
+        let calls_before = RPC_METHOD_CALLS.with_label_values(&[method]).get();
+        let samples_before = RPC_METHOD_LATENCY
+            .with_label_values(&[method])
+            .get_sample_count();
+        let other_samples_before = RPC_METHOD_LATENCY
+            .with_label_values(&[other_method])
+            .get_sample_count();
+        {
+            let _timer = record_rpc_call(method);
+        }
+
+        assert_eq!(
+            RPC_METHOD_CALLS.with_label_values(&[method]).get() - calls_before,
+            1
+        );
+        assert_eq!(
+            RPC_METHOD_LATENCY
+                .with_label_values(&[method])
+                .get_sample_count()
+                - samples_before,
+            1
+        );
+        assert_eq!(
+            RPC_METHOD_LATENCY
+                .with_label_values(&[other_method])
+                .get_sample_count(),
+            other_samples_before
+        );
+    }
+
+    #[tokio::test]
+    async fn test_get_latest_blockhash_without_commitment_uses_default() {
+        let data_cache = DataCache::new_for_tests();
+        let finalized = data_cache
+            .block_information_store
+            .get_latest_block_information(CommitmentConfig::finalized())
+            .await;
+        let confirmed = BlockInformation {
+            slot: finalized.slot + 10,
+            block_height: finalized.block_height + 10,
+            blockhash: Hash::new_unique(),
+            commitment_config: CommitmentConfig::confirmed(),
+            ..finalized
+        };
+        data_cache
+            .block_information_store
+            .add_block(confirmed.clone())
+            .await;
+        let bridge = test_bridge(
+            Arc::new(RpcClient::new_mock("succeeds".to_string())),
+            data_cache,
+            None,
+            LiteBridgeConfig {
+                default_commitment: CommitmentConfig::finalized(),
+                ..test_bridge_config()
+            },
+        );
+
+        // a config without commitment and no config at all fall back to the same default
+        let without_commitment = bridge
+            .get_latest_blockhash(Some(RpcContextConfig {
+                commitment: None,
+                min_context_slot: None,
+            }))
+            .await
+            .unwrap();
+        let without_config = bridge.get_latest_blockhash(None).await.unwrap();
+        assert_eq!(without_commitment.context.slot, finalized.slot);
+        assert_eq!(
+            without_commitment.value.blockhash,
+            finalized.blockhash.to_string()
+        );
+        assert_eq!(without_config.context.slot, finalized.slot);
+
+        // an explicit commitment wins over the default
+        let with_commitment = bridge
+            .get_latest_blockhash(Some(RpcContextConfig {
+                commitment: Some(CommitmentConfig::confirmed()),
+                min_context_slot: None,
+            }))
+            .await
+            .unwrap();
+        assert_eq!(with_commitment.context.slot, confirmed.slot);
+        assert_eq!(
+            with_commitment.value.blockhash,
+            confirmed.blockhash.to_string()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_get_slot_uses_default_commitment() {
+        let data_cache = DataCache::new_for_tests();
+        let finalized = data_cache
+            .block_information_store
+            .get_latest_block_information(CommitmentConfig::finalized())
+            .await;
+        data_cache
+            .block_information_store
+            .add_block(BlockInformation {
+                slot: finalized.slot + 10,
+                block_height: finalized.block_height + 10,
+                blockhash: Hash::new_unique(),
+                commitment_config: CommitmentConfig::confirmed(),
+                ..finalized
+            })
+            .await;
+
+        assert_eq!(
+            latest_slot(&data_cache, None, CommitmentConfig::confirmed()).await,
+            finalized.slot + 10
+        );
+        assert_eq!(
+            latest_slot(&data_cache, None, CommitmentConfig::finalized()).await,
+            finalized.slot
+        );
+        // an explicit commitment wins over the default
+        assert_eq!(
+            latest_slot(
+                &data_cache,
+                Some(RpcContextConfig {
+                    commitment: Some(CommitmentConfig::finalized()),
+                    min_context_slot: None,
+                }),
+                CommitmentConfig::confirmed()
+            )
+            .await,
+            finalized.slot
+        );
+    }
+
+    #[tokio::test]
+    async fn test_blocks_with_limit() {
+        let data_cache = test_data_cache_at_slot(100);
+        let finalized = data_cache
+            .block_information_store
+            .get_latest_block_information(CommitmentConfig::finalized())
+            .await;
+        for offset in 1..=10 {
+            data_cache
+                .block_information_store
+                .add_block(BlockInformation {
+                    slot: finalized.slot + offset,
+                    block_height: finalized.block_height + offset,
+                    blockhash: Hash::new_unique(),
+                    commitment_config: CommitmentConfig::confirmed(),
+                    ..finalized.clone()
+                })
+                .await;
+        }
+        // a processed fork block is not counted
+        data_cache
+            .block_information_store
+            .add_block(BlockInformation {
+                slot: finalized.slot + 11,
+                block_height: finalized.block_height + 11,
+                blockhash: Hash::new_unique(),
+                commitment_config: CommitmentConfig::processed(),
+                ..finalized.clone()
+            })
+            .await;
+        let confirmed = CommitmentConfig::confirmed();
+        let start_slot = finalized.slot + 2;
+
+        let slots = blocks_with_limit(&data_cache, start_slot, 5, confirmed)
+            .await
+            .unwrap();
+        assert_eq!(
+            slots,
+            Some((start_slot..start_slot + 5).collect::<Vec<_>>())
+        );
+
+        // fewer slots near the tip
+        let slots = blocks_with_limit(&data_cache, finalized.slot + 8, 5, confirmed)
+            .await
+            .unwrap();
+        assert_eq!(
+            slots,
+            Some(vec![
+                finalized.slot + 8,
+                finalized.slot + 9,
+                finalized.slot + 10
+            ])
+        );
+        // confirmed blocks are above the finalized tip
+        let slots = blocks_with_limit(&data_cache, start_slot, 5, CommitmentConfig::finalized())
+            .await
+            .unwrap();
+        assert_eq!(slots, Some(vec![]));
+        // older than the store, not truncated to the retained blocks
+        let slots = blocks_with_limit(&data_cache, finalized.slot - 1, 5, confirmed)
+            .await
+            .unwrap();
+        assert_eq!(slots, None);
+
+        let too_large = MAX_GET_CONFIRMED_BLOCKS_RANGE as usize + 1;
+        assert!(
+            blocks_with_limit(&data_cache, start_slot, too_large, confirmed)
+                .await
+                .is_err()
+        );
+        assert!(
+            blocks_with_limit(&data_cache, start_slot, 5, CommitmentConfig::processed())
+                .await
+                .is_err()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_block_production_clamped_to_retained_slots() {
+        use solana_lite_rpc_core::structures::leaderschedule::{
+            CalculatedSchedule, LeaderScheduleData,
+        };
+
+        let leader = Pubkey::new_unique();
+        let data_cache = test_data_cache_at_slot(100);
+        *data_cache.leader_schedule.write().await = CalculatedSchedule {
+            current: Some(LeaderScheduleData {
+                schedule_by_node: HashMap::new(),
+                schedule_by_slot: vec![leader; 1000],
+                epoch: 0,
+            }),
+            ..Default::default()
+        };
+        let finalized = data_cache
+            .block_information_store
+            .get_latest_block_information(CommitmentConfig::finalized())
+            .await;
+        for offset in 1..=5 {
+            data_cache
+                .block_information_store
+                .add_block(BlockInformation {
+                    slot: finalized.slot + offset,
+                    block_height: finalized.block_height + offset,
+                    blockhash: Hash::new_unique(),
+                    commitment_config: CommitmentConfig::confirmed(),
+                    ..finalized.clone()
+                })
+                .await;
+        }
+        let bridge = test_bridge(
+            Arc::new(RpcClient::new_mock("fails".to_string())),
+            data_cache,
+            None,
+            test_bridge_config(),
+        );
+
+        // the epoch starts at slot 0, the slots before the store are not counted as skipped
+        let block_production = bridge.get_block_production(None).await.unwrap().value;
+        assert_eq!(block_production.range.first_slot, 100);
+        assert_eq!(block_production.range.last_slot, 105);
+        assert_eq!(
+            block_production.by_identity,
+            HashMap::from([(leader.to_string(), (6, 6))])
+        );
+    }
+
+    #[tokio::test]
+    async fn test_get_blocks_agrees_with_blocks_with_limit() {
+        let data_cache = test_data_cache_at_slot(100);
+        let finalized = data_cache
+            .block_information_store
+            .get_latest_block_information(CommitmentConfig::finalized())
+            .await;
+        for offset in 1..=5 {
+            data_cache
+                .block_information_store
+                .add_block(BlockInformation {
+                    slot: finalized.slot + offset,
+                    block_height: finalized.block_height + offset,
+                    blockhash: Hash::new_unique(),
+                    commitment_config: CommitmentConfig::confirmed(),
+                    ..finalized.clone()
+                })
+                .await;
+        }
+        let bridge = test_bridge(
+            Arc::new(RpcClient::new_mock("fails".to_string())),
+            data_cache,
+            None,
+            test_bridge_config(),
+        );
+        let context_config = Some(RpcContextConfig {
+            commitment: Some(CommitmentConfig::confirmed()),
+            min_context_slot: None,
+        });
+
+        let blocks = bridge
+            .get_blocks(finalized.slot, None, Some(CommitmentConfig::confirmed()))
+            .await
+            .unwrap();
+        let blocks_with_limit = bridge
+            .get_blocks_with_limit(finalized.slot, 10, context_config)
+            .await
+            .unwrap();
+        assert_eq!(blocks, (finalized.slot..=finalized.slot + 5).collect_vec());
+        assert_eq!(blocks, blocks_with_limit);
+
+        // not passed through to the upstream rpc
+        let not_available =
+            jsonrpsee::types::ErrorObjectOwned::from(SolanaRpcError::BlockNotAvailable {
+                slot: finalized.slot - 1,
+            });
+        assert_eq!(
+            bridge
+                .get_blocks(finalized.slot - 1, None, None)
+                .await
+                .unwrap_err(),
+            not_available
+        );
+        assert_eq!(
+            bridge
+                .get_blocks_with_limit(finalized.slot - 1, 10, context_config)
+                .await
+                .unwrap_err(),
+            not_available
+        );
+    }
+
+    #[tokio::test]
+    async fn test_older_blocks_with_limit_forwarded_to_upstream() {
+        use solana_lite_rpc_util::http_mock::{start_http_mock, HttpResponse};
+
+        let (addr, mut requests) = start_http_mock(|_, _| {
+            HttpResponse::json(r#"{"jsonrpc":"2.0","result":[97,98,99],"id":1}"#)
+        })
+        .await;
+        let bridge = test_bridge(
+            Arc::new(RpcClient::new(format!("http://{addr}"))),
+            test_data_cache_at_slot(100),
+            None,
+            LiteBridgeConfig {
+                upstream_passthrough_methods: vec!["getBlocksWithLimit".to_string()],
+                ..test_bridge_config()
+            },
+        );
+
+        // below the retained blocks
+        let blocks = bridge.get_blocks_with_limit(97, 3, None).await.unwrap();
+        assert_eq!(blocks, vec![97, 98, 99]);
+        let request = requests.recv().await.unwrap();
+        assert!(request.body.contains(r#""method":"getBlocksWithLimit""#));
+        assert!(request.body.contains("[97,3,"));
+    }
+
+    #[tokio::test]
+    async fn test_stake_minimum_delegation_cached_with_context_slot() {
+        let data_cache = DataCache::new_for_tests();
+        let confirmed = data_cache
+            .block_information_store
+            .get_latest_block_information(CommitmentConfig::confirmed())
+            .await;
+        let stake_minimum_delegation = StakeMinimumDelegation::new(None);
+
+        let response = stake_minimum_delegation_response(
+            &data_cache,
+            &stake_minimum_delegation,
+            None,
+            CommitmentConfig::confirmed(),
+            || async { Ok::<_, anyhow::Error>(1_000_000_000) },
+        )
+        .await
+        .unwrap();
+        assert_eq!(response.value, 1_000_000_000);
+        assert_eq!(response.context.slot, confirmed.slot);
+
+        // served from the cache, the rpc is not asked again
+        let response = stake_minimum_delegation_response(
+            &data_cache,
+            &stake_minimum_delegation,
+            None,
+            CommitmentConfig::confirmed(),
+            || async { anyhow::bail!("must be cached") },
+        )
+        .await
+        .unwrap();
+        assert_eq!(response.value, 1_000_000_000);
+        assert_eq!(response.context.slot, confirmed.slot);
+    }
+
+    #[test]
+    fn test_minimum_balance_for_rent_exemption() {
+        let rent = Rent::default();
+        assert_eq!(minimum_balance_for_rent_exemption(&rent, 0), Ok(890_880));
+        // spl token account
+        assert_eq!(
+            minimum_balance_for_rent_exemption(&rent, 165),
+            Ok(2_039_280)
+        );
+
+        let max_len = MAX_PERMITTED_DATA_LENGTH as usize;
+        assert!(minimum_balance_for_rent_exemption(&rent, max_len).is_ok());
+        let err = minimum_balance_for_rent_exemption(&rent, max_len + 1).unwrap_err();
+        assert_eq!(
+            err.code(),
+            jsonrpsee::types::error::ErrorCode::InvalidParams.code()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_get_minimum_balance_for_rent_exemption() {
+        let bridge = test_bridge(
+            Arc::new(RpcClient::new_mock("fails".to_string())),
+            DataCache::new_for_tests(),
+            None,
+            LiteBridgeConfig {
+                rent_lamports_per_byte_year: Some(Rent::default().lamports_per_byte_year),
+                ..test_bridge_config()
+            },
+        );
+
+        // a bare number like solana's rpc, whatever the commitment
+        let minimum_balance = bridge
+            .get_minimum_balance_for_rent_exemption(165, Some(CommitmentConfig::finalized()))
+            .await
+            .unwrap();
+        assert_eq!(
+            serde_json::to_value(minimum_balance).unwrap(),
+            serde_json::json!(2_039_280u64)
+        );
+        assert_eq!(
+            bridge
+                .get_minimum_balance_for_rent_exemption(165, None)
+                .await
+                .unwrap(),
+            2_039_280
+        );
+    }
+
+    #[tokio::test]
+    async fn test_expired_blockhash_is_rejected() {
+        let data_cache = DataCache::new_for_tests();
+        let finalized = data_cache
+            .block_information_store
+            .get_latest_block_information(CommitmentConfig::finalized())
+            .await;
+        let expired_blockhash = Hash::new_unique();
+        data_cache
+            .block_information_store
+            .add_block(BlockInformation {
+                slot: finalized.slot + 1,
+                block_height: finalized.block_height + 1,
+                last_valid_blockheight: finalized.block_height + 5,
+                blockhash: expired_blockhash,
+                ..finalized
+            })
+            .await;
+        let latest_blockhash = Hash::new_unique();
+        data_cache
+            .block_information_store
+            .add_block(BlockInformation {
+                slot: finalized.slot + 10,
+                block_height: finalized.block_height + 10,
+                last_valid_blockheight: finalized.block_height + 160,
+                blockhash: latest_blockhash,
+                ..finalized
+            })
+            .await;
+
+        let bridge = test_bridge(
+            Arc::new(RpcClient::new_mock("succeeds".to_string())),
+            data_cache,
+            None,
+            test_bridge_config(),
+        );
+
+        let send = |recent_blockhash| {
+            let transaction = VersionedTransaction {
+                signatures: vec![Signature::new_unique()],
+                message: VersionedMessage::Legacy(Message {
+                    recent_blockhash,
+                    ..Message::default()
+                }),
+            };
+            let tx = bs58::encode(bincode::serialize(&transaction).unwrap()).into_string();
+            bridge.send_transaction(tx, None)
+        };
+
+        // rejected by the transaction service
+        let expected: jsonrpsee::types::ErrorObjectOwned = SolanaRpcError::BlockhashNotFound.into();
+        assert_eq!(send(expired_blockhash).await.unwrap_err(), expected);
+        assert_eq!(send(Hash::new_unique()).await.unwrap_err(), expected);
+    }
+
+    #[tokio::test]
+    async fn test_accounts_served_after_warm_up() {
+        use solana_lite_rpc_core::structures::account_filter::AccountFilter;
+        use solana_rpc_client::mock_sender::Mocks;
+        use solana_sdk::account::Account;
+
+        let pubkey = Pubkey::new_unique();
+        let account = Account {
+            lamports: 42_000,
+            owner: Pubkey::new_unique(),
+            ..Account::default()
+        };
+        let filters = vec![AccountFilter {
+            accounts: vec![pubkey.to_string()],
+            program_id: None,
+            filters: None,
+        }];
+        let (account_notification_sender, _) = tokio::sync::broadcast::channel(16);
+        let account_service = AccountService::new(
+            Arc::new(InmemoryAccountStore::default()),
+            account_notification_sender,
+        );
+        let bridge = test_bridge(
+            Arc::new(RpcClient::new_mock("succeeds".to_string())),
+            DataCache::new_for_tests(),
+            Some(account_service.clone()),
+            test_bridge_config(),
+        );
+        let warming_up =
+            jsonrpsee::types::ErrorObjectOwned::from(SolanaRpcError::AccountsWarmingUp);
+
+        // the snapshot is not loaded yet, the account is not reported as missing
+        assert_eq!(
+            bridge
+                .get_account_info(pubkey.to_string(), None)
+                .await
+                .unwrap_err(),
+            warming_up
+        );
+
+        // every getMultipleAccounts attempt fails, the snapshot is incomplete
+        let failing_rpc = Arc::new(RpcClient::new_mock("fails".to_string()));
+        assert!(account_service
+            .populate_from_rpc(failing_rpc, &filters, 1)
+            .await
+            .is_err());
+        assert_eq!(
+            bridge
+                .get_account_info(pubkey.to_string(), None)
+                .await
+                .unwrap_err(),
+            warming_up
+        );
+
+        let mut mocks = Mocks::new();
+        mocks.insert(
+            RpcRequest::GetMultipleAccounts,
+            serde_json::to_value(RpcResponse {
+                context: RpcResponseContext {
+                    slot: 100,
+                    api_version: None,
+                },
+                value: vec![Some(UiAccount::encode(
+                    &pubkey,
+                    &account,
+                    solana_account_decoder::UiAccountEncoding::Base64,
+                    None,
+                    None,
+                ))],
+            })
+            .unwrap(),
+        );
+        let rpc = Arc::new(RpcClient::new_mock_with_mocks(
+            "succeeds".to_string(),
+            mocks,
+        ));
+        account_service
+            .populate_from_rpc(rpc, &filters, 1)
+            .await
+            .unwrap();
+        assert_eq!(account_service.ready_slot(), Some(100));
+        let served = bridge
+            .get_account_info(pubkey.to_string(), None)
+            .await
+            .unwrap();
+        assert_eq!(served.value.map(|account| account.lamports), Some(42_000));
+    }
+
+    #[tokio::test]
+    async fn test_get_balance_min_context_slot_not_reached() {
+        let data_cache = DataCache::new_for_tests();
+        let context_slot = data_cache
+            .block_information_store
+            .get_latest_block_information(CommitmentConfig::confirmed())
+            .await
+            .slot;
+        let bridge = test_bridge(
+            Arc::new(RpcClient::new_mock("succeeds".to_string())),
+            data_cache,
+            None,
+            test_bridge_config(),
+        );
+        let get_balance = |min_context_slot| {
+            bridge.get_balance(
+                Pubkey::new_unique().to_string(),
+                Some(RpcContextConfig {
+                    commitment: Some(CommitmentConfig::confirmed()),
+                    min_context_slot: Some(min_context_slot),
+                }),
+            )
+        };
+
+        assert_eq!(
+            get_balance(context_slot + 1).await.unwrap_err(),
+            jsonrpsee::types::ErrorObjectOwned::from(SolanaRpcError::MinContextSlotNotReached {
+                context_slot
+            })
+        );
+        // reached, fails later because accounts are disabled
+        assert_eq!(
+            get_balance(context_slot).await.unwrap_err().code(),
+            jsonrpsee::types::error::ErrorCode::MethodNotFound.code()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_get_balance_of_account_updated_before_min_context_slot() {
+        use solana_lite_rpc_accounts::account_store_interface::AccountStorageInterface;
+        use solana_lite_rpc_core::structures::account_data::AccountData;
+        use solana_sdk::account::Account;
+
+        let store = Arc::new(InmemoryAccountStore::default());
+        let pubkey = Pubkey::new_unique();
+        store
+            .initilize_or_update_account(AccountData {
+                pubkey,
+                account: Arc::new(Account {
+                    lamports: 42_000,
+                    owner: Pubkey::new_unique(),
+                    ..Account::default()
+                }),
+                updated_slot: 10,
+            })
+            .await;
+        let (account_notification_sender, _) = tokio::sync::broadcast::channel(16);
+        let account_service = AccountService::new(store, account_notification_sender);
+        account_service.mark_ready(10);
+        let bridge = test_bridge(
+            Arc::new(RpcClient::new_mock("succeeds".to_string())),
+            test_data_cache_at_slot(100),
+            Some(account_service),
+            test_bridge_config(),
+        );
+
+        // the node reached slot 50, the account was not written since slot 10
+        let balance = bridge
+            .get_balance(
+                pubkey.to_string(),
+                Some(RpcContextConfig {
+                    commitment: Some(CommitmentConfig::finalized()),
+                    min_context_slot: Some(50),
+                }),
+            )
+            .await
+            .unwrap();
+        assert_eq!(balance.context.slot, 100);
+        assert_eq!(balance.value, 42_000);
+    }
+
+    #[test]
+    fn test_estimate_future_block_time() {
+        let latest = BlockInformation {
+            slot: 1000,
+            block_height: 900,
+            last_valid_blockheight: 1050,
+            cleanup_slot: 2000,
+            blockhash: Hash::new_unique(),
+            commitment_config: CommitmentConfig::confirmed(),
+            block_time: 1_700_000_000,
+            parent_slot: 999,
+            block_time_estimated: false,
+        };
+
+        // 10 slots of 400ms
+        assert_eq!(estimate_future_block_time(&latest, 1010), Ok(1_700_000_004));
+        assert_eq!(
+            serde_json::to_value(RpcBlockTime::WithEstimate {
+                block_time: 1_700_000_004,
+                estimated: true,
+            })
+            .unwrap(),
+            serde_json::json!({ "blockTime": 1_700_000_004u64, "estimated": true })
+        );
+        assert_eq!(
+            serde_json::to_value(RpcBlockTime::Actual(1_700_000_000)).unwrap(),
+            serde_json::json!(1_700_000_000u64)
+        );
+
+        assert_eq!(
+            estimate_future_block_time(&latest, 1000 + MAX_BLOCK_TIME_ESTIMATE_SLOTS + 1),
+            Err(SolanaRpcError::BlockNotAvailable {
+                slot: 1000 + MAX_BLOCK_TIME_ESTIMATE_SLOTS + 1
+            })
+        );
+    }
+
+    #[tokio::test]
+    async fn test_get_block_time_reports_interpolated_time_as_estimated() {
+        let data_cache = test_data_cache_at_slot(100);
+        let parent = BlockInformation {
+            slot: 101,
+            block_height: 101,
+            last_valid_blockheight: 401,
+            cleanup_slot: 1101,
+            blockhash: Hash::new_unique(),
+            commitment_config: CommitmentConfig::confirmed(),
+            block_time: 1_700_000_000,
+            parent_slot: 100,
+            block_time_estimated: false,
+        };
+        // no block time, interpolated from the parent
+        let child = BlockInformation {
+            slot: 111,
+            block_height: 102,
+            blockhash: Hash::new_unique(),
+            block_time: 0,
+            parent_slot: 101,
+            ..parent.clone()
+        };
+        data_cache.block_information_store.add_block(parent).await;
+        data_cache.block_information_store.add_block(child).await;
+        let bridge = test_bridge(
+            Arc::new(RpcClient::new_mock("fails".to_string())),
+            data_cache,
+            None,
+            test_bridge_config(),
+        );
+        let config = || {
+            Some(BlockTimeConfig {
+                estimate_future_slots: true,
+            })
+        };
+
+        assert_eq!(
+            bridge.get_block_time(101, config()).await.unwrap(),
+            RpcBlockTime::WithEstimate {
+                block_time: 1_700_000_000,
+                estimated: false,
+            }
+        );
+        assert_eq!(
+            bridge.get_block_time(111, config()).await.unwrap(),
+            RpcBlockTime::WithEstimate {
+                block_time: 1_700_000_004,
+                estimated: true,
+            }
+        );
+    }
+
+    #[test]
+    fn test_latest_priofees_at_percentile() {
+        let stats = BlockPrioData {
+            transaction_data: (1..=100).map(|x| PrioFeesData::from((x * 10, 1))).collect(),
+            ..Default::default()
+        }
+        .calculate_stats();
+
+        let LatestPrioFees::Stats(full) = latest_priofees(stats.clone(), None) else {
+            panic!("expected the full stats");
+        };
+        assert_eq!(full.by_tx, stats.by_tx);
+        assert_eq!(full.by_cu, stats.by_cu);
+
+        // interpolated between the p70 and p75 buckets
+        let LatestPrioFees::AtPercentile(collapsed) = latest_priofees(stats, Some(0.73)) else {
+            panic!("expected the fee at the percentile");
+        };
+        assert_eq!(
+            collapsed,
+            PrioFeeAtPercentile {
+                percentile: 0.73,
+                fee: 740,
+            }
+        );
+
+        assert!(validate_percentile(Some(1.5)).is_err());
+        assert!(validate_percentile(Some(0.0)).is_err());
+    }
+
+    #[test]
+    fn test_ui_block_from_history() {
+        let block = ProducedBlock::new(
+            solana_lite_rpc_core::structures::produced_block::ProducedBlockInner {
+                transactions: vec![],
+                leader_id: None,
+                blockhash: Hash::new_unique(),
+                block_height: 90,
+                slot: 100,
+                parent_slot: 99,
+                block_time: 1_700_000_000,
+                previous_blockhash: Hash::new_unique(),
+                rewards: None,
+                stats: Default::default(),
+            },
+            CommitmentConfig::confirmed(),
+        );
+        let config = RpcBlockConfig {
+            transaction_details: Some(TransactionDetails::Signatures),
+            rewards: Some(false),
+            commitment: Some(CommitmentConfig::confirmed()),
+            ..RpcBlockConfig::default()
+        };
+
+        let ui_block = ui_block_from_history(&block, &config).unwrap();
+        assert_eq!(ui_block.blockhash, block.blockhash.to_string());
+        assert_eq!(ui_block.parent_slot, 99);
+        assert_eq!(ui_block.block_height, Some(90));
+        assert_eq!(ui_block.signatures, Some(vec![]));
+        assert!(ui_block.rewards.is_none());
+
+        // full transactions need the metas, the block is not finalized yet
+        let full = RpcBlockConfig {
+            transaction_details: Some(TransactionDetails::Full),
+            ..config
+        };
+        assert!(ui_block_from_history(&block, &full).is_none());
+        let finalized = RpcBlockConfig {
+            commitment: Some(CommitmentConfig::finalized()),
+            ..config
+        };
+        assert!(ui_block_from_history(&block, &finalized).is_none());
+    }
+
+    #[tokio::test]
+    async fn test_get_block_from_recent_blocks() {
+        let block = ProducedBlock::new(
+            solana_lite_rpc_core::structures::produced_block::ProducedBlockInner {
+                transactions: vec![],
+                leader_id: None,
+                blockhash: Hash::new_unique(),
+                block_height: 90,
+                slot: 100,
+                parent_slot: 99,
+                block_time: 1_700_000_000,
+                previous_blockhash: Hash::new_unique(),
+                rewards: None,
+                stats: Default::default(),
+            },
+            CommitmentConfig::confirmed(),
+        );
+        let bridge = test_bridge(
+            Arc::new(RpcClient::new_mock("fails".to_string())),
+            DataCache::new_for_tests(),
+            None,
+            LiteBridgeConfig {
+                history: History::new().with_recent_blocks(100),
+                ..test_bridge_config()
+            },
+        );
+        bridge.history.add_block(block.clone());
+        let config = RpcBlockConfig {
+            transaction_details: Some(TransactionDetails::None),
+            commitment: Some(CommitmentConfig::confirmed()),
+            ..RpcBlockConfig::default()
+        };
+
+        let ui_block = bridge
+            .get_block(100, Some(RpcEncodingConfigWrapper::Current(Some(config))))
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(ui_block.blockhash, block.blockhash.to_string());
+        assert_eq!(ui_block.rewards, Some(vec![]));
+
+        // not retained, getBlock is not configured for passthrough
+        assert!(bridge
+            .get_block(50, Some(RpcEncodingConfigWrapper::Current(Some(config))))
+            .await
+            .is_err());
+    }
 }
@@ -8,9 +8,10 @@ use solana_lite_rpc_core::{
 };
 use std::{str::FromStr, sync::Arc, time::Duration};
 use tokio::sync::broadcast::error::RecvError::{Closed, Lagged};
+use tracing::Instrument;
 
 use crate::{
-    jsonrpsee_subscrption_handler_sink::JsonRpseeSubscriptionHandlerSink,
+    bridge::transaction_span, jsonrpsee_subscrption_handler_sink::JsonRpseeSubscriptionHandlerSink,
     rpc_pubsub::LiteRpcPubSubServer,
 };
 use jsonrpsee::{
@@ -144,16 +145,23 @@ impl LiteRpcPubSubServer for LitePubSubBridge {
     ) -> SubscriptionResult {
         RPC_SIGNATURE_SUBSCRIBE.inc();
         let signature = Signature::from_str(&signature)?;
-        let sink = pending.accept().await?;
-
-        let jsonrpsee_sink = JsonRpseeSubscriptionHandlerSink::new(sink);
-        self.data_cache.tx_subs.signature_subscribe(
-            signature,
-            config.commitment.unwrap_or_default(),
-            Arc::new(jsonrpsee_sink),
-        );
-
-        Ok(())
+        let span = transaction_span("signatureSubscribe", Some(&signature));
+        async {
+            let sink = pending.accept().await?;
+
+            let commitment = config.commitment.unwrap_or_default();
+            log::debug!("subscribed to {:?} commitment", commitment.commitment);
+            let jsonrpsee_sink = JsonRpseeSubscriptionHandlerSink::new(sink);
+            self.data_cache.tx_subs.signature_subscribe(
+                signature,
+                commitment,
+                Arc::new(jsonrpsee_sink),
+            );
+
+            Ok::<_, StringError>(())
+        }
+        .instrument(span)
+        .await
     }
 
     async fn slot_updates_subscribe(
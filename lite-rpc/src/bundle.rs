@@ -0,0 +1,118 @@
+use serde_json::json;
+use solana_lite_rpc_core::encoding::BASE64;
+use solana_rpc_client::nonblocking::rpc_client::RpcClient;
+use solana_rpc_client_api::client_error::Error as ClientError;
+use solana_rpc_client_api::request::RpcRequest;
+
+// same limit as the jito block engine
+pub const MAX_BUNDLE_SIZE: usize = 5;
+
+/// Index of the transaction in the bundle that failed validation
+#[derive(Debug)]
+pub struct BundleError {
+    pub index: usize,
+    pub error: anyhow::Error,
+}
+
+/// Validates every transaction of the bundle, the relay only receives bundles without a known invalid transaction
+pub fn validate_bundle<V>(wire_txs: &[Vec<u8>], validate: V) -> Result<(), BundleError>
+where
+    V: Fn(&[u8]) -> anyhow::Result<()>,
+{
+    for (index, wire_tx) in wire_txs.iter().enumerate() {
+        validate(wire_tx).map_err(|error| BundleError { index, error })?;
+    }
+    Ok(())
+}
+
+/// params of the jito style sendBundle request, the relay lands the transactions all or none in bundle order
+pub fn bundle_relay_params(wire_txs: &[Vec<u8>]) -> serde_json::Value {
+    let encoded = wire_txs
+        .iter()
+        .map(|wire_tx| BASE64.encode(wire_tx))
+        .collect::<Vec<_>>();
+    json!([encoded, { "encoding": "base64" }])
+}
+
+/// Submits the bundle to the relay, returns the bundle id assigned by the relay
+pub async fn submit_bundle(relay: &RpcClient, wire_txs: &[Vec<u8>]) -> Result<String, ClientError> {
+    relay
+        .send::<String>(
+            RpcRequest::Custom {
+                method: "sendBundle",
+            },
+            bundle_relay_params(wire_txs),
+        )
+        .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_forwarded_bundle_preserves_order() {
+        use solana_lite_rpc_util::http_mock::{start_http_mock, HttpResponse};
+        use solana_sdk::hash::Hash;
+        use solana_sdk::pubkey::Pubkey;
+        use solana_sdk::signature::Keypair;
+        use solana_sdk::transaction::Transaction;
+
+        let (addr, mut requests) = start_http_mock(|_, _| {
+            HttpResponse::json(r#"{"jsonrpc":"2.0","result":"bundle-id","id":1}"#)
+        })
+        .await;
+        let relay = RpcClient::new(format!("http://{addr}"));
+        let payer = Keypair::new();
+        let txs = (1..=3)
+            .map(|lamports| {
+                solana_sdk::system_transaction::transfer(
+                    &payer,
+                    &Pubkey::new_unique(),
+                    lamports,
+                    Hash::new_unique(),
+                )
+            })
+            .collect::<Vec<_>>();
+        let wire_txs = txs
+            .iter()
+            .map(|tx| bincode::serialize(tx).unwrap())
+            .collect::<Vec<_>>();
+
+        let bundle_id = submit_bundle(&relay, &wire_txs).await.unwrap();
+        assert_eq!(bundle_id, "bundle-id");
+
+        let request = requests.recv().await.unwrap();
+        let body: serde_json::Value = serde_json::from_str(&request.body).unwrap();
+        assert_eq!(body["method"], "sendBundle");
+        assert_eq!(body["params"][1], json!({ "encoding": "base64" }));
+        let forwarded_signatures = body["params"][0]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|encoded| {
+                BASE64
+                    .deserialize::<Transaction>(&encoded.as_str().unwrap().to_string())
+                    .unwrap()
+                    .signatures[0]
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            forwarded_signatures,
+            txs.iter().map(|tx| tx.signatures[0]).collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn test_invalid_bundle_reports_index() {
+        let err = validate_bundle(&[vec![1], vec![2], vec![3]], |wire_tx| {
+            if wire_tx[0] == 3 {
+                anyhow::bail!("Blockhash is expired");
+            }
+            Ok(())
+        })
+        .unwrap_err();
+
+        assert_eq!(err.index, 2);
+    }
+}
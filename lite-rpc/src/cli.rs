@@ -1,19 +1,25 @@
-use std::borrow::Cow;
-use std::fmt::{Debug, Display, Formatter};
+use std::collections::HashMap;
 use std::net::SocketAddr;
 use std::str::FromStr;
 use std::{env, time::Duration};
 
 use crate::postgres_logger::{self, PostgresSessionConfig};
 use crate::{
-    DEFAULT_FANOUT_SIZE, DEFAULT_GRPC_ADDR, DEFAULT_RETRY_TIMEOUT, DEFAULT_RPC_ADDR,
-    DEFAULT_WS_ADDR, MAX_RETRIES,
+    DEFAULT_ACCOUNT_PRIOFEES_RETENTION_SLOTS, DEFAULT_FANOUT_SIZE, DEFAULT_GRPC_ADDR,
+    DEFAULT_HEALTH_CHECK_SLOT_DISTANCE, DEFAULT_RETRY_TIMEOUT, DEFAULT_RPC_ADDR,
+    DEFAULT_SUPPLY_CACHE_INTERVAL_SECS, DEFAULT_UPSTREAM_MAX_QUEUE_WAIT_MS,
+    DEFAULT_UPSTREAM_MAX_REQUESTS_PER_SECOND, DEFAULT_UPSTREAM_MAX_RETRIES,
+    DEFAULT_UPSTREAM_RETRY_BACKOFF_MS, DEFAULT_WS_ADDR, MAX_ALLOWED_RETRIES, MAX_RETRIES,
 };
 use anyhow::Context;
 use clap::Parser;
 use dotenv::dotenv;
+use solana_lite_rpc_cluster_endpoints::grpc_inspect::ConfirmationRuleSet;
+use solana_lite_rpc_cluster_endpoints::grpc_multiplex_config::GrpcMultiplexConfig;
+use solana_lite_rpc_core::structures::leaderschedule::DEFAULT_LEADER_SCHEDULE_LOOKAHEAD_SLOTS;
+use solana_lite_rpc_services::data_caching_service::DEFAULT_SLOT_DRIFT_ALARM_THRESHOLD;
 use solana_lite_rpc_services::quic_connection_utils::QuicConnectionParameters;
-use solana_rpc_client_api::client_error::reqwest::Url;
+use solana_sdk::commitment_config::CommitmentLevel;
 
 #[derive(Parser, Debug, Clone)]
 #[command(author, version, about, long_about = None)]
@@ -42,6 +48,8 @@ pub struct Config {
     pub prometheus_addr: String,
     #[serde(default = "Config::default_maximum_retries_per_tx")]
     pub maximum_retries_per_tx: usize,
+    #[serde(default = "Config::default_max_allowed_retries")]
+    pub max_allowed_retries: usize,
     #[serde(default = "Config::default_transaction_retry_after_secs")]
     pub transaction_retry_after_secs: u64,
     #[serde(default)]
@@ -73,6 +81,10 @@ pub struct Config {
     #[serde(default)]
     pub enable_grpc_stream_inspection: bool,
 
+    /// invariants checked by the grpc stream inspection
+    #[serde(default)]
+    pub grpc_inspect_rules: ConfirmationRuleSet,
+
     /// postgres config
     #[serde(default)]
     pub postgres: Option<postgres_logger::PostgresSessionConfig>,
@@ -94,6 +106,90 @@ pub struct Config {
 
     #[serde(default)]
     pub quic_connection_parameters: Option<QuicConnectionParameters>,
+
+    #[serde(default = "Config::default_health_check_slot_distance")]
+    pub health_check_slot_distance: u64,
+
+    /// serve older blocks from the postgres blockstore, configured with the PG_* env variables
+    #[serde(default)]
+    pub enable_postgres_block_history: bool,
+
+    /// number of recent slots whose blocks are kept in memory for getBlock, older blocks are evicted,
+    /// none keeps no blocks in memory
+    #[serde(default)]
+    pub block_retention_slots: Option<u64>,
+
+    /// number of recent slots whose account prioritization fees are kept for getLatestAccountPrioFees
+    #[serde(default = "Config::default_account_priofees_retention_slots")]
+    pub account_priofees_retention_slots: usize,
+
+    /// commitment used when a request does not specify one
+    #[serde(default)]
+    pub default_commitment: CommitmentLevel,
+
+    /// fetched from the rpc on startup if not set
+    #[serde(default)]
+    pub genesis_hash: Option<String>,
+
+    /// shared rate limit of the requests proxied to the upstream rpc
+    #[serde(default = "Config::default_upstream_max_requests_per_second")]
+    pub upstream_max_requests_per_second: u32,
+
+    /// retries of a proxied request rate limited by the upstream rpc
+    #[serde(default = "Config::default_upstream_max_retries")]
+    pub upstream_max_retries: u32,
+
+    #[serde(default = "Config::default_upstream_retry_backoff_ms")]
+    pub upstream_retry_backoff_ms: u64,
+
+    /// proxied requests which would wait longer than this for the rate limit are rejected
+    #[serde(default = "Config::default_upstream_max_queue_wait_ms")]
+    pub upstream_max_queue_wait_ms: u64,
+
+    /// unimplemented methods forwarded to the upstream rpc, e.g. getBlock, instead of answering MethodNotFound
+    #[serde(default)]
+    pub upstream_passthrough_methods: Vec<String>,
+
+    /// getSupply is fetched from the upstream rpc at most once per interval
+    #[serde(default = "Config::default_supply_cache_interval_secs")]
+    pub supply_cache_interval_secs: u64,
+
+    /// concurrent connections accepted by each of the http and websocket servers
+    #[serde(default)]
+    pub max_connections: Option<u32>,
+
+    /// request size limit of both servers, by default http accepts requests up to the response size limit
+    #[serde(default)]
+    pub max_request_body_size: Option<u32>,
+
+    /// returned by getStakeMinimumDelegation, fetched once from the rpc if not set
+    #[serde(default)]
+    pub stake_minimum_delegation: Option<u64>,
+
+    /// rent rate of getMinimumBalanceForRentExemption, fetched once from the rent sysvar if not set
+    #[serde(default)]
+    pub rent_lamports_per_byte_year: Option<u64>,
+
+    /// getClusterNodes leaves out nodes not refreshed within the ttl,
+    /// should be longer than the 10 minute cluster info polling interval
+    #[serde(default)]
+    pub cluster_nodes_ttl_secs: Option<u64>,
+
+    /// warn if the estimated slot is further away from the processed slot
+    #[serde(default = "Config::default_slot_drift_alarm_threshold")]
+    pub slot_drift_alarm_threshold: u64,
+
+    /// slots of the next epoch cached with the current epoch for getSlotLeaders, 0 disables the cache
+    #[serde(default = "Config::default_leader_schedule_lookahead_slots")]
+    pub leader_schedule_lookahead_slots: u64,
+
+    /// sendTransaction does not forward a signature again while its blockhash is valid
+    #[serde(default = "Config::default_enable_transaction_dedup")]
+    pub enable_transaction_dedup: bool,
+
+    /// jito style block engine sendBundle forwards to, sendBundle is disabled if not set
+    #[serde(default)]
+    pub bundle_relay_url: Option<String>,
 }
 
 impl Config {
@@ -151,6 +247,10 @@ impl Config {
             .map(|max| max.parse().unwrap())
             .unwrap_or(config.maximum_retries_per_tx);
 
+        config.max_allowed_retries = env::var("MAX_ALLOWED_RETRIES")
+            .map(|max| max.parse().unwrap())
+            .unwrap_or(config.max_allowed_retries);
+
         config.transaction_retry_after_secs = env::var("RETRY_TIMEOUT")
             .map(|secs| secs.parse().unwrap())
             .unwrap_or(config.transaction_retry_after_secs);
@@ -161,49 +261,19 @@ impl Config {
             .map(|value| value.parse::<bool>().unwrap())
             .unwrap_or(config.use_grpc);
 
-        // source 1
-        config.grpc_addr = env::var("GRPC_ADDR").unwrap_or(config.grpc_addr);
-        config.grpc_x_token = env::var("GRPC_X_TOKEN")
-            .map(Some)
-            .unwrap_or(config.grpc_x_token);
-
-        assert!(
-            env::var("GRPC_ADDR1").is_err(),
-            "use GRPC_ADDR instead of GRPC_ADDR1"
-        );
-        assert!(
-            env::var("GRPC_X_TOKEN1").is_err(),
-            "use GRPC_X_TOKEN instead of GRPC_X_TOKEN1"
-        );
-
-        // source 2
-        config.grpc_addr2 = env::var("GRPC_ADDR2")
-            .map(Some)
-            .unwrap_or(config.grpc_addr2);
-        config.grpc_x_token2 = env::var("GRPC_X_TOKEN2")
-            .map(Some)
-            .unwrap_or(config.grpc_x_token2);
-
-        // source 3
-        config.grpc_addr3 = env::var("GRPC_ADDR3")
-            .map(Some)
-            .unwrap_or(config.grpc_addr3);
-        config.grpc_x_token3 = env::var("GRPC_X_TOKEN3")
-            .map(Some)
-            .unwrap_or(config.grpc_x_token3);
-
-        // source 4
-        config.grpc_addr4 = env::var("GRPC_ADDR4")
-            .map(Some)
-            .unwrap_or(config.grpc_addr4);
-        config.grpc_x_token4 = env::var("GRPC_X_TOKEN4")
-            .map(Some)
-            .unwrap_or(config.grpc_x_token4);
-
         config.enable_grpc_stream_inspection = env::var("ENABLE_GRPC_STREAM_INSPECTION")
             .map(|value| value.parse::<bool>().expect("bool value"))
             .unwrap_or(config.enable_grpc_stream_inspection);
 
+        if let Ok(rules) = env::var("GRPC_INSPECT_RULES") {
+            config.grpc_inspect_rules.rules =
+                ConfirmationRuleSet::parse_rules(&rules).context("GRPC_INSPECT_RULES")?;
+        }
+
+        config.grpc_inspect_rules.violation_level = env::var("GRPC_INSPECT_VIOLATION_LEVEL")
+            .map(|value| value.parse().expect("warn or error"))
+            .unwrap_or(config.grpc_inspect_rules.violation_level);
+
         config.max_number_of_connection = env::var("MAX_NB_OF_CONNECTIONS_WITH_LEADERS")
             .map(|x| x.parse().ok())
             .unwrap_or(config.max_number_of_connection);
@@ -224,6 +294,102 @@ impl Config {
             .ok()
             .or(config.enable_accounts_on_demand_accounts_service);
 
+        config.health_check_slot_distance = env::var("HEALTH_CHECK_SLOT_DISTANCE")
+            .map(|distance| distance.parse().unwrap())
+            .unwrap_or(config.health_check_slot_distance);
+
+        config.enable_postgres_block_history = env::var("ENABLE_POSTGRES_BLOCK_HISTORY")
+            .map(|value| value.parse::<bool>().unwrap())
+            .unwrap_or(config.enable_postgres_block_history);
+
+        config.block_retention_slots = env::var("BLOCK_RETENTION_SLOTS")
+            .map(|slots| Some(slots.parse().unwrap()))
+            .unwrap_or(config.block_retention_slots);
+        if config.block_retention_slots == Some(0) {
+            anyhow::bail!("BLOCK_RETENTION_SLOTS must be greater than 0");
+        }
+
+        config.account_priofees_retention_slots = env::var("ACCOUNT_PRIOFEES_RETENTION_SLOTS")
+            .map(|blocks| blocks.parse().unwrap())
+            .unwrap_or(config.account_priofees_retention_slots);
+        if config.account_priofees_retention_slots == 0 {
+            anyhow::bail!("ACCOUNT_PRIOFEES_RETENTION_SLOTS must be greater than 0");
+        }
+
+        config.default_commitment = env::var("DEFAULT_COMMITMENT")
+            .map(|value| CommitmentLevel::from_str(&value).unwrap())
+            .unwrap_or(config.default_commitment);
+
+        config.genesis_hash = env::var("GENESIS_HASH").ok().or(config.genesis_hash);
+
+        config.upstream_max_requests_per_second = env::var("UPSTREAM_MAX_REQUESTS_PER_SECOND")
+            .map(|value| value.parse().unwrap())
+            .unwrap_or(config.upstream_max_requests_per_second);
+        if config.upstream_max_requests_per_second == 0 {
+            anyhow::bail!("UPSTREAM_MAX_REQUESTS_PER_SECOND must be greater than 0");
+        }
+
+        config.upstream_max_retries = env::var("UPSTREAM_MAX_RETRIES")
+            .map(|value| value.parse().unwrap())
+            .unwrap_or(config.upstream_max_retries);
+
+        config.upstream_retry_backoff_ms = env::var("UPSTREAM_RETRY_BACKOFF_MS")
+            .map(|value| value.parse().unwrap())
+            .unwrap_or(config.upstream_retry_backoff_ms);
+
+        config.upstream_max_queue_wait_ms = env::var("UPSTREAM_MAX_QUEUE_WAIT_MS")
+            .map(|value| value.parse().unwrap())
+            .unwrap_or(config.upstream_max_queue_wait_ms);
+
+        config.upstream_passthrough_methods = env::var("UPSTREAM_PASSTHROUGH_METHODS")
+            .map(|value| {
+                value
+                    .split(',')
+                    .map(|method| method.trim().to_string())
+                    .collect()
+            })
+            .unwrap_or(config.upstream_passthrough_methods);
+
+        config.supply_cache_interval_secs = env::var("SUPPLY_CACHE_INTERVAL_SECS")
+            .map(|value| value.parse().unwrap())
+            .unwrap_or(config.supply_cache_interval_secs);
+
+        config.max_connections = env::var("MAX_CONNECTIONS")
+            .map(|value| Some(value.parse().unwrap()))
+            .unwrap_or(config.max_connections);
+
+        config.max_request_body_size = env::var("MAX_REQUEST_BODY_SIZE")
+            .map(|value| Some(value.parse().unwrap()))
+            .unwrap_or(config.max_request_body_size);
+
+        config.stake_minimum_delegation = env::var("STAKE_MINIMUM_DELEGATION")
+            .map(|value| Some(value.parse().unwrap()))
+            .unwrap_or(config.stake_minimum_delegation);
+
+        config.rent_lamports_per_byte_year = env::var("RENT_LAMPORTS_PER_BYTE_YEAR")
+            .map(|value| Some(value.parse().unwrap()))
+            .unwrap_or(config.rent_lamports_per_byte_year);
+
+        config.cluster_nodes_ttl_secs = env::var("CLUSTER_NODES_TTL_SECS")
+            .map(|value| Some(value.parse().unwrap()))
+            .unwrap_or(config.cluster_nodes_ttl_secs);
+
+        config.slot_drift_alarm_threshold = env::var("SLOT_DRIFT_ALARM_THRESHOLD")
+            .map(|value| value.parse().unwrap())
+            .unwrap_or(config.slot_drift_alarm_threshold);
+
+        config.leader_schedule_lookahead_slots = env::var("LEADER_SCHEDULE_LOOKAHEAD_SLOTS")
+            .map(|value| value.parse().unwrap())
+            .unwrap_or(config.leader_schedule_lookahead_slots);
+
+        config.enable_transaction_dedup = env::var("ENABLE_TRANSACTION_DEDUP")
+            .map(|value| value.parse::<bool>().unwrap())
+            .unwrap_or(config.enable_transaction_dedup);
+
+        config.bundle_relay_url = env::var("BUNDLE_RELAY_URL")
+            .ok()
+            .or(config.bundle_relay_url);
+
         config.postgres = PostgresSessionConfig::new_from_env()?.or(config.postgres);
         config.quic_connection_parameters = config
             .quic_connection_parameters
@@ -263,93 +429,75 @@ impl Config {
         MAX_RETRIES
     }
 
+    pub const fn default_max_allowed_retries() -> usize {
+        MAX_ALLOWED_RETRIES
+    }
+
     pub const fn default_transaction_retry_after_secs() -> u64 {
         DEFAULT_RETRY_TIMEOUT
     }
 
-    pub fn default_grpc_addr() -> String {
-        DEFAULT_GRPC_ADDR.to_string()
+    pub const fn default_health_check_slot_distance() -> u64 {
+        DEFAULT_HEALTH_CHECK_SLOT_DISTANCE
     }
 
-    pub fn get_grpc_sources(&self) -> Vec<GrpcSource> {
-        let mut sources: Vec<GrpcSource> = vec![];
+    pub const fn default_account_priofees_retention_slots() -> usize {
+        DEFAULT_ACCOUNT_PRIOFEES_RETENTION_SLOTS
+    }
 
-        sources.push(GrpcSource {
-            addr: self.grpc_addr.clone(),
-            x_token: self.grpc_x_token.clone(),
-        });
+    pub const fn default_upstream_max_requests_per_second() -> u32 {
+        DEFAULT_UPSTREAM_MAX_REQUESTS_PER_SECOND
+    }
 
-        if self.grpc_addr2.is_some() {
-            sources.push(GrpcSource {
-                addr: self.grpc_addr2.clone().unwrap(),
-                x_token: self.grpc_x_token2.clone(),
-            });
-        }
+    pub const fn default_upstream_max_retries() -> u32 {
+        DEFAULT_UPSTREAM_MAX_RETRIES
+    }
 
-        if self.grpc_addr3.is_some() {
-            sources.push(GrpcSource {
-                addr: self.grpc_addr3.clone().unwrap(),
-                x_token: self.grpc_x_token3.clone(),
-            });
-        }
+    pub const fn default_upstream_retry_backoff_ms() -> u64 {
+        DEFAULT_UPSTREAM_RETRY_BACKOFF_MS
+    }
 
-        if self.grpc_addr4.is_some() {
-            sources.push(GrpcSource {
-                addr: self.grpc_addr4.clone().unwrap(),
-                x_token: self.grpc_x_token4.clone(),
-            });
-        }
+    pub const fn default_upstream_max_queue_wait_ms() -> u64 {
+        DEFAULT_UPSTREAM_MAX_QUEUE_WAIT_MS
+    }
 
-        sources
+    pub const fn default_supply_cache_interval_secs() -> u64 {
+        DEFAULT_SUPPLY_CACHE_INTERVAL_SECS
     }
-}
 
-#[derive(Clone)]
-pub struct GrpcSource {
-    pub addr: String,
-    pub x_token: Option<String>,
-}
+    pub const fn default_slot_drift_alarm_threshold() -> u64 {
+        DEFAULT_SLOT_DRIFT_ALARM_THRESHOLD
+    }
 
-impl Display for GrpcSource {
-    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
-        write!(
-            f,
-            "GrpcSource {} (x-token {})",
-            url_obfuscate_api_token(&self.addr),
-            obfuscate_token(&self.x_token)
-        )
+    pub const fn default_leader_schedule_lookahead_slots() -> u64 {
+        DEFAULT_LEADER_SCHEDULE_LOOKAHEAD_SLOTS
     }
-}
 
-impl Debug for GrpcSource {
-    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
-        Display::fmt(self, f)
+    pub const fn default_enable_transaction_dedup() -> bool {
+        true
     }
-}
 
-/// obfuscate urls with api token like http://mango.rpcpool.com/a991fba00fagbad
-fn url_obfuscate_api_token(url: &str) -> Cow<str> {
-    if let Ok(mut parsed) = Url::parse(url) {
-        if parsed.path() == "/" {
-            return Cow::Borrowed(url);
-        } else {
-            parsed.set_path("omitted-secret");
-            Cow::Owned(parsed.to_string())
-        }
-    } else {
-        Cow::Borrowed(url)
+    pub fn default_grpc_addr() -> String {
+        DEFAULT_GRPC_ADDR.to_string()
     }
-}
 
-fn obfuscate_token(token: &Option<String>) -> String {
-    match token {
-        None => "n/a".to_string(),
-        Some(token) => {
-            let mut token = token.clone();
-            token.truncate(5);
-            token += "...";
-            token
-        }
+    /// the grpc sources of the config file, the GRPC_* env variables take precedence
+    pub fn grpc_multiplex_config(&self) -> anyhow::Result<GrpcMultiplexConfig> {
+        let mut vars: HashMap<String, String> = [
+            ("GRPC_ADDR", Some(&self.grpc_addr)),
+            ("GRPC_X_TOKEN", self.grpc_x_token.as_ref()),
+            ("GRPC_ADDR2", self.grpc_addr2.as_ref()),
+            ("GRPC_X_TOKEN2", self.grpc_x_token2.as_ref()),
+            ("GRPC_ADDR3", self.grpc_addr3.as_ref()),
+            ("GRPC_X_TOKEN3", self.grpc_x_token3.as_ref()),
+            ("GRPC_ADDR4", self.grpc_addr4.as_ref()),
+            ("GRPC_X_TOKEN4", self.grpc_x_token4.as_ref()),
+        ]
+        .into_iter()
+        .filter_map(|(name, value)| Some((name.to_string(), value?.clone())))
+        .collect();
+        vars.extend(env::vars());
+        GrpcMultiplexConfig::from_vars(&vars)
     }
 }
 
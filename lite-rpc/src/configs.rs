@@ -1,18 +1,23 @@
 use serde::{Deserialize, Serialize};
-use solana_lite_rpc_core::encoding::BinaryEncoding;
+use solana_rpc_client_api::config::RpcSendTransactionConfig;
 use solana_sdk::commitment_config::CommitmentLevel;
 
 #[derive(Debug, Default, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct SendTransactionConfig {
-    //    #[serde(default)]
-    //    pub skip_preflight: bool,
-    //    #[serde(default)]
-    //    pub preflight_commitment: CommitmentLevel,
+    #[serde(flatten)]
+    pub rpc_config: RpcSendTransactionConfig,
+    /// sendTransaction only returns once the transaction reached this commitment,
+    /// fails if the blockhash expired before
+    pub wait_for_commitment: Option<CommitmentLevel>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ClusterNodesConfig {
+    /// only return nodes with a tpu address
     #[serde(default)]
-    pub encoding: BinaryEncoding,
-    pub max_retries: Option<u16>,
-    //    pub min_context_slot: Option<Slot>,
+    pub only_with_tpu: bool,
 }
 
 #[derive(Debug, Default, Serialize, Deserialize)]
@@ -21,3 +26,63 @@ pub struct IsBlockHashValidConfig {
     pub commitment: Option<CommitmentLevel>,
     //    pub minContextSlot: Option<u64>,
 }
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BlockTimeConfig {
+    /// slots slightly ahead of the tip get an estimated block time instead of an error
+    #[serde(default)]
+    pub estimate_future_slots: bool,
+}
+
+/// getBlockTime returns the plain block time unless future slots may be estimated
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum RpcBlockTime {
+    Actual(u64),
+    WithEstimate {
+        #[serde(rename = "blockTime")]
+        block_time: u64,
+        estimated: bool,
+    },
+}
+
+/// time bucketed history for getLatestAccountPrioFees, covering `window_secs` seconds before the latest block
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AccountPrioFeesHistoryConfig {
+    #[serde(default = "AccountPrioFeesHistoryConfig::default_bucket_secs")]
+    pub bucket_secs: u64,
+    #[serde(default = "AccountPrioFeesHistoryConfig::default_window_secs")]
+    pub window_secs: u64,
+}
+
+impl AccountPrioFeesHistoryConfig {
+    fn default_bucket_secs() -> u64 {
+        5
+    }
+
+    fn default_window_secs() -> u64 {
+        60
+    }
+}
+
+impl Default for AccountPrioFeesHistoryConfig {
+    fn default() -> Self {
+        Self {
+            bucket_secs: Self::default_bucket_secs(),
+            window_secs: Self::default_window_secs(),
+        }
+    }
+}
+
+/// optional subsystems of this lite-rpc instance, methods of a disabled subsystem fail
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RpcCapabilities {
+    /// account methods like getAccountInfo and getProgramAccounts
+    pub accounts: bool,
+    pub prioritization_fees: bool,
+    /// getBlock and getBlockTime beyond the in-memory caches
+    pub history: bool,
+}
@@ -0,0 +1,76 @@
+use std::future::Future;
+
+use anyhow::Context;
+use log::{info, warn};
+use solana_lite_rpc_core::solana_utils::hash_from_str;
+use solana_sdk::hash::Hash;
+
+/// Resolves the genesis hash once at startup, a configured value takes precedence over the upstream rpc.
+/// Returns none if the upstream rpc could not provide it, getGenesisHash will then fail.
+pub async fn resolve_genesis_hash<F, Fut>(
+    configured: Option<&str>,
+    fetch_from_rpc: F,
+) -> anyhow::Result<Option<Hash>>
+where
+    F: FnOnce() -> Fut,
+    Fut: Future<Output = anyhow::Result<Hash>>,
+{
+    if let Some(configured) = configured {
+        let genesis_hash = hash_from_str(configured)
+            .map_err(|err| anyhow::anyhow!("{err}"))
+            .context("invalid genesis hash in config")?;
+        info!("using configured genesis hash {genesis_hash}");
+        return Ok(Some(genesis_hash));
+    }
+
+    match fetch_from_rpc().await {
+        Ok(genesis_hash) => {
+            info!("fetched genesis hash {genesis_hash} from rpc");
+            Ok(Some(genesis_hash))
+        }
+        Err(err) => {
+            warn!("failed to fetch genesis hash from rpc, getGenesisHash disabled: {err:?}");
+            Ok(None)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_configured_genesis_hash_is_not_fetched() {
+        let configured = Hash::new_unique();
+        let genesis_hash = resolve_genesis_hash(Some(&configured.to_string()), || async {
+            panic!("must not fetch a configured genesis hash")
+        })
+        .await
+        .unwrap();
+        assert_eq!(genesis_hash, Some(configured));
+    }
+
+    #[tokio::test]
+    async fn test_genesis_hash_fetched_once() {
+        let fetched = Hash::new_unique();
+        let fetch_count = AtomicUsize::new(0);
+        let genesis_hash = resolve_genesis_hash(None, || async {
+            fetch_count.fetch_add(1, Ordering::Relaxed);
+            Ok(fetched)
+        })
+        .await
+        .unwrap();
+        assert_eq!(genesis_hash, Some(fetched));
+        assert_eq!(fetch_count.load(Ordering::Relaxed), 1);
+    }
+
+    #[tokio::test]
+    async fn test_genesis_hash_unavailable() {
+        let genesis_hash = resolve_genesis_hash(None, || async { anyhow::bail!("rpc down") })
+            .await
+            .unwrap();
+        assert_eq!(genesis_hash, None);
+    }
+}
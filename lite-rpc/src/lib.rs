@@ -3,22 +3,56 @@ use solana_transaction_status::TransactionConfirmationStatus;
 
 pub mod bridge;
 pub mod bridge_pubsub;
+pub mod bundle;
 pub mod cli;
 pub mod configs;
 pub mod errors;
+pub mod genesis_hash;
 pub mod jsonrpsee_subscrption_handler_sink;
 pub mod postgres_logger;
+pub mod rent;
 pub mod rpc;
 pub mod rpc_errors;
 pub mod rpc_pubsub;
 pub mod service_spawner;
+pub mod stake_minimum_delegation;
 pub mod start_server;
+pub mod supply;
+pub mod upstream;
 
 #[from_env]
 pub const DEFAULT_RPC_ADDR: &str = "http://0.0.0.0:8899";
 #[from_env]
 pub const DEFAULT_WS_ADDR: &str = "ws://0.0.0.0:8900";
 
+// getHealth reports unhealthy above this distance to the estimated cluster slot
+#[from_env]
+pub const DEFAULT_HEALTH_CHECK_SLOT_DISTANCE: u64 = 128;
+
+// requests proxied to the upstream rpc, e.g. requestAirdrop
+#[from_env]
+pub const DEFAULT_UPSTREAM_MAX_REQUESTS_PER_SECOND: u32 = 10;
+#[from_env]
+pub const DEFAULT_UPSTREAM_MAX_RETRIES: u32 = 3;
+#[from_env]
+pub const DEFAULT_UPSTREAM_RETRY_BACKOFF_MS: u64 = 250;
+#[from_env]
+pub const DEFAULT_UPSTREAM_MAX_QUEUE_WAIT_MS: u64 = 5_000;
+#[from_env]
+pub const DEFAULT_SUPPLY_CACHE_INTERVAL_SECS: u64 = 60;
+
+// signatures of confirmed blocks kept in memory before they are spilled to postgres
+#[from_env]
+pub const DEFAULT_SIGNATURE_INDEX_MEMORY_CAPACITY: usize = 1_000_000;
+
+// blocks which could not be persisted are backfilled from the rpc node this often
+#[from_env]
+pub const DEFAULT_HISTORY_BACKFILL_INTERVAL_SECS: u64 = 60;
+
+// ~80 seconds of slots for the bucketed history of getLatestAccountPrioFees
+#[from_env]
+pub const DEFAULT_ACCOUNT_PRIOFEES_RETENTION_SLOTS: usize = 200;
+
 #[from_env]
 pub const DEFAULT_MAX_NUMBER_OF_TXS_IN_QUEUE: usize = 200_000;
 
@@ -29,6 +63,10 @@ pub const DEFAULT_FANOUT_SIZE: u64 = 18;
 #[from_env]
 pub const MAX_RETRIES: usize = 40;
 
+/// ceiling for max_retries requested in sendTransaction
+#[from_env]
+pub const MAX_ALLOWED_RETRIES: usize = 100;
+
 pub const DEFAULT_RETRY_TIMEOUT: u64 = 3;
 
 #[from_env]
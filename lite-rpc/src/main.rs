@@ -1,41 +1,58 @@
 pub mod rpc_tester;
 
 use crate::rpc_tester::RpcTester;
-use anyhow::bail;
+use anyhow::{bail, Context};
 use dashmap::DashMap;
 use itertools::Itertools;
-use lite_rpc::bridge::LiteBridge;
+use lite_rpc::bridge::{LiteBridge, LiteBridgeConfig};
 use lite_rpc::bridge_pubsub::LitePubSubBridge;
 use lite_rpc::cli::Config;
+use lite_rpc::genesis_hash::resolve_genesis_hash;
 use lite_rpc::postgres_logger::PostgresLogger;
 use lite_rpc::service_spawner::ServiceSpawner;
-use lite_rpc::start_server::start_servers;
-use lite_rpc::DEFAULT_MAX_NUMBER_OF_TXS_IN_QUEUE;
+use lite_rpc::start_server::{start_servers, ServerConfiguration};
+use lite_rpc::upstream::UpstreamConfig;
+use lite_rpc::{
+    DEFAULT_HISTORY_BACKFILL_INTERVAL_SECS, DEFAULT_MAX_NUMBER_OF_TXS_IN_QUEUE,
+    DEFAULT_SIGNATURE_INDEX_MEMORY_CAPACITY,
+};
 use log::info;
 use solana_lite_rpc_accounts::account_service::AccountService;
 use solana_lite_rpc_accounts::account_store_interface::AccountStorageInterface;
 use solana_lite_rpc_accounts::inmemory_account_store::InmemoryAccountStore;
 use solana_lite_rpc_accounts_on_demand::accounts_on_demand::AccountsOnDemand;
 use solana_lite_rpc_address_lookup_tables::address_lookup_table_store::AddressLookupTableStore;
+use solana_lite_rpc_blockstore::block_stores::block_persist_retry::{
+    start_block_persist_task, start_dead_letter_task, BlockPersistRetryConfig,
+};
+use solana_lite_rpc_blockstore::block_stores::multiple_strategy_block_store::MultipleStrategyBlockStorage;
+use solana_lite_rpc_blockstore::block_stores::postgres::postgres_block_store_query::PostgresQueryBlockStore;
+use solana_lite_rpc_blockstore::block_stores::postgres::postgres_block_store_writer::PostgresBlockStore;
+use solana_lite_rpc_blockstore::block_stores::postgres::PostgresSessionConfig as BlockstorePostgresSessionConfig;
+use solana_lite_rpc_blockstore::block_stores::signature_slot_index::SignatureSlotIndex;
 use solana_lite_rpc_blockstore::history::History;
-use solana_lite_rpc_cluster_endpoints::endpoint_stremers::EndpointStreaming;
-
-use solana_lite_rpc_cluster_endpoints::geyser_grpc_connector::{
-    GrpcConnectionTimeouts, GrpcSourceConfig,
+use solana_lite_rpc_cluster_endpoints::endpoint_stremers::{
+    EndpointStreaming, EndpointSubscription,
 };
+
 use solana_lite_rpc_cluster_endpoints::grpc_inspect::{
     debugtask_blockstream_confirmation_sequence, debugtask_blockstream_slot_progression,
+    ConfirmationRuleSet,
 };
 use solana_lite_rpc_cluster_endpoints::grpc_subscription::create_grpc_subscription;
 use solana_lite_rpc_cluster_endpoints::json_rpc_leaders_getter::JsonRpcLeaderGetter;
 use solana_lite_rpc_cluster_endpoints::json_rpc_subscription::create_json_rpc_polling_subscription;
-use solana_lite_rpc_cluster_endpoints::rpc_polling::poll_blocks::NUM_PARALLEL_TASKS_DEFAULT;
+use solana_lite_rpc_cluster_endpoints::rpc_polling::poll_blocks::{
+    fetch_block, NUM_PARALLEL_TASKS_DEFAULT,
+};
 use solana_lite_rpc_core::keypair_loader::load_identity_keypair;
 use solana_lite_rpc_core::stores::{
     block_information_store::{BlockInformation, BlockInformationStore},
     cluster_info_store::ClusterInfo,
     data_cache::{DataCache, SlotCache},
+    latest_block_cache::LatestBlockCache,
     subscription_store::SubscriptionStore,
+    transaction_count_store::TransactionCountStore,
     tx_store::TxStore,
 };
 use solana_lite_rpc_core::structures::account_filter::AccountFilters;
@@ -67,6 +84,7 @@ use std::time::Duration;
 use tokio::io::AsyncReadExt;
 use tokio::sync::mpsc;
 use tokio::sync::RwLock;
+use tokio_util::sync::CancellationToken;
 use tracing_subscriber::fmt::format::FmtSpan;
 use tracing_subscriber::EnvFilter;
 
@@ -96,9 +114,22 @@ pub async fn start_postgres(
     Ok((Some(postgres_send), postgres))
 }
 
-pub async fn start_lite_rpc(args: Config, rpc_client: Arc<RpcClient>) -> anyhow::Result<()> {
-    let grpc_sources = args.get_grpc_sources();
-    log::info!("grpc_sources:{grpc_sources:?}");
+pub async fn start_lite_rpc(
+    args: Config,
+    rpc_client: Arc<RpcClient>,
+    cancel: CancellationToken,
+) -> anyhow::Result<()> {
+    // the grpc sources are only configured when subscribing through geyser
+    let grpc_multiplex_config = if args.use_grpc {
+        let grpc_multiplex_config = args.grpc_multiplex_config()?;
+        log::info!(
+            "grpc_sources: {}",
+            grpc_multiplex_config.sources.iter().join(", ")
+        );
+        Some(grpc_multiplex_config)
+    } else {
+        None
+    };
     let Config {
         lite_rpc_ws_addr,
         lite_rpc_http_addr,
@@ -107,15 +138,37 @@ pub async fn start_lite_rpc(args: Config, rpc_client: Arc<RpcClient>) -> anyhow:
         prometheus_addr,
         identity_keypair,
         maximum_retries_per_tx,
+        max_allowed_retries,
         transaction_retry_after_secs,
         quic_proxy_addr,
-        use_grpc,
         enable_grpc_stream_inspection,
+        grpc_inspect_rules,
         enable_address_lookup_tables,
         address_lookup_tables_binary,
         account_filters,
         enable_accounts_on_demand_accounts_service,
         quic_connection_parameters,
+        health_check_slot_distance,
+        genesis_hash,
+        enable_postgres_block_history,
+        block_retention_slots,
+        account_priofees_retention_slots,
+        default_commitment,
+        upstream_max_requests_per_second,
+        upstream_max_retries,
+        upstream_retry_backoff_ms,
+        upstream_max_queue_wait_ms,
+        upstream_passthrough_methods,
+        supply_cache_interval_secs,
+        max_connections,
+        max_request_body_size,
+        stake_minimum_delegation,
+        rent_lamports_per_byte_year,
+        cluster_nodes_ttl_secs,
+        slot_drift_alarm_threshold,
+        leader_schedule_lookahead_slots,
+        enable_transaction_dedup,
+        bundle_relay_url,
         ..
     } = args;
 
@@ -124,6 +177,7 @@ pub async fn start_lite_rpc(args: Config, rpc_client: Arc<RpcClient>) -> anyhow:
             .await?
             .unwrap_or_else(Keypair::new),
     );
+    let identity = validator_identity.pubkey();
 
     let retry_after = Duration::from_secs(transaction_retry_after_secs);
 
@@ -144,29 +198,21 @@ pub async fn start_lite_rpc(args: Config, rpc_client: Arc<RpcClient>) -> anyhow:
         log::info!("Accounts on demand service is disabled");
     }
 
-    let timeouts = GrpcConnectionTimeouts {
-        connect_timeout: Duration::from_secs(15),
-        request_timeout: Duration::from_secs(15),
-        subscribe_timeout: Duration::from_secs(15),
-        receive_timeout: Duration::from_secs(15),
-    };
-
-    let gprc_sources = grpc_sources
-        .iter()
-        .map(|s| GrpcSourceConfig::new(s.addr.clone(), s.x_token.clone(), None, timeouts.clone()))
-        .collect_vec();
+    let gprc_sources = grpc_multiplex_config
+        .as_ref()
+        .map(|grpc_multiplex_config| grpc_multiplex_config.grpc_source_configs())
+        .unwrap_or_default();
 
-    let (subscriptions, cluster_endpoint_tasks) = if use_grpc {
+    let EndpointSubscription {
+        streaming: subscriptions,
+        tasks: cluster_endpoint_tasks,
+    } = if let Some(grpc_multiplex_config) = grpc_multiplex_config {
         info!("Creating geyser subscription...");
         create_grpc_subscription(
             rpc_client.clone(),
-            grpc_sources
-                .iter()
-                .map(|s| {
-                    GrpcSourceConfig::new(s.addr.clone(), s.x_token.clone(), None, timeouts.clone())
-                })
-                .collect(),
+            grpc_multiplex_config,
             account_filters.clone(),
+            cancel,
         )?
     } else {
         info!("Creating RPC poll subscription...");
@@ -182,8 +228,10 @@ pub async fn start_lite_rpc(args: Config, rpc_client: Arc<RpcClient>) -> anyhow:
         processed_account_stream,
     } = subscriptions;
 
+    let (epoch_data, _current_epoch_info) = EpochCache::bootstrap_epoch(&rpc_client).await?;
+
     if enable_grpc_stream_inspection {
-        setup_grpc_stream_debugging(&blocks_notifier)
+        setup_grpc_stream_debugging(&blocks_notifier, &epoch_data, grpc_inspect_rules)
     } else {
         info!("Disabled grpc stream inspection");
     }
@@ -215,13 +263,23 @@ pub async fn start_lite_rpc(args: Config, rpc_client: Arc<RpcClient>) -> anyhow:
             blockinfo_notifier.resubscribe(),
         );
 
-        account_service
-            .populate_from_rpc(
-                rpc_client.clone(),
-                &account_filters,
-                MAX_CONNECTIONS_IN_PARALLEL,
-            )
-            .await?;
+        // the snapshot is loaded while the server is started, account methods answer
+        // warming up until it is complete
+        tokio::spawn({
+            let account_service = account_service.clone();
+            let rpc_client = rpc_client.clone();
+            let account_filters = account_filters.clone();
+            async move {
+                if let Err(err) = account_service
+                    .populate_from_rpc(rpc_client, &account_filters, MAX_CONNECTIONS_IN_PARALLEL)
+                    .await
+                {
+                    log::error!(
+                        "Initial account snapshot not loaded, accounts stay warming up: {err:?}"
+                    );
+                }
+            }
+        });
         Some(account_service)
     } else {
         None
@@ -235,8 +293,6 @@ pub async fn start_lite_rpc(args: Config, rpc_client: Arc<RpcClient>) -> anyhow:
     .await;
     info!("Got finalized block info: {:?}", finalized_block_info.slot);
 
-    let (epoch_data, _current_epoch_info) = EpochCache::bootstrap_epoch(&rpc_client).await?;
-
     let block_information_store =
         BlockInformationStore::new(BlockInformation::from_block_info(&finalized_block_info));
 
@@ -250,12 +306,17 @@ pub async fn start_lite_rpc(args: Config, rpc_client: Arc<RpcClient>) -> anyhow:
             store: Arc::new(DashMap::new()),
         },
         epoch_data,
-        leader_schedule: Arc::new(RwLock::new(CalculatedSchedule::default())),
+        leader_schedule: Arc::new(RwLock::new(CalculatedSchedule::with_lookahead_slots(
+            leader_schedule_lookahead_slots,
+        ))),
+        latest_block_cache: LatestBlockCache::default(),
+        transaction_count: TransactionCountStore::default(),
     };
 
     let data_cache_service = DataCachingService {
         data_cache: data_cache.clone(),
         clean_duration: Duration::from_secs(120),
+        slot_drift_alarm_threshold,
     };
 
     // to avoid laggin we resubscribe to block notification
@@ -298,7 +359,7 @@ pub async fn start_lite_rpc(args: Config, rpc_client: Arc<RpcClient>) -> anyhow:
     let (account_priofees_task, account_priofees_service) =
         AccountPrioService::start_account_priofees_task(
             blocks_notifier.resubscribe(),
-            100,
+            account_priofees_retention_slots,
             address_lookup_tables,
         );
 
@@ -333,22 +394,116 @@ pub async fn start_lite_rpc(args: Config, rpc_client: Arc<RpcClient>) -> anyhow:
         DEFAULT_MAX_NUMBER_OF_TXS_IN_QUEUE,
         notification_channel.clone(),
         maximum_retries_per_tx,
+        max_allowed_retries,
+        enable_transaction_dedup,
         slot_notifier.resubscribe(),
     );
 
     let support_service =
         tokio::spawn(async move { spawner.spawn_support_services(prometheus_addr).await });
 
-    let history = History::new();
+    let (history, block_persist_task, dead_letter_task) = if enable_postgres_block_history {
+        let pg_session_config = BlockstorePostgresSessionConfig::new_from_env()?
+            .context("postgres block history requires PG_ENABLED and PG_CONFIG")?;
+        let block_storage_query =
+            PostgresQueryBlockStore::new(data_cache.epoch_data.clone(), pg_session_config.clone())
+                .await;
+        // confirmed blocks are saved with retries, signatures evicted from memory are written to the transaction_slots table of their epoch
+        let block_store_writer = Arc::new(
+            PostgresBlockStore::new(data_cache.epoch_data.clone(), pg_session_config).await,
+        );
+        let (block_dead_letters, block_persist_task) = start_block_persist_task(
+            blocks_notifier.resubscribe(),
+            block_store_writer.clone(),
+            BlockPersistRetryConfig::default(),
+        );
+        // gaps left by blocks which could not be persisted are backfilled from the rpc node
+        let backfill_rpc_client = rpc_client.clone();
+        let dead_letter_task = start_dead_letter_task(
+            block_dead_letters,
+            block_store_writer.clone(),
+            data_cache.epoch_data.clone(),
+            Duration::from_secs(DEFAULT_HISTORY_BACKFILL_INTERVAL_SECS),
+            move |slot| {
+                let rpc_client = backfill_rpc_client.clone();
+                async move { fetch_block(&rpc_client, slot, CommitmentConfig::confirmed()).await }
+            },
+        );
+        let history = History::new_with_block_store(Arc::new(MultipleStrategyBlockStorage::new(
+            block_storage_query.clone(),
+            None,
+        )))
+        .with_epoch_block_store(data_cache.epoch_data.clone(), Arc::new(block_storage_query))
+        .with_signature_slot_index(SignatureSlotIndex::new(
+            DEFAULT_SIGNATURE_INDEX_MEMORY_CAPACITY,
+            block_store_writer,
+        ));
+        (history, block_persist_task, dead_letter_task)
+    } else {
+        (
+            History::new(),
+            tokio::spawn(async {
+                std::future::pending::<()>().await;
+                unreachable!()
+            }),
+            tokio::spawn(async {
+                std::future::pending::<()>().await;
+                unreachable!()
+            }),
+        )
+    };
+    let (history, recent_blocks_task) = match block_retention_slots {
+        Some(retention_slots) => {
+            let history = history.with_recent_blocks(retention_slots);
+            let recent_blocks_task =
+                history.start_recent_blocks_task(blocks_notifier.resubscribe());
+            (history, recent_blocks_task)
+        }
+        None => (
+            history,
+            tokio::spawn(async {
+                std::future::pending::<()>().await;
+                unreachable!()
+            }),
+        ),
+    };
+
+    let signature_slot_index_task =
+        history.start_signature_slot_index_task(blocks_notifier.resubscribe());
+
+    let genesis_hash = resolve_genesis_hash(genesis_hash.as_deref(), || async {
+        Ok(rpc_client.get_genesis_hash().await?)
+    })
+    .await?;
 
     let rpc_service = LiteBridge::new(
         rpc_client.clone(),
         data_cache.clone(),
         transaction_service,
-        history,
         block_priofees_service.clone(),
         account_priofees_service.clone(),
         accounts_service.clone(),
+        LiteBridgeConfig {
+            history,
+            health_check_slot_distance,
+            identity,
+            genesis_hash,
+            default_commitment: CommitmentConfig {
+                commitment: default_commitment,
+            },
+            upstream: UpstreamConfig {
+                max_requests_per_second: upstream_max_requests_per_second,
+                max_retries: upstream_max_retries,
+                initial_backoff: Duration::from_millis(upstream_retry_backoff_ms),
+                max_queue_wait: Duration::from_millis(upstream_max_queue_wait_ms),
+            },
+            upstream_passthrough_methods,
+            stake_minimum_delegation,
+            rent_lamports_per_byte_year,
+            cluster_nodes_ttl: cluster_nodes_ttl_secs.map(Duration::from_secs),
+            supply_cache_interval: Duration::from_secs(supply_cache_interval_secs),
+            bundle_relay_url,
+        },
     );
 
     let pubsub_service = LitePubSubBridge::new(
@@ -360,12 +515,18 @@ pub async fn start_lite_rpc(args: Config, rpc_client: Arc<RpcClient>) -> anyhow:
         accounts_service.clone(),
     );
 
+    let default_server_configuration = ServerConfiguration::default();
+    let server_configuration = ServerConfiguration {
+        max_connection: max_connections.unwrap_or(default_server_configuration.max_connection),
+        max_request_body_size,
+        ..default_server_configuration
+    };
     let bridge_service = tokio::spawn(start_servers(
         rpc_service,
         pubsub_service,
         lite_rpc_ws_addr,
         lite_rpc_http_addr,
-        None,
+        Some(server_configuration),
     ));
     drop(slot_notifier);
 
@@ -389,7 +550,7 @@ pub async fn start_lite_rpc(args: Config, rpc_client: Arc<RpcClient>) -> anyhow:
         res = futures::future::select_all(data_caching_service) => {
             anyhow::bail!("Data caching service failed {res:?}")
         }
-        res = futures::future::select_all(cluster_endpoint_tasks) => {
+        res = cluster_endpoint_tasks.run() => {
             anyhow::bail!("cluster endpoint failure {res:?}")
         }
         res = block_priofees_task => {
@@ -398,21 +559,43 @@ pub async fn start_lite_rpc(args: Config, rpc_client: Arc<RpcClient>) -> anyhow:
         res = account_priofees_task => {
             anyhow::bail!("account prioritization fees task failed {res:?}")
         }
+        res = recent_blocks_task => {
+            anyhow::bail!("recent blocks task failed {res:?}")
+        }
+        res = signature_slot_index_task => {
+            anyhow::bail!("signature slot index task failed {res:?}")
+        }
+        res = block_persist_task => {
+            anyhow::bail!("block persist task failed {res:?}")
+        }
+        res = dead_letter_task => {
+            anyhow::bail!("dead-letter task failed {res:?}")
+        }
     }
 }
 
-fn setup_grpc_stream_debugging(blocks_notifier: &BlockStream) {
+fn setup_grpc_stream_debugging(
+    blocks_notifier: &BlockStream,
+    epoch_cache: &EpochCache,
+    rule_set: ConfirmationRuleSet,
+) {
     info!("Setting up grpc stream inspection");
     // note: check failes for commitment_config processed because sources might disagree on the blocks
     debugtask_blockstream_slot_progression(
         blocks_notifier.resubscribe(),
         CommitmentConfig::confirmed(),
+        epoch_cache.clone(),
     );
     debugtask_blockstream_slot_progression(
         blocks_notifier.resubscribe(),
         CommitmentConfig::finalized(),
+        epoch_cache.clone(),
+    );
+    debugtask_blockstream_confirmation_sequence(
+        blocks_notifier.resubscribe(),
+        epoch_cache.clone(),
+        rule_set,
     );
-    debugtask_blockstream_confirmation_sequence(blocks_notifier.resubscribe());
 }
 
 #[tokio::main()]
@@ -429,7 +612,8 @@ pub async fn main() -> anyhow::Result<()> {
 
     info!("Use RPC address: {}", obfuscate_rpcurl(rpc_addr));
 
-    let main = start_lite_rpc(config, rpc_client);
+    let cancel = CancellationToken::new();
+    let main = start_lite_rpc(config, rpc_client, cancel.clone());
 
     tokio::select! {
         err = rpc_tester => {
@@ -443,6 +627,7 @@ pub async fn main() -> anyhow::Result<()> {
         }
         _ = ctrl_c_signal => {
             log::info!("Received ctrl+c signal");
+            cancel.cancel();
             Ok(())
         }
     }
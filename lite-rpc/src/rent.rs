@@ -0,0 +1,59 @@
+use std::future::Future;
+
+use log::info;
+use solana_sdk::rent::Rent;
+use tokio::sync::OnceCell;
+
+/// The rent parameters only change with a feature activation.
+/// A configured rent rate takes precedence, otherwise the rent sysvar is fetched from the upstream rpc on first use and cached.
+pub struct RentCache {
+    cached: OnceCell<Rent>,
+}
+
+impl RentCache {
+    pub fn new(configured_lamports_per_byte_year: Option<u64>) -> Self {
+        Self {
+            cached: OnceCell::new_with(configured_lamports_per_byte_year.map(
+                |lamports_per_byte_year| Rent {
+                    lamports_per_byte_year,
+                    ..Rent::default()
+                },
+            )),
+        }
+    }
+
+    /// a failed fetch is not cached, the next call fetches again
+    pub async fn get_or_fetch<F, Fut, E>(&self, fetch_from_rpc: F) -> Result<Rent, E>
+    where
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = Result<Rent, E>>,
+    {
+        self.cached
+            .get_or_try_init(|| async {
+                let rent = fetch_from_rpc().await?;
+                info!("fetched rent {rent:?} from rpc");
+                Ok(rent)
+            })
+            .await
+            .cloned()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_configured_rent_is_not_fetched() {
+        let rent_cache = RentCache::new(Some(1));
+        let rent = rent_cache
+            .get_or_fetch(|| async { anyhow::bail!("must not fetch a configured rent") })
+            .await
+            .unwrap();
+        assert_eq!(rent.lamports_per_byte_year, 1);
+        assert_eq!(
+            rent.exemption_threshold,
+            Rent::default().exemption_threshold
+        );
+    }
+}
@@ -1,21 +1,29 @@
-use crate::configs::IsBlockHashValidConfig;
+use crate::configs::{
+    AccountPrioFeesHistoryConfig, BlockTimeConfig, ClusterNodesConfig, IsBlockHashValidConfig,
+    RpcBlockTime, RpcCapabilities, SendTransactionConfig,
+};
 use jsonrpsee::core::RpcResult;
 use jsonrpsee::proc_macros::rpc;
-use solana_account_decoder::UiAccount;
+use solana_account_decoder::{parse_token::UiTokenAmount, UiAccount};
+use solana_lite_rpc_accounts::program_accounts_page::RpcProgramAccountsPage;
 use solana_lite_rpc_prioritization_fees::prioritization_fee_calculation_method::PrioritizationFeeCalculationMethod;
-use solana_lite_rpc_prioritization_fees::rpc_data::{AccountPrioFeesStats, PrioFeesStats};
+use solana_lite_rpc_prioritization_fees::rpc_data::{AccountPrioFeesWithHistory, LatestPrioFees};
 use solana_rpc_client_api::config::{
-    RpcAccountInfoConfig, RpcBlocksConfigWrapper, RpcContextConfig, RpcGetVoteAccountsConfig,
+    RpcAccountInfoConfig, RpcBlockConfig, RpcBlockProductionConfig, RpcBlocksConfigWrapper,
+    RpcContextConfig, RpcEncodingConfigWrapper, RpcEpochConfig, RpcGetVoteAccountsConfig,
     RpcLeaderScheduleConfig, RpcProgramAccountsConfig, RpcRequestAirdropConfig,
     RpcSendTransactionConfig, RpcSignatureStatusConfig, RpcSignaturesForAddressConfig,
+    RpcSupplyConfig, RpcTokenAccountsFilter,
 };
 use solana_rpc_client_api::response::{
-    OptionalContext, Response as RpcResponse, RpcBlockhash,
-    RpcConfirmedTransactionStatusWithSignature, RpcContactInfo, RpcKeyedAccount, RpcPerfSample,
-    RpcPrioritizationFee, RpcVersionInfo, RpcVoteAccountStatus,
+    OptionalContext, Response as RpcResponse, RpcBlockProduction, RpcBlockhash,
+    RpcConfirmedTransactionStatusWithSignature, RpcContactInfo, RpcIdentity, RpcInflationReward,
+    RpcKeyedAccount, RpcPerfSample, RpcPrioritizationFee, RpcSupply, RpcVersionInfo,
+    RpcVoteAccountStatus,
 };
 use solana_sdk::commitment_config::CommitmentConfig;
 use solana_sdk::epoch_info::EpochInfo;
+use solana_sdk::epoch_schedule::EpochSchedule;
 use solana_sdk::pubkey::Pubkey;
 use solana_sdk::slot_history::Slot;
 use solana_transaction_status::{TransactionStatus, UiConfirmedBlock};
@@ -28,7 +36,11 @@ pub trait LiteRpc {
     // ***********************
 
     #[method(name = "getBlock")]
-    async fn get_block(&self, slot: u64) -> RpcResult<Option<UiConfirmedBlock>>;
+    async fn get_block(
+        &self,
+        slot: u64,
+        config: Option<RpcEncodingConfigWrapper<RpcBlockConfig>>,
+    ) -> RpcResult<Option<UiConfirmedBlock>>;
 
     #[method(name = "getBlocks")]
     async fn get_blocks(
@@ -38,6 +50,15 @@ pub trait LiteRpc {
         commitment: Option<CommitmentConfig>,
     ) -> RpcResult<Vec<Slot>>;
 
+    /// confirmed or finalized slots starting at `start_slot` of the blocks still in memory
+    #[method(name = "getBlocksWithLimit")]
+    async fn get_blocks_with_limit(
+        &self,
+        start_slot: Slot,
+        limit: usize,
+        config: Option<RpcContextConfig>,
+    ) -> RpcResult<Vec<Slot>>;
+
     #[method(name = "getSignaturesForAddress")]
     async fn get_signatures_for_address(
         &self,
@@ -59,7 +80,10 @@ pub trait LiteRpc {
     // ***********************
 
     #[method(name = "getClusterNodes")]
-    async fn get_cluster_nodes(&self) -> RpcResult<Vec<RpcContactInfo>>;
+    async fn get_cluster_nodes(
+        &self,
+        config: Option<ClusterNodesConfig>,
+    ) -> RpcResult<Vec<RpcContactInfo>>;
 
     // ***********************
     // Validator Domain
@@ -68,11 +92,63 @@ pub trait LiteRpc {
     #[method(name = "getSlot")]
     async fn get_slot(&self, config: Option<RpcContextConfig>) -> RpcResult<Slot>;
 
+    /// highest processed slot, LiteRPC does not retransmit shreds
+    #[method(name = "getMaxRetransmitSlot")]
+    async fn get_max_retransmit_slot(&self) -> RpcResult<Slot>;
+
+    /// highest processed slot, LiteRPC does not insert shreds
+    #[method(name = "getMaxShredInsertSlot")]
+    async fn get_max_shred_insert_slot(&self) -> RpcResult<Slot>;
+
+    #[method(name = "getHealth")]
+    async fn get_health(&self) -> RpcResult<String>;
+
+    #[method(name = "getIdentity")]
+    fn get_identity(&self) -> RpcResult<RpcIdentity>;
+
+    #[method(name = "getGenesisHash")]
+    fn get_genesis_hash(&self) -> RpcResult<String>;
+
     #[method(name = "getBlockHeight")]
     async fn get_block_height(&self, config: Option<RpcContextConfig>) -> RpcResult<u64>;
 
+    /// number of transactions in the blocks ingested since LiteRPC started (node-relative),
+    /// not the cluster-absolute count reported by a validator
+    #[method(name = "getTransactionCount")]
+    fn get_transaction_count(&self, config: Option<RpcContextConfig>) -> RpcResult<u64>;
+
     #[method(name = "getBlockTime")]
-    async fn get_block_time(&self, block: u64) -> RpcResult<u64>;
+    async fn get_block_time(
+        &self,
+        block: u64,
+        config: Option<BlockTimeConfig>,
+    ) -> RpcResult<RpcBlockTime>;
+
+    #[method(name = "getInflationReward")]
+    async fn get_inflation_reward(
+        &self,
+        address_strs: Vec<String>,
+        config: Option<RpcEpochConfig>,
+    ) -> RpcResult<Vec<Option<RpcInflationReward>>>;
+
+    #[method(name = "getStakeMinimumDelegation")]
+    async fn get_stake_minimum_delegation(
+        &self,
+        config: Option<RpcContextConfig>,
+    ) -> RpcResult<RpcResponse<u64>>;
+
+    #[method(name = "getMinimumBalanceForRentExemption")]
+    async fn get_minimum_balance_for_rent_exemption(
+        &self,
+        data_len: usize,
+        commitment: Option<CommitmentConfig>,
+    ) -> RpcResult<u64>;
+
+    #[method(name = "getSupply")]
+    async fn get_supply(
+        &self,
+        config: Option<RpcSupplyConfig>,
+    ) -> RpcResult<RpcResponse<RpcSupply>>;
 
     #[method(name = "getFirstAvailableBlock")]
     async fn get_first_available_block(&self) -> RpcResult<u64>;
@@ -123,6 +199,7 @@ pub trait LiteRpc {
     async fn get_recent_prioritization_fees(
         &self,
         pubkey_strs: Vec<String>,
+        percentile: Option<f32>,
     ) -> RpcResult<Vec<RpcPrioritizationFee>>;
 
     // ***********************
@@ -133,9 +210,22 @@ pub trait LiteRpc {
     async fn send_transaction(
         &self,
         tx: String,
+        send_transaction_config: Option<SendTransactionConfig>,
+    ) -> RpcResult<String>;
+
+    /// forwards the transactions to the configured bundle relay which lands them atomically in order,
+    /// returns the bundle id of the relay
+    #[method(name = "sendBundle")]
+    async fn send_bundle(
+        &self,
+        txs: Vec<String>,
         send_transaction_config: Option<RpcSendTransactionConfig>,
     ) -> RpcResult<String>;
 
+    /// reports which optional subsystems are enabled, clients can branch before calling them
+    #[method(name = "getCapabilities")]
+    fn get_capabilities(&self) -> RpcResult<RpcCapabilities>;
+
     // ***********************
     // Deprecated
     // ***********************
@@ -156,6 +246,9 @@ pub trait LiteRpc {
     #[method(name = "getEpochInfo")]
     async fn get_epoch_info(&self, config: Option<RpcContextConfig>) -> RpcResult<EpochInfo>;
 
+    #[method(name = "getEpochSchedule")]
+    async fn get_epoch_schedule(&self) -> RpcResult<EpochSchedule>;
+
     #[method(name = "getLeaderSchedule")]
     async fn get_leader_schedule(
         &self,
@@ -166,6 +259,12 @@ pub trait LiteRpc {
     #[method(name = "getSlotLeaders")]
     async fn get_slot_leaders(&self, start_slot: u64, limit: u64) -> RpcResult<Vec<Pubkey>>;
 
+    #[method(name = "getBlockProduction")]
+    async fn get_block_production(
+        &self,
+        config: Option<RpcBlockProductionConfig>,
+    ) -> RpcResult<RpcResponse<RpcBlockProduction>>;
+
     #[method(name = "getVoteAccounts")]
     async fn get_vote_accounts(
         &self,
@@ -181,14 +280,17 @@ pub trait LiteRpc {
     async fn get_latest_block_priofees(
         &self,
         method: Option<PrioritizationFeeCalculationMethod>,
-    ) -> RpcResult<RpcResponse<PrioFeesStats>>;
+        percentile: Option<f32>,
+    ) -> RpcResult<RpcResponse<LatestPrioFees>>;
 
+    /// with `history` the response also contains the fees of the account per time bucket
     #[method(name = "getLatestAccountPrioFees")]
     async fn get_latest_account_priofees(
         &self,
         account: String,
         method: Option<PrioritizationFeeCalculationMethod>,
-    ) -> RpcResult<RpcResponse<AccountPrioFeesStats>>;
+        history: Option<AccountPrioFeesHistoryConfig>,
+    ) -> RpcResult<RpcResponse<AccountPrioFeesWithHistory>>;
 
     // **************************
     // Accounts
@@ -215,10 +317,36 @@ pub trait LiteRpc {
         config: Option<RpcProgramAccountsConfig>,
     ) -> RpcResult<OptionalContext<Vec<RpcKeyedAccount>>>;
 
+    // cursor based pagination of getProgramAccounts
+    // (this is special method not available in solana rpc)
+    #[method(name = "getProgramAccountsPaginated")]
+    async fn get_program_accounts_paginated(
+        &self,
+        program_id_str: String,
+        config: Option<RpcProgramAccountsConfig>,
+        limit: usize,
+        cursor: Option<String>,
+    ) -> RpcResult<RpcResponse<RpcProgramAccountsPage>>;
+
     #[method(name = "getBalance")]
     async fn get_balance(
         &self,
         pubkey_str: String,
         config: Option<RpcContextConfig>,
     ) -> RpcResult<RpcResponse<u64>>;
+
+    #[method(name = "getTokenAccountsByOwner")]
+    async fn get_token_accounts_by_owner(
+        &self,
+        owner_str: String,
+        filter: RpcTokenAccountsFilter,
+        config: Option<RpcAccountInfoConfig>,
+    ) -> RpcResult<RpcResponse<Vec<RpcKeyedAccount>>>;
+
+    #[method(name = "getTokenAccountBalance")]
+    async fn get_token_account_balance(
+        &self,
+        pubkey_str: String,
+        commitment: Option<CommitmentConfig>,
+    ) -> RpcResult<RpcResponse<UiTokenAmount>>;
 }
@@ -1,6 +1,163 @@
+use jsonrpsee::types::error::{ErrorCode, ErrorObject, ErrorObjectOwned};
+use serde_json::json;
+
 #[derive(Copy, Clone, Debug, Eq, PartialEq)]
 #[repr(i32)]
 pub enum RpcErrors {
     // Account does not satisfy any account filters or account does not exists.
     AccountNotFound = 0,
 }
+
+// solana json rpc server error codes, see solana-rpc-client-api custom_error.rs
+pub const JSON_RPC_SERVER_ERROR_SEND_TRANSACTION_PREFLIGHT_FAILURE: i32 = -32002;
+pub const JSON_RPC_SERVER_ERROR_BLOCK_NOT_AVAILABLE: i32 = -32004;
+pub const JSON_RPC_SERVER_ERROR_NODE_UNHEALTHY: i32 = -32005;
+pub const JSON_RPC_SERVER_ERROR_MIN_CONTEXT_SLOT_NOT_REACHED: i32 = -32016;
+
+/// Errors reported with the same code and data as the solana rpc, clients branch on them
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum SolanaRpcError {
+    InvalidPubkey(String),
+    BlockhashNotFound,
+    BlockNotAvailable { slot: u64 },
+    NodeUnhealthy { num_slots_behind: u64 },
+    AccountsWarmingUp,
+    TransactionTooLarge { size: usize, max_size: usize },
+    TooManyFilters { max_filters: usize },
+    InvalidFilter { index: usize, reason: String },
+    MinContextSlotNotReached { context_slot: u64 },
+}
+
+impl SolanaRpcError {
+    pub fn code(&self) -> i32 {
+        match self {
+            Self::InvalidPubkey(_)
+            | Self::TransactionTooLarge { .. }
+            | Self::TooManyFilters { .. }
+            | Self::InvalidFilter { .. } => ErrorCode::InvalidParams.code(),
+            Self::BlockhashNotFound => JSON_RPC_SERVER_ERROR_SEND_TRANSACTION_PREFLIGHT_FAILURE,
+            Self::BlockNotAvailable { .. } => JSON_RPC_SERVER_ERROR_BLOCK_NOT_AVAILABLE,
+            Self::NodeUnhealthy { .. } | Self::AccountsWarmingUp => {
+                JSON_RPC_SERVER_ERROR_NODE_UNHEALTHY
+            }
+            Self::MinContextSlotNotReached { .. } => {
+                JSON_RPC_SERVER_ERROR_MIN_CONTEXT_SLOT_NOT_REACHED
+            }
+        }
+    }
+
+    pub fn message(&self) -> String {
+        match self {
+            Self::InvalidPubkey(pubkey) => format!("Invalid param: {pubkey} is not a valid pubkey"),
+            Self::BlockhashNotFound => {
+                "Transaction simulation failed: Blockhash not found".to_string()
+            }
+            Self::BlockNotAvailable { slot } => format!("Block not available for slot {slot}"),
+            Self::NodeUnhealthy { num_slots_behind } => {
+                format!("Node is behind by {num_slots_behind} slots")
+            }
+            Self::AccountsWarmingUp => {
+                "Node is warming up: accounts are not loaded yet".to_string()
+            }
+            Self::TransactionTooLarge { size, max_size } => {
+                format!("transaction too large: {size} bytes (max: {max_size} bytes)")
+            }
+            Self::TooManyFilters { max_filters } => {
+                format!("Too many filters provided; max {max_filters}")
+            }
+            Self::InvalidFilter { index, reason } => {
+                format!("Invalid param: filter {index}: {reason}")
+            }
+            Self::MinContextSlotNotReached { .. } => {
+                "Minimum context slot has not been reached".to_string()
+            }
+        }
+    }
+
+    fn data(&self) -> Option<serde_json::Value> {
+        match self {
+            Self::NodeUnhealthy { num_slots_behind } => {
+                Some(json!({ "numSlotsBehind": num_slots_behind }))
+            }
+            Self::MinContextSlotNotReached { context_slot } => {
+                Some(json!({ "contextSlot": context_slot }))
+            }
+            Self::BlockhashNotFound => Some(json!({
+                "err": "BlockhashNotFound",
+                "logs": [],
+                "accounts": null,
+                "unitsConsumed": 0,
+                "returnData": null,
+            })),
+            Self::InvalidPubkey(_)
+            | Self::BlockNotAvailable { .. }
+            | Self::AccountsWarmingUp
+            | Self::TransactionTooLarge { .. }
+            | Self::TooManyFilters { .. }
+            | Self::InvalidFilter { .. } => None,
+        }
+    }
+}
+
+impl From<SolanaRpcError> for ErrorObjectOwned {
+    fn from(error: SolanaRpcError) -> Self {
+        ErrorObject::owned(error.code(), error.message(), error.data())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_node_unhealthy() {
+        let error: ErrorObjectOwned = SolanaRpcError::NodeUnhealthy {
+            num_slots_behind: 200,
+        }
+        .into();
+        assert_eq!(error.code(), -32005);
+        assert_eq!(error.message(), "Node is behind by 200 slots");
+        assert_eq!(
+            error.data().map(|data| data.get()),
+            Some(r#"{"numSlotsBehind":200}"#)
+        );
+    }
+
+    #[test]
+    fn test_min_context_slot_not_reached() {
+        let error: ErrorObjectOwned =
+            SolanaRpcError::MinContextSlotNotReached { context_slot: 100 }.into();
+        assert_eq!(error.code(), -32016);
+        assert_eq!(error.message(), "Minimum context slot has not been reached");
+        assert_eq!(
+            error.data().map(|data| data.get()),
+            Some(r#"{"contextSlot":100}"#)
+        );
+    }
+
+    #[test]
+    fn test_blockhash_not_found() {
+        let error: ErrorObjectOwned = SolanaRpcError::BlockhashNotFound.into();
+        assert_eq!(error.code(), -32002);
+        let data: serde_json::Value = serde_json::from_str(error.data().unwrap().get()).unwrap();
+        assert_eq!(data["err"], "BlockhashNotFound");
+    }
+
+    #[test]
+    fn test_invalid_params() {
+        let error: ErrorObjectOwned = SolanaRpcError::InvalidPubkey("xyz".to_string()).into();
+        assert_eq!(error.code(), -32602);
+        assert!(error.data().is_none());
+
+        let error: ErrorObjectOwned = SolanaRpcError::TransactionTooLarge {
+            size: 1500,
+            max_size: 1232,
+        }
+        .into();
+        assert_eq!(error.code(), -32602);
+        assert_eq!(
+            error.message(),
+            "transaction too large: 1500 bytes (max: 1232 bytes)"
+        );
+    }
+}
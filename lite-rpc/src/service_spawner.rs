@@ -6,7 +6,7 @@ use solana_lite_rpc_core::{
     AnyhowJoinHandle,
 };
 use solana_lite_rpc_services::{
-    data_caching_service::DataCachingService,
+    data_caching_service::{DataCachingService, DEFAULT_SLOT_DRIFT_ALARM_THRESHOLD},
     metrics_capture::MetricsCapture,
     prometheus_sync::PrometheusSync,
     tpu_utils::tpu_service::TpuService,
@@ -51,6 +51,7 @@ impl ServiceSpawner {
         let data_service = DataCachingService {
             data_cache: self.data_cache.clone(),
             clean_duration: Duration::from_secs(120),
+            slot_drift_alarm_threshold: DEFAULT_SLOT_DRIFT_ALARM_THRESHOLD,
         };
 
         data_service.listen(
@@ -71,6 +72,8 @@ impl ServiceSpawner {
         max_nb_txs_in_queue: usize,
         notifier: Option<NotificationSender>,
         max_retries: usize,
+        max_allowed_retries: usize,
+        transaction_dedup: bool,
         slot_notifications: SlotStream,
     ) -> (TransactionService, AnyhowJoinHandle) {
         let service_builder = TransactionServiceBuilder::new(
@@ -78,11 +81,13 @@ impl ServiceSpawner {
             tx_replayer,
             tpu_service,
             max_nb_txs_in_queue,
-        );
+        )
+        .with_transaction_dedup(transaction_dedup);
         service_builder.start(
             notifier,
             self.data_cache.block_information_store.clone(),
             max_retries,
+            max_allowed_retries,
             slot_notifications,
         )
     }
@@ -0,0 +1,74 @@
+use std::future::Future;
+
+use log::info;
+use tokio::sync::OnceCell;
+
+/// The minimum stake delegation only changes with a feature activation.
+/// A configured value takes precedence, otherwise it is fetched from the upstream rpc on first use and cached.
+pub struct StakeMinimumDelegation {
+    cached: OnceCell<u64>,
+}
+
+impl StakeMinimumDelegation {
+    pub fn new(configured: Option<u64>) -> Self {
+        Self {
+            cached: OnceCell::new_with(configured),
+        }
+    }
+
+    /// a failed fetch is not cached, the next call fetches again
+    pub async fn get_or_fetch<F, Fut, E>(&self, fetch_from_rpc: F) -> Result<u64, E>
+    where
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = Result<u64, E>>,
+    {
+        self.cached
+            .get_or_try_init(|| async {
+                let lamports = fetch_from_rpc().await?;
+                info!("fetched stake minimum delegation {lamports} from rpc");
+                Ok(lamports)
+            })
+            .await
+            .copied()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_configured_value_is_not_fetched() {
+        let stake_minimum_delegation = StakeMinimumDelegation::new(Some(1_000_000_000));
+        let lamports = stake_minimum_delegation
+            .get_or_fetch(|| async { anyhow::bail!("must not fetch a configured value") })
+            .await
+            .unwrap();
+        assert_eq!(lamports, 1_000_000_000);
+    }
+
+    #[tokio::test]
+    async fn test_fetched_once_and_cached() {
+        let stake_minimum_delegation = StakeMinimumDelegation::new(None);
+        let fetch_count = AtomicUsize::new(0);
+
+        let failed = stake_minimum_delegation
+            .get_or_fetch(|| async { anyhow::bail!("rpc down") })
+            .await;
+        assert!(failed.is_err());
+
+        for _ in 0..3 {
+            let lamports = stake_minimum_delegation
+                .get_or_fetch(|| async {
+                    fetch_count.fetch_add(1, Ordering::Relaxed);
+                    Ok::<_, anyhow::Error>(1)
+                })
+                .await
+                .unwrap();
+            assert_eq!(lamports, 1);
+        }
+        assert_eq!(fetch_count.load(Ordering::Relaxed), 1);
+    }
+}
@@ -4,14 +4,26 @@ use crate::{
 };
 
 use hyper::Method;
-use jsonrpsee::server::ServerBuilder;
+use jsonrpsee::server::{ServerBuilder, ServerHandle};
+use jsonrpsee::Methods;
+use prometheus::{opts, register_int_gauge_vec, IntGauge, IntGaugeVec};
 use solana_lite_rpc_core::AnyhowJoinHandle;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::task::{Context, Poll};
 use std::time::Duration;
+use tower::{Layer, Service};
 use tower_http::cors::{Any, CorsLayer};
 
+lazy_static::lazy_static! {
+    static ref ACTIVE_CONNECTIONS: IntGaugeVec =
+        register_int_gauge_vec!(opts!("literpc_rpc_active_connections", "Number of open connections to the rpc servers"), &["server"]).unwrap();
+}
+
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 pub struct ServerConfiguration {
-    pub max_request_body_size: u32,
+    /// none keeps the http limit at the response size and the jsonrpsee default on websocket
+    pub max_request_body_size: Option<u32>,
 
     pub max_response_body_size: u32,
 
@@ -23,7 +35,7 @@ pub struct ServerConfiguration {
 impl Default for ServerConfiguration {
     fn default() -> Self {
         Self {
-            max_request_body_size: 50 * (1 << 10),       // 50kb
+            max_request_body_size: None,
             max_response_body_size: 500_000 * (1 << 10), // 500MB response size
             max_connection: 1000000,
             max_subscriptions_per_connection: 1000,
@@ -31,6 +43,64 @@ impl Default for ServerConfiguration {
     }
 }
 
+/// the servers build the middleware service once per connection
+#[derive(Clone)]
+struct ConnectionCounterLayer {
+    active_connections: IntGauge,
+}
+
+impl ConnectionCounterLayer {
+    fn new(server: &str) -> Self {
+        Self {
+            active_connections: ACTIVE_CONNECTIONS.with_label_values(&[server]),
+        }
+    }
+}
+
+impl<S> Layer<S> for ConnectionCounterLayer {
+    type Service = ConnectionCounter<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        self.active_connections.inc();
+        ConnectionCounter {
+            inner,
+            _connection: Arc::new(ActiveConnection(self.active_connections.clone())),
+        }
+    }
+}
+
+struct ActiveConnection(IntGauge);
+
+impl Drop for ActiveConnection {
+    fn drop(&mut self) {
+        self.0.dec();
+    }
+}
+
+#[derive(Clone)]
+struct ConnectionCounter<S> {
+    inner: S,
+    // shared by the clones of the connection service
+    _connection: Arc<ActiveConnection>,
+}
+
+impl<S, Request> Service<Request> for ConnectionCounter<S>
+where
+    S: Service<Request>,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = S::Future;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, request: Request) -> Self::Future {
+        self.inner.call(request)
+    }
+}
+
 pub async fn start_servers(
     rpc: LiteBridge,
     pubsub: LitePubSubBridge,
@@ -42,33 +112,20 @@ pub async fn start_servers(
     let pubsub = pubsub.into_rpc();
     let server_configuration = server_configuration.unwrap_or_default();
 
-    let ws_server_handle = ServerBuilder::default()
+    let mut ws_server_builder = ServerBuilder::default()
+        .set_middleware(tower::ServiceBuilder::new().layer(ConnectionCounterLayer::new("ws")))
         .ws_only()
         .max_connections(server_configuration.max_connection)
-        .max_subscriptions_per_connection(server_configuration.max_subscriptions_per_connection)
+        .max_subscriptions_per_connection(server_configuration.max_subscriptions_per_connection);
+    if let Some(max_request_body_size) = server_configuration.max_request_body_size {
+        ws_server_builder = ws_server_builder.max_request_body_size(max_request_body_size);
+    }
+    let ws_server_handle = ws_server_builder
         .build(ws_addr.clone())
         .await?
         .start(pubsub);
 
-    let cors = CorsLayer::new()
-        .max_age(Duration::from_secs(86400))
-        // Allow `POST` when accessing the resource
-        .allow_methods([Method::POST, Method::GET, Method::OPTIONS])
-        // Allow requests from any origin
-        .allow_origin(Any)
-        .allow_headers(Any);
-
-    let middleware = tower::ServiceBuilder::new().layer(cors);
-
-    let http_server_handle = ServerBuilder::default()
-        .set_middleware(middleware)
-        .max_connections(server_configuration.max_connection)
-        .max_request_body_size(server_configuration.max_response_body_size)
-        .max_response_body_size(server_configuration.max_response_body_size)
-        .http_only()
-        .build(http_addr.clone())
-        .await?
-        .start(rpc);
+    let (_, http_server_handle) = start_http_server(rpc, &http_addr, &server_configuration).await?;
 
     let ws_server: AnyhowJoinHandle = tokio::spawn(async move {
         log::info!("Websocket Server started at {ws_addr:?}");
@@ -91,3 +148,84 @@ pub async fn start_servers(
         },
     }
 }
+
+async fn start_http_server(
+    rpc: impl Into<Methods>,
+    http_addr: &str,
+    server_configuration: &ServerConfiguration,
+) -> anyhow::Result<(SocketAddr, ServerHandle)> {
+    let cors = CorsLayer::new()
+        .max_age(Duration::from_secs(86400))
+        // Allow `POST` when accessing the resource
+        .allow_methods([Method::POST, Method::GET, Method::OPTIONS])
+        // Allow requests from any origin
+        .allow_origin(Any)
+        .allow_headers(Any);
+
+    let middleware = tower::ServiceBuilder::new()
+        .layer(ConnectionCounterLayer::new("http"))
+        .layer(cors);
+
+    let http_server = ServerBuilder::default()
+        .set_middleware(middleware)
+        .max_connections(server_configuration.max_connection)
+        .max_request_body_size(
+            server_configuration
+                .max_request_body_size
+                .unwrap_or(server_configuration.max_response_body_size),
+        )
+        .max_response_body_size(server_configuration.max_response_body_size)
+        .http_only()
+        .build(http_addr)
+        .await?;
+    let local_addr = http_server.local_addr()?;
+    Ok((local_addr, http_server.start(rpc)))
+}
+
+#[cfg(test)]
+mod tests {
+    use jsonrpsee::RpcModule;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpStream;
+
+    use super::*;
+
+    async fn get_health(stream: &mut TcpStream) -> String {
+        let body = r#"{"jsonrpc":"2.0","id":1,"method":"getHealth"}"#;
+        let request = format!(
+            "POST / HTTP/1.1\r\nHost: localhost\r\nContent-Type: application/json\r\nContent-Length: {}\r\n\r\n{body}",
+            body.len()
+        );
+        stream.write_all(request.as_bytes()).await.unwrap();
+        let mut buf = [0u8; 4096];
+        let n = stream.read(&mut buf).await.unwrap();
+        String::from_utf8_lossy(&buf[..n]).to_string()
+    }
+
+    #[tokio::test]
+    async fn test_connection_beyond_limit_rejected() {
+        let mut rpc = RpcModule::new(());
+        rpc.register_method("getHealth", |_, _| "ok").unwrap();
+        let server_configuration = ServerConfiguration {
+            max_connection: 1,
+            ..ServerConfiguration::default()
+        };
+        let (addr, server_handle) = start_http_server(rpc, "127.0.0.1:0", &server_configuration)
+            .await
+            .unwrap();
+        let active_connections = ACTIVE_CONNECTIONS.with_label_values(&["http"]);
+
+        // kept alive, holds the only connection
+        let mut first = TcpStream::connect(addr).await.unwrap();
+        let response = get_health(&mut first).await;
+        assert!(response.starts_with("HTTP/1.1 200"), "{response}");
+        assert!(response.contains(r#""result":"ok""#), "{response}");
+        assert!(active_connections.get() >= 1);
+
+        let mut second = TcpStream::connect(addr).await.unwrap();
+        let response = get_health(&mut second).await;
+        assert!(response.starts_with("HTTP/1.1 429"), "{response}");
+
+        server_handle.stop().unwrap();
+    }
+}
@@ -0,0 +1,150 @@
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::Arc;
+use std::time::Duration;
+
+use solana_rpc_client_api::response::{Response as RpcResponse, RpcSupply};
+use solana_sdk::commitment_config::CommitmentLevel;
+use tokio::sync::Mutex;
+use tokio::time::Instant;
+
+/// key of a cached supply: commitment and excludeNonCirculatingAccountsList
+type SupplyKey = (CommitmentLevel, bool);
+
+type CachedSupply = Arc<Mutex<Option<(Instant, RpcResponse<RpcSupply>)>>>;
+
+/// Computing the supply is expensive for the upstream rpc,
+/// it is fetched at most once per interval and served with the context slot of the fetch otherwise.
+pub struct SupplyCache {
+    interval: Duration,
+    // the lock of a key is held while fetching so concurrent requests for that key wait for the same fetch
+    cached: std::sync::Mutex<HashMap<SupplyKey, CachedSupply>>,
+}
+
+impl SupplyCache {
+    pub fn new(interval: Duration) -> Self {
+        Self {
+            interval,
+            cached: std::sync::Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub async fn get_or_fetch<F, Fut, E>(
+        &self,
+        commitment: CommitmentLevel,
+        exclude_non_circulating_accounts_list: bool,
+        fetch_from_rpc: F,
+    ) -> Result<RpcResponse<RpcSupply>, E>
+    where
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = Result<RpcResponse<RpcSupply>, E>>,
+    {
+        let key = (commitment, exclude_non_circulating_accounts_list);
+        let entry = self.cached.lock().unwrap().entry(key).or_default().clone();
+        let mut cached = entry.lock().await;
+        if let Some((fetched_at, supply)) = cached.as_ref() {
+            if fetched_at.elapsed() < self.interval {
+                return Ok(supply.clone());
+            }
+        }
+
+        let supply = fetch_from_rpc().await?;
+        *cached = Some((Instant::now(), supply.clone()));
+        Ok(supply)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use solana_rpc_client_api::response::RpcResponseContext;
+
+    use super::*;
+
+    fn supply(slot: u64, total: u64) -> RpcResponse<RpcSupply> {
+        RpcResponse {
+            context: RpcResponseContext {
+                slot,
+                api_version: None,
+            },
+            value: RpcSupply {
+                total,
+                circulating: total / 2,
+                non_circulating: total / 2,
+                non_circulating_accounts: vec![],
+            },
+        }
+    }
+
+    #[tokio::test]
+    async fn test_upstream_fetched_once_per_interval() {
+        let supply_cache = SupplyCache::new(Duration::from_secs(60));
+        let fetch_count = AtomicUsize::new(0);
+
+        for slot in [100, 101, 102] {
+            let response = supply_cache
+                .get_or_fetch(CommitmentLevel::Finalized, true, || async {
+                    fetch_count.fetch_add(1, Ordering::Relaxed);
+                    Ok::<_, anyhow::Error>(supply(slot, 1_000))
+                })
+                .await
+                .unwrap();
+            // served with the context slot of the first fetch
+            assert_eq!(response.context.slot, 100);
+            assert_eq!(response.value.total, 1_000);
+        }
+        assert_eq!(fetch_count.load(Ordering::Relaxed), 1);
+
+        // a different exclude option is cached separately
+        supply_cache
+            .get_or_fetch(CommitmentLevel::Finalized, false, || async {
+                fetch_count.fetch_add(1, Ordering::Relaxed);
+                Ok::<_, anyhow::Error>(supply(103, 1_000))
+            })
+            .await
+            .unwrap();
+        assert_eq!(fetch_count.load(Ordering::Relaxed), 2);
+    }
+
+    #[tokio::test]
+    async fn test_fetch_does_not_block_other_keys() {
+        let supply_cache = SupplyCache::new(Duration::from_secs(60));
+        let (release_sx, release_rx) = tokio::sync::oneshot::channel::<()>();
+
+        let blocked = supply_cache.get_or_fetch(CommitmentLevel::Finalized, true, || async {
+            release_rx.await.unwrap();
+            Ok::<_, anyhow::Error>(supply(100, 1_000))
+        });
+        let other = async {
+            let response = supply_cache
+                .get_or_fetch(CommitmentLevel::Confirmed, true, || async {
+                    Ok::<_, anyhow::Error>(supply(101, 2_000))
+                })
+                .await
+                .unwrap();
+            // the finalized fetch is still pending
+            release_sx.send(()).unwrap();
+            response
+        };
+
+        let (blocked, other) = tokio::join!(blocked, other);
+        assert_eq!(blocked.unwrap().context.slot, 100);
+        assert_eq!(other.context.slot, 101);
+    }
+
+    #[tokio::test]
+    async fn test_refetched_after_interval() {
+        let supply_cache = SupplyCache::new(Duration::ZERO);
+
+        for slot in [100, 101] {
+            let response = supply_cache
+                .get_or_fetch(CommitmentLevel::Finalized, true, || async {
+                    Ok::<_, anyhow::Error>(supply(slot, 1_000))
+                })
+                .await
+                .unwrap();
+            assert_eq!(response.context.slot, slot);
+        }
+    }
+}
@@ -0,0 +1,376 @@
+use std::collections::HashSet;
+use std::future::Future;
+use std::time::Duration;
+
+use jsonrpsee::core::RpcResult;
+use jsonrpsee::types::error::{ErrorCode, ErrorObject, ErrorObjectOwned};
+use log::{debug, warn};
+use prometheus::{opts, register_int_counter, IntCounter};
+use serde::de::DeserializeOwned;
+use solana_rpc_client::nonblocking::rpc_client::RpcClient;
+use solana_rpc_client_api::client_error::{Error as ClientError, ErrorKind as ClientErrorKind};
+use solana_rpc_client_api::request::{RpcError, RpcRequest};
+use tokio::sync::Mutex;
+use tokio::time::Instant;
+
+use crate::{
+    DEFAULT_UPSTREAM_MAX_QUEUE_WAIT_MS, DEFAULT_UPSTREAM_MAX_REQUESTS_PER_SECOND,
+    DEFAULT_UPSTREAM_MAX_RETRIES, DEFAULT_UPSTREAM_RETRY_BACKOFF_MS,
+};
+
+lazy_static::lazy_static! {
+    static ref UPSTREAM_REQUESTS: IntCounter =
+        register_int_counter!(opts!("literpc_upstream_requests", "Number of requests proxied to the upstream rpc")).unwrap();
+    static ref UPSTREAM_RATE_LIMITED: IntCounter =
+        register_int_counter!(opts!("literpc_upstream_rate_limited", "Number of proxied requests rate limited by the upstream rpc")).unwrap();
+    static ref UPSTREAM_QUEUE_FULL: IntCounter =
+        register_int_counter!(opts!("literpc_upstream_queue_full", "Number of proxied requests rejected because the rate limit queue was full")).unwrap();
+}
+
+const HTTP_TOO_MANY_REQUESTS: u16 = 429;
+
+/// methods LiteRPC does not implement, they can be forwarded to the upstream rpc
+pub const PASSTHROUGH_METHODS: &[&str] = &[
+    "getBlock",
+    "getBlocks",
+    "getBlocksWithLimit",
+    "getSignaturesForAddress",
+    "getFirstAvailableBlock",
+    "getVoteAccounts",
+    "requestAirdrop",
+];
+
+#[derive(Debug, Clone, Copy)]
+pub struct UpstreamConfig {
+    /// shared by all proxied methods
+    pub max_requests_per_second: u32,
+    /// retries of a rate limited request, the backoff doubles with every retry
+    pub max_retries: u32,
+    pub initial_backoff: Duration,
+    /// requests which would wait longer for their turn are rejected instead of queued
+    pub max_queue_wait: Duration,
+}
+
+impl Default for UpstreamConfig {
+    fn default() -> Self {
+        Self {
+            max_requests_per_second: DEFAULT_UPSTREAM_MAX_REQUESTS_PER_SECOND,
+            max_retries: DEFAULT_UPSTREAM_MAX_RETRIES,
+            initial_backoff: Duration::from_millis(DEFAULT_UPSTREAM_RETRY_BACKOFF_MS),
+            max_queue_wait: Duration::from_millis(DEFAULT_UPSTREAM_MAX_QUEUE_WAIT_MS),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum UpstreamError {
+    RateLimited { method: String, attempts: u32 },
+    QueueFull { method: String },
+    Client(ClientError),
+}
+
+impl From<UpstreamError> for ErrorObjectOwned {
+    fn from(error: UpstreamError) -> Self {
+        match error {
+            UpstreamError::RateLimited { method, attempts } => ErrorObject::owned(
+                ErrorCode::ServerIsBusy.code(),
+                format!("upstream rate limited {method} after {attempts} attempts"),
+                None::<()>,
+            ),
+            UpstreamError::QueueFull { method } => ErrorObject::owned(
+                ErrorCode::ServerIsBusy.code(),
+                format!("upstream rate limit exceeded, {method} rejected"),
+                None::<()>,
+            ),
+            // forward the error of the upstream rpc
+            UpstreamError::Client(ClientError {
+                kind: ClientErrorKind::RpcError(RpcError::RpcResponseError { code, message, .. }),
+                ..
+            }) => ErrorObject::owned(code as i32, message, None::<()>),
+            UpstreamError::Client(err) => {
+                warn!("upstream request failed: {err:?}");
+                ErrorCode::InternalError.into()
+            }
+        }
+    }
+}
+
+/// spaces the requests evenly, callers wait for their turn up to `max_wait`
+struct RateLimiter {
+    interval: Duration,
+    max_wait: Duration,
+    next_request: Mutex<Instant>,
+}
+
+impl RateLimiter {
+    fn new(max_requests_per_second: u32, max_wait: Duration) -> Self {
+        assert!(
+            max_requests_per_second > 0,
+            "max_requests_per_second must be greater than 0"
+        );
+        Self {
+            interval: Duration::from_secs(1) / max_requests_per_second,
+            max_wait,
+            next_request: Mutex::new(Instant::now()),
+        }
+    }
+
+    /// false without taking a turn if the caller would wait longer than `max_wait`
+    async fn acquire(&self) -> bool {
+        let request_at = {
+            let mut next_request = self.next_request.lock().await;
+            let now = Instant::now();
+            let request_at = (*next_request).max(now);
+            if request_at - now > self.max_wait {
+                return false;
+            }
+            *next_request = request_at + self.interval;
+            request_at
+        };
+        tokio::time::sleep_until(request_at).await;
+        true
+    }
+}
+
+/// Rate limits and retries the requests lite-rpc proxies to the upstream rpc
+pub struct Upstream {
+    config: UpstreamConfig,
+    rate_limiter: RateLimiter,
+    // unimplemented methods forwarded to the upstream rpc
+    passthrough_methods: HashSet<&'static str>,
+}
+
+impl Upstream {
+    pub fn new(config: UpstreamConfig) -> Self {
+        Self {
+            config,
+            rate_limiter: RateLimiter::new(config.max_requests_per_second, config.max_queue_wait),
+            passthrough_methods: HashSet::new(),
+        }
+    }
+
+    /// forward the given methods to the upstream rpc instead of answering MethodNotFound,
+    /// methods implemented by LiteRPC always stay local
+    pub fn with_passthrough_methods(mut self, methods: &[String]) -> Self {
+        for method in methods {
+            match PASSTHROUGH_METHODS
+                .iter()
+                .find(|known| **known == method.as_str())
+            {
+                Some(known) => {
+                    self.passthrough_methods.insert(*known);
+                }
+                None => warn!("{method} cannot be forwarded to the upstream rpc - ignored"),
+            }
+        }
+        self
+    }
+
+    pub fn is_passthrough(&self, method: &str) -> bool {
+        self.passthrough_methods.contains(method)
+    }
+
+    /// forwards the request unchanged to the upstream rpc,
+    /// MethodNotFound if the method is not configured for passthrough
+    pub async fn passthrough<T: DeserializeOwned>(
+        &self,
+        rpc_client: &RpcClient,
+        method: &'static str,
+        params: serde_json::Value,
+    ) -> RpcResult<T> {
+        if !self.is_passthrough(method) {
+            return Err(ErrorCode::MethodNotFound.into());
+        }
+        Ok(self
+            .call(method, || {
+                rpc_client.send::<T>(RpcRequest::Custom { method }, params.clone())
+            })
+            .await?)
+    }
+
+    /// retries `request` with exponential backoff while the upstream rpc rate limits it
+    pub async fn call<T, F, Fut>(&self, method: &str, mut request: F) -> Result<T, UpstreamError>
+    where
+        F: FnMut() -> Fut,
+        Fut: Future<Output = Result<T, ClientError>>,
+    {
+        let attempts = self.config.max_retries + 1;
+        for attempt in 1..=attempts {
+            if !self.rate_limiter.acquire().await {
+                UPSTREAM_QUEUE_FULL.inc();
+                warn!("upstream rate limit queue is full, rejecting {method}");
+                return Err(UpstreamError::QueueFull {
+                    method: method.to_string(),
+                });
+            }
+            UPSTREAM_REQUESTS.inc();
+            match request().await {
+                Ok(response) => return Ok(response),
+                Err(err) if is_rate_limited(&err) => {
+                    UPSTREAM_RATE_LIMITED.inc();
+                    debug!("upstream rate limited {method}, attempt {attempt} of {attempts}");
+                    if attempt < attempts {
+                        tokio::time::sleep(self.backoff(attempt)).await;
+                    }
+                }
+                Err(err) => return Err(UpstreamError::Client(err)),
+            }
+        }
+        warn!("upstream rate limited {method} after {attempts} attempts");
+        Err(UpstreamError::RateLimited {
+            method: method.to_string(),
+            attempts,
+        })
+    }
+
+    fn backoff(&self, attempt: u32) -> Duration {
+        self.config
+            .initial_backoff
+            .saturating_mul(2u32.saturating_pow(attempt.saturating_sub(1)))
+    }
+}
+
+/// http 429 or a json rpc error with code 429, depending on the rpc provider
+fn is_rate_limited(err: &ClientError) -> bool {
+    match &err.kind {
+        ClientErrorKind::Reqwest(err) => err
+            .status()
+            .is_some_and(|status| status.as_u16() == HTTP_TOO_MANY_REQUESTS),
+        ClientErrorKind::RpcError(RpcError::RpcResponseError { code, .. }) => {
+            *code == HTTP_TOO_MANY_REQUESTS as i64
+        }
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    use solana_lite_rpc_util::http_mock::{start_http_mock, HttpResponse};
+    use solana_rpc_client_api::request::RpcResponseErrorData;
+
+    use super::*;
+
+    fn test_config() -> UpstreamConfig {
+        UpstreamConfig {
+            max_requests_per_second: 1000,
+            max_retries: 3,
+            initial_backoff: Duration::from_millis(1),
+            max_queue_wait: Duration::from_secs(1),
+        }
+    }
+
+    /// answers the first `rate_limited` requests with 429, then returns slot 42
+    async fn start_mock_upstream(rate_limited: usize) -> (String, Arc<AtomicUsize>) {
+        let requests = Arc::new(AtomicUsize::new(0));
+        let served = requests.clone();
+        let (addr, _) = start_http_mock(move |request_number, _| {
+            served.fetch_add(1, Ordering::Relaxed);
+            if request_number < rate_limited {
+                HttpResponse::empty("429 Too Many Requests")
+            } else {
+                HttpResponse::json(r#"{"jsonrpc":"2.0","result":42,"id":1}"#)
+            }
+        })
+        .await;
+        (format!("http://{addr}"), requests)
+    }
+
+    #[tokio::test]
+    async fn test_rate_limited_twice_then_succeeds() {
+        let (url, requests) = start_mock_upstream(2).await;
+        let rpc_client = RpcClient::new(url);
+        let upstream = Upstream::new(test_config());
+
+        let slot = upstream
+            .call("getSlot", || rpc_client.get_slot())
+            .await
+            .unwrap();
+        assert_eq!(slot, 42);
+        assert_eq!(requests.load(Ordering::Relaxed), 3);
+    }
+
+    #[tokio::test]
+    async fn test_rate_limited_after_exhausting_retries() {
+        let upstream = Upstream::new(test_config());
+        let attempts = AtomicUsize::new(0);
+
+        let result = upstream
+            .call("requestAirdrop", || async {
+                attempts.fetch_add(1, Ordering::Relaxed);
+                Err::<(), _>(ClientError::from(ClientErrorKind::RpcError(
+                    RpcError::RpcResponseError {
+                        code: 429,
+                        message: "Too many requests".to_string(),
+                        data: RpcResponseErrorData::Empty,
+                    },
+                )))
+            })
+            .await;
+
+        assert_eq!(attempts.load(Ordering::Relaxed), 4);
+        let error: ErrorObjectOwned = result.unwrap_err().into();
+        assert_eq!(error.code(), ErrorCode::ServerIsBusy.code());
+        assert_eq!(
+            error.message(),
+            "upstream rate limited requestAirdrop after 4 attempts"
+        );
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_rejected_when_queue_wait_exceeds_limit() {
+        // one request per second, callers wait at most 1.5s for their turn
+        let upstream = Upstream::new(UpstreamConfig {
+            max_requests_per_second: 1,
+            max_queue_wait: Duration::from_millis(1500),
+            ..test_config()
+        });
+        let requests = AtomicUsize::new(0);
+        let request = || async {
+            requests.fetch_add(1, Ordering::Relaxed);
+            Ok::<_, ClientError>(())
+        };
+
+        // the turns at 0s and 1s are taken, the third caller would wait 2s
+        let (first, second, third) = tokio::join!(
+            upstream.call("getSlot", request),
+            upstream.call("getSlot", request),
+            upstream.call("getSlot", request)
+        );
+        first.unwrap();
+        second.unwrap();
+        let error: ErrorObjectOwned = third.unwrap_err().into();
+        assert_eq!(error.code(), ErrorCode::ServerIsBusy.code());
+        assert_eq!(requests.load(Ordering::Relaxed), 2);
+    }
+
+    #[tokio::test]
+    async fn test_passthrough_only_unimplemented_methods() {
+        let (url, requests) = start_mock_upstream(0).await;
+        let rpc_client = RpcClient::new(url);
+        let upstream = Upstream::new(test_config()).with_passthrough_methods(&[
+            "getFirstAvailableBlock".to_string(),
+            "getSlot".to_string(),
+        ]);
+
+        let first_available_block: u64 = upstream
+            .passthrough(&rpc_client, "getFirstAvailableBlock", serde_json::json!([]))
+            .await
+            .unwrap();
+        assert_eq!(first_available_block, 42);
+        assert_eq!(requests.load(Ordering::Relaxed), 1);
+
+        // getSlot is implemented by LiteRPC and stays local
+        assert!(!upstream.is_passthrough("getSlot"));
+        let error = upstream
+            .passthrough::<u64>(&rpc_client, "getSlot", serde_json::json!([]))
+            .await
+            .unwrap_err();
+        assert_eq!(error.code(), ErrorCode::MethodNotFound.code());
+        // not configured
+        assert!(!upstream.is_passthrough("getVoteAccounts"));
+        assert_eq!(requests.load(Ordering::Relaxed), 1);
+    }
+}
@@ -8,7 +8,7 @@ use tokio::{sync::broadcast::Sender, task::JoinHandle};
 
 use crate::{
     account_priofees::AccountPrioStore,
-    rpc_data::{AccountPrioFeesStats, AccountPrioFeesUpdateMessage},
+    rpc_data::{AccountPrioFeesBucket, AccountPrioFeesStats, AccountPrioFeesUpdateMessage},
 };
 use tokio::sync::broadcast::error::RecvError::{Closed, Lagged};
 
@@ -74,4 +74,14 @@ impl AccountPrioService {
     pub fn get_n_last_stats(&self, account: &Pubkey, nb: usize) -> (Slot, AccountPrioFeesStats) {
         self.account_store.get_n_last_stats(account, nb)
     }
+
+    pub fn get_bucketed_stats(
+        &self,
+        account: &Pubkey,
+        bucket_secs: u64,
+        window_secs: u64,
+    ) -> Vec<AccountPrioFeesBucket> {
+        self.account_store
+            .get_bucketed_stats(account, bucket_secs, window_secs)
+    }
 }
@@ -14,7 +14,7 @@ use solana_sdk::{pubkey::Pubkey, slot_history::Slot};
 
 use crate::{
     prioritization_fee_data::{BlockPrioData, PrioFeesData},
-    rpc_data::{AccountPrioFeesStats, AccountPrioFeesUpdateMessage},
+    rpc_data::{AccountPrioFeesBucket, AccountPrioFeesStats, AccountPrioFeesUpdateMessage},
 };
 
 lazy_static::lazy_static! {
@@ -139,6 +139,7 @@ impl AccountPrioStore {
                 nb_total_tx: tx_count,
                 non_vote_cu_consumed: cu_consumed,
                 total_cu_consumed: cu_consumed,
+                block_time: produced_block.block_time,
             }
         };
 
@@ -304,4 +305,130 @@ impl AccountPrioStore {
             },
         )
     }
+
+    /// stats per `bucket_secs` seconds (by block time) of the retained blocks within `window_secs`
+    /// of the latest block of the account, oldest bucket first, buckets without blocks are left out.
+    /// `bucket_secs` must be greater than 0, the rpc rejects an empty bucket
+    pub fn get_bucketed_stats(
+        &self,
+        account: &Pubkey,
+        bucket_secs: u64,
+        window_secs: u64,
+    ) -> Vec<AccountPrioFeesBucket> {
+        let Some(latest_block_time) =
+            self.account_by_prio_fees_all
+                .get(account)
+                .and_then(|account_prio| {
+                    account_prio
+                        .stats_by_slot
+                        .values()
+                        .map(|block| block.block_time)
+                        .max()
+                })
+        else {
+            return vec![];
+        };
+        let window_start = latest_block_time.saturating_sub(window_secs);
+
+        let aggregate_buckets = |by_account: &DashMap<Pubkey, AccountPrio>| {
+            let mut buckets: BTreeMap<u64, BlockPrioData> = BTreeMap::new();
+            if let Some(account_prio) = by_account.get(account) {
+                for block in account_prio
+                    .stats_by_slot
+                    .values()
+                    .filter(|block| block.block_time >= window_start)
+                {
+                    let start_time = block.block_time - block.block_time % bucket_secs;
+                    let bucket = buckets.entry(start_time).or_default();
+                    *bucket = bucket.add(block);
+                }
+            }
+            buckets
+        };
+        let mut write_only = aggregate_buckets(&self.account_by_prio_fees_writeonly);
+        aggregate_buckets(&self.account_by_prio_fees_all)
+            .into_iter()
+            .map(|(start_time, all)| AccountPrioFeesBucket {
+                start_time,
+                stats: AccountPrioFeesStats {
+                    write_stats: write_only
+                        .remove(&start_time)
+                        .unwrap_or_default()
+                        .calculate_stats(),
+                    all_stats: all.calculate_stats(),
+                },
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rpc_data::PrioFeesStats;
+
+    fn block_prio_data(block_time: u64, priorities: &[u64]) -> BlockPrioData {
+        BlockPrioData {
+            transaction_data: priorities
+                .iter()
+                .map(|priority| PrioFeesData::from((*priority, 1000)))
+                .collect(),
+            nb_non_vote_tx: priorities.len() as u64,
+            nb_total_tx: priorities.len() as u64,
+            non_vote_cu_consumed: 1000 * priorities.len() as u64,
+            total_cu_consumed: 1000 * priorities.len() as u64,
+            block_time,
+        }
+    }
+
+    #[test]
+    fn test_bucketed_stats() {
+        let store = AccountPrioStore::new(1000, None);
+        let account = Pubkey::new_unique();
+        // (slot, block_time, priorities); one block per 2 seconds from t=1000 to t=1018
+        let blocks = (0..10).map(|i| (100 + i, 1000 + 2 * i, vec![10 * (i + 1)]));
+        let mut all = AccountPrio {
+            stats_by_slot: BTreeMap::new(),
+        };
+        let mut write_only = AccountPrio {
+            stats_by_slot: BTreeMap::new(),
+        };
+        for (slot, block_time, priorities) in blocks {
+            all.stats_by_slot
+                .insert(slot, block_prio_data(block_time, &priorities));
+            // only the even blocks write lock the account
+            if slot % 2 == 0 {
+                write_only
+                    .stats_by_slot
+                    .insert(slot, block_prio_data(block_time, &priorities));
+            }
+        }
+        store.account_by_prio_fees_all.insert(account, all);
+        store
+            .account_by_prio_fees_writeonly
+            .insert(account, write_only);
+
+        // 5 second buckets over the last 10 seconds: t=1008..=1018
+        let buckets = store.get_bucketed_stats(&account, 5, 10);
+        assert_eq!(
+            buckets.iter().map(|bucket| bucket.start_time).collect_vec(),
+            vec![1005, 1010, 1015]
+        );
+        let p75 = |stats: &PrioFeesStats| stats.get_percentile(0.75).unwrap().0;
+        // only the block at t=1008 is within the window
+        assert_eq!(buckets[0].stats.all_stats.tx_count.total, 1);
+        assert_eq!(p75(&buckets[0].stats.all_stats), 50);
+        // blocks at t=1010, 1012, 1014
+        assert_eq!(buckets[1].stats.all_stats.tx_count.total, 3);
+        assert_eq!(p75(&buckets[1].stats.all_stats), 80);
+        assert_eq!(buckets[1].stats.write_stats.tx_count.total, 1);
+        assert_eq!(p75(&buckets[1].stats.write_stats), 70);
+        // blocks at t=1016, 1018
+        assert_eq!(buckets[2].stats.all_stats.tx_count.total, 2);
+        assert_eq!(p75(&buckets[2].stats.all_stats), 100);
+
+        assert!(store
+            .get_bucketed_stats(&Pubkey::new_unique(), 5, 10)
+            .is_empty());
+    }
 }
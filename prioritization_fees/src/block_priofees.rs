@@ -31,6 +31,19 @@ impl PrioFeesService {
             .map(|(slot, value)| (*slot, value.calculate_stats()))
     }
 
+    /// stats of each of the last `nb` blocks, ordered by slot
+    pub async fn get_recent_priofees(&self, nb: usize) -> Vec<(Slot, PrioFeesStats)> {
+        let lock = self.block_fees_store.recent.read().await;
+        let mut recent = lock
+            .iter()
+            .rev()
+            .take(nb)
+            .map(|(slot, value)| (*slot, value.calculate_stats()))
+            .collect::<Vec<_>>();
+        recent.reverse();
+        recent
+    }
+
     pub async fn get_last_n_priofees_aggregate(&self, nb: usize) -> Option<(Slot, PrioFeesStats)> {
         let lock = self.block_fees_store.recent.read().await;
         let last_slot = match lock.last_key_value().map(|x| *x.0) {
@@ -49,6 +62,24 @@ impl PrioFeesService {
             .calculate_stats();
         Some((last_slot, prio_fees))
     }
+
+    /// aggregate over the blocks within `window_secs` seconds up to the latest block (by block time)
+    /// the number of blocks varies with the block rate
+    pub async fn get_time_window_priofees_aggregate(
+        &self,
+        window_secs: u64,
+    ) -> Option<(Slot, PrioFeesStats)> {
+        let lock = self.block_fees_store.recent.read().await;
+        let (last_slot, last_block) = lock.last_key_value()?;
+        let window_start = last_block.block_time.saturating_sub(window_secs);
+
+        let prio_fees = lock
+            .values()
+            .filter(|x| x.block_time > window_start)
+            .fold(BlockPrioData::default(), |acc, x| acc.add(x))
+            .calculate_stats();
+        Some((*last_slot, prio_fees))
+    }
 }
 
 pub fn start_block_priofees_task(
@@ -107,6 +138,7 @@ pub fn start_block_priofees_task(
                         nb_total_tx,
                         non_vote_cu_consumed,
                         total_cu_consumed,
+                        block_time: block.block_time,
                     };
 
                     trace!("Got prio fees stats for processed block {}", processed_slot);
@@ -160,3 +192,106 @@ pub fn start_block_priofees_task(
         },
     )
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn create_test_service(blocks: Vec<(Slot, u64, u64)>) -> PrioFeesService {
+        let recent = blocks
+            .into_iter()
+            .map(|(slot, block_time, priority)| {
+                (
+                    slot,
+                    BlockPrioData {
+                        transaction_data: vec![PrioFeesData::from((priority, 1000))],
+                        nb_non_vote_tx: 1,
+                        nb_total_tx: 1,
+                        non_vote_cu_consumed: 1000,
+                        total_cu_consumed: 1000,
+                        block_time,
+                    },
+                )
+            })
+            .collect();
+        let (block_fees_stream, _) = tokio::sync::broadcast::channel(1);
+        PrioFeesService {
+            block_fees_store: PrioFeeStore {
+                recent: Arc::new(RwLock::new(recent)),
+            },
+            block_fees_stream,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_time_window_aggregation() {
+        // (slot, block_time, priority); block rate drops after slot 103
+        let service = create_test_service(vec![
+            (100, 1_000, 10),
+            (101, 1_001, 20),
+            (102, 1_001, 30),
+            (103, 1_002, 40),
+            (104, 1_010, 50),
+            (105, 1_030, 60),
+        ]);
+
+        // blocks 104 and 105
+        let (slot, stats) = service
+            .get_time_window_priofees_aggregate(25)
+            .await
+            .unwrap();
+        assert_eq!(slot, 105);
+        assert_eq!(stats.tx_count.total, 2);
+        assert_eq!(stats.by_tx.first(), Some(&50));
+        assert_eq!(stats.by_tx.last(), Some(&60));
+
+        // blocks 101 to 105, block 100 is exactly 30s older than the latest block
+        let (_, stats) = service
+            .get_time_window_priofees_aggregate(30)
+            .await
+            .unwrap();
+        assert_eq!(stats.tx_count.total, 5);
+        assert_eq!(stats.by_tx.first(), Some(&20));
+
+        // all blocks
+        let (_, stats) = service
+            .get_time_window_priofees_aggregate(60)
+            .await
+            .unwrap();
+        assert_eq!(stats.tx_count.total, 6);
+        assert_eq!(stats.cu_consumed.total, 6000);
+
+        assert!(create_test_service(vec![])
+            .get_time_window_priofees_aggregate(30)
+            .await
+            .is_none());
+    }
+
+    #[tokio::test]
+    async fn test_recent_priofees_per_slot() {
+        let service =
+            create_test_service(vec![(100, 1_000, 10), (101, 1_001, 20), (103, 1_002, 40)]);
+
+        let recent = service.get_recent_priofees(2).await;
+        assert_eq!(
+            recent
+                .iter()
+                .map(|(slot, stats)| (*slot, stats.by_tx.clone()))
+                .collect::<Vec<_>>(),
+            vec![(101, vec![20]), (103, vec![40])]
+        );
+        assert_eq!(service.get_recent_priofees(10).await.len(), 3);
+        assert!(create_test_service(vec![])
+            .get_recent_priofees(10)
+            .await
+            .is_empty());
+    }
+
+    #[test]
+    fn test_parse_time_window_method() {
+        use crate::prioritization_fee_calculation_method::PrioritizationFeeCalculationMethod;
+        let method: PrioritizationFeeCalculationMethod =
+            serde_json::from_str("\"last_n_seconds(30)\"").unwrap();
+        assert_eq!(method, PrioritizationFeeCalculationMethod::LastNSeconds(30));
+    }
+}
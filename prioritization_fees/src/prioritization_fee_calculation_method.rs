@@ -6,6 +6,8 @@ pub enum PrioritizationFeeCalculationMethod {
     #[default]
     Latest,
     LastNBlocks(usize),
+    // aggregate over the blocks of the last n seconds (by block time)
+    LastNSeconds(u64),
     Unknown,
 }
 
@@ -27,6 +29,15 @@ impl<'de> Deserialize<'de> for PrioritizationFeeCalculationMethod {
                 return Ok(PrioritizationFeeCalculationMethod::Unknown);
             };
             Ok(PrioritizationFeeCalculationMethod::LastNBlocks(nb))
+        } else if s.starts_with("last_n_seconds") {
+            let Ok(seconds) = s
+                .replace("last_n_seconds(", "")
+                .replace(')', "")
+                .parse::<u64>()
+            else {
+                return Ok(PrioritizationFeeCalculationMethod::Unknown);
+            };
+            Ok(PrioritizationFeeCalculationMethod::LastNSeconds(seconds))
         } else {
             return Ok(PrioritizationFeeCalculationMethod::Unknown);
         }
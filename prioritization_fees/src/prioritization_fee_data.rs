@@ -25,6 +25,8 @@ pub struct BlockPrioData {
     pub nb_total_tx: u64,
     pub non_vote_cu_consumed: u64,
     pub total_cu_consumed: u64,
+    // unix timestamp in seconds, latest block time when aggregated
+    pub block_time: u64,
 }
 
 impl BlockPrioData {
@@ -55,6 +57,7 @@ impl BlockPrioData {
             nb_total_tx: self.nb_total_tx + rhs.nb_total_tx,
             non_vote_cu_consumed: self.non_vote_cu_consumed + rhs.non_vote_cu_consumed,
             total_cu_consumed: self.total_cu_consumed + rhs.total_cu_consumed,
+            block_time: self.block_time.max(rhs.block_time),
         }
     }
 }
@@ -23,16 +23,51 @@ pub struct PrioFeesStats {
     pub cu_consumed: TxAggregateStats,
 }
 
+/// fee at a single percentile, the higher of the by tx and by cu fees
+#[derive(Clone, Serialize, Debug, PartialEq)]
+pub struct PrioFeeAtPercentile {
+    pub percentile: f32,
+    pub fee: u64,
+}
+
+/// the full stats unless a percentile was requested
+#[derive(Clone, Serialize, Debug)]
+#[serde(untagged)]
+pub enum LatestPrioFees {
+    Stats(PrioFeesStats),
+    AtPercentile(PrioFeeAtPercentile),
+}
+
 impl PrioFeesStats {
+    /// fees (by tx, by cu) at the given percentile, rounded up to the next percentile available in the stats
     pub fn get_percentile(&self, percentile: f32) -> Option<(u64, u64)> {
-        let index_tx = self.by_tx_percentiles.iter().position(|x| *x == percentile);
-        let index_cu = self.by_cu_percentiles.iter().position(|x| *x == percentile);
+        let index_tx = self.by_tx_percentiles.iter().position(|x| *x >= percentile);
+        let index_cu = self.by_cu_percentiles.iter().position(|x| *x >= percentile);
         if let (Some(index_tx), Some(index_cu)) = (index_tx, index_cu) {
             Some((self.by_tx[index_tx], self.by_cu[index_cu]))
         } else {
             None
         }
     }
+
+    /// fees (by tx, by cu) at the given percentile, linearly interpolated between the two surrounding percentiles available in the stats
+    pub fn get_interpolated_percentile(&self, percentile: f32) -> Option<(u64, u64)> {
+        let fee_tx = interpolate(&self.by_tx_percentiles, &self.by_tx, percentile)?;
+        let fee_cu = interpolate(&self.by_cu_percentiles, &self.by_cu, percentile)?;
+        Some((fee_tx, fee_cu))
+    }
+}
+
+fn interpolate(percentiles: &[f32], fees: &[u64], percentile: f32) -> Option<u64> {
+    let upper = percentiles.iter().position(|x| *x >= percentile)?;
+    if upper == 0 || percentiles[upper] == percentile {
+        return Some(fees[upper]);
+    }
+    let lower = upper - 1;
+    let ratio =
+        ((percentile - percentiles[lower]) / (percentiles[upper] - percentiles[lower])) as f64;
+    let (lower_fee, upper_fee) = (fees[lower] as f64, fees[upper] as f64);
+    Some((lower_fee + (upper_fee - lower_fee) * ratio).round() as u64)
 }
 
 #[derive(Clone, Serialize, Debug, Eq, PartialEq, Hash)]
@@ -61,8 +96,75 @@ pub struct AccountPrioFeesStats {
     pub all_stats: PrioFeesStats,
 }
 
+/// fees of an account within `bucket_secs` seconds starting at `start_time` (by block time)
+#[derive(Clone, Serialize, Debug)]
+pub struct AccountPrioFeesBucket {
+    pub start_time: u64,
+    #[serde(flatten)]
+    pub stats: AccountPrioFeesStats,
+}
+
+#[derive(Clone, Serialize, Debug)]
+pub struct AccountPrioFeesWithHistory {
+    #[serde(flatten)]
+    pub latest: AccountPrioFeesStats,
+    /// oldest bucket first, only if requested
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub history: Option<Vec<AccountPrioFeesBucket>>,
+}
+
 #[derive(Clone)]
 pub struct AccountPrioFeesUpdateMessage {
     pub slot: Slot,
     pub accounts_data: Arc<HashMap<Pubkey, AccountPrioFeesStats>>,
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::prioritization_fee_data::{BlockPrioData, PrioFeesData};
+
+    #[test]
+    fn test_get_percentile() {
+        let stats = BlockPrioData {
+            transaction_data: (1..=100).map(|x| PrioFeesData::from((x * 10, 1))).collect(),
+            ..Default::default()
+        }
+        .calculate_stats();
+
+        let p50 = stats.get_percentile(0.5).unwrap();
+        let p90 = stats.get_percentile(0.9).unwrap();
+        assert_eq!(p50.0, 510);
+        assert_eq!(p90.0, 910);
+        assert!(p90.0 > p50.0);
+        assert!(p90.1 > p50.1);
+
+        // rounded up to p75
+        assert_eq!(stats.get_percentile(0.73), stats.get_percentile(0.75));
+        assert_eq!(stats.get_percentile(1.0).unwrap().0, 1000);
+        assert_eq!(stats.get_percentile(1.5), None);
+    }
+
+    #[test]
+    fn test_get_interpolated_percentile() {
+        let stats = BlockPrioData {
+            transaction_data: (1..=100).map(|x| PrioFeesData::from((x * 10, 1))).collect(),
+            ..Default::default()
+        }
+        .calculate_stats();
+
+        // between the p70 and p75 buckets instead of rounded up to p75
+        assert_eq!(stats.get_percentile(0.73), Some((760, 750)));
+        assert_eq!(stats.get_interpolated_percentile(0.73), Some((740, 730)));
+
+        // same as the bucket for percentiles available in the stats
+        assert_eq!(
+            stats.get_interpolated_percentile(0.75),
+            stats.get_percentile(0.75)
+        );
+        assert_eq!(
+            stats.get_interpolated_percentile(0.0),
+            stats.get_percentile(0.0)
+        );
+        assert_eq!(stats.get_interpolated_percentile(1.5), None);
+    }
+}
@@ -1,13 +1,14 @@
 use std::time::Duration;
 
 use anyhow::{bail, Context};
-use log::warn;
+use log::{info, warn};
 use prometheus::core::GenericGauge;
 use prometheus::{opts, register_int_counter, register_int_gauge, IntCounter};
 use solana_lite_rpc_core::stores::{
     block_information_store::BlockInformation, data_cache::DataCache,
 };
 use solana_lite_rpc_core::structures::block_info::BlockInfo;
+use solana_lite_rpc_core::structures::slot_notification::SlotNotification;
 use solana_lite_rpc_core::types::{BlockStream, ClusterInfoStream, SlotStream, VoteAccountStream};
 use solana_lite_rpc_core::AnyhowJoinHandle;
 use solana_sdk::clock::MAX_RECENT_BLOCKHASHES;
@@ -15,6 +16,7 @@ use solana_sdk::commitment_config::CommitmentLevel;
 use solana_transaction_status::{TransactionConfirmationStatus, TransactionStatus};
 use tokio::sync::broadcast::error::RecvError;
 use tokio::sync::broadcast::Receiver;
+use tracing::info_span;
 
 lazy_static::lazy_static! {
     static ref NB_CLUSTER_NODES: GenericGauge<prometheus::core::AtomicI64> =
@@ -26,6 +28,9 @@ lazy_static::lazy_static! {
     static ref ESTIMATED_SLOT: GenericGauge<prometheus::core::AtomicI64> =
     register_int_gauge!(opts!("literpc_estimated_slot", "Estimated slot seen by last rpc")).unwrap();
 
+    static ref ESTIMATED_SLOT_DRIFT: GenericGauge<prometheus::core::AtomicI64> =
+    register_int_gauge!(opts!("literpc_estimated_slot_drift", "Distance between the estimated and the processed slot")).unwrap();
+
     static ref TXS_CONFIRMED: IntCounter =
     register_int_counter!(opts!("literpc_txs_confirmed", "Number of Transactions Confirmed")).unwrap();
 
@@ -36,9 +41,55 @@ lazy_static::lazy_static! {
     register_int_counter!(opts!("literpc_txs_processed", "Number of Transactions Processed")).unwrap();
 }
 
+// default distance between the estimated and the processed slot which raises the drift alarm
+pub const DEFAULT_SLOT_DRIFT_ALARM_THRESHOLD: u64 = 150;
+
+/// Warns if the estimated slot diverges from the processed slot, which indicates a broken slot estimator.
+/// Logs once when the drift exceeds the threshold and again only after it recovered.
+pub struct SlotDriftAlarm {
+    threshold: u64,
+    alarming: bool,
+}
+
+impl SlotDriftAlarm {
+    pub fn new(threshold: u64) -> Self {
+        Self {
+            threshold,
+            alarming: false,
+        }
+    }
+
+    /// returns the drift if the alarm was raised by this notification
+    pub fn observe(&mut self, slot_notification: &SlotNotification) -> Option<u64> {
+        let drift = slot_notification
+            .estimated_processed_slot
+            .abs_diff(slot_notification.processed_slot);
+        ESTIMATED_SLOT_DRIFT.set(drift as i64);
+        if drift <= self.threshold {
+            if self.alarming {
+                info!("estimated slot drift recovered to {drift} slots");
+            }
+            self.alarming = false;
+            return None;
+        }
+        if self.alarming {
+            return None;
+        }
+        self.alarming = true;
+        warn!(
+            "estimated slot {} drifted {drift} slots from processed slot {} (threshold {})",
+            slot_notification.estimated_processed_slot,
+            slot_notification.processed_slot,
+            self.threshold
+        );
+        Some(drift)
+    }
+}
+
 pub struct DataCachingService {
     pub data_cache: DataCache,
     pub clean_duration: Duration,
+    pub slot_drift_alarm_threshold: u64,
 }
 
 impl DataCachingService {
@@ -72,6 +123,7 @@ impl DataCachingService {
                 block_information_store_block
                     .add_block(BlockInformation::from_block(&block))
                     .await;
+                data_cache.transaction_count.add_block(&block);
 
                 let confirmation_status = match block.commitment_config.commitment {
                     CommitmentLevel::Finalized => TransactionConfirmationStatus::Finalized,
@@ -99,7 +151,14 @@ impl DataCachingService {
                         },
                         last_valid_blockheight,
                     ) {
-                        // transaction updated
+                        // transaction updated, logged in the span of the transaction sent by lite-rpc
+                        info_span!("transaction", signature = %tx.signature).in_scope(|| {
+                            tracing::debug!(
+                                slot = block.slot,
+                                status = ?confirmation_status,
+                                "transaction confirmation"
+                            );
+                        });
                         match confirmation_status {
                             TransactionConfirmationStatus::Finalized => {
                                 TXS_FINALIZED.inc();
@@ -142,6 +201,7 @@ impl DataCachingService {
         });
 
         let data_cache = self.data_cache.clone();
+        let mut slot_drift_alarm = SlotDriftAlarm::new(self.slot_drift_alarm_threshold);
         let slot_cache_jh = tokio::spawn(async move {
             let mut slot_notification = slot_notification;
             loop {
@@ -149,7 +209,9 @@ impl DataCachingService {
                     Ok(slot_notification) => {
                         CURRENT_SLOT.set(slot_notification.processed_slot as i64);
                         ESTIMATED_SLOT.set(slot_notification.estimated_processed_slot as i64);
+                        slot_drift_alarm.observe(&slot_notification);
                         data_cache.slot_cache.update(slot_notification);
+                        data_cache.latest_block_cache.invalidate();
                     }
                     Err(e) => {
                         bail!("Error in slot notification {e:?}");
@@ -204,3 +266,38 @@ impl DataCachingService {
         ]
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_slot_drift_alarm() {
+        let mut alarm = SlotDriftAlarm::new(DEFAULT_SLOT_DRIFT_ALARM_THRESHOLD);
+        assert_eq!(
+            alarm.observe(&SlotNotification {
+                processed_slot: 1000,
+                estimated_processed_slot: 1002,
+            }),
+            None
+        );
+        assert_eq!(ESTIMATED_SLOT_DRIFT.get(), 2);
+
+        // broken estimator far ahead of the processed slot
+        let drifted = SlotNotification {
+            processed_slot: 1000,
+            estimated_processed_slot: 5000,
+        };
+        assert_eq!(alarm.observe(&drifted), Some(4000));
+        assert_eq!(ESTIMATED_SLOT_DRIFT.get(), 4000);
+        // warned only once while drifting
+        assert_eq!(alarm.observe(&drifted), None);
+
+        alarm.observe(&SlotNotification {
+            processed_slot: 5000,
+            estimated_processed_slot: 5000,
+        });
+        assert_eq!(ESTIMATED_SLOT_DRIFT.get(), 0);
+        assert_eq!(alarm.observe(&drifted), Some(4000));
+    }
+}
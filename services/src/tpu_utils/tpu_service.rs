@@ -119,29 +119,19 @@ impl TpuService {
         let last_slot = estimated_slot + fanout;
         let current_slot = current_slot.saturating_sub(4);
 
-        let cluster_nodes = self.data_cache.cluster_info.cluster_nodes.clone();
-
         let next_leaders = self
             .leader_schedule
             .get_slot_leaders(current_slot, last_slot)
             .await?;
         // get next leader with its tpu port
-        let connections_to_keep: HashMap<_, _> = next_leaders
-            .iter()
-            .map(|x| {
-                let contact_info = cluster_nodes.get(&x.pubkey);
-                let tpu_port = match contact_info {
-                    Some(info) => info.tpu,
-                    _ => None,
-                };
-                (x.pubkey, tpu_port)
-            })
-            .filter(|x| x.1.is_some())
-            .map(|x| {
-                let mut addr = x.1.unwrap();
+        let connections_to_keep: HashMap<_, _> = self
+            .data_cache
+            .tpu_peers_of_leaders(next_leaders.iter().map(|leader| leader.pubkey))
+            .into_iter()
+            .map(|(leader, mut addr)| {
                 // add quic port offset
                 addr.set_port(addr.port() + QUIC_PORT_OFFSET);
-                (x.0, addr)
+                (leader, addr)
             })
             .collect();
 
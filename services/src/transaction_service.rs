@@ -1,7 +1,13 @@
 // This class will manage the lifecycle for a transaction
 // It will send, replay if necessary and confirm by listening to blocks
 
-use std::{sync::Arc, time::Duration};
+use std::{
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
 
 use crate::{
     tpu_utils::tpu_service::TpuService,
@@ -9,7 +15,10 @@ use crate::{
     tx_sender::TxSender,
 };
 use anyhow::bail;
-use prometheus::{histogram_opts, register_histogram, Histogram};
+use dashmap::DashMap;
+use prometheus::{
+    histogram_opts, opts, register_histogram, register_int_counter, Histogram, IntCounter,
+};
 use solana_lite_rpc_core::{
     solana_utils::SerializableTransaction, structures::transaction_sent_info::SentTransactionInfo,
     types::SlotStream,
@@ -21,6 +30,7 @@ use solana_lite_rpc_core::{
 };
 use solana_sdk::{
     compute_budget::{self, ComputeBudgetInstruction},
+    signature::Signature,
     transaction::VersionedTransaction,
 };
 use tokio::{
@@ -34,6 +44,47 @@ lazy_static::lazy_static! {
         "Priority fees of transactions sent by lite-rpc",
     ))
     .unwrap();
+    static ref DUPLICATE_TXS: IntCounter =
+    register_int_counter!(opts!("literpc_txs_duplicate", "Transactions not forwarded again as the signature was already sent")).unwrap();
+}
+
+/// signatures sent while their blockhash is still valid, a duplicate submission is not forwarded again
+#[derive(Clone, Default)]
+pub struct RecentSignatures {
+    last_valid_block_heights: Arc<DashMap<Signature, u64>>,
+    cleaned_at_block_height: Arc<AtomicU64>,
+}
+
+impl RecentSignatures {
+    /// false if the signature was already sent
+    fn insert(
+        &self,
+        signature: Signature,
+        last_valid_block_height: u64,
+        block_height: u64,
+    ) -> bool {
+        // expired signatures are removed once per block height
+        if self
+            .cleaned_at_block_height
+            .swap(block_height, Ordering::Relaxed)
+            != block_height
+        {
+            self.last_valid_block_heights
+                .retain(|_, last_valid| *last_valid >= block_height);
+        }
+        match self.last_valid_block_heights.entry(signature) {
+            dashmap::mapref::entry::Entry::Occupied(_) => false,
+            dashmap::mapref::entry::Entry::Vacant(entry) => {
+                entry.insert(last_valid_block_height);
+                true
+            }
+        }
+    }
+
+    /// the signature was not forwarded, a resubmission must not be dropped as a duplicate
+    fn remove(&self, signature: &Signature) {
+        self.last_valid_block_heights.remove(signature);
+    }
 }
 
 #[derive(Clone)]
@@ -42,6 +93,7 @@ pub struct TransactionServiceBuilder {
     tx_replayer: TransactionReplayer,
     tpu_service: TpuService,
     max_nb_txs_in_queue: usize,
+    transaction_dedup: bool,
 }
 
 impl TransactionServiceBuilder {
@@ -56,14 +108,22 @@ impl TransactionServiceBuilder {
             tx_replayer,
             tpu_service,
             max_nb_txs_in_queue,
+            transaction_dedup: false,
         }
     }
 
+    /// duplicate submissions of a signature are not forwarded again while its blockhash is valid
+    pub fn with_transaction_dedup(mut self, transaction_dedup: bool) -> Self {
+        self.transaction_dedup = transaction_dedup;
+        self
+    }
+
     pub fn start(
         self,
         notifier: Option<NotificationSender>,
         block_information_store: BlockInformationStore,
         max_retries: usize,
+        max_allowed_retries: usize,
         slot_notifications: SlotStream,
     ) -> (TransactionService, AnyhowJoinHandle) {
         let (transaction_channel, tx_recv) = mpsc::channel(self.max_nb_txs_in_queue);
@@ -103,20 +163,35 @@ impl TransactionServiceBuilder {
                 replay_channel,
                 block_information_store,
                 max_retries,
+                max_allowed_retries,
                 replay_offset: self.tx_replayer.retry_offset,
+                recent_signatures: self.transaction_dedup.then(RecentSignatures::default),
             },
             jh_services,
         )
     }
 }
 
+/// the recent blockhash of a transaction is unknown or no longer valid, the transaction would never land
+#[derive(thiserror::Error, Debug)]
+pub enum BlockhashError {
+    #[error("Blockhash not found in block store")]
+    NotFound,
+    #[error("Blockhash is expired")]
+    Expired,
+}
+
 #[derive(Clone)]
 pub struct TransactionService {
     pub transaction_channel: Sender<SentTransactionInfo>,
     pub replay_channel: UnboundedSender<TransactionReplay>,
     pub block_information_store: BlockInformationStore,
     pub max_retries: usize,
+    // ceiling for the max_retries requested by a client
+    pub max_allowed_retries: usize,
     pub replay_offset: Duration,
+    // none if transaction dedup is disabled
+    pub recent_signatures: Option<RecentSignatures>,
 }
 
 impl TransactionService {
@@ -129,32 +204,58 @@ impl TransactionService {
         self.send_wire_transaction(raw_tx, max_retries).await
     }
 
-    pub async fn send_wire_transaction(
+    /// checks that the transaction would be accepted by `send_wire_transaction`, without sending it
+    pub fn validate_wire_transaction(&self, raw_tx: &[u8]) -> anyhow::Result<()> {
+        self.decode_with_block_info(raw_tx).map(|_| ())
+    }
+
+    fn decode_with_block_info(
         &self,
-        raw_tx: Vec<u8>,
-        max_retries: Option<u16>,
-    ) -> anyhow::Result<String> {
-        let tx = match bincode::deserialize::<VersionedTransaction>(&raw_tx) {
+        raw_tx: &[u8],
+    ) -> anyhow::Result<(VersionedTransaction, BlockInformation)> {
+        let tx = match bincode::deserialize::<VersionedTransaction>(raw_tx) {
             Ok(tx) => tx,
             Err(err) => {
                 bail!(err.to_string());
             }
         };
-        let signature = tx.signatures[0];
 
-        let Some(BlockInformation {
-            slot,
-            last_valid_blockheight,
-            ..
-        }) = self
+        let Some(block_info) = self
             .block_information_store
             .get_block_info(tx.get_recent_blockhash())
         else {
-            bail!("Blockhash not found in block store".to_string());
+            bail!(BlockhashError::NotFound);
         };
 
-        if self.block_information_store.get_last_blockheight() > last_valid_blockheight {
-            bail!("Blockhash is expired");
+        let block_height = self.block_information_store.get_last_blockheight();
+        if block_height > block_info.last_valid_blockheight {
+            bail!(BlockhashError::Expired);
+        }
+        Ok((tx, block_info))
+    }
+
+    pub async fn send_wire_transaction(
+        &self,
+        raw_tx: Vec<u8>,
+        max_retries: Option<u16>,
+    ) -> anyhow::Result<String> {
+        let (
+            tx,
+            BlockInformation {
+                slot,
+                last_valid_blockheight,
+                ..
+            },
+        ) = self.decode_with_block_info(&raw_tx)?;
+        let signature = tx.signatures[0];
+        let block_height = self.block_information_store.get_last_blockheight();
+
+        if let Some(recent_signatures) = &self.recent_signatures {
+            if !recent_signatures.insert(signature, last_valid_blockheight, block_height) {
+                log::debug!("transaction {signature} already sent, not forwarding it again");
+                DUPLICATE_TXS.inc();
+                return Ok(signature.to_string());
+            }
         }
 
         let prioritization_fee = {
@@ -177,7 +278,8 @@ impl TransactionService {
 
         PRIORITY_FEES_HISTOGRAM.observe(prioritization_fee as f64);
 
-        let max_replay = max_retries.map_or(self.max_retries, |x| x as usize);
+        let max_replay = clamp_max_retries(max_retries, self.max_retries, self.max_allowed_retries);
+        log::debug!("sending transaction {signature} with {max_replay} replays");
         let transaction_info = SentTransactionInfo {
             signature,
             last_valid_block_height: last_valid_blockheight,
@@ -190,6 +292,9 @@ impl TransactionService {
             .send(transaction_info.clone())
             .await
         {
+            if let Some(recent_signatures) = &self.recent_signatures {
+                recent_signatures.remove(&signature);
+            }
             bail!(
                 "Internal error sending transaction on send channel error {}",
                 e
@@ -213,4 +318,110 @@ impl TransactionService {
     }
 }
 
-mod test {}
+/// retries requested by the client or the default, never more than `max_allowed_retries`
+fn clamp_max_retries(
+    requested: Option<u16>,
+    default_max_retries: usize,
+    max_allowed_retries: usize,
+) -> usize {
+    let max_retries = requested.map_or(default_max_retries, |x| x as usize);
+    if max_retries > max_allowed_retries {
+        log::debug!("clamping max_retries {max_retries} to {max_allowed_retries}");
+        max_allowed_retries
+    } else {
+        max_retries
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_clamp_max_retries() {
+        assert_eq!(clamp_max_retries(Some(1000), 40, 100), 100);
+        assert_eq!(clamp_max_retries(Some(5), 40, 100), 5);
+        assert_eq!(clamp_max_retries(None, 40, 100), 40);
+        // default above the ceiling is clamped as well
+        assert_eq!(clamp_max_retries(None, 40, 10), 10);
+    }
+
+    /// service with transaction dedup at a finalized block, and a transaction signed with its blockhash
+    fn test_service_and_transaction() -> (
+        TransactionService,
+        mpsc::Receiver<SentTransactionInfo>,
+        mpsc::UnboundedReceiver<TransactionReplay>,
+        VersionedTransaction,
+    ) {
+        let blockhash = solana_sdk::hash::Hash::new_unique();
+        let block_information_store = BlockInformationStore::new(BlockInformation {
+            slot: 100,
+            block_height: 100,
+            last_valid_blockheight: 250,
+            cleanup_slot: 1100,
+            blockhash,
+            commitment_config: solana_sdk::commitment_config::CommitmentConfig::finalized(),
+            block_time: 0,
+            parent_slot: 99,
+            block_time_estimated: false,
+        });
+        let (transaction_channel, tx_recv) = mpsc::channel(16);
+        let (replay_channel, replay_recv) = mpsc::unbounded_channel();
+        let transaction_service = TransactionService {
+            transaction_channel,
+            replay_channel,
+            block_information_store,
+            max_retries: 40,
+            max_allowed_retries: 100,
+            replay_offset: Duration::from_secs(3),
+            recent_signatures: Some(RecentSignatures::default()),
+        };
+
+        let payer = solana_sdk::signature::Keypair::new();
+        let tx = VersionedTransaction::from(
+            solana_sdk::transaction::Transaction::new_signed_with_payer(
+                &[],
+                Some(&solana_sdk::signer::Signer::pubkey(&payer)),
+                &[&payer],
+                blockhash,
+            ),
+        );
+        (transaction_service, tx_recv, replay_recv, tx)
+    }
+
+    #[tokio::test]
+    async fn test_duplicate_transaction_is_forwarded_once() {
+        let (transaction_service, mut tx_recv, _replay_recv, tx) = test_service_and_transaction();
+
+        let first = transaction_service
+            .send_transaction(tx.clone(), None)
+            .await
+            .unwrap();
+        let second = transaction_service
+            .send_transaction(tx.clone(), None)
+            .await
+            .unwrap();
+        assert_eq!(first, second);
+        assert_eq!(first, tx.signatures[0].to_string());
+
+        assert!(tx_recv.try_recv().is_ok());
+        assert!(tx_recv.try_recv().is_err());
+    }
+
+    #[tokio::test]
+    async fn test_failed_send_is_not_a_duplicate() {
+        let (transaction_service, tx_recv, _replay_recv, tx) = test_service_and_transaction();
+        // the sender task is gone
+        drop(tx_recv);
+
+        assert!(transaction_service
+            .send_transaction(tx.clone(), None)
+            .await
+            .is_err());
+        // reported as failed again instead of accepted as already sent
+        assert!(transaction_service
+            .send_transaction(tx, None)
+            .await
+            .is_err());
+    }
+}
@@ -7,6 +7,7 @@ use log::{trace, warn};
 
 use prometheus::{core::GenericGauge, opts, register_int_counter, register_int_gauge, IntCounter};
 use tokio::sync::mpsc::Receiver;
+use tracing::{info_span, Instrument};
 
 use crate::tpu_utils::tpu_service::TpuService;
 use solana_lite_rpc_core::{
@@ -97,7 +98,9 @@ impl TxSender {
                 tokio::select! {
                     transaction_info = recv.recv() => {
                         if let Some(transaction_info) = transaction_info {
-                            self.forward_txs(&transaction_info).await;
+                            self.forward_txs(&transaction_info)
+                                .instrument(info_span!("transaction", signature = %transaction_info.signature))
+                                .await;
 
                             if notifier.is_some() {
                                 let forwarded_slot = self.data_cache.slot_cache.get_current_slot();
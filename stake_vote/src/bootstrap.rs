@@ -118,6 +118,7 @@ pub fn bootstrap_leaderschedule_from_rpc(
             schedule_by_slot: next_schedule_by_slot,
             epoch: current_epoch.epoch + 1,
         }),
+        ..Default::default()
     })
 }
 
@@ -296,14 +297,14 @@ fn process_bootstrap_event(
                 );
             }
 
+            let snapshot_slot = stakestore.take_snapshot_slot();
             //merge new PA with stake map and vote map in a specific task
             let jh = tokio::task::spawn_blocking({
                 move || {
-                    //update pa_list to set slot update to start epoq one.
-                    crate::stake::merge_program_account_in_strake_map(
+                    crate::stake::merge_bootstrap_program_accounts(
                         &mut stake_map,
                         stakes,
-                        0, //with RPC no way to know the slot of the account update. Set to 0.
+                        snapshot_slot,
                     );
                     crate::vote::merge_program_account_in_vote_map(
                         &mut vote_map,
@@ -381,7 +382,7 @@ fn bootstrap_accounts(
         })
 }
 
-fn get_stake_account(rpc_url: String) -> Result<(Vec<(Pubkey, Account)>, String), ClientError> {
+pub fn get_stake_account(rpc_url: String) -> Result<(Vec<(Pubkey, Account)>, String), ClientError> {
     let rpc_client = RpcClient::new_with_timeout_and_commitment(
         rpc_url.clone(),
         Duration::from_secs(600),
@@ -488,6 +489,7 @@ pub fn bootstrap_current_leader_schedule(
                 schedule_by_slot: next_schedule.get_slot_leaders().to_vec(),
                 epoch: next_epoch,
             }),
+            ..Default::default()
         },
         EpochVoteStakes {
             epoch: current_epoch,
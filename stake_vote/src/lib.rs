@@ -12,8 +12,10 @@ use solana_rpc_client_api::response::RpcVoteAccountStatus;
 use solana_sdk::commitment_config::CommitmentConfig;
 use solana_sdk::pubkey::Pubkey;
 use std::collections::HashMap;
+use std::path::PathBuf;
 use std::sync::Arc;
 use tokio::sync::mpsc::Receiver;
+use tokio_util::sync::CancellationToken;
 use yellowstone_grpc_client::GeyserGrpcClient;
 use yellowstone_grpc_proto::geyser::CommitmentLevel;
 use yellowstone_grpc_proto::prelude::subscribe_update::UpdateOneof;
@@ -25,6 +27,7 @@ mod account;
 mod bootstrap;
 mod epoch;
 mod leader_schedule;
+mod resync;
 mod rpcrequest;
 mod stake;
 mod utils;
@@ -55,7 +58,7 @@ pub async fn bootstrat_literpc_leader_schedule(
                 .update_stakes_for_identity(vote_stakes)
                 .await;
             let mut data_schedule = data_cache.leader_schedule.write().await;
-            *data_schedule = leader_schedule;
+            data_schedule.replace(leader_schedule, data_cache.epoch_data.get_epoch_schedule());
         }
         None => {
             log::info!("Leader schedule bootstrap file not found. Try to boot from rpc.");
@@ -66,7 +69,8 @@ pub async fn bootstrat_literpc_leader_schedule(
                 Ok(leader_schedule) => {
                     log::info!("Leader schedule bootstrap from rpc done.",);
                     let mut data_schedule = data_cache.leader_schedule.write().await;
-                    *data_schedule = leader_schedule;
+                    data_schedule
+                        .replace(leader_schedule, data_cache.epoch_data.get_epoch_schedule());
                 }
                 Err(err) => {
                     log::warn!(
@@ -88,14 +92,21 @@ pub async fn start_stakes_and_votes_loop(
     )>,
     rpc_client: Arc<RpcClient>,
     grpc_url: String,
+    //warm start from this stake snapshot, saved at each epoch change and when the loop stops.
+    stake_snapshot_path: Option<PathBuf>,
+    cancel: CancellationToken,
 ) -> anyhow::Result<tokio::task::JoinHandle<()>> {
     log::info!("Start Stake and Vote loop on :{grpc_url}.");
     let mut stake_vote_geyser_stream = subscribe_geyser_stake_vote_owner(grpc_url.clone()).await?;
-    let mut stake_history_geyser_stream = subscribe_geyser_stake_history(grpc_url).await?;
+    let mut stake_history_geyser_stream = subscribe_geyser_stake_history(grpc_url.clone()).await?;
     log::info!("Stake and Vote geyser subscription done.");
     let jh = tokio::spawn(async move {
         //Stake account management struct
-        let mut stakestore = stake::StakeStore::new(STAKESTORE_INITIAL_CAPACITY);
+        //the snapshot stakes are served until the bootstrap reconciles them with the fetched accounts.
+        let (mut stakestore, mut last_stake_history) = match &stake_snapshot_path {
+            Some(path) => stake::StakeStore::load_snapshot(path, STAKESTORE_INITIAL_CAPACITY),
+            None => (stake::StakeStore::new(STAKESTORE_INITIAL_CAPACITY), None),
+        };
 
         //Vote account management struct
         let mut votestore = vote::VoteStore::new(VOTESTORE_INITIAL_CAPACITY);
@@ -107,6 +118,8 @@ pub async fn start_stakes_and_votes_loop(
         //future execution collection.
         let mut spawned_leader_schedule_task = FuturesUnordered::new();
         let mut spawned_bootstrap_task = FuturesUnordered::new();
+        let mut stake_resync = crate::resync::StakeResync::new(rpc_client.url());
+        let stake_history_rpc_url = rpc_client.url();
         let jh = tokio::spawn(async move {
             BootstrapEvent::InitBootstrap {
                 sleep_time: 1,
@@ -125,6 +138,10 @@ pub async fn start_stakes_and_votes_loop(
 
         loop {
             tokio::select! {
+                _ = cancel.cancelled() => {
+                    log::info!("Stake and Vote loop cancelled.");
+                    break;
+                }
                 //manage confirm new slot notification to detect epoch change.
                 Ok(_) = slot_notification.recv() => {
                     //log::info!("Stake and Vote receive a slot.");
@@ -162,7 +179,7 @@ pub async fn start_stakes_and_votes_loop(
                         .await;
 
                     let current_epoch = data_cache.get_current_epoch(commitment).await;
-                    rpc_request_processor.process_get_vote_accounts(slot, current_epoch.epoch, config, return_channel, &mut votestore).await;
+                    rpc_request_processor.process_get_vote_accounts(slot, current_epoch.epoch, config, return_channel, &mut votestore, &stakestore).await;
                 }
                 //manage rpc waiting request notification.
                 Some(Ok((votes, vote_accounts, rpc_vote_accounts))) = rpc_request_processor.rpc_exec_task.next() =>  {
@@ -175,42 +192,63 @@ pub async fn start_stakes_and_votes_loop(
                 }
                 //manage rpc waiting request notification.
                 Some(Ok((current_slot, epoch, config))) = rpc_request_processor.rpc_notify_task.next() =>  {
-                    rpc_request_processor.take_vote_accounts_and_process(&mut votestore, current_slot, epoch, config).await;
+                    rpc_request_processor.take_vote_accounts_and_process(&mut votestore, &stakestore, current_slot, epoch, config).await;
                 }
                 //manage geyser stake_history notification
                 ret = stake_history_geyser_stream.next() => {
-                    match ret {
+                    let stake_history_data = match ret {
                         Some(Ok(msg)) => {
-                            if let Some(UpdateOneof::Account(account))  = msg.update_oneof {
-                                if let Some(account) = account.account {
+                            match msg.update_oneof {
+                                Some(UpdateOneof::Account(account)) => account.account.and_then(|account| {
                                     let acc_id = Pubkey::try_from(account.pubkey).expect("valid pubkey");
-                                    if acc_id  == solana_sdk::sysvar::stake_history::ID {
-                                        log::debug!("Geyser notifstake_history");
-                                        match crate::account::read_historystake_from_account(account.data.as_slice())  {
-                                            Some(stake_history) => {
-                                                let schedule_event = current_schedule_epoch.set_epoch_stake_history(stake_history);
-                                                if bootstrap_done {
-                                                    if let Some(init_event) = schedule_event {
-                                                        crate::leader_schedule::run_leader_schedule_events(
-                                                            init_event,
-                                                            &mut spawned_leader_schedule_task,
-                                                            &mut stakestore,
-                                                            &mut votestore,
-                                                        );
-                                                    }
-                                                }
-                                            }
-                                            None => log::error!("Bootstrap error, can't read stake blockstore from geyser account data."),
-                                        }
-                                    }
-                                }
+                                    (acc_id  == solana_sdk::sysvar::stake_history::ID).then_some(account.data)
+                                }),
+                                _ => None,
                             }
                         },
-                         None |  Some(Err(_))  => {
-                            //TODO Restart geyser connection and the bootstrap.
+                        None |  Some(Err(_))  => {
+                            //the stream is closed, reconnect and reload the stake history sysvar to not miss an update.
                             log::error!("The stake_history geyser stream close or in error try to reconnect and resynchronize.");
-                            break;
-                         }
+                            match subscribe_geyser_stake_history(grpc_url.clone()).await {
+                                Ok(stream) => stake_history_geyser_stream = stream,
+                                Err(err) => {
+                                    log::error!("Stake history geyser stream reconnection error:{err}. Stop stake and vote loop.");
+                                    break;
+                                }
+                            }
+                            let rpc_url = stake_history_rpc_url.clone();
+                            match tokio::task::spawn_blocking(move || crate::bootstrap::get_stakehistory_account(rpc_url)).await {
+                                Ok(Ok(account)) => Some(account.data),
+                                Ok(Err(err)) => {
+                                    log::error!("Can't reload the stake history after the geyser reconnection:{err}");
+                                    None
+                                }
+                                Err(err) => {
+                                    log::error!("Stake history reload task panicked:{err}");
+                                    None
+                                }
+                            }
+                        }
+                    };
+                    if let Some(data) = stake_history_data {
+                        log::debug!("Geyser notifstake_history");
+                        match crate::account::read_historystake_from_account(data.as_slice())  {
+                            Some(stake_history) => {
+                                last_stake_history = Some(stake_history.clone());
+                                let schedule_event = current_schedule_epoch.set_epoch_stake_history(stake_history);
+                                if bootstrap_done {
+                                    if let Some(init_event) = schedule_event {
+                                        crate::leader_schedule::run_leader_schedule_events(
+                                            init_event,
+                                            &mut spawned_leader_schedule_task,
+                                            &mut stakestore,
+                                            &mut votestore,
+                                        );
+                                    }
+                                }
+                            }
+                            None => log::error!("Bootstrap error, can't read stake blockstore from geyser account data."),
+                        }
                     }
                 }
                 //manage geyser account notification
@@ -218,70 +256,79 @@ pub async fn start_stakes_and_votes_loop(
                 //see https://github.com/solana-labs/solana/pull/33292
                 ret = stake_vote_geyser_stream.next() => {
                     match ret {
-                         Some(message) => {
-                            //process the message
-                            match message {
-                                Ok(msg) => {
-                                    match msg.update_oneof {
-                                        Some(UpdateOneof::Account(account)) => {
-                                            // log::info!("Stake and Vote geyser receive an account:{}.",
-                                            //     account.account.clone().map(|a|
-                                            //         solana_sdk::pubkey::Pubkey::try_from(a.pubkey).map(|k| k.to_string())
-                                            //         .unwrap_or("bad pubkey".to_string()).to_string())
-                                            //         .unwrap_or("no content".to_string())
-                                            // );
-                                            //store new account stake.
-                                            let current_slot = solana_lite_rpc_core::solana_utils::get_current_confirmed_slot(&data_cache).await;
+                        Some(Ok(msg)) => {
+                            match msg.update_oneof {
+                                Some(UpdateOneof::Account(account)) => {
+                                    // log::info!("Stake and Vote geyser receive an account:{}.",
+                                    //     account.account.clone().map(|a|
+                                    //         solana_sdk::pubkey::Pubkey::try_from(a.pubkey).map(|k| k.to_string())
+                                    //         .unwrap_or("bad pubkey".to_string()).to_string())
+                                    //         .unwrap_or("no content".to_string())
+                                    // );
+                                    //store new account stake.
+                                    let current_slot = solana_lite_rpc_core::solana_utils::get_current_confirmed_slot(&data_cache).await;
 
-                                            if let Some(account) = AccountPretty::new_from_geyser(account, current_slot) {
-                                                match account.owner {
-                                                    solana_sdk::stake::program::ID => {
-                                                        log::trace!("Geyser notif stake account:{}", account);
-                                                        if let Some(ref mut counter) = account_update_notification {
-                                                            *counter +=1;
-                                                        }
-                                                        if let Err(err) = stakestore.notify_stake_change(
-                                                            account,
-                                                            current_schedule_epoch.last_slot_in_epoch,
-                                                        ) {
-                                                            log::warn!("Can't add new stake from account data err:{}", err);
-                                                            continue;
-                                                        }
-                                                    }
-                                                    solana_sdk::vote::program::ID => {
-                                                        //log::info!("Geyser notif VOTE account:{}", account);
-                                                        let account_pubkey = account.pubkey;
-                                                        //process vote accout notification
-                                                        if let Err(err) = votestore.notify_vote_change(account, current_schedule_epoch.last_slot_in_epoch) {
-                                                            log::warn!("Can't add new stake from account data err:{} account:{}", err, account_pubkey);
-                                                            continue;
-                                                        }
-                                                    }
-                                                    _ => log::warn!("receive an account notification from a unknown owner:{account:?}"),
+                                    if let Some(account) = AccountPretty::new_from_geyser(account, current_slot) {
+                                        match account.owner {
+                                            solana_sdk::stake::program::ID => {
+                                                log::trace!("Geyser notif stake account:{}", account);
+                                                if let Some(ref mut counter) = account_update_notification {
+                                                    *counter +=1;
+                                                }
+                                                if let Err(err) = stakestore.notify_stake_change(
+                                                    account,
+                                                    current_schedule_epoch.last_slot_in_epoch,
+                                                ) {
+                                                    log::warn!("Can't add new stake from account data err:{}", err);
+                                                    continue;
                                                 }
                                             }
-                                        }
-                                        Some(UpdateOneof::Ping(_)) => log::trace!("UpdateOneof::Ping"),
-                                        Some(UpdateOneof::Slot(slot)) => {
-                                            log::trace!("Receive slot slot: {slot:?}");
-                                        }
-                                        bad_msg => {
-                                            log::info!("Geyser stream unexpected message received:{:?}", bad_msg);
+                                            solana_sdk::vote::program::ID => {
+                                                //log::info!("Geyser notif VOTE account:{}", account);
+                                                let account_pubkey = account.pubkey;
+                                                //process vote accout notification
+                                                if let Err(err) = votestore.notify_vote_change(account, current_schedule_epoch.last_slot_in_epoch) {
+                                                    log::warn!("Can't add new stake from account data err:{} account:{}", err, account_pubkey);
+                                                    continue;
+                                                }
+                                            }
+                                            _ => log::warn!("receive an account notification from a unknown owner:{account:?}"),
                                         }
                                     }
                                 }
-                                Err(error) => {
-                                    log::error!("Geyser stream receive an error has message: {error:?}, try to reconnect and resynchronize.");
-                                    //todo reconnect and resynchronize.
-                                    //break;
+                                Some(UpdateOneof::Ping(_)) => log::trace!("UpdateOneof::Ping"),
+                                Some(UpdateOneof::Slot(slot)) => {
+                                    log::trace!("Receive slot slot: {slot:?}");
                                 }
+                                bad_msg => {
+                                    log::info!("Geyser stream unexpected message received:{:?}", bad_msg);
+                                }
+                            }
+                        }
+                        error_or_end => {
+                            //the stream is closed after an error, it's reconnected and resynchronized then.
+                            match error_or_end {
+                                Some(Err(error)) => log::error!("Geyser stream receive an error has message: {error:?}, try to reconnect and resynchronize."),
+                                _ => log::error!("The geyser stream close try to reconnect and resynchronize."),
                             }
-                         }
-                         None => {
-                            //TODO Restart geyser connection and the bootstrap.
-                            log::error!("The geyser stream close try to reconnect and resynchronize.");
-                            break;
-                         }
+                            match subscribe_geyser_stake_vote_owner(grpc_url.clone()).await {
+                                Ok(stream) => {
+                                    stake_vote_geyser_stream = stream;
+                                    //the bootstrap load all the stakes, no gap to recover before.
+                                    if bootstrap_done {
+                                        let BlockInformation { slot, .. } = data_cache
+                                            .block_information_store
+                                            .get_latest_block(CommitmentConfig::finalized())
+                                            .await;
+                                        stake_resync.notify_stream_gap(slot);
+                                    }
+                                }
+                                Err(err) => {
+                                    log::error!("Geyser stream reconnection fail:{err}");
+                                    break;
+                                }
+                            }
+                        }
                     }
                 }
                 //manage bootstrap event
@@ -294,7 +341,7 @@ pub async fn start_stakes_and_votes_loop(
                                         .identity_stakes
                                         .update_stakes_for_identity(vote_stakes).await;
                                     let mut data_schedule = data_cache.leader_schedule.write().await;
-                                    *data_schedule = current_schedule_data;
+                                    data_schedule.replace(current_schedule_data, data_cache.epoch_data.get_epoch_schedule());
                                 }
                                 Err(err) => {
                                     log::warn!("Error during current leader schedule bootstrap from files:{err}")
@@ -310,6 +357,10 @@ pub async fn start_stakes_and_votes_loop(
                         Err(err) => log::error!("Stake / Vote Account bootstrap fail because '{err}'"),
                     }
                 }
+                //manage stake resync event
+                Some(Ok(event)) = stake_resync.tasks.next() => {
+                    stake_resync.process_event(event, &mut stakestore).await;
+                }
                 //Manage leader schedule generation process
                 Some(Ok(event)) = spawned_leader_schedule_task.next() =>  {
                     let new_leader_schedule = crate::leader_schedule::run_leader_schedule_events(
@@ -323,17 +374,38 @@ pub async fn start_stakes_and_votes_loop(
                         //only done once epoch. Avoid to use a Mutex.
                         log::info!("End leader schedule calculus  for epoch:{}", new_leader_schedule.epoch);
                         let mut data_schedule = data_cache.leader_schedule.write().await;
-                        data_schedule.current = data_schedule.next.take();
-                        data_schedule.next = Some(new_leader_schedule.rpc_data);
+                        data_schedule.rotate(new_leader_schedule.rpc_data, data_cache.epoch_data.get_epoch_schedule());
+                        //the stake map is merged back, the new epoch is active.
+                        stakestore.apply_epoch_rotation(current_schedule_epoch.last_slot_in_epoch);
+                        save_stake_snapshot(&stakestore, stake_snapshot_path.as_ref(), last_stake_history.as_ref());
                     }
 
                 }
             }
         }
+        save_stake_snapshot(
+            &stakestore,
+            stake_snapshot_path.as_ref(),
+            last_stake_history.as_ref(),
+        );
     });
     Ok(jh)
 }
 
+fn save_stake_snapshot(
+    stakestore: &stake::StakeStore,
+    path: Option<&PathBuf>,
+    stake_history: Option<&solana_sdk::stake_history::StakeHistory>,
+) {
+    let Some(path) = path else {
+        return;
+    };
+    match stakestore.save_snapshot(path, stake_history) {
+        Ok(()) => log::info!("Stake snapshot saved to {}", path.display()),
+        Err(err) => log::warn!("Can't save stake snapshot to {}:{err}", path.display()),
+    }
+}
+
 //subscribe Geyser grpc
 async fn subscribe_geyser_stake_vote_owner(
     grpc_url: String,
@@ -0,0 +1,128 @@
+use crate::stake::StakeMap;
+use crate::stake::StakeStore;
+use crate::utils::Takable;
+use crate::Slot;
+use futures_util::stream::FuturesUnordered;
+use solana_sdk::account::Account;
+use solana_sdk::pubkey::Pubkey;
+use std::time::Duration;
+use tokio::task::JoinHandle;
+
+/*
+Stake resync state diagram, started when the geyser account stream reconnects
+
+  FetchAccounts(resync_slot)
+     |                |
+   Error        AccountsFetched(accounts, resync_slot)
+     |                |
+ |Wait(10s)|    |extract stake map|
+     |           |               |
+FetchAccounts   Taken       |resync stake map|
+                 |               |
+           |wait merge|     StoreResynced(stakes)
+                 |               |
+          AccountsFetched   |merge stake map|
+                                 |
+                                End
+*/
+
+pub enum StakeResyncEvent {
+    FetchAccounts { sleep_time: u64, resync_slot: Slot },
+    AccountsFetched(Vec<(Pubkey, Account)>, Slot),
+    StoreResynced(StakeMap),
+}
+
+//Reload all the stake accounts after a geyser stream gap to recover the stake changes notified during the gap.
+pub struct StakeResync {
+    pub tasks: FuturesUnordered<JoinHandle<StakeResyncEvent>>,
+    rpc_url: String,
+    running: bool,
+    //a new gap occurs during the running resync, the reload may not contain its changes.
+    gap_during_resync: Option<Slot>,
+}
+
+impl StakeResync {
+    pub fn new(rpc_url: String) -> Self {
+        StakeResync {
+            tasks: FuturesUnordered::new(),
+            rpc_url,
+            running: false,
+            gap_during_resync: None,
+        }
+    }
+
+    //resync_slot must not be after the slot of the reloaded accounts.
+    //Use the finalized slot before the reload because the stake accounts are fetched at finalized.
+    pub fn notify_stream_gap(&mut self, resync_slot: Slot) {
+        if self.running {
+            log::info!("Stake resync already running, resync again after it.");
+            self.gap_during_resync = Some(resync_slot);
+            return;
+        }
+        log::info!("Start stake resync at slot:{resync_slot}");
+        self.running = true;
+        self.fetch_accounts(0, resync_slot);
+    }
+
+    fn fetch_accounts(&mut self, sleep_time: u64, resync_slot: Slot) {
+        let rpc_url = self.rpc_url.clone();
+        let jh = tokio::task::spawn_blocking(move || {
+            if sleep_time > 0 {
+                std::thread::sleep(Duration::from_secs(sleep_time));
+            }
+            match crate::bootstrap::get_stake_account(rpc_url) {
+                Ok((stakes, _)) => StakeResyncEvent::AccountsFetched(stakes, resync_slot),
+                Err(err) => {
+                    log::warn!("Stake resync error during fetching accounts err:{err}. Retry");
+                    StakeResyncEvent::FetchAccounts {
+                        sleep_time: 10,
+                        resync_slot,
+                    }
+                }
+            }
+        });
+        self.tasks.push(jh);
+    }
+
+    pub async fn process_event(&mut self, event: StakeResyncEvent, stakestore: &mut StakeStore) {
+        match event {
+            StakeResyncEvent::FetchAccounts {
+                sleep_time,
+                resync_slot,
+            } => self.fetch_accounts(sleep_time, resync_slot),
+            StakeResyncEvent::AccountsFetched(..) => {
+                //the stake map is taken during the leader schedule calculus, wait it's merged back.
+                let Some((mut stake_map, event)) = crate::utils::wait_for_merge_or_get_content(
+                    &mut stakestore.stakes,
+                    event,
+                    &mut self.tasks,
+                )
+                .await
+                else {
+                    return;
+                };
+                let StakeResyncEvent::AccountsFetched(stakes, resync_slot) = event else {
+                    unreachable!("waiting event is AccountsFetched");
+                };
+                let jh = tokio::task::spawn_blocking(move || {
+                    crate::stake::resync_program_account_in_stake_map(
+                        &mut stake_map,
+                        stakes,
+                        resync_slot,
+                    );
+                    StakeResyncEvent::StoreResynced(stake_map)
+                });
+                self.tasks.push(jh);
+            }
+            StakeResyncEvent::StoreResynced(stake_map) => {
+                if let Err(err) = stakestore.stakes.merge(stake_map) {
+                    log::error!("Stake resync can't merge back the stake map:{err}");
+                }
+                self.running = false;
+                if let Some(resync_slot) = self.gap_during_resync.take() {
+                    self.notify_stream_gap(resync_slot);
+                }
+            }
+        }
+    }
+}
@@ -1,3 +1,4 @@
+use crate::stake::StakeStore;
 use crate::utils::wait_for_merge_or_get_content;
 use crate::utils::Takable;
 use crate::vote::EpochVoteStakesCache;
@@ -33,6 +34,7 @@ impl RpcRequestData {
         config: GetVoteAccountsConfig,
         return_channel: oneshot::Sender<RpcVoteAccountStatus>,
         votestore: &mut VoteStore,
+        stakestore: &StakeStore,
     ) {
         match self.pending_rpc_request {
             Some(ref mut pending) => pending.push(return_channel),
@@ -40,7 +42,7 @@ impl RpcRequestData {
                 self.pending_rpc_request = Some(vec![return_channel]);
             }
         }
-        self.take_vote_accounts_and_process(votestore, current_slot, epoch, config)
+        self.take_vote_accounts_and_process(votestore, stakestore, current_slot, epoch, config)
             .await;
     }
     pub async fn notify_end_rpc_get_vote_accounts(
@@ -56,7 +58,9 @@ impl RpcRequestData {
         //avoid clone on the first request
         if let Some(mut pending_rpc_request) = self.pending_rpc_request.take() {
             if pending_rpc_request.len() > 1 {
-                for return_channel in pending_rpc_request.drain(0..pending_rpc_request.len().saturating_sub(1)) {
+                for return_channel in
+                    pending_rpc_request.drain(0..pending_rpc_request.len().saturating_sub(1))
+                {
                     if return_channel.send(rpc_vote_accounts.clone()).is_err() {
                         log::error!("Vote accounts RPC channel send closed.");
                     }
@@ -76,6 +80,7 @@ impl RpcRequestData {
     pub async fn take_vote_accounts_and_process(
         &mut self,
         votestore: &mut VoteStore,
+        stakestore: &StakeStore,
         current_slot: Slot,
         epoch: u64,
         config: GetVoteAccountsConfig,
@@ -89,6 +94,15 @@ impl RpcRequestData {
             .await
         {
             //validate that we have the epoch.
+            //Without epoch stakes the delegated stakes are used, unless the stakes are taken.
+            let voter_stakes = match vote_accounts.vote_stakes_for_epoch(epoch) {
+                Some(_) => None,
+                None => stakestore
+                    .stakes
+                    .content
+                    .as_ref()
+                    .map(|stakes| stakes.voter_stakes().clone()),
+            };
 
             let jh = tokio::task::spawn_blocking({
                 move || match vote_accounts.vote_stakes_for_epoch(epoch) {
@@ -103,14 +117,19 @@ impl RpcRequestData {
                     }
                     None => {
                         log::warn!("Get  vote account for epoch:{epoch}.  No data  available");
-                        (
-                            votes,
-                            vote_accounts,
-                            RpcVoteAccountStatus {
+                        let rpc_vote_accounts = match voter_stakes {
+                            Some(voter_stakes) => crate::vote::get_rpc_vote_accounts_info(
+                                current_slot,
+                                &votes,
+                                &crate::vote::vote_stakes_from_voter_stakes(&votes, &voter_stakes),
+                                config,
+                            ),
+                            None => RpcVoteAccountStatus {
                                 current: vec![],
                                 delinquent: vec![],
                             },
-                        )
+                        };
+                        (votes, vote_accounts, rpc_vote_accounts)
                     }
                 }
             });
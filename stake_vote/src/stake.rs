@@ -4,13 +4,83 @@ use crate::utils::UpdateAction;
 use crate::AccountPretty;
 use crate::Slot;
 use anyhow::bail;
+use itertools::Itertools;
 use serde::{Deserialize, Serialize};
 use solana_sdk::account::Account;
 use solana_sdk::pubkey::Pubkey;
 use solana_sdk::stake::state::Delegation;
-use std::collections::HashMap;
+use solana_sdk::stake_history::StakeHistory;
+use std::collections::{HashMap, HashSet};
+use std::ops::Deref;
+use std::path::Path;
 
-pub type StakeMap = HashMap<Pubkey, StoredStake>;
+//Stake accounts with their delegated stake aggregated by vote account.
+//The aggregate is updated with each stake change so reading the stake of the vote accounts
+//doesn't need to go through all the stake accounts.
+#[derive(Debug, Default, Clone)]
+pub struct StakeMap {
+    stakes: HashMap<Pubkey, StoredStake>,
+    voter_stakes: HashMap<Pubkey, u64>,
+}
+
+impl StakeMap {
+    pub fn with_capacity(capacity: usize) -> Self {
+        StakeMap {
+            stakes: HashMap::with_capacity(capacity),
+            voter_stakes: HashMap::new(),
+        }
+    }
+
+    //delegated stake by vote account without the deactivated delegations.
+    //Warmup and cooldown are applied when the epoch stakes are calculated.
+    pub fn voter_stakes(&self) -> &HashMap<Pubkey, u64> {
+        &self.voter_stakes
+    }
+
+    //a deactivated delegation stays in the stake account until it's withdrawn.
+    fn is_deactivated(delegation: &Delegation) -> bool {
+        delegation.deactivation_epoch != u64::MAX
+    }
+
+    fn add_voter_stake(voter_stakes: &mut HashMap<Pubkey, u64>, delegation: &Delegation) {
+        if Self::is_deactivated(delegation) {
+            return;
+        }
+        *voter_stakes.entry(delegation.voter_pubkey).or_default() += delegation.stake;
+    }
+
+    fn sub_voter_stake(voter_stakes: &mut HashMap<Pubkey, u64>, delegation: &Delegation) {
+        if Self::is_deactivated(delegation) {
+            return;
+        }
+        if let std::collections::hash_map::Entry::Occupied(mut entry) =
+            voter_stakes.entry(delegation.voter_pubkey)
+        {
+            *entry.get_mut() = entry.get().saturating_sub(delegation.stake);
+            if *entry.get() == 0 {
+                entry.remove();
+            }
+        }
+    }
+}
+
+impl Deref for StakeMap {
+    type Target = HashMap<Pubkey, StoredStake>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.stakes
+    }
+}
+
+impl FromIterator<StoredStake> for StakeMap {
+    fn from_iter<I: IntoIterator<Item = StoredStake>>(stakes: I) -> Self {
+        let mut map = StakeMap::default();
+        for stake in stakes {
+            StakeStore::notify_stake(&mut map, stake);
+        }
+        map
+    }
+}
 
 #[derive(Debug, Default, Clone, Serialize, Deserialize)]
 pub struct StoredStake {
@@ -30,12 +100,77 @@ impl TakableContent<StoredStake> for StakeMap {
 #[derive(Debug, Default)]
 pub struct StakeStore {
     pub stakes: TakableMap<StoredStake, StakeMap>,
+    //set when the store is loaded from a snapshot, the snapshot stakes are older than this slot.
+    snapshot_slot: Option<Slot>,
 }
 
 impl StakeStore {
     pub fn new(capacity: usize) -> Self {
         StakeStore {
-            stakes: TakableMap::new(HashMap::with_capacity(capacity)),
+            stakes: TakableMap::new(StakeMap::with_capacity(capacity)),
+            snapshot_slot: None,
+        }
+    }
+
+    //the bootstrap reconciles the snapshot stakes with the fetched stake accounts once.
+    pub fn take_snapshot_slot(&mut self) -> Option<Slot> {
+        self.snapshot_slot.take()
+    }
+
+    //save the stakes and the stake history to warm start after a restart.
+    //Fail if the stakes are currently taken.
+    pub fn save_snapshot(
+        &self,
+        path: impl AsRef<Path>,
+        stake_history: Option<&StakeHistory>,
+    ) -> anyhow::Result<()> {
+        let Some(stakes) = self.stakes.content.as_ref() else {
+            bail!("Can't save stake snapshot while the stakes are taken");
+        };
+        //bincode because json map keys can only be String.
+        let content = bincode::serialize(&(&stakes.stakes, stake_history))?;
+        std::fs::write(path, content)?;
+        Ok(())
+    }
+
+    //load a snapshot saved with save_snapshot.
+    //A missing or corrupt file return an empty store and no stake history.
+    pub fn load_snapshot(path: impl AsRef<Path>, capacity: usize) -> (Self, Option<StakeHistory>) {
+        let path = path.as_ref();
+        let snapshot = std::fs::read(path)
+            .map_err(anyhow::Error::from)
+            .and_then(|content| {
+                Ok(bincode::deserialize::<(
+                    HashMap<Pubkey, StoredStake>,
+                    Option<StakeHistory>,
+                )>(&content)?)
+            });
+        match snapshot {
+            Ok((stakes, stake_history)) => {
+                log::info!(
+                    "Stake snapshot loaded from {} with {} stakes",
+                    path.display(),
+                    stakes.len()
+                );
+                let snapshot_slot = stakes
+                    .values()
+                    .map(|stake| stake.last_update_slot + 1)
+                    .max();
+                (
+                    StakeStore {
+                        stakes: TakableMap::new(stakes.into_values().collect()),
+                        snapshot_slot,
+                    },
+                    stake_history,
+                )
+            }
+            Err(err) => {
+                log::warn!(
+                    "Can't load stake snapshot from {}, start with an empty stake store:{err}",
+                    path.display()
+                );
+                (StakeStore::new(capacity), None)
+            }
         }
     }
 
@@ -75,6 +210,16 @@ impl StakeStore {
         Ok(())
     }
 
+    //at epoch change, apply the stake changes staged because they were done after the end of the previous epoch.
+    //current_end_epoch_slot is the last slot of the new epoch.
+    pub fn apply_epoch_rotation(&mut self, current_end_epoch_slot: Slot) {
+        let applied = self.stakes.apply_updates_until(current_end_epoch_slot);
+        log::info!(
+            "Stake epoch rotation applied {applied} staged updates, {} still staged",
+            self.stakes.updates.len()
+        );
+    }
+
     fn process_stake_action(stakes: &mut StakeMap, action: UpdateAction<StoredStake>) {
         match action {
             UpdateAction::Notify(_, stake) => {
@@ -85,7 +230,7 @@ impl StakeStore {
     }
     fn notify_stake(map: &mut StakeMap, stake: StoredStake) {
         //log::info!("stake_map_notify_stake stake:{stake:?}");
-        match map.entry(stake.pubkey) {
+        match map.stakes.entry(stake.pubkey) {
             // If value already exists, then increment it by one
             std::collections::hash_map::Entry::Occupied(occupied) => {
                 let strstake = occupied.into_mut(); // <-- get mut reference to existing value
@@ -93,6 +238,9 @@ impl StakeStore {
                                                     //several instructions can be done in the same slot.
                 if strstake.last_update_slot <= stake.last_update_slot {
                     log::trace!("stake_map_notify_stake Stake store updated stake: {} old_stake:{strstake:?} stake:{stake:?}", stake.pubkey);
+                    //replace the old delegation in the aggregate, a stale notification doesn't change it.
+                    StakeMap::sub_voter_stake(&mut map.voter_stakes, &strstake.stake);
+                    StakeMap::add_voter_stake(&mut map.voter_stakes, &stake.stake);
                     *strstake = stake;
                 }
             }
@@ -102,6 +250,7 @@ impl StakeStore {
                     "stake_map_notify_stake Stake store insert stake: {} stake:{stake:?}",
                     stake.pubkey
                 );
+                StakeMap::add_voter_stake(&mut map.voter_stakes, &stake.stake);
                 vacant.insert(stake);
             }
         };
@@ -114,36 +263,505 @@ impl StakeStore {
             .unwrap_or(false)
         {
             log::info!("Stake remove_from_store for {}", account_pk.to_string());
-            stakes.remove(account_pk);
+            if let Some(removed) = stakes.stakes.remove(account_pk) {
+                StakeMap::sub_voter_stake(&mut stakes.voter_stakes, &removed.stake);
+            }
         }
     }
 }
 
+//number of program accounts merged between two progress notifications.
+pub const PA_MERGE_PROGRESS_CHUNK_SIZE: usize = 10_000;
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct ProgramAccountMergeStats {
+    pub total: usize,
+    pub processed: usize,
+    //accounts with a delegated stake merged in the stake map.
+    pub merged: usize,
+    //accounts whose data can't be deserialized.
+    pub failed: usize,
+}
+
+//merge the stake program accounts by chunk of PA_MERGE_PROGRESS_CHUNK_SIZE accounts.
+//on_progress is called after each chunk.
 pub fn merge_program_account_in_strake_map(
     stake_map: &mut StakeMap,
     stakes_list: Vec<(Pubkey, Account)>,
     last_update_slot: Slot,
-) {
-    stakes_list
-        .into_iter()
-        .filter_map(|(pk, account)| {
+    mut on_progress: impl FnMut(&ProgramAccountMergeStats),
+) -> ProgramAccountMergeStats {
+    let mut stats = ProgramAccountMergeStats {
+        total: stakes_list.len(),
+        ..Default::default()
+    };
+    for chunk in &stakes_list.into_iter().chunks(PA_MERGE_PROGRESS_CHUNK_SIZE) {
+        for (pk, account) in chunk {
+            stats.processed += 1;
             match crate::account::read_stake_from_account_data(&account.data) {
-                Ok(opt_stake) => opt_stake.map(|stake| (pk, stake, account.lamports)),
+                Ok(Some(delegated_stake)) => {
+                    let stake = StoredStake {
+                        pubkey: pk,
+                        lamports: account.lamports,
+                        stake: delegated_stake,
+                        last_update_slot,
+                        write_version: 0,
+                    };
+                    StakeStore::notify_stake(stake_map, stake);
+                    stats.merged += 1;
+                }
+                Ok(None) => (),
                 Err(err) => {
-                    log::warn!("Error during pa account data deserialisation:{err}");
-                    None
+                    log::debug!("Error during pa account {pk} data deserialisation:{err}");
+                    stats.failed += 1;
                 }
             }
-        })
-        .for_each(|(pk, delegated_stake, lamports)| {
-            let stake = StoredStake {
-                pubkey: pk,
-                lamports,
-                stake: delegated_stake,
-                last_update_slot,
-                write_version: 0,
-            };
+        }
+        on_progress(&stats);
+    }
+    if stats.failed > 0 {
+        log::warn!(
+            "{} of {} stake pa accounts can't be deserialized",
+            stats.failed,
+            stats.total
+        );
+    }
+    stats
+}
+
+//merge the stake program accounts fetched by the bootstrap.
+//After a warm start from a snapshot, the snapshot stakes are reconciled like after a geyser stream gap
+//so the stakes closed since the snapshot are removed.
+pub fn merge_bootstrap_program_accounts(
+    stake_map: &mut StakeMap,
+    stakes_list: Vec<(Pubkey, Account)>,
+    snapshot_slot: Option<Slot>,
+) -> ProgramAccountMergeStats {
+    match snapshot_slot {
+        Some(snapshot_slot) => {
+            resync_program_account_in_stake_map(stake_map, stakes_list, snapshot_slot)
+        }
+        None => merge_program_account_in_strake_map(
+            stake_map,
+            stakes_list,
+            0, //with RPC no way to know the slot of the account update. Set to 0.
+            |stats| {
+                log::info!(
+                    "Bootstrap merged {}/{} stake accounts",
+                    stats.processed,
+                    stats.total
+                )
+            },
+        ),
+    }
+}
+
+//reconcile the stake map with a full stake program accounts reload done after a geyser stream gap.
+//The reloaded accounts are merged at resync_slot so an update notified after it is kept.
+//Stakes missing from the reload and not updated after resync_slot have been closed during the gap.
+pub fn resync_program_account_in_stake_map(
+    stake_map: &mut StakeMap,
+    stakes_list: Vec<(Pubkey, Account)>,
+    resync_slot: Slot,
+) -> ProgramAccountMergeStats {
+    let reloaded: HashSet<Pubkey> = stakes_list.iter().map(|(pk, _)| *pk).collect();
+    let closed = stake_map
+        .values()
+        .filter(|stake| !reloaded.contains(&stake.pubkey) && stake.last_update_slot < resync_slot)
+        .map(|stake| stake.pubkey)
+        .collect_vec();
+    for account_pk in &closed {
+        StakeStore::remove_stake(stake_map, account_pk, resync_slot);
+    }
+    let stats = merge_program_account_in_strake_map(stake_map, stakes_list, resync_slot, |_| ());
+    log::info!(
+        "Stake resync at slot {resync_slot} merged {} stake accounts and removed {}",
+        stats.merged,
+        closed.len()
+    );
+    stats
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solana_sdk::stake_history::StakeHistoryEntry;
+
+    fn snapshot_path(name: &str) -> std::path::PathBuf {
+        std::env::temp_dir().join(format!("stake-snapshot-{name}-{}.bin", std::process::id()))
+    }
+
+    fn test_stake(pubkey: Pubkey, voter: Pubkey, stake: u64, slot: Slot) -> StoredStake {
+        StoredStake {
+            pubkey,
+            lamports: stake + 100,
+            stake: Delegation {
+                voter_pubkey: voter,
+                stake,
+                ..Delegation::default()
+            },
+            last_update_slot: slot,
+            write_version: slot,
+        }
+    }
+
+    #[test]
+    fn test_snapshot_round_trip() {
+        let path = snapshot_path("round-trip");
+        let stakes: StakeMap = (1..=3)
+            .map(|slot| test_stake(Pubkey::new_unique(), Pubkey::new_unique(), slot * 900, slot))
+            .collect();
+        let store = StakeStore {
+            stakes: TakableMap::new(stakes.clone()),
+            ..Default::default()
+        };
+        let mut stake_history = StakeHistory::default();
+        stake_history.add(
+            10,
+            StakeHistoryEntry {
+                effective: 100,
+                activating: 10,
+                deactivating: 1,
+            },
+        );
+
+        store.save_snapshot(&path, Some(&stake_history)).unwrap();
+        let (loaded, loaded_history) = StakeStore::load_snapshot(&path, 10);
+        std::fs::remove_file(&path).unwrap();
+
+        let loaded_stakes = loaded.stakes.content.unwrap();
+        assert_eq!(loaded_stakes.len(), stakes.len());
+        assert_eq!(loaded_stakes.voter_stakes(), stakes.voter_stakes());
+        for (pubkey, stake) in stakes.iter() {
+            let loaded_stake = &loaded_stakes[pubkey];
+            assert_eq!(loaded_stake.lamports, stake.lamports);
+            assert_eq!(loaded_stake.stake, stake.stake);
+            assert_eq!(loaded_stake.last_update_slot, stake.last_update_slot);
+            assert_eq!(loaded_stake.write_version, stake.write_version);
+        }
+        assert_eq!(loaded_history, Some(stake_history));
+    }
+
+    #[test]
+    fn test_restart_from_snapshot() {
+        use solana_sdk::stake::state::{Meta, Stake, StakeState};
+
+        let stake_account = |voter: Pubkey, stake: u64| Account {
+            lamports: stake + 100,
+            data: bincode::serialize(&StakeState::Stake(
+                Meta::default(),
+                Stake {
+                    delegation: Delegation {
+                        voter_pubkey: voter,
+                        stake,
+                        ..Delegation::default()
+                    },
+                    credits_observed: 0,
+                },
+            ))
+            .unwrap(),
+            owner: solana_sdk::stake::program::id(),
+            ..Account::default()
+        };
+        let path = snapshot_path("restart");
+        let voter = Pubkey::new_unique();
+        let (kept, closed, notified) = (
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+        );
+        let store = StakeStore {
+            stakes: TakableMap::new(
+                [
+                    test_stake(kept, voter, 100, 10),
+                    test_stake(closed, voter, 50, 12),
+                    test_stake(notified, voter, 30, 12),
+                ]
+                .into_iter()
+                .collect(),
+            ),
+            ..Default::default()
+        };
+        store.save_snapshot(&path, None).unwrap();
+
+        //restart, the snapshot stakes are served before the bootstrap.
+        let (mut restarted, _) = StakeStore::load_snapshot(&path, 10);
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(
+            restarted.stakes.content.as_ref().unwrap().voter_stakes()[&voter],
+            180
+        );
+        //notified by the geyser stream after the restart.
+        restarted.stakes.add_value(
+            UpdateAction::Notify(20, test_stake(notified, voter, 40, 20)),
+            false,
+        );
+
+        //the bootstrap fetch doesn't contain the stake closed since the snapshot.
+        let snapshot_slot = restarted.take_snapshot_slot();
+        assert_eq!(snapshot_slot, Some(13));
+        let stake_map = restarted.stakes.content.as_mut().unwrap();
+        merge_bootstrap_program_accounts(
+            stake_map,
+            vec![
+                (kept, stake_account(voter, 110)),
+                (notified, stake_account(voter, 35)),
+            ],
+            snapshot_slot,
+        );
+
+        assert_eq!(stake_map.len(), 2);
+        assert!(!stake_map.contains_key(&closed));
+        assert_eq!(stake_map[&kept].stake.stake, 110);
+        assert_eq!(stake_map[&notified].stake.stake, 40);
+        assert_eq!(stake_map.voter_stakes()[&voter], 150);
+        //reconciled once.
+        assert_eq!(restarted.take_snapshot_slot(), None);
+    }
+
+    #[test]
+    fn test_missing_or_corrupt_snapshot_is_empty() {
+        let (store, stake_history) = StakeStore::load_snapshot(snapshot_path("missing"), 10);
+        assert!(store.stakes.content.unwrap().is_empty());
+        assert!(stake_history.is_none());
+
+        let path = snapshot_path("corrupt");
+        std::fs::write(&path, b"not a snapshot").unwrap();
+        let (store, stake_history) = StakeStore::load_snapshot(&path, 10);
+        std::fs::remove_file(&path).unwrap();
+        assert!(store.stakes.content.unwrap().is_empty());
+        assert!(stake_history.is_none());
+    }
 
-            StakeStore::notify_stake(stake_map, stake);
+    #[test]
+    fn test_voter_stakes_aggregate() {
+        let mut store = StakeStore::new(10);
+        let (voter1, voter2) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let (stake1, stake2) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let voter_stakes = |store: &StakeStore| {
+            let voter_stakes = store.stakes.content.as_ref().unwrap().voter_stakes();
+            (
+                voter_stakes.get(&voter1).copied().unwrap_or_default(),
+                voter_stakes.get(&voter2).copied().unwrap_or_default(),
+            )
+        };
+
+        store.stakes.add_value(
+            UpdateAction::Notify(10, test_stake(stake1, voter1, 100, 10)),
+            false,
+        );
+        store.stakes.add_value(
+            UpdateAction::Notify(10, test_stake(stake2, voter1, 50, 10)),
+            false,
+        );
+        assert_eq!(voter_stakes(&store), (150, 0));
+
+        //redelegate to another vote account.
+        store.stakes.add_value(
+            UpdateAction::Notify(11, test_stake(stake1, voter2, 120, 11)),
+            false,
+        );
+        assert_eq!(voter_stakes(&store), (50, 120));
+
+        //a stale notification is ignored.
+        store.stakes.add_value(
+            UpdateAction::Notify(9, test_stake(stake1, voter1, 500, 9)),
+            false,
+        );
+        assert_eq!(voter_stakes(&store), (50, 120));
+
+        //a deactivated delegation is not counted, withdrawing it doesn't change the aggregate.
+        let stake3 = Pubkey::new_unique();
+        let mut deactivated = test_stake(stake3, voter2, 80, 11);
+        deactivated.stake.deactivation_epoch = 5;
+        store
+            .stakes
+            .add_value(UpdateAction::Notify(11, deactivated), false);
+        assert_eq!(voter_stakes(&store), (50, 120));
+        store
+            .stakes
+            .add_value(UpdateAction::Remove(stake3, 12), false);
+        assert_eq!(voter_stakes(&store), (50, 120));
+
+        //deactivating an active delegation removes it from the aggregate.
+        let mut deactivating = test_stake(stake1, voter2, 120, 12);
+        deactivating.stake.deactivation_epoch = 6;
+        store
+            .stakes
+            .add_value(UpdateAction::Notify(12, deactivating), false);
+        assert_eq!(voter_stakes(&store), (50, 0));
+        store.stakes.add_value(
+            UpdateAction::Notify(13, test_stake(stake1, voter2, 120, 13)),
+            false,
+        );
+        assert_eq!(voter_stakes(&store), (50, 120));
+
+        store
+            .stakes
+            .add_value(UpdateAction::Remove(stake2, 9), false);
+        assert_eq!(voter_stakes(&store), (50, 120));
+        store
+            .stakes
+            .add_value(UpdateAction::Remove(stake2, 12), false);
+        assert_eq!(voter_stakes(&store), (0, 120));
+        assert!(!store
+            .stakes
+            .content
+            .as_ref()
+            .unwrap()
+            .voter_stakes()
+            .contains_key(&voter1));
+    }
+
+    #[test]
+    fn test_merge_program_accounts_counts() {
+        use solana_sdk::stake::state::{Meta, Stake, StakeState};
+
+        let stake_account = |stake_state: &StakeState| Account {
+            lamports: 1000,
+            data: bincode::serialize(stake_state).unwrap(),
+            owner: solana_sdk::stake::program::id(),
+            ..Account::default()
+        };
+        let voter = Pubkey::new_unique();
+        let delegated = StakeState::Stake(
+            Meta::default(),
+            Stake {
+                delegation: Delegation {
+                    voter_pubkey: voter,
+                    stake: 900,
+                    ..Delegation::default()
+                },
+                credits_observed: 0,
+            },
+        );
+        let mut accounts = vec![];
+        for _ in 0..PA_MERGE_PROGRESS_CHUNK_SIZE + 5 {
+            accounts.push((Pubkey::new_unique(), stake_account(&delegated)));
+        }
+        accounts.push((
+            Pubkey::new_unique(),
+            stake_account(&StakeState::Initialized(Meta::default())),
+        ));
+        accounts.push((
+            Pubkey::new_unique(),
+            Account {
+                data: vec![1, 2, 3],
+                ..Account::default()
+            },
+        ));
+        accounts.push((Pubkey::new_unique(), Account::default()));
+
+        let mut stake_map = StakeMap::default();
+        let mut progress = vec![];
+        let stats = merge_program_account_in_strake_map(&mut stake_map, accounts, 10, |stats| {
+            progress.push(stats.processed)
         });
+
+        assert_eq!(
+            stats,
+            ProgramAccountMergeStats {
+                total: PA_MERGE_PROGRESS_CHUNK_SIZE + 8,
+                processed: PA_MERGE_PROGRESS_CHUNK_SIZE + 8,
+                merged: PA_MERGE_PROGRESS_CHUNK_SIZE + 5,
+                failed: 2,
+            }
+        );
+        assert_eq!(
+            progress,
+            vec![
+                PA_MERGE_PROGRESS_CHUNK_SIZE,
+                PA_MERGE_PROGRESS_CHUNK_SIZE + 8
+            ]
+        );
+        assert_eq!(stake_map.len(), PA_MERGE_PROGRESS_CHUNK_SIZE + 5);
+        assert_eq!(
+            stake_map.voter_stakes()[&voter],
+            900 * (PA_MERGE_PROGRESS_CHUNK_SIZE as u64 + 5)
+        );
+    }
+
+    #[test]
+    fn test_staged_update_applied_at_epoch_rotation() {
+        let mut store = StakeStore::new(10);
+        let (stake, voter) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let current_end_epoch_slot = 100;
+
+        //done in the next epoch, staged.
+        store.stakes.add_value(
+            UpdateAction::Notify(150, test_stake(stake, voter, 100, 150)),
+            150 > current_end_epoch_slot,
+        );
+        assert!(store.stakes.content.as_ref().unwrap().is_empty());
+
+        //the previous epoch is still active.
+        store.apply_epoch_rotation(current_end_epoch_slot);
+        assert!(store.stakes.content.as_ref().unwrap().is_empty());
+        assert_eq!(store.stakes.updates.len(), 1);
+
+        store.apply_epoch_rotation(200);
+        assert!(store.stakes.updates.is_empty());
+        let stakes = store.stakes.content.as_ref().unwrap();
+        assert_eq!(stakes[&stake].last_update_slot, 150);
+        assert_eq!(stakes.voter_stakes()[&voter], 100);
+    }
+
+    #[test]
+    fn test_resync_after_stream_gap() {
+        use solana_sdk::stake::state::{Meta, Stake, StakeState};
+
+        let stake_account = |voter: Pubkey, stake: u64| Account {
+            lamports: stake + 100,
+            data: bincode::serialize(&StakeState::Stake(
+                Meta::default(),
+                Stake {
+                    delegation: Delegation {
+                        voter_pubkey: voter,
+                        stake,
+                        ..Delegation::default()
+                    },
+                    credits_observed: 0,
+                },
+            ))
+            .unwrap(),
+            owner: solana_sdk::stake::program::id(),
+            ..Account::default()
+        };
+        let (voter1, voter2) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let (updated, closed, created, notified) = (
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+        );
+
+        //state before the gap.
+        let mut stake_map: StakeMap = [
+            test_stake(updated, voter1, 100, 10),
+            test_stake(closed, voter1, 50, 10),
+        ]
+        .into_iter()
+        .collect();
+        //notified by the reconnected stream after the resync slot.
+        StakeStore::notify_stake(&mut stake_map, test_stake(notified, voter2, 30, 25));
+
+        //during the gap updated is redelegated, closed is closed and created is created.
+        let reloaded = vec![
+            (updated, stake_account(voter2, 120)),
+            (created, stake_account(voter1, 70)),
+            (notified, stake_account(voter2, 10)),
+        ];
+        let stats = resync_program_account_in_stake_map(&mut stake_map, reloaded, 20);
+
+        assert_eq!(stats.merged, 3);
+        assert_eq!(stake_map.len(), 3);
+        assert!(!stake_map.contains_key(&closed));
+        assert_eq!(stake_map[&updated].stake.voter_pubkey, voter2);
+        assert_eq!(stake_map[&updated].last_update_slot, 20);
+        assert_eq!(stake_map[&created].stake.stake, 70);
+        //the newer stream notification is kept.
+        assert_eq!(stake_map[&notified].stake.stake, 30);
+        assert_eq!(stake_map.voter_stakes()[&voter1], 70);
+        assert_eq!(stake_map.voter_stakes()[&voter2], 150);
+    }
 }
@@ -74,6 +74,14 @@ pub enum UpdateAction<Account> {
     Remove(Pubkey, Slot),
 }
 
+impl<Account> UpdateAction<Account> {
+    pub fn update_slot(&self) -> Slot {
+        match self {
+            UpdateAction::Notify(slot, _) | UpdateAction::Remove(_, slot) => *slot,
+        }
+    }
+}
+
 pub enum TakeResult<C> {
     //Vec because can wait on several collection to be merged
     Taken(Vec<Arc<Notify>>),
@@ -227,6 +235,25 @@ impl<T: Default, C: TakableContent<T> + Default> TakableMap<T, C> {
             }
         }
     }
+
+    //apply the waiting updates done until last_slot, in their arrival order.
+    //Do nothing if the content is taken, the merge will apply them.
+    //Return the number of applied updates.
+    pub fn apply_updates_until(&mut self, last_slot: Slot) -> usize {
+        let Some(content) = self.content.as_mut() else {
+            return 0;
+        };
+        let (to_apply, waiting): (Vec<_>, Vec<_>) = self
+            .updates
+            .drain(..)
+            .partition(|update| update.update_slot() <= last_slot);
+        self.updates = waiting;
+        let applied = to_apply.len();
+        for update in to_apply {
+            content.add_value(update);
+        }
+        applied
+    }
 }
 
 #[cfg(test)]
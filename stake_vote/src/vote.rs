@@ -17,6 +17,7 @@ use std::sync::Arc;
 
 pub type VoteMap = HashMap<Pubkey, Arc<StoredVote>>;
 pub type VoteContent = (VoteMap, EpochVoteStakesCache);
+pub type VoteAction = UpdateAction<StoredVote>;
 
 #[derive(Debug, Clone)]
 pub struct EpochVoteStakes {
@@ -42,7 +43,7 @@ impl EpochVoteStakesCache {
 }
 
 impl TakableContent<StoredVote> for VoteContent {
-    fn add_value(&mut self, val: UpdateAction<StoredVote>) {
+    fn add_value(&mut self, val: VoteAction) {
         VoteStore::process_vote_action(&mut self.0, val);
     }
 }
@@ -119,7 +120,11 @@ impl VoteStore {
                 if epoch_credits.len() > MAX_RPC_VOTE_ACCOUNT_INFO_EPOCH_CREDITS_HISTORY {
                     epoch_credits
                         .iter()
-                        .skip(epoch_credits.len().saturating_sub(MAX_RPC_VOTE_ACCOUNT_INFO_EPOCH_CREDITS_HISTORY))
+                        .skip(
+                            epoch_credits
+                                .len()
+                                .saturating_sub(MAX_RPC_VOTE_ACCOUNT_INFO_EPOCH_CREDITS_HISTORY),
+                        )
                         .cloned()
                         .collect()
                 } else {
@@ -145,7 +150,7 @@ impl VoteStore {
         Ok(())
     }
 
-    fn process_vote_action(votes: &mut VoteMap, action: UpdateAction<StoredVote>) {
+    fn process_vote_action(votes: &mut VoteMap, action: VoteAction) {
         match action {
             UpdateAction::Notify(_, vote) => {
                 Self::vote_map_insert_vote(votes, vote);
@@ -172,8 +177,11 @@ impl VoteStore {
         let vote_account_pk = vote_data.pubkey;
         match map.entry(vote_account_pk) {
             std::collections::hash_map::Entry::Occupied(occupied) => {
+                //several updates can be done in the same slot, the write version orders them.
                 let voteacc = occupied.into_mut(); // <-- get mut reference to existing value
-                if voteacc.last_update_slot <= vote_data.last_update_slot {
+                if (voteacc.last_update_slot, voteacc.write_version)
+                    <= (vote_data.last_update_slot, vote_data.write_version)
+                {
                     // generate a lot of trace log::trace!(
                     //     "Vote updated for: {vote_account_pk} node_id:{} root_slot:{:?}",
                     //     vote_data.vote_data.node_pubkey,
@@ -260,7 +268,8 @@ pub fn get_rpc_vote_accounts_info(
         })
         .partition(|vote_account_info| {
             if current_slot >= delinquent_validator_slot_distance {
-                vote_account_info.last_vote > current_slot.saturating_sub(delinquent_validator_slot_distance)
+                vote_account_info.last_vote
+                    > current_slot.saturating_sub(delinquent_validator_slot_distance)
             } else {
                 vote_account_info.last_vote > 0
             }
@@ -281,6 +290,22 @@ pub fn get_rpc_vote_accounts_info(
     }
 }
 
+//Stakes of the vote accounts from the live stake accounts, used while the epoch stakes are not calculated.
+//The stake is the delegated one without the deactivated delegations, warmup and cooldown are not applied.
+pub fn vote_stakes_from_voter_stakes(
+    votes: &VoteMap,
+    voter_stakes: &HashMap<Pubkey, u64>,
+) -> HashMap<Pubkey, (u64, Arc<StoredVote>)> {
+    voter_stakes
+        .iter()
+        .filter_map(|(voter, stake)| {
+            votes
+                .get(voter)
+                .map(|vote| (*voter, (*stake, Arc::clone(vote))))
+        })
+        .collect()
+}
+
 pub fn get_rpc_vote_account_info_from_current_epoch_stakes(
     current_epoch_stakes: &EpochVoteStakes,
 ) -> RpcVoteAccountStatus {
@@ -294,3 +319,111 @@ pub fn get_rpc_vote_account_info_from_current_epoch_stakes(
         delinquent: vec![], //no info about delinquent at startup.
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solana_sdk::vote::state::VoteStateVersions;
+
+    fn vote_account(
+        pubkey: Pubkey,
+        commission: u8,
+        slot: Slot,
+        write_version: u64,
+        lamports: u64,
+    ) -> AccountPretty {
+        let vote_state = VoteState {
+            node_pubkey: Pubkey::new_unique(),
+            commission,
+            root_slot: Some(slot.saturating_sub(32)),
+            epoch_credits: vec![(1, 100, 0)],
+            ..VoteState::default()
+        };
+        AccountPretty {
+            is_startup: false,
+            slot,
+            pubkey,
+            lamports,
+            owner: solana_sdk::vote::program::id(),
+            executable: false,
+            rent_epoch: 0,
+            data: bincode::serialize(&VoteStateVersions::new_current(vote_state)).unwrap(),
+            write_version,
+            txn_signature: String::new(),
+        }
+    }
+
+    fn commission(store: &VoteStore, pubkey: &Pubkey) -> Option<u8> {
+        store
+            .votes
+            .content
+            .as_ref()
+            .unwrap()
+            .0
+            .get(pubkey)
+            .map(|vote| vote.vote_data.commission)
+    }
+
+    #[test]
+    fn test_notify_and_remove_vote() {
+        let mut store = VoteStore::new(10);
+        let pubkey = Pubkey::new_unique();
+
+        store
+            .notify_vote_change(vote_account(pubkey, 5, 10, 1, 1000), 100)
+            .unwrap();
+        let stored = Arc::clone(&store.votes.content.as_ref().unwrap().0[&pubkey]);
+        assert_eq!(stored.vote_data.commission, 5);
+        assert_eq!(stored.vote_data.root_slot, Some(0));
+        assert_eq!(stored.vote_data.epoch_credits, vec![(1, 100, 0)]);
+
+        //a remove from an older slot is ignored
+        store
+            .notify_vote_change(vote_account(pubkey, 5, 9, 1, 0), 100)
+            .unwrap();
+        assert_eq!(commission(&store, &pubkey), Some(5));
+
+        store
+            .notify_vote_change(vote_account(pubkey, 5, 11, 1, 0), 100)
+            .unwrap();
+        assert_eq!(commission(&store, &pubkey), None);
+    }
+
+    #[test]
+    fn test_same_slot_updates_ordered_by_write_version() {
+        let mut store = VoteStore::new(10);
+        let pubkey = Pubkey::new_unique();
+
+        store
+            .notify_vote_change(vote_account(pubkey, 5, 10, 2, 1000), 100)
+            .unwrap();
+        //older write version of the same slot arrives late
+        store
+            .notify_vote_change(vote_account(pubkey, 7, 10, 1, 1000), 100)
+            .unwrap();
+        assert_eq!(commission(&store, &pubkey), Some(5));
+
+        store
+            .notify_vote_change(vote_account(pubkey, 8, 10, 3, 1000), 100)
+            .unwrap();
+        assert_eq!(commission(&store, &pubkey), Some(8));
+    }
+
+    #[test]
+    fn test_vote_stakes_from_voter_stakes() {
+        let mut store = VoteStore::new(10);
+        let voter = Pubkey::new_unique();
+        store
+            .notify_vote_change(vote_account(voter, 5, 10, 1, 1000), 100)
+            .unwrap();
+        let votes = &store.votes.content.as_ref().unwrap().0;
+
+        //stake delegated to an unknown vote account is left out
+        let voter_stakes = HashMap::from([(voter, 150), (Pubkey::new_unique(), 70)]);
+        let vote_stakes = vote_stakes_from_voter_stakes(votes, &voter_stakes);
+
+        assert_eq!(vote_stakes.len(), 1);
+        assert_eq!(vote_stakes[&voter].0, 150);
+        assert!(Arc::ptr_eq(&vote_stakes[&voter].1, &votes[&voter]));
+    }
+}
@@ -0,0 +1,107 @@
+use std::net::SocketAddr;
+
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::mpsc::{self, UnboundedReceiver};
+
+/// request received by the mock server
+#[derive(Debug, Clone)]
+pub struct HttpRequest {
+    /// request line and headers
+    pub head: String,
+    pub body: String,
+}
+
+/// response of the mock server, e.g. status "200 OK"
+pub struct HttpResponse {
+    pub status: &'static str,
+    pub body: String,
+}
+
+impl HttpResponse {
+    pub fn empty(status: &'static str) -> Self {
+        Self {
+            status,
+            body: String::new(),
+        }
+    }
+
+    pub fn json(body: impl Into<String>) -> Self {
+        Self {
+            status: "200 OK",
+            body: body.into(),
+        }
+    }
+}
+
+/// reads one request, none if the connection was closed before the request was complete
+pub async fn read_http_request(stream: &mut TcpStream) -> std::io::Result<Option<HttpRequest>> {
+    let mut request = Vec::new();
+    let mut buf = [0u8; 4096];
+    loop {
+        let n = stream.read(&mut buf).await?;
+        if n == 0 {
+            return Ok(None);
+        }
+        request.extend_from_slice(&buf[..n]);
+        let text = String::from_utf8_lossy(&request);
+        let Some((head, body)) = text.split_once("\r\n\r\n") else {
+            continue;
+        };
+        let content_length = head
+            .lines()
+            .find_map(|line| {
+                let (name, value) = line.split_once(':')?;
+                name.eq_ignore_ascii_case("content-length")
+                    .then(|| value.trim().parse::<usize>().ok())?
+            })
+            .unwrap_or_default();
+        if body.len() >= content_length {
+            return Ok(Some(HttpRequest {
+                head: head.to_string(),
+                body: body.to_string(),
+            }));
+        }
+    }
+}
+
+/// Minimal http server for tests of http clients, one request per connection.
+/// `respond` gets the number of the request (starting at 0), the received requests are sent to the returned channel.
+pub async fn start_http_mock<F>(respond: F) -> (SocketAddr, UnboundedReceiver<HttpRequest>)
+where
+    F: Fn(usize, &HttpRequest) -> HttpResponse + Send + 'static,
+{
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let (requests_sx, requests_rx) = mpsc::unbounded_channel();
+    tokio::spawn(async move {
+        let mut request_number = 0;
+        loop {
+            let Ok((mut stream, _)) = listener.accept().await else {
+                return;
+            };
+            // connections closed before sending a complete request are dropped
+            let Ok(Some(request)) = read_http_request(&mut stream).await else {
+                continue;
+            };
+            let response = respond(request_number, &request);
+            request_number += 1;
+            let content_type = if response.body.is_empty() {
+                ""
+            } else {
+                "Content-Type: application/json\r\n"
+            };
+            let response = format!(
+                "HTTP/1.1 {}\r\n{content_type}Content-Length: {}\r\nConnection: close\r\n\r\n{}",
+                response.status,
+                response.body.len(),
+                response.body
+            );
+            let _ = stream.write_all(response.as_bytes()).await;
+            let _ = stream.shutdown().await;
+            // the test may not wait for the requests
+            let _ = requests_sx.send(request);
+        }
+    });
+    (addr, requests_rx)
+}
@@ -1,6 +1,8 @@
 pub mod encoding;
 pub mod histogram_nbuckets;
 pub mod histogram_percentiles;
+#[cfg(any(test, feature = "test-utils"))]
+pub mod http_mock;
 pub mod secrets;
 pub mod statistics;
 